@@ -12,15 +12,11 @@ use opencv::imgcodecs::IMWRITE_WEBP_QUALITY;
 use opencv::imgproc::{get_text_size, FILLED, FONT_ITALIC, LINE_4};
 use opencv::prelude::*;
 
-use sdcar_types::reader::parquet::{HasRowIter, ImageParquetRowIter};
+use sdcar_types::session::{Session, Topic};
 
 use sdcar_vision::stereo_calibration::StereoCalibration;
 use sdcar_vision::stereo_rectify::StereoRectify;
 
-fn string_to_static_str(s: String) -> &'static str {
-    Box::leak(s.into_boxed_str())
-}
-
 fn main() {
     println!("OpenCV version: {:?}", opencv::core::CV_VERSION);
 
@@ -29,26 +25,16 @@ fn main() {
     let path = Path::new("/u02/data/poc5/20211214_2nd_data_collection");
     // let run = "20211028165901";
     let run = "20211216145545";
-    let left_image_file_str = string_to_static_str(format!(
-        "{}/parquet/left_image_{}.parquet",
-        &path.display(),
-        run
-    ));
-    let left_image_file = Path::new(&left_image_file_str);
-    let right_image_file_str = string_to_static_str(format!(
-        "{}/parquet/right_image_{}.parquet",
-        &path.display(),
-        run
-    ));
-    let right_image_file = Path::new(&right_image_file_str);
-    let image_data_path_str = string_to_static_str(format!("{}/images", &path.display()));
-    let image_data_path = Path::new(&image_data_path_str);
-    let rectified_image_path_str =
-        string_to_static_str(format!("{}/images/rectified", &path.display()));
-    let rectified_image_path = Path::new(&rectified_image_path_str);
-
-    println!("left_image_file: {}", &left_image_file.display());
-    println!("right_image_file: {}", &right_image_file.display());
+    let session = Session::open(path).expect("cannot open data collection");
+    let run = session.run(run).expect("run not found in data collection");
+    let image_data_path = run.image_dir();
+    let rectified_image_path = run.rectified_image_dir();
+
+    for topic in [Topic::LeftImage, Topic::RightImage] {
+        if let Some(file) = run.topic_path(topic) {
+            println!("{}_file: {}", topic, file.display());
+        }
+    }
     println!("image_data_path: {}", &image_data_path.display());
     if !Path::new(&rectified_image_path).exists() {
         println!(
@@ -64,8 +50,8 @@ fn main() {
         }
     }
 
-    let left_image_row_iter = ImageParquetRowIter::new(left_image_file_str);
-    let right_image_row_iter = ImageParquetRowIter::new(right_image_file_str);
+    let left_image_row_iter = run.left_image().expect("cannot open left_image");
+    let right_image_row_iter = run.right_image().expect("cannot open right_image");
 
     let image_size = Size::new(2048, 1536);
     println!("image_size: {:?}", &image_size);
@@ -84,7 +70,7 @@ fn main() {
     webp_params.push(70);
 
     let rectify = |oid, camera: &str, vis: bool, wait: bool| {
-        let image_file_name = format!("{}/{}.webp", image_data_path_str, oid);
+        let image_file_name = format!("{}/{}.webp", image_data_path.display(), oid);
         println!("{}", &image_file_name);
         let image = imgcodecs::imread(&image_file_name, imgcodecs::IMREAD_COLOR).unwrap();
 
@@ -108,7 +94,7 @@ fn main() {
                 println!("error: match camera defaulted!");
                 Mat::default()},
         };
-        let rectified_file_name = format!("{}/{}.webp", rectified_image_path_str, oid);
+        let rectified_file_name = format!("{}/{}.webp", rectified_image_path.display(), oid);
         println!("{}", &rectified_file_name);
         if vis {
             let mut vis_img_rectified = Mat::default();
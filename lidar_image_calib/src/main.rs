@@ -1,3 +1,4 @@
+extern crate chrono;
 extern crate nalgebra as na;
 extern crate opencv;
 extern crate sdcar_calib;
@@ -15,7 +16,9 @@ use opencv::videoio::{VideoWriter};
 use opencv::prelude::*;
 
 use sdcar_pcl::prelude::*;
-use sdcar_types::reader::parquet::*;
+use sdcar_types::reader::merged::MergedReader;
+use sdcar_types::reader::sync::ApproximateTimeSync;
+use sdcar_types::session::Session;
 use std::path::Path;
 use std::sync::{Arc, RwLock};
 
@@ -333,10 +336,6 @@ impl AppState {
     }
 }
 
-fn string_to_static_str(s: String) -> &'static str {
-    Box::leak(s.into_boxed_str())
-}
-
 fn main() -> Result<()> {
     println!("OpenCV version: {:?}", CV_VERSION);
 
@@ -345,21 +344,20 @@ fn main() -> Result<()> {
     let path = "/u02/data/poc5/20211214_2nd_data_collection";
     // let run = "20211028165901";
     let run = "20211216145545";
-    let pc2_file = string_to_static_str(format!("{}/parquet/point_cloud2_{}.parquet", path, run));
-    let image_file = string_to_static_str(format!("{}/parquet/left_image_{}.parquet", path, run));
-    let image_data_path = string_to_static_str(format!("{}/images/rectified/", path));
-
-    println!("pc2_file: {}", pc2_file);
-    println!("image_file: {}", image_file);
-    println!("image_data_path: {}", image_data_path);
-
-    let point_cloud2_row_iter = PointCloud2ParquetRowIter::<'static>::new(pc2_file);
-    let image_row_iter = ImageParquetRowIter::<'static>::new(image_file);
-    let mut point_cloud_image_iter = PointCloudXYZIntensityImageRowIter::new(
-        point_cloud2_row_iter,
-        image_row_iter,
-        image_data_path,
-    );
+    let session = Session::open(path).expect("cannot open data collection");
+    let run = session.run(run).expect("run not found in data collection");
+    let image_data_path = run.rectified_image_dir();
+
+    println!("run: {} topics: {:?}", run.id(), run.topics().collect::<Vec<_>>());
+    println!("image_data_path: {}", image_data_path.display());
+
+    let point_cloud2_row_iter = run.point_cloud2().expect("cannot open point_cloud2");
+    let image_row_iter = run.left_image().expect("cannot open left_image");
+    let reader = MergedReader::new()
+        .with_source("point_cloud2", point_cloud2_row_iter)
+        .with_source("left_image", image_row_iter);
+    // each sweep with the left image closest to it, within half a 10 Hz sweep
+    let mut synced_frames = ApproximateTimeSync::new(reader, chrono::Duration::milliseconds(50));
 
     let clusters: ClustersRef<XYZ, Intensity> =
         ClustersRef::<XYZ, Intensity>::new(Clusters::<XYZ, Intensity>::new());
@@ -421,68 +419,63 @@ fn main() -> Result<()> {
 
     let mut keep_iterating = true;
     while keep_iterating {
-        if let Some(data) = point_cloud_image_iter.next() {
-            match data {
-                DataKind::PointCloud(pc) => {
-                    app_state.next_point_cloud(pc);
-                    let (_inlier_ref, outlier_ref, clusters_ref, bounding_box_ref) =
-                        do_point_cloud(app_state.point_cloud_ref());
-                    app_state.lidar_bounding_boxes = Some(bounding_box_ref);
-                    app_state.clusters = clusters_ref;
-                    // print_lidar_bb(app_state.lidar_bounding_boxes.clone());
-                    show_lidar_top_view(outlier_ref, app_state.lidar_bounding_boxes.clone(), false)
-                        .unwrap();
-                }
-                DataKind::ImageFile(image_file) => {
-                    app_state.next_image_file(image_file.clone());
-                    // println!("app_state.image_file_name(): {}", &app_state.image_file_name());
-                    let image =
-                        imgcodecs::imread(&app_state.image_file_name(), imgcodecs::IMREAD_COLOR)
-                            .unwrap();
-                    // let image = stereo_rectify.rectify_left(image);
-                    app_state.camera_image = Some(image.clone());
-                    // highgui::named_window("daheng_left", 0).unwrap();
-                    // highgui::imshow("daheng_left", &image).unwrap();
-                    // highgui::wait_key(15).unwrap();
-                    let vis = false;
-                    // detect_objects(&image, confidence_threshold, nms_threshold, classes_file, cfg_file, darknet_model, vis).unwrap();
-                    let bounding_boxes = app_state
-                        .dnn_object_detection
-                        .detect_objects(&image, vis, false, false)
-                        .unwrap();
-                    app_state.image_bounding_boxes = Some(Arc::new(RwLock::new(bounding_boxes)));
-                    // print_image_bb(app_state.image_bounding_boxes.clone());
-                }
-                _ => {}
-            }
-            if app_state.camera_image.clone().is_some() && app_state.next_point_cloud_ref.is_some()
-            {
-                cluster_lidar_with_image.cluster(
-                    app_state.point_cloud_ref().clone(),
-                    app_state.image_bounding_boxes.clone().unwrap().clone(),
-                    0.1,
-                );
+        if let Some(set) = synced_frames.next() {
+            let pc2_msg = set.point_cloud2(0).expect("no point cloud in the set");
+            app_state.next_point_cloud(Arc::new(PointCloudXYZIntensity::from(pc2_msg)));
+            let (_inlier_ref, outlier_ref, clusters_ref, bounding_box_ref) =
+                do_point_cloud(app_state.point_cloud_ref());
+            app_state.lidar_bounding_boxes = Some(bounding_box_ref);
+            app_state.clusters = clusters_ref;
+            // print_lidar_bb(app_state.lidar_bounding_boxes.clone());
+            show_lidar_top_view(outlier_ref, app_state.lidar_bounding_boxes.clone(), false)
+                .unwrap();
+
+            let mut image_file = ImageFile::from(set.image(1).expect("no image in the set"));
+            image_file.path(image_data_path.to_string_lossy().to_string());
+            app_state.next_image_file(Arc::new(image_file));
+            // println!("app_state.image_file_name(): {}", &app_state.image_file_name());
+            let image =
+                imgcodecs::imread(&app_state.image_file_name(), imgcodecs::IMREAD_COLOR)
+                    .unwrap();
+            // let image = stereo_rectify.rectify_left(image);
+            app_state.camera_image = Some(image.clone());
+            // highgui::named_window("daheng_left", 0).unwrap();
+            // highgui::imshow("daheng_left", &image).unwrap();
+            // highgui::wait_key(15).unwrap();
+            let vis = false;
+            // detect_objects(&image, confidence_threshold, nms_threshold, classes_file, cfg_file, darknet_model, vis).unwrap();
+            let bounding_boxes = app_state
+                .dnn_object_detection
+                .detect_objects(&image, vis, false, false)
+                .unwrap();
+            app_state.image_bounding_boxes = Some(Arc::new(RwLock::new(bounding_boxes)));
+            // print_image_bb(app_state.image_bounding_boxes.clone());
+
+            cluster_lidar_with_image.cluster(
+                app_state.point_cloud_ref().clone(),
+                app_state.image_bounding_boxes.clone().unwrap().clone(),
+                0.1,
+            );
 
-                let calib_frame = fuse_image_lidar(
-                    app_state.camera_image.clone().unwrap(),
-                    Some(app_state.point_cloud_ref().clone()),
-                    app_state.image_bounding_boxes.clone(),
-                    app_state.lidar_bounding_boxes.clone(),
-                    app_state.clusters.clone(),
-                );
-                app_state.calib_frames.push_back(calib_frame);
-                while app_state.calib_frames.len() > 2 {
-                    app_state.calib_frames.pop_front();
-                }
-                let vis_img = app_state.state_image()?;
+            let calib_frame = fuse_image_lidar(
+                app_state.camera_image.clone().unwrap(),
+                Some(app_state.point_cloud_ref().clone()),
+                app_state.image_bounding_boxes.clone(),
+                app_state.lidar_bounding_boxes.clone(),
+                app_state.clusters.clone(),
+            );
+            app_state.calib_frames.push_back(calib_frame);
+            while app_state.calib_frames.len() > 2 {
+                app_state.calib_frames.pop_front();
+            }
+            let vis_img = app_state.state_image()?;
 
-                video_writer.write(&vis_img);
+            video_writer.write(&vis_img);
 
-                let winname = "App State";
-                highgui::named_window(winname, highgui::WINDOW_AUTOSIZE)?;
-                highgui::imshow(winname, &vis_img)?;
-                // println!("{:?}", image_frame);
-            }
+            let winname = "App State";
+            highgui::named_window(winname, highgui::WINDOW_AUTOSIZE)?;
+            highgui::imshow(winname, &vis_img)?;
+            // println!("{:?}", image_frame);
             let wait_key = highgui::wait_key(5).unwrap();
             if wait_key == 113 {
                 keep_iterating = false;
@@ -0,0 +1,367 @@
+use crate::webp;
+
+use sdcar_types::cdr::sensor_msgs::ImageData;
+use sdcar_types::cdr::FromCdr;
+use sdcar_types::msg::camera_info::CameraInfo;
+use sdcar_types::msg::imu::Imu;
+use sdcar_types::msg::nav_sat_fix::NavSatFix;
+use sdcar_types::msg::point_cloud2::PointCloud2;
+use sdcar_types::msg::MsgKind;
+use sdcar_types::reader::rosbag2::{BagTopic, Rosbag2};
+use sdcar_types::session::Topic;
+use sdcar_types::writer::parquet::MsgKindParquetWriter;
+use sdcar_types::{Error, Result};
+
+use chrono::{Local, NaiveDateTime, TimeZone};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// ROS 2 message type a session topic is converted from. There is no CDR
+/// decoder for `VehicleState`, whose message definition is not part of
+/// this repository, so bags recording it do not convert, see
+/// `is_vehicle_state`.
+pub fn ros_type(topic: Topic) -> Option<&'static str> {
+    match topic {
+        Topic::PointCloud2 => Some(PointCloud2::ROS_TYPE),
+        Topic::LeftImage | Topic::RightImage => Some(ImageData::ROS_TYPE),
+        Topic::NavSatFix => Some(NavSatFix::ROS_TYPE),
+        Topic::Imu => Some(Imu::ROS_TYPE),
+        Topic::LeftCameraInfo | Topic::RightCameraInfo => Some(CameraInfo::ROS_TYPE),
+        Topic::VehicleState => None,
+    }
+}
+
+/// The session topic a bag topic converts to by default: the one of its
+/// message type, with cameras told apart by a `left` or `right` component
+/// of the name, such as `/left/image_raw` or `/camera_right/camera_info`.
+fn default_topic(bag_topic: &BagTopic) -> Option<Topic> {
+    let side = |left, right| {
+        let mut parts = bag_topic.name.split(['/', '_']);
+        match parts.find(|part| *part == "left" || *part == "right") {
+            Some("left") => Some(left),
+            Some(_) => Some(right),
+            None => None,
+        }
+    };
+    match bag_topic.msg_type.as_str() {
+        PointCloud2::ROS_TYPE => Some(Topic::PointCloud2),
+        NavSatFix::ROS_TYPE => Some(Topic::NavSatFix),
+        Imu::ROS_TYPE => Some(Topic::Imu),
+        ImageData::ROS_TYPE => side(Topic::LeftImage, Topic::RightImage),
+        CameraInfo::ROS_TYPE => side(Topic::LeftCameraInfo, Topic::RightCameraInfo),
+        _ => None,
+    }
+}
+
+/// Whether a bag topic records vehicle states, by a `VehicleState` message
+/// type or a `vehicle_state` topic name. These cannot be decoded, and
+/// leaving them out silently would make a run without the vehicle state
+/// that dead reckoning and deskewing need.
+pub fn is_vehicle_state(bag_topic: &BagTopic) -> bool {
+    let last = |name: &str| name.rsplit('/').next().unwrap_or_default().to_string();
+    last(&bag_topic.msg_type) == "VehicleState"
+        || last(&bag_topic.name) == Topic::VehicleState.name()
+}
+
+/// Which bag topic, by index into `Rosbag2::topics`, is written to which
+/// session topic.
+#[derive(Debug, Clone)]
+pub struct TopicMap {
+    entries: Vec<(usize, Topic)>,
+}
+
+impl TopicMap {
+    /// Maps bag topics by message type unless given in `explicit` as bag
+    /// topic name and session topic. Two bag topics defaulting to the same
+    /// session topic is an error, as is an explicit mapping of mismatched
+    /// message type.
+    pub fn new(bag: &Rosbag2, explicit: &[(String, Topic)]) -> Result<Self> {
+        let mut entries: Vec<(usize, Topic)> = Vec::new();
+        for (name, topic) in explicit {
+            let index = bag
+                .topic_index(name)
+                .ok_or_else(|| Error::Bag(format!("no topic {}", name)))?;
+            let msg_type = &bag.topics()[index].msg_type;
+            if ros_type(*topic) != Some(msg_type.as_str()) {
+                return Err(Error::Bag(format!(
+                    "{} is {} and cannot be written as {}",
+                    name, msg_type, topic
+                )));
+            }
+            if let Some((other, _)) = entries.iter().find(|(_, mapped)| mapped == topic) {
+                return Err(Error::Bag(format!(
+                    "{} and {} are both mapped to {}",
+                    bag.topics()[*other].name,
+                    name,
+                    topic
+                )));
+            }
+            entries.push((index, *topic));
+        }
+
+        let explicit_len = entries.len();
+        for (index, bag_topic) in bag.topics().iter().enumerate() {
+            if entries.iter().any(|(mapped, _)| *mapped == index) {
+                continue;
+            }
+            let topic = match default_topic(bag_topic) {
+                Some(topic) => topic,
+                None => continue,
+            };
+            match entries.iter().position(|(_, mapped)| *mapped == topic) {
+                Some(position) if position < explicit_len => continue,
+                Some(position) => {
+                    return Err(Error::Bag(format!(
+                        "{} and {} both map to {}, choose one with --topic",
+                        bag.topics()[entries[position].0].name,
+                        bag_topic.name,
+                        topic
+                    )))
+                }
+                None => entries.push((index, topic)),
+            }
+        }
+        entries.sort_by_key(|(_, topic)| *topic);
+        Ok(Self { entries })
+    }
+
+    /// Pairs of bag topic index and session topic, in `Topic::ALL` order.
+    pub fn entries(&self) -> &[(usize, Topic)] {
+        &self.entries
+    }
+
+    /// Bag topics that are not converted.
+    pub fn unmapped<'b>(&self, bag: &'b Rosbag2) -> Vec<&'b BagTopic> {
+        bag.topics()
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !self.entries.iter().any(|(mapped, _)| mapped == index))
+            .map(|(_, bag_topic)| bag_topic)
+            .collect()
+    }
+}
+
+/// Outcome of converting one bag topic.
+#[derive(Debug, Clone)]
+pub struct TopicSummary {
+    pub topic: Topic,
+    pub bag_topic: String,
+    /// The parquet file written, `None` if the bag topic has no messages.
+    pub path: Option<PathBuf>,
+    pub rows: usize,
+    /// Messages that failed to decode and were left out.
+    pub skipped: usize,
+    /// Why each skipped message failed to decode, in bag order.
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    pub topics: Vec<TopicSummary>,
+    pub images_written: usize,
+    /// Image blobs already present in the collection.
+    pub images_existing: usize,
+}
+
+struct Output {
+    summary: TopicSummary,
+    writer: Option<MsgKindParquetWriter>,
+}
+
+/// Converts the topics in `map` into `<collection>/parquet/<topic>_<run>.parquet`
+/// files, writing the pixels of image topics to `<collection>/images/<oid>.webp`.
+/// A message that fails to decode, or a vehicle state topic that cannot be,
+/// stops the conversion unless `skip_errors`.
+pub fn convert(
+    bag: &Rosbag2,
+    map: &TopicMap,
+    collection: &Path,
+    run: &str,
+    skip_errors: bool,
+) -> Result<Summary> {
+    if !skip_errors {
+        if let Some(bag_topic) = map
+            .unmapped(bag)
+            .into_iter()
+            .find(|bag_topic| is_vehicle_state(bag_topic))
+        {
+            return Err(Error::Bag(format!(
+                "{} ({}) has vehicle states, which cannot be decoded from bags",
+                bag_topic.name, bag_topic.msg_type
+            )));
+        }
+    }
+    let parquet_dir = collection.join("parquet");
+    let image_dir = collection.join("images");
+    fs::create_dir_all(&parquet_dir)?;
+    fs::create_dir_all(&image_dir)?;
+
+    let mut outputs: Vec<Output> = Vec::new();
+    for (index, topic) in map.entries() {
+        outputs.push(Output {
+            summary: TopicSummary {
+                topic: *topic,
+                bag_topic: bag.topics()[*index].name.clone(),
+                path: None,
+                rows: 0,
+                skipped: 0,
+                errors: Vec::new(),
+            },
+            writer: None,
+        });
+    }
+
+    let mut summary = Summary::default();
+    let written = write_messages(
+        bag,
+        map,
+        &parquet_dir,
+        &image_dir,
+        run,
+        skip_errors,
+        &mut outputs,
+        &mut summary,
+    );
+    if let Err(e) = written {
+        discard(outputs);
+        return Err(e);
+    }
+
+    let mut outputs = outputs.into_iter();
+    while let Some(mut output) = outputs.next() {
+        if let Err(e) = output.finish() {
+            discard(outputs);
+            return Err(e);
+        }
+        summary.topics.push(output.summary);
+    }
+    Ok(summary)
+}
+
+impl Output {
+    /// Closes the parquet file and moves it into place.
+    fn finish(&mut self) -> Result<()> {
+        if let (Some(writer), Some(path)) = (self.writer.take(), self.summary.path.as_ref()) {
+            let partial = writer.path().to_path_buf();
+            let finished = writer
+                .close()
+                .map_err(Error::from)
+                .and_then(|_| fs::rename(&partial, path).map_err(Error::from));
+            if finished.is_err() {
+                let _ = fs::remove_file(&partial);
+            }
+            finished?;
+        }
+        Ok(())
+    }
+}
+
+/// Removes the unfinished parquet files of a failed conversion. Image blobs
+/// are complete once written, so they are kept.
+fn discard(outputs: impl IntoIterator<Item = Output>) {
+    for output in outputs {
+        if let Some(writer) = output.writer {
+            let partial = writer.path().to_path_buf();
+            drop(writer);
+            let _ = fs::remove_file(partial);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_messages(
+    bag: &Rosbag2,
+    map: &TopicMap,
+    parquet_dir: &Path,
+    image_dir: &Path,
+    run: &str,
+    skip_errors: bool,
+    outputs: &mut [Output],
+    summary: &mut Summary,
+) -> Result<()> {
+    let output_of: HashMap<usize, usize> = map
+        .entries()
+        .iter()
+        .enumerate()
+        .map(|(output, (index, _))| (*index, output))
+        .collect();
+    let indices: Vec<usize> = map.entries().iter().map(|(index, _)| *index).collect();
+    for message in bag.topic_messages(&indices) {
+        let message = message?;
+        let output = &mut outputs[output_of[&message.topic]];
+        let topic = output.summary.topic;
+        let decoded = decode(topic, &message.data, image_dir, summary);
+        let msg_kind = match decoded {
+            Ok(msg_kind) => msg_kind,
+            Err(e) => {
+                let index = output.summary.rows + output.summary.skipped;
+                let e = e.with_row(index);
+                if !skip_errors {
+                    return Err(Error::Bag(format!("{}: {}", output.summary.bag_topic, e)));
+                }
+                output.summary.errors.push(e.to_string());
+                output.summary.skipped += 1;
+                continue;
+            }
+        };
+        let writer = match output.writer.as_mut() {
+            Some(writer) => writer,
+            None => {
+                let path = parquet_dir.join(format!("{}_{}.parquet", topic, run));
+                output.summary.path = Some(path.clone());
+                // renamed once closed, so Session never sees an unfinished file
+                output.writer.insert(MsgKindParquetWriter::new(
+                    path.with_extension("parquet.partial"),
+                    &msg_kind,
+                )?)
+            }
+        };
+        writer.write(msg_kind)?;
+        output.summary.rows += 1;
+    }
+    Ok(())
+}
+
+/// The run id of a bag: the start time `ros2 bag record` puts in the
+/// default bag name, or the local time of the first message.
+pub fn default_run(bag: &Rosbag2) -> Option<String> {
+    let from_name = bag
+        .path()
+        .and_then(Path::file_stem)
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| stem.strip_prefix("rosbag2_"))
+        .and_then(|time| NaiveDateTime::parse_from_str(time, "%Y_%m_%d-%H_%M_%S").ok());
+    let start = match from_name {
+        Some(start) => start,
+        None => {
+            let log_time = bag.messages().next()?.ok()?.log_time;
+            Local.timestamp_nanos(log_time).naive_local()
+        }
+    };
+    Some(start.format("%Y%m%d%H%M%S").to_string())
+}
+
+fn decode(topic: Topic, data: &[u8], image_dir: &Path, summary: &mut Summary) -> Result<MsgKind> {
+    Ok(match topic {
+        Topic::PointCloud2 => MsgKind::PointCloud2(Arc::new(PointCloud2::from_cdr_bytes(data)?)),
+        Topic::LeftImage | Topic::RightImage => {
+            let image = ImageData::from_cdr_bytes(data)?;
+            if webp::write_blob(image_dir, &image)? {
+                summary.images_written += 1;
+            } else {
+                summary.images_existing += 1;
+            }
+            MsgKind::Image(Arc::new(image.image))
+        }
+        Topic::NavSatFix => MsgKind::NavSatFix(Arc::new(NavSatFix::from_cdr_bytes(data)?)),
+        Topic::Imu => MsgKind::Imu(Arc::new(Imu::from_cdr_bytes(data)?)),
+        Topic::LeftCameraInfo | Topic::RightCameraInfo => {
+            MsgKind::CameraInfo(Arc::new(CameraInfo::from_cdr_bytes(data)?))
+        }
+        Topic::VehicleState => {
+            return Err(Error::Bag(format!("{} is not recorded in bags", topic)))
+        }
+    })
+}
@@ -0,0 +1,9 @@
+//! Converts rosbag2 recordings into the parquet layout of a data collection
+//! session, as the `rosbag2_parquet` binary does.
+
+extern crate chrono;
+extern crate image_webp;
+extern crate sdcar_types;
+
+pub mod convert;
+pub mod webp;
@@ -0,0 +1,151 @@
+extern crate rosbag2_parquet;
+extern crate sdcar_types;
+
+use rosbag2_parquet::convert::{convert, default_run, is_vehicle_state, ros_type, TopicMap};
+use sdcar_types::reader::rosbag2::Rosbag2;
+use sdcar_types::session::Topic;
+
+use std::path::Path;
+use std::process::exit;
+
+const USAGE: &str = "usage: rosbag2_parquet [--run <run>] [--topic <bag topic>=<topic>]... [--skip-errors] <bag> <collection>
+
+Converts a rosbag2 recording, a bag directory or a single .db3 or .mcap
+file, into <collection>/parquet/<topic>_<run>.parquet files, writing the
+pixels of images to <collection>/images/<oid>.webp.
+
+Bag topics are mapped to topics by message type, cameras by a left or
+right component of their name; --topic maps a bag topic explicitly. The
+run defaults to the time in a rosbag2_%Y_%m_%d-%H_%M_%S bag name, else
+the local time of the first message. --skip-errors leaves out messages
+that fail to decode, and vehicle state topics, which cannot be decoded
+from bags, instead of stopping.";
+
+fn fail(message: String) -> ! {
+    eprintln!("{}", message);
+    exit(1);
+}
+
+fn parse_topic_arg(arg: &str) -> (String, Topic) {
+    let (bag_topic, name) = arg.rsplit_once('=').unwrap_or_else(|| {
+        eprintln!("{}", USAGE);
+        exit(2);
+    });
+    let topic = Topic::from_name(name)
+        .filter(|topic| ros_type(*topic).is_some())
+        .unwrap_or_else(|| {
+            let names: Vec<&str> = Topic::ALL
+                .iter()
+                .filter(|topic| ros_type(**topic).is_some())
+                .map(|topic| topic.name())
+                .collect();
+            fail(format!(
+                "unknown topic {}, one of {}",
+                name,
+                names.join(", ")
+            ))
+        });
+    (bag_topic.to_string(), topic)
+}
+
+fn main() {
+    let mut run = None;
+    let mut explicit = Vec::new();
+    let mut skip_errors = false;
+    let mut positional = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--run" | "--topic" => {
+                let value = args.next().unwrap_or_else(|| {
+                    eprintln!("{}", USAGE);
+                    exit(2);
+                });
+                if arg == "--run" {
+                    run = Some(value);
+                } else {
+                    explicit.push(parse_topic_arg(&value));
+                }
+            }
+            "--skip-errors" => skip_errors = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => positional.push(arg),
+        }
+    }
+    if positional.len() != 2 {
+        eprintln!("{}", USAGE);
+        exit(2);
+    }
+    let (bag_path, collection) = (&positional[0], Path::new(&positional[1]));
+
+    let bag = Rosbag2::open(bag_path).unwrap_or_else(|e| fail(format!("{}: {}", bag_path, e)));
+    let run = run.or_else(|| default_run(&bag)).unwrap_or_else(|| {
+        fail(format!(
+            "{}: no messages, give the run with --run",
+            bag_path
+        ))
+    });
+    if run.is_empty() || !run.bytes().all(|b| b.is_ascii_digit()) {
+        fail(format!(
+            "run {} is not all digits as <topic>_<run>.parquet requires",
+            run
+        ));
+    }
+    let map =
+        TopicMap::new(&bag, &explicit).unwrap_or_else(|e| fail(format!("{}: {}", bag_path, e)));
+    if map.entries().is_empty() {
+        fail(format!("{}: no topic to convert", bag_path));
+    }
+    for bag_topic in map.unmapped(&bag) {
+        // convert fails on vehicle states unless skipping errors
+        if !is_vehicle_state(bag_topic) {
+            println!("not converting {} ({})", bag_topic.name, bag_topic.msg_type);
+        } else if skip_errors {
+            eprintln!(
+                "skipping {} ({}): vehicle states cannot be decoded from bags",
+                bag_topic.name, bag_topic.msg_type
+            );
+        }
+    }
+
+    let summary = convert(&bag, &map, collection, &run, skip_errors)
+        .unwrap_or_else(|e| fail(format!("{}: {}", bag_path, e)));
+    for topic in summary.topics.iter() {
+        for error in topic.errors.iter() {
+            eprintln!("skipping {}: {}", topic.bag_topic, error);
+        }
+    }
+    println!("run {}", run);
+    for topic in summary.topics.iter() {
+        let skipped = if topic.skipped > 0 {
+            format!(", {} skipped", topic.skipped)
+        } else {
+            String::new()
+        };
+        match topic.path.as_ref() {
+            Some(path) => println!(
+                "  {:<18} {:>8} rows{}  {} -> {}",
+                topic.topic.name(),
+                topic.rows,
+                skipped,
+                topic.bag_topic,
+                path.display()
+            ),
+            None => println!(
+                "  {:<18}        - no messages{}  {}",
+                topic.topic.name(),
+                skipped,
+                topic.bag_topic
+            ),
+        }
+    }
+    println!(
+        "  images: {} written, {} already present in {}",
+        summary.images_written,
+        summary.images_existing,
+        collection.join("images").display()
+    );
+}
@@ -0,0 +1,85 @@
+use image_webp::{ColorType, WebPEncoder};
+use sdcar_types::cdr::sensor_msgs::ImageData;
+use sdcar_types::{Error, Result};
+
+use std::fs;
+use std::io::BufWriter;
+use std::path::Path;
+
+const SUPPORTED_ENCODINGS: &str = "mono8, rgb8, bgr8, rgba8 or bgra8";
+
+/// Writes the pixels of `image` to `<image_dir>/<oid>.webp`, losslessly.
+/// Blobs are content addressed, so an existing file is left as is. Returns
+/// whether a file was written.
+pub fn write_blob(image_dir: &Path, image: &ImageData) -> Result<bool> {
+    let path = image_dir.join(format!("{}.webp", image.image.image_oid));
+    if path.exists() {
+        return Ok(false);
+    }
+    let (pixels, color) = to_webp_pixels(image)?;
+    // written aside and renamed so an interrupted run leaves no partial blob
+    let partial = path.with_extension("webp.partial");
+    let writer = BufWriter::new(fs::File::create(&partial)?);
+    WebPEncoder::new(writer)
+        .encode(&pixels, image.image.width, image.image.height, color)
+        .map_err(|e| Error::decode("data", "webp encodable image", e.to_string()))?;
+    fs::rename(&partial, &path)?;
+    Ok(true)
+}
+
+/// Packs the rows of `image` without their padding, in a channel order the
+/// encoder accepts.
+pub fn to_webp_pixels(image: &ImageData) -> Result<(Vec<u8>, ColorType)> {
+    let encoding = image.image.encoding.as_str();
+    let (channels, color) = match encoding {
+        "mono8" | "8UC1" => (1, ColorType::L8),
+        "rgb8" | "bgr8" | "8UC3" => (3, ColorType::Rgb8),
+        "rgba8" | "bgra8" | "8UC4" => (4, ColorType::Rgba8),
+        // webp has 8 bit channels only, so depth and thermal images would
+        // lose their low byte
+        "mono16" | "16UC1" => {
+            return Err(Error::decode(
+                "encoding",
+                SUPPORTED_ENCODINGS,
+                format!("found {}, which webp cannot hold losslessly", encoding),
+            ))
+        }
+        other => {
+            return Err(Error::decode(
+                "encoding",
+                SUPPORTED_ENCODINGS,
+                format!("found {}", other),
+            ))
+        }
+    };
+    let width = image.image.width as usize;
+    let height = image.image.height as usize;
+    let step = image.image.step as usize;
+    let row_len = width * channels;
+    if step < row_len || image.data.len() < step * height {
+        return Err(Error::decode(
+            "data",
+            "height * step bytes",
+            format!(
+                "{} bytes for {}x{} {} with step {}",
+                image.data.len(),
+                width,
+                height,
+                encoding,
+                step
+            ),
+        ));
+    }
+
+    let mut pixels = Vec::with_capacity(width * height * channels);
+    for row in image.data.chunks(step).take(height) {
+        pixels.extend_from_slice(&row[..row_len]);
+    }
+    // OpenCV's 8UC3 and 8UC4 are BGR ordered
+    if encoding.starts_with("bgr") || encoding == "8UC3" || encoding == "8UC4" {
+        for pixel in pixels.chunks_exact_mut(channels) {
+            pixel.swap(0, 2);
+        }
+    }
+    Ok((pixels, color))
+}
@@ -0,0 +1,29 @@
+#![allow(dead_code)]
+
+pub use sdcar_types::test_support::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A directory under the system temp dir, removed when dropped, so a
+/// failing test does not leave it behind.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let dir =
+            std::env::temp_dir().join(format!("rosbag2_parquet_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,362 @@
+extern crate chrono;
+extern crate rosbag2_parquet;
+extern crate sdcar_types;
+
+mod common;
+
+use common::*;
+
+use chrono::{Local, TimeZone};
+use rosbag2_parquet::convert::*;
+use sdcar_types::msg::imu::Imu;
+use sdcar_types::msg::nav_sat_fix::NavSatFix;
+use sdcar_types::reader::parquet::*;
+use sdcar_types::reader::rosbag2::Rosbag2;
+use sdcar_types::session::{Session, Topic};
+use sdcar_types::Error;
+use std::fs;
+use std::path::Path;
+
+fn mono8(i: i32, frame_id: &str) -> Vec<u8> {
+    image_cdr(i, frame_id, "mono8", 2, &[i as u8, 1, 2, 3])
+}
+
+// two seconds of gps, imu and a stereo pair, with a tf topic left unmapped
+fn recording() -> Recorded {
+    let mut recorded = Vec::new();
+    for i in 0..2 {
+        recorded.push((
+            "/gps/fix",
+            NAV_SAT_FIX,
+            log_time(i, 500_000_000),
+            nav_sat_fix_cdr(i, false),
+        ));
+        recorded.push(("/imu/data", IMU, log_time(i, 250_000_000), imu_cdr(i)));
+        recorded.push((
+            "/camera/left/image_raw",
+            IMAGE,
+            log_time(i, 750_000_000),
+            mono8(i, "left"),
+        ));
+        recorded.push((
+            "/camera/right/image_raw",
+            IMAGE,
+            log_time(i, 750_000_000),
+            mono8(i, "right"),
+        ));
+        recorded.push(("/tf", TF, log_time(i, 0), vec![0, 1, 0, 0]));
+    }
+    recorded.sort_by_key(|(_, _, log_time, _)| *log_time);
+    recorded
+}
+
+fn bag(recorded: &Recorded) -> Rosbag2 {
+    Rosbag2::from_mcap_bytes(mcap_bytes(recorded, &[])).unwrap()
+}
+
+fn bag_error(result: Result<TopicMap, Error>) -> String {
+    match result {
+        Err(Error::Bag(message)) => message,
+        other => panic!(
+            "expected a bag error, got {:?}",
+            other.map(|map| map.entries().to_vec())
+        ),
+    }
+}
+
+fn mapped(bag: &Rosbag2, map: &TopicMap) -> Vec<(String, Topic)> {
+    map.entries()
+        .iter()
+        .map(|(index, topic)| (bag.topics()[*index].name.clone(), *topic))
+        .collect()
+}
+
+#[test]
+fn topic_map_default_test() {
+    let bag = bag(&recording());
+    let map = TopicMap::new(&bag, &[]).unwrap();
+    // by message type, cameras by a left or right name component, in Topic order
+    assert_eq!(
+        mapped(&bag, &map),
+        vec![
+            ("/camera/left/image_raw".to_string(), Topic::LeftImage),
+            ("/camera/right/image_raw".to_string(), Topic::RightImage),
+            ("/gps/fix".to_string(), Topic::NavSatFix),
+            ("/imu/data".to_string(), Topic::Imu),
+        ]
+    );
+    let unmapped: Vec<&str> = map
+        .unmapped(&bag)
+        .iter()
+        .map(|topic| topic.name.as_str())
+        .collect();
+    assert_eq!(unmapped, vec!["/tf"]);
+
+    // underscores separate name components too
+    let recorded: Recorded = vec![(
+        "/camera_right/camera_info",
+        CAMERA_INFO,
+        log_time(0, 0),
+        Vec::new(),
+    )];
+    let info = Rosbag2::from_mcap_bytes(mcap_bytes(&Vec::new(), &[(recorded[0].0, recorded[0].1)]))
+        .unwrap();
+    let map = TopicMap::new(&info, &[]).unwrap();
+    assert_eq!(map.entries(), &[(0, Topic::RightCameraInfo)]);
+
+    // an image topic of neither side is left unmapped
+    let center = Rosbag2::from_mcap_bytes(mcap_bytes(
+        &vec![(
+            "/center/image_raw",
+            IMAGE,
+            log_time(0, 0),
+            mono8(0, "center"),
+        )],
+        &[],
+    ))
+    .unwrap();
+    assert!(TopicMap::new(&center, &[]).unwrap().entries().is_empty());
+}
+
+#[test]
+fn topic_map_explicit_test() {
+    let mut recorded = recording();
+    recorded.push((
+        "/gps/fix_rtk",
+        NAV_SAT_FIX,
+        log_time(3, 0),
+        nav_sat_fix_cdr(3, false),
+    ));
+    let bag = bag(&recorded);
+    let message = bag_error(TopicMap::new(&bag, &[]));
+    assert!(
+        message.contains("/gps/fix")
+            && message.contains("/gps/fix_rtk")
+            && message.contains("--topic"),
+        "{}",
+        message
+    );
+
+    // an explicit mapping wins over the default of another bag topic
+    let map = TopicMap::new(&bag, &[("/gps/fix_rtk".to_string(), Topic::NavSatFix)]).unwrap();
+    let mapped = mapped(&bag, &map);
+    assert!(mapped.contains(&("/gps/fix_rtk".to_string(), Topic::NavSatFix)));
+    assert!(!mapped.iter().any(|(name, _)| name == "/gps/fix"));
+
+    // and can swap the cameras
+    let swapped = TopicMap::new(
+        &bag,
+        &[
+            ("/camera/left/image_raw".to_string(), Topic::RightImage),
+            ("/camera/right/image_raw".to_string(), Topic::LeftImage),
+            ("/gps/fix".to_string(), Topic::NavSatFix),
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        &self::mapped(&bag, &swapped)[..2],
+        &[
+            ("/camera/right/image_raw".to_string(), Topic::LeftImage),
+            ("/camera/left/image_raw".to_string(), Topic::RightImage)
+        ]
+    );
+
+    let missing = bag_error(TopicMap::new(
+        &bag,
+        &[("/gps/missing".to_string(), Topic::NavSatFix)],
+    ));
+    assert!(missing.contains("no topic /gps/missing"), "{}", missing);
+    let mistyped = bag_error(TopicMap::new(
+        &bag,
+        &[("/imu/data".to_string(), Topic::NavSatFix)],
+    ));
+    assert!(mistyped.contains("sensor_msgs/msg/Imu"), "{}", mistyped);
+    let twice = bag_error(TopicMap::new(
+        &bag,
+        &[
+            ("/gps/fix".to_string(), Topic::NavSatFix),
+            ("/gps/fix_rtk".to_string(), Topic::NavSatFix),
+        ],
+    ));
+    assert!(twice.contains("both mapped"), "{}", twice);
+}
+
+#[test]
+fn default_run_test() {
+    let dir = TempDir::new("default_run");
+    let named = dir.path().join("rosbag2_2021_10_28-17_39_01.mcap");
+    fs::write(&named, mcap_bytes(&recording(), &[])).unwrap();
+    assert_eq!(
+        default_run(&Rosbag2::open(&named).unwrap()).as_deref(),
+        Some("20211028173901")
+    );
+
+    // otherwise the local time of the first message
+    let renamed = dir.path().join("drive.mcap");
+    fs::rename(&named, &renamed).unwrap();
+    let first = Local
+        .timestamp_nanos(log_time(0, 0))
+        .format("%Y%m%d%H%M%S")
+        .to_string();
+    assert_eq!(
+        default_run(&Rosbag2::open(&renamed).unwrap()),
+        Some(first.clone())
+    );
+    assert_eq!(default_run(&bag(&recording())), Some(first));
+
+    let empty = Rosbag2::from_mcap_bytes(mcap_bytes(&Vec::new(), &[("/imu/data", IMU)])).unwrap();
+    assert_eq!(default_run(&empty), None);
+}
+
+fn parquet_files(collection: &Path) -> Vec<String> {
+    let mut files: Vec<String> = fs::read_dir(collection.join("parquet"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn convert_test() {
+    let dir = TempDir::new("convert");
+    let bag = Rosbag2::from_mcap_bytes(mcap_bytes(
+        &recording(),
+        &[("/camera/left/camera_info", CAMERA_INFO)],
+    ))
+    .unwrap();
+    let map = TopicMap::new(&bag, &[]).unwrap();
+    let summary = convert(&bag, &map, dir.path(), "20211028173901", false).unwrap();
+
+    let rows: Vec<(Topic, usize, bool)> = summary
+        .topics
+        .iter()
+        .map(|topic| (topic.topic, topic.rows, topic.path.is_some()))
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            (Topic::LeftImage, 2, true),
+            (Topic::RightImage, 2, true),
+            (Topic::NavSatFix, 2, true),
+            (Topic::Imu, 2, true),
+            (Topic::LeftCameraInfo, 0, false),
+        ]
+    );
+    // the left and right images of a pair are the same pixels, so one blob each
+    assert_eq!((summary.images_written, summary.images_existing), (2, 2));
+    assert_eq!(fs::read_dir(dir.path().join("images")).unwrap().count(), 2);
+    assert_eq!(
+        parquet_files(dir.path()),
+        vec![
+            "imu_20211028173901.parquet",
+            "left_image_20211028173901.parquet",
+            "nav_sat_fix_20211028173901.parquet",
+            "right_image_20211028173901.parquet"
+        ]
+    );
+
+    let session = Session::open(dir.path()).unwrap();
+    let run = session.run("20211028173901").unwrap();
+    let fixes: Vec<_> =
+        MsgParquetRowIter::<NavSatFix>::try_new(run.topic_path(Topic::NavSatFix).unwrap())
+            .unwrap()
+            .collect();
+    assert_eq!(fixes.len(), 2);
+    assert_eq!(fixes[1].header.stamp.sec(), START_SEC + 1);
+    assert_eq!(fixes[1].altitude, 45.5);
+    let imus: Vec<_> = MsgParquetRowIter::<Imu>::try_new(run.topic_path(Topic::Imu).unwrap())
+        .unwrap()
+        .collect();
+    assert_eq!(imus[0].header.frame_id(), "imu_link");
+
+    // converting again finds the image blobs in place
+    let again = convert(&bag, &map, dir.path(), "20211028173901", false).unwrap();
+    assert_eq!((again.images_written, again.images_existing), (0, 4));
+}
+
+#[test]
+fn convert_error_test() {
+    let dir = TempDir::new("convert_error");
+    let mut recorded = recording();
+    // a truncated fix after the first of every topic has been written
+    recorded.push((
+        "/gps/fix",
+        NAV_SAT_FIX,
+        log_time(5, 0),
+        nav_sat_fix_cdr(5, false)[..40].to_vec(),
+    ));
+    recorded.push(("/imu/data", IMU, log_time(6, 0), imu_cdr(6)));
+    let bag = bag(&recorded);
+    let map = TopicMap::new(&bag, &[]).unwrap();
+
+    match convert(&bag, &map, dir.path(), "20211028173901", false) {
+        Err(Error::Bag(message)) => assert!(message.starts_with("/gps/fix: "), "{}", message),
+        other => panic!(
+            "expected a bag error, got {:?}",
+            other.map(|summary| summary.topics.len())
+        ),
+    }
+    // no unfinished parquet file is left behind
+    assert!(
+        parquet_files(dir.path()).is_empty(),
+        "{:?}",
+        parquet_files(dir.path())
+    );
+
+    let summary = convert(&bag, &map, dir.path(), "20211028173901", true).unwrap();
+    let fixes = summary
+        .topics
+        .iter()
+        .find(|topic| topic.topic == Topic::NavSatFix)
+        .unwrap();
+    assert_eq!((fixes.rows, fixes.skipped), (2, 1));
+    assert_eq!(fixes.errors.len(), 1);
+    assert!(fixes.errors[0].contains("row 2"), "{}", fixes.errors[0]);
+    let imus = summary
+        .topics
+        .iter()
+        .find(|topic| topic.topic == Topic::Imu)
+        .unwrap();
+    assert_eq!(imus.rows, 3);
+    assert!(parquet_files(dir.path())
+        .iter()
+        .all(|file| file.ends_with(".parquet")));
+}
+
+#[test]
+fn convert_vehicle_state_test() {
+    let dir = TempDir::new("convert_vehicle_state");
+    for (name, msg_type) in [
+        ("/can/state", "sdcar_msgs/msg/VehicleState"),
+        ("/vehicle_state", "std_msgs/msg/ByteMultiArray"),
+    ] {
+        let mut recorded = recording();
+        recorded.push((name, msg_type, log_time(2, 0), vec![0, 1, 0, 0]));
+        let bag = bag(&recorded);
+        let map = TopicMap::new(&bag, &[]).unwrap();
+        assert!(is_vehicle_state(map.unmapped(&bag)[1]));
+
+        // a run without its vehicle states is not made silently
+        match convert(&bag, &map, dir.path(), "20211028173901", false) {
+            Err(Error::Bag(message)) => assert!(
+                message.contains(name) && message.contains("vehicle states"),
+                "{}",
+                message
+            ),
+            other => panic!(
+                "expected a bag error, got {:?}",
+                other.map(|summary| summary.topics.len())
+            ),
+        }
+        assert!(!dir.path().join("parquet").exists());
+        assert_eq!(
+            convert(&bag, &map, dir.path(), "20211028173901", true)
+                .unwrap()
+                .topics
+                .len(),
+            4
+        );
+        fs::remove_dir_all(dir.path().join("parquet")).unwrap();
+    }
+}
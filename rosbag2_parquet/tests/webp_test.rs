@@ -0,0 +1,82 @@
+extern crate image_webp;
+extern crate rosbag2_parquet;
+extern crate sdcar_types;
+
+use image_webp::ColorType;
+use rosbag2_parquet::webp::to_webp_pixels;
+use sdcar_types::cdr::sensor_msgs::{image_oid, ImageData};
+use sdcar_types::msg::image::Image;
+use sdcar_types::Error;
+
+fn image_data(encoding: &str, width: u32, height: u32, step: u32, data: Vec<u8>) -> ImageData {
+    let image = Image::new(
+        1635404341,
+        0,
+        "left".to_string(),
+        height,
+        width,
+        encoding.to_string(),
+        false,
+        step,
+        image_oid(&data),
+    );
+    ImageData { image, data }
+}
+
+#[test]
+fn webp_pixels_padding_test() {
+    // two mono8 rows of two pixels, each padded to four bytes
+    let mono = image_data("mono8", 2, 2, 4, vec![1, 2, 0, 0, 3, 4, 0, 0]);
+    let (pixels, color) = to_webp_pixels(&mono).unwrap();
+    assert_eq!(pixels, vec![1, 2, 3, 4]);
+    assert!(matches!(color, ColorType::L8));
+
+    let rgb = image_data("rgb8", 1, 2, 4, vec![10, 20, 30, 0, 40, 50, 60, 0]);
+    let (pixels, color) = to_webp_pixels(&rgb).unwrap();
+    assert_eq!(pixels, vec![10, 20, 30, 40, 50, 60]);
+    assert!(matches!(color, ColorType::Rgb8));
+
+    // a step too short for the row, or data too short for the rows
+    assert!(to_webp_pixels(&image_data("mono8", 2, 2, 1, vec![0; 4])).is_err());
+    assert!(to_webp_pixels(&image_data("mono8", 2, 2, 4, vec![0; 6])).is_err());
+}
+
+#[test]
+fn webp_pixels_channel_order_test() {
+    let bgr = image_data("bgr8", 2, 1, 6, vec![3, 2, 1, 6, 5, 4]);
+    let (pixels, color) = to_webp_pixels(&bgr).unwrap();
+    assert_eq!(pixels, vec![1, 2, 3, 4, 5, 6]);
+    assert!(matches!(color, ColorType::Rgb8));
+
+    // OpenCV's 8UC3 is BGR as well
+    let (pixels, _) = to_webp_pixels(&image_data("8UC3", 2, 1, 6, vec![3, 2, 1, 6, 5, 4])).unwrap();
+    assert_eq!(pixels, vec![1, 2, 3, 4, 5, 6]);
+
+    let bgra = image_data(
+        "bgra8",
+        1,
+        2,
+        8,
+        vec![3, 2, 1, 255, 0, 0, 0, 0, 6, 5, 4, 128, 0, 0, 0, 0],
+    );
+    let (pixels, color) = to_webp_pixels(&bgra).unwrap();
+    assert_eq!(pixels, vec![1, 2, 3, 255, 4, 5, 6, 128]);
+    assert!(matches!(color, ColorType::Rgba8));
+}
+
+#[test]
+fn webp_pixels_unsupported_test() {
+    for encoding in ["yuv422", "mono16", "16UC1"] {
+        match to_webp_pixels(&image_data(encoding, 2, 2, 4, vec![0; 8])) {
+            Err(Error::Decode { field, reason, .. }) => {
+                assert_eq!(field, "encoding");
+                assert!(reason.contains(encoding), "{}", reason);
+            }
+            other => panic!(
+                "expected {} to be rejected, got {:?}",
+                encoding,
+                other.map(|(pixels, _)| pixels)
+            ),
+        }
+    }
+}
@@ -0,0 +1,8 @@
+//! Reports the messages, timing and layout of every topic of a session's
+//! runs, as the `sdcar_inspect` binary prints them.
+
+extern crate chrono;
+extern crate sdcar_types;
+extern crate serde;
+
+pub mod report;
@@ -0,0 +1,73 @@
+extern crate sdcar_inspect;
+extern crate sdcar_types;
+extern crate serde_json;
+
+use sdcar_inspect::report::{inspect_run, RunReport};
+use sdcar_types::session::Session;
+
+use std::process::exit;
+
+const USAGE: &str = "usage: sdcar_inspect [--json] <collection> [<run>...]
+
+Summarises every topic of the given runs, or of all runs found in
+<collection>/parquet when none are given. Exits with status 1 when a
+topic fails to read.";
+
+fn main() {
+    let mut json = false;
+    let mut positional = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => positional.push(arg),
+        }
+    }
+    if positional.is_empty() {
+        eprintln!("{}", USAGE);
+        exit(2);
+    }
+
+    let session = Session::open(&positional[0]).unwrap_or_else(|e| {
+        eprintln!("{}: {}", positional[0], e);
+        exit(1);
+    });
+    let runs = if positional.len() > 1 {
+        positional[1..]
+            .iter()
+            .map(|id| {
+                session.run(id).unwrap_or_else(|| {
+                    eprintln!("run {} not found in {}", id, positional[0]);
+                    exit(1);
+                })
+            })
+            .collect()
+    } else {
+        session.runs().iter().collect::<Vec<_>>()
+    };
+
+    let reports: Vec<RunReport> = runs.into_iter().map(inspect_run).collect();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reports).unwrap());
+    } else {
+        for report in reports.iter() {
+            report.print();
+        }
+    }
+    for report in reports.iter() {
+        for topic in report.failed() {
+            eprintln!(
+                "run {}: {}: {}",
+                report.run,
+                topic.file,
+                topic.error.as_deref().unwrap_or_default()
+            );
+        }
+    }
+    if reports.iter().any(|report| !report.failed().is_empty()) {
+        exit(1);
+    }
+}
@@ -0,0 +1,361 @@
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use serde::Serialize;
+
+use sdcar_types::diagnostics::{Diagnostics, StreamReport};
+use sdcar_types::msg::camera_info::CameraInfo;
+use sdcar_types::msg::image::Image;
+use sdcar_types::msg::imu::Imu;
+use sdcar_types::msg::nav_sat_fix::NavSatFix;
+use sdcar_types::msg::point_cloud2::PointCloud2;
+use sdcar_types::msg::vehicle_state::VehicleState;
+use sdcar_types::reader::parquet::*;
+use sdcar_types::session::{Run, Topic};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Messages are decoded a batch at a time; point clouds make large batches
+/// expensive, so keep them small.
+const BATCH_SIZE: usize = 16;
+
+#[derive(Debug, Serialize)]
+pub struct RunReport {
+    pub run: String,
+    pub topics: Vec<TopicReport>,
+    /// Offsets of every topic to the first one, e.g. camera latency
+    /// relative to the lidar.
+    pub offsets: Vec<OffsetReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OffsetReport {
+    pub topic: String,
+    pub reference: String,
+    pub offset_ms: f64,
+    pub spread_ms: f64,
+    pub pairs: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GapReport {
+    pub start: String,
+    pub duration_ms: f64,
+    pub missing: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct TopicReport {
+    pub topic: String,
+    pub file: String,
+    pub messages: usize,
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub duration_sec: f64,
+    pub rate_hz: Option<f64>,
+    pub jitter_ms: Option<f64>,
+    pub avg_gap_ms: Option<f64>,
+    pub max_gap_ms: Option<f64>,
+    pub max_gap_at: Option<String>,
+    pub dropped_frames: usize,
+    pub out_of_order: usize,
+    pub gaps: Vec<GapReport>,
+    pub frame_ids: Vec<String>,
+    pub skipped_rows: usize,
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub point_layouts: Vec<PointLayout>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub image_formats: Vec<ImageFormat>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct PointFieldReport {
+    pub name: String,
+    pub offset: u32,
+    pub datatype: u8,
+    pub count: u32,
+}
+
+/// A distinct point cloud field layout and how many clouds used it.
+#[derive(Debug, Serialize)]
+pub struct PointLayout {
+    pub fields: Vec<PointFieldReport>,
+    pub point_step: u32,
+    pub is_bigendian: bool,
+    pub messages: usize,
+}
+
+/// A distinct image encoding and size and how many images used it.
+#[derive(Debug, Serialize)]
+pub struct ImageFormat {
+    pub encoding: String,
+    pub width: u32,
+    pub height: u32,
+    pub messages: usize,
+}
+
+/// What the report needs from each message type beyond its header.
+trait Inspect: ParquetMsg {
+    fn describe(&self, _details: &mut Details) {}
+}
+
+#[derive(Default)]
+struct Details {
+    point_layouts: BTreeMap<(Vec<PointFieldReport>, u32, bool), usize>,
+    image_formats: BTreeMap<(String, u32, u32), usize>,
+}
+
+impl Inspect for PointCloud2 {
+    fn describe(&self, details: &mut Details) {
+        let fields = self
+            .fields
+            .iter()
+            .map(|field| PointFieldReport {
+                name: field.name.clone(),
+                offset: field.offset,
+                datatype: field.datatype,
+                count: field.count,
+            })
+            .collect();
+        *details
+            .point_layouts
+            .entry((fields, self.point_step, self.is_bigendian))
+            .or_default() += 1;
+    }
+}
+
+impl Inspect for Image {
+    fn describe(&self, details: &mut Details) {
+        *details
+            .image_formats
+            .entry((self.encoding.clone(), self.width, self.height))
+            .or_default() += 1;
+    }
+}
+
+impl Inspect for NavSatFix {}
+
+impl Inspect for VehicleState {}
+
+impl Inspect for Imu {}
+
+impl Inspect for CameraInfo {}
+
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.num_nanoseconds().unwrap_or(i64::MAX) as f64 / 1e6
+}
+
+/// Fills in the timing fields from the stream's diagnostics.
+fn timing(report: &mut TopicReport, stream: &StreamReport) {
+    report.messages = stream.messages;
+    report.start = stream.start.map(format_timestamp);
+    report.end = stream.end.map(format_timestamp);
+    report.duration_sec = millis(stream.duration()) / 1e3;
+    report.rate_hz = stream.rate.map(|rate| rate.rate_hz);
+    report.jitter_ms = stream.rate.map(|rate| millis(rate.jitter));
+    report.avg_gap_ms = stream.mean_interval.map(millis);
+    if let Some((max, at)) = stream.max_interval {
+        report.max_gap_ms = Some(millis(max));
+        report.max_gap_at = Some(format_timestamp(at));
+    }
+    report.dropped_frames = stream.dropped();
+    report.out_of_order = stream.out_of_order;
+    report.gaps = stream
+        .gaps
+        .iter()
+        .map(|gap| GapReport {
+            start: format_timestamp(gap.start),
+            duration_ms: millis(gap.duration()),
+            missing: gap.missing,
+        })
+        .collect();
+}
+
+/// Reads every message of `path`, pushing its timestamps to `stream` of
+/// `diagnostics`; the timing fields are filled in once all topics are read.
+fn inspect_topic<M: Inspect>(
+    topic: Topic,
+    path: &Path,
+    diagnostics: &mut Diagnostics,
+    stream: usize,
+) -> TopicReport {
+    let mut report = TopicReport {
+        topic: topic.name().to_string(),
+        file: path.display().to_string(),
+        ..Default::default()
+    };
+    let mut iter = match MsgParquetRowIter::<M>::try_new(path)
+        .and_then(|iter| iter.with_read_mode(ReadMode::Batches(BATCH_SIZE)))
+    {
+        Ok(iter) => iter,
+        Err(e) => {
+            report.error = Some(e.to_string());
+            return report;
+        }
+    };
+    iter.set_error_policy(RowErrorPolicy::Skip);
+
+    let mut frame_ids = BTreeSet::new();
+    let mut details = Details::default();
+    for msg in iter.by_ref() {
+        diagnostics.push(stream, msg.timestamp());
+        let frame_id = msg.header().frame_id();
+        if !frame_ids.contains(frame_id) {
+            frame_ids.insert(frame_id.to_string());
+        }
+        msg.describe(&mut details);
+    }
+    report.skipped_rows = iter.skipped_rows();
+    report.error = iter.error().map(|e| e.to_string());
+    report.frame_ids = frame_ids.into_iter().collect();
+    report.point_layouts = details
+        .point_layouts
+        .into_iter()
+        .map(
+            |((fields, point_step, is_bigendian), messages)| PointLayout {
+                fields,
+                point_step,
+                is_bigendian,
+                messages,
+            },
+        )
+        .collect();
+    report.image_formats = details
+        .image_formats
+        .into_iter()
+        .map(|((encoding, width, height), messages)| ImageFormat {
+            encoding,
+            width,
+            height,
+            messages,
+        })
+        .collect();
+    report
+}
+
+pub fn inspect_run(run: &Run) -> RunReport {
+    let mut diagnostics = Diagnostics::new();
+    let mut topics: Vec<TopicReport> = run
+        .topics()
+        .filter_map(|topic| {
+            let path = run.topic_path(topic)?;
+            let stream = diagnostics.add_stream(topic.name());
+            let diagnostics = &mut diagnostics;
+            Some(match topic {
+                Topic::PointCloud2 => {
+                    inspect_topic::<PointCloud2>(topic, path, diagnostics, stream)
+                }
+                Topic::LeftImage | Topic::RightImage => {
+                    inspect_topic::<Image>(topic, path, diagnostics, stream)
+                }
+                Topic::NavSatFix => inspect_topic::<NavSatFix>(topic, path, diagnostics, stream),
+                Topic::VehicleState => {
+                    inspect_topic::<VehicleState>(topic, path, diagnostics, stream)
+                }
+                Topic::Imu => inspect_topic::<Imu>(topic, path, diagnostics, stream),
+                Topic::LeftCameraInfo | Topic::RightCameraInfo => {
+                    inspect_topic::<CameraInfo>(topic, path, diagnostics, stream)
+                }
+            })
+        })
+        .collect();
+
+    let diagnostics = diagnostics.report(0);
+    for (report, stream) in topics.iter_mut().zip(diagnostics.streams.iter()) {
+        timing(report, stream);
+    }
+    let offsets = diagnostics
+        .offsets
+        .iter()
+        .filter_map(|offset| {
+            let estimate = offset.estimate?;
+            Some(OffsetReport {
+                topic: diagnostics.names[offset.source].clone(),
+                reference: diagnostics.names[offset.reference].clone(),
+                offset_ms: millis(estimate.offset),
+                spread_ms: millis(estimate.spread),
+                pairs: estimate.pairs,
+            })
+        })
+        .collect();
+    RunReport {
+        run: run.id().to_string(),
+        topics,
+        offsets,
+    }
+}
+
+fn optional(value: Option<f64>, precision: usize) -> String {
+    value.map_or("-".to_string(), |v| format!("{:.*}", precision, v))
+}
+
+impl RunReport {
+    /// Topics whose file could not be read to the end.
+    pub fn failed(&self) -> Vec<&TopicReport> {
+        self.topics
+            .iter()
+            .filter(|topic| topic.error.is_some())
+            .collect()
+    }
+
+    /// Prints a human readable summary, one block per topic.
+    pub fn print(&self) {
+        println!("run {}", self.run);
+        for topic in self.topics.iter() {
+            println!("  {} ({})", topic.topic, topic.file);
+            println!(
+                "    messages: {}  start: {}  end: {}  duration: {:.3} s",
+                topic.messages,
+                topic.start.as_deref().unwrap_or("-"),
+                topic.end.as_deref().unwrap_or("-"),
+                topic.duration_sec
+            );
+            println!(
+                "    rate: {} Hz  jitter: {} ms  avg gap: {} ms  max gap: {} ms at {}",
+                optional(topic.rate_hz, 2),
+                optional(topic.jitter_ms, 2),
+                optional(topic.avg_gap_ms, 1),
+                optional(topic.max_gap_ms, 1),
+                topic.max_gap_at.as_deref().unwrap_or("-")
+            );
+            println!(
+                "    dropped frames: {}  out of order: {}  skipped rows: {}",
+                topic.dropped_frames, topic.out_of_order, topic.skipped_rows
+            );
+            println!("    frame_ids: {}", topic.frame_ids.join(", "));
+            for layout in topic.point_layouts.iter() {
+                let fields: Vec<String> = layout
+                    .fields
+                    .iter()
+                    .map(|f| format!("{}@{}:{}x{}", f.name, f.offset, f.datatype, f.count))
+                    .collect();
+                println!(
+                    "    fields: {}  point_step: {}  bigendian: {}  ({} clouds)",
+                    fields.join(" "),
+                    layout.point_step,
+                    layout.is_bigendian,
+                    layout.messages
+                );
+            }
+            for format in topic.image_formats.iter() {
+                println!(
+                    "    encoding: {}  {}x{}  ({} images)",
+                    format.encoding, format.width, format.height, format.messages
+                );
+            }
+            if let Some(error) = &topic.error {
+                println!("    error: {}", error);
+            }
+        }
+        for offset in self.offsets.iter() {
+            println!(
+                "  offset {} - {}: {:.1} ms (spread {:.1} ms, {} pairs)",
+                offset.topic, offset.reference, offset.offset_ms, offset.spread_ms, offset.pairs
+            );
+        }
+    }
+}
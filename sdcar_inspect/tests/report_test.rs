@@ -0,0 +1,246 @@
+extern crate chrono;
+extern crate sdcar_inspect;
+extern crate sdcar_types;
+
+use chrono::{DateTime, TimeZone, Utc};
+use sdcar_inspect::report::inspect_run;
+use sdcar_types::msg::image::Image;
+use sdcar_types::msg::point_cloud2::PointCloud2;
+use sdcar_types::session::Session;
+use sdcar_types::writer::parquet::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+const RUN: &str = "20211016041835";
+const BROKEN_RUN: &str = "20211016050000";
+
+/// A collection directory, removed when dropped, so a failing test does
+/// not leave it behind.
+struct Collection(PathBuf);
+
+impl Collection {
+    fn new(name: &str) -> Self {
+        let dir =
+            std::env::temp_dir().join(format!("sdcar_inspect_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("parquet")).unwrap();
+        Self(dir)
+    }
+
+    fn file(&self, topic: &str, run: &str) -> PathBuf {
+        self.0
+            .join("parquet")
+            .join(format!("{}_{}.parquet", topic, run))
+    }
+}
+
+impl Drop for Collection {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn stamp(ms: i64) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(1634357915000 + ms).unwrap()
+}
+
+// a 10 Hz stream missing the messages at `missing` (multiples of 100 ms)
+fn stamps_10hz(count: i64, offset_ms: i64, missing: &[i64]) -> Vec<DateTime<Utc>> {
+    (0..count)
+        .map(|i| i * 100)
+        .filter(|ms| !missing.contains(ms))
+        .map(|ms| stamp(ms + offset_ms))
+        .collect()
+}
+
+fn write_point_clouds(path: &Path, stamps: &[DateTime<Utc>]) {
+    let mut writer = PointCloud2ParquetWriter::new(path).unwrap();
+    for stamp in stamps.iter() {
+        writer
+            .write(Arc::new(PointCloud2::new(
+                stamp.timestamp() as i32,
+                stamp.timestamp_subsec_nanos(),
+                "lidar".to_string(),
+                1,
+                0,
+                Vec::new(),
+                false,
+                32,
+                0,
+                Vec::new(),
+                true,
+            )))
+            .unwrap();
+    }
+    writer.close().unwrap();
+}
+
+fn write_images(path: &Path, stamps: &[DateTime<Utc>]) {
+    let mut writer = ImageParquetWriter::new(path).unwrap();
+    for (i, stamp) in stamps.iter().enumerate() {
+        writer
+            .write(Arc::new(Image::new(
+                stamp.timestamp() as i32,
+                stamp.timestamp_subsec_nanos(),
+                "left_camera".to_string(),
+                1536,
+                2048,
+                "bgr8".to_string(),
+                false,
+                6144,
+                format!("oid{}", i),
+            )))
+            .unwrap();
+    }
+    writer.close().unwrap();
+}
+
+// a good run of a lidar and a camera 40 ms behind it that dropped three
+// frames, and a run whose imu file is not parquet
+fn collection(name: &str) -> Collection {
+    let collection = Collection::new(name);
+    write_point_clouds(
+        &collection.file("point_cloud2", RUN),
+        &stamps_10hz(50, 0, &[]),
+    );
+    write_images(
+        &collection.file("left_image", RUN),
+        &stamps_10hz(50, 40, &[1000, 1100, 3000]),
+    );
+    write_point_clouds(
+        &collection.file("point_cloud2", BROKEN_RUN),
+        &stamps_10hz(10, 0, &[]),
+    );
+    fs::write(collection.file("imu", BROKEN_RUN), b"not parquet").unwrap();
+    collection
+}
+
+#[test]
+fn inspect_run_test() {
+    let collection = collection("report");
+    let session = Session::open(&collection.0).unwrap();
+    let report = inspect_run(session.run(RUN).unwrap());
+    assert_eq!(report.run, RUN);
+    assert!(report.failed().is_empty());
+    let topics: Vec<&str> = report
+        .topics
+        .iter()
+        .map(|topic| topic.topic.as_str())
+        .collect();
+    assert_eq!(topics, vec!["point_cloud2", "left_image"]);
+
+    let lidar = &report.topics[0];
+    assert_eq!(lidar.messages, 50);
+    assert_eq!(
+        lidar.start.as_deref(),
+        Some("2021-10-16T04:18:35.000000000Z")
+    );
+    assert_eq!(lidar.dropped_frames, 0);
+    assert!(lidar.gaps.is_empty());
+    assert_eq!(lidar.frame_ids, vec!["lidar"]);
+    assert_eq!(lidar.point_layouts.len(), 1);
+    assert_eq!(
+        (
+            lidar.point_layouts[0].point_step,
+            lidar.point_layouts[0].messages
+        ),
+        (32, 50)
+    );
+
+    let camera = &report.topics[1];
+    assert_eq!(camera.messages, 47);
+    assert!((camera.duration_sec - 4.9).abs() < 1e-9);
+    assert!((camera.rate_hz.unwrap() - 10.0).abs() < 1e-9);
+    assert_eq!(camera.jitter_ms, Some(0.0));
+    assert_eq!(camera.max_gap_ms, Some(300.0));
+    assert_eq!(
+        camera.max_gap_at.as_deref(),
+        Some("2021-10-16T04:18:35.940000000Z")
+    );
+    assert_eq!(camera.dropped_frames, 3);
+    assert_eq!(camera.out_of_order, 0);
+    let gaps: Vec<(&str, f64, usize)> = camera
+        .gaps
+        .iter()
+        .map(|gap| (gap.start.as_str(), gap.duration_ms, gap.missing))
+        .collect();
+    assert_eq!(
+        gaps,
+        vec![
+            ("2021-10-16T04:18:35.940000000Z", 300.0, 2),
+            ("2021-10-16T04:18:37.940000000Z", 200.0, 1)
+        ]
+    );
+    assert_eq!(camera.image_formats.len(), 1);
+    assert_eq!(
+        (
+            camera.image_formats[0].encoding.as_str(),
+            camera.image_formats[0].width,
+            camera.image_formats[0].messages
+        ),
+        ("bgr8", 2048, 47)
+    );
+    assert!(camera.error.is_none());
+
+    // the camera runs 40 ms behind the lidar
+    assert_eq!(report.offsets.len(), 1);
+    let offset = &report.offsets[0];
+    assert_eq!(
+        (offset.topic.as_str(), offset.reference.as_str()),
+        ("left_image", "point_cloud2")
+    );
+    assert_eq!(offset.offset_ms, 40.0);
+    assert_eq!(offset.pairs, 47);
+
+    let broken = inspect_run(session.run(BROKEN_RUN).unwrap());
+    let failed = broken.failed();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].topic, "imu");
+    assert_eq!(failed[0].messages, 0);
+    assert_eq!(broken.topics[0].messages, 10);
+}
+
+#[test]
+fn inspect_exit_status_test() {
+    let collection = collection("exit_status");
+    let inspect = |runs: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_sdcar_inspect"))
+            .arg(&collection.0)
+            .args(runs)
+            .output()
+            .unwrap()
+    };
+
+    let output = inspect(&[RUN]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("rate: 10.00 Hz  jitter: 0.00 ms  avg gap: 106.5 ms  max gap: 300.0 ms at 2021-10-16T04:18:35.940000000Z"), "{}", stdout);
+    assert!(
+        stdout.contains("dropped frames: 3  out of order: 0  skipped rows: 0"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("offset left_image - point_cloud2: 40.0 ms"),
+        "{}",
+        stdout
+    );
+
+    // a topic that fails to read fails the check, with every run still reported
+    let output = inspect(&[]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains(&format!("run {}", RUN)) && stdout.contains(&format!("run {}", BROKEN_RUN)),
+        "{}",
+        stdout
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(&format!("imu_{}.parquet", BROKEN_RUN)),
+        "{}",
+        stderr
+    );
+}
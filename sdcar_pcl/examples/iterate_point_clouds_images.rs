@@ -4,9 +4,9 @@ extern crate sdcar_types;
 use sdcar_pcl::prelude::*;
 use sdcar_types::reader::parquet::*;
 
-fn print_parquet(pc2_file: &'static str, image_file: &'static str, image_data_path: &'static str) {
-    let point_cloud2_row_iter = PointCloud2ParquetRowIter::<'static>::new(pc2_file);
-    let image_row_iter = ImageParquetRowIter::<'static>::new(image_file);
+fn print_parquet(pc2_file: &str, image_file: &str, image_data_path: &str) {
+    let point_cloud2_row_iter = PointCloud2ParquetRowIter::new(pc2_file);
+    let image_row_iter = ImageParquetRowIter::new(image_file);
     let iter = PointCloudXYZIntensityImageRowIter::new(
         point_cloud2_row_iter,
         image_row_iter,
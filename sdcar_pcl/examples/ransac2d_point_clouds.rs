@@ -18,15 +18,15 @@ fn inlier_parquet(path: &str) {
 
     for row in iter {
         let row_start = Instant::now();
-        let pc = PointCloud2::from(row);
+        let pc = PointCloud2::try_from(row).unwrap();
         let row_loaded = Instant::now();
         println!(
             "{:?} {} {} {} {} {} {} {:?} {} {} {} data.len() = {} {}",
             row_loaded.duration_since(row_start),
-            pc.stamp_sec,
-            pc.stamp_nanosec,
-            pc.timestamp,
-            pc.frame_id,
+            pc.header.stamp.sec(),
+            pc.header.stamp.nanosec(),
+            pc.header.timestamp(),
+            pc.header.frame_id,
             pc.height,
             pc.width,
             pc.fields,
@@ -0,0 +1,211 @@
+extern crate nalgebra;
+extern crate rand;
+extern crate sdcar_pcl;
+extern crate sdcar_types;
+
+use nalgebra::Vector3;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sdcar_pcl::filters::crop_box::*;
+use sdcar_pcl::prelude::*;
+use sdcar_pcl::search::kdtree::KdTree;
+use sdcar_types::msg::point_cloud2::{PointCloud2, PointField};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const NUM_CLOUDS: usize = 10;
+const POINTS_PER_CLOUD: usize = 128 * 1024;
+const POINT_STEP: usize = 32;
+
+/// A scan of a ground plane with a few boxes standing on it, roughly what
+/// a lidar on a car sees.
+fn point_cloud2(seed: u64) -> PointCloud2 {
+    let fields = vec![
+        PointField::new("x".to_string(), 0, PointField::FLOAT32, 1),
+        PointField::new("y".to_string(), 4, PointField::FLOAT32, 1),
+        PointField::new("z".to_string(), 8, PointField::FLOAT32, 1),
+        PointField::new("intensity".to_string(), 16, PointField::FLOAT32, 1),
+    ];
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut data = Vec::with_capacity(POINTS_PER_CLOUD * POINT_STEP);
+    for i in 0..POINTS_PER_CLOUD {
+        let (x, y, z) = if i % 10 == 0 {
+            let (cx, cy) = [(8.0, 2.0), (-12.0, -3.0), (20.0, 6.0)][i % 3];
+            (
+                cx + rng.gen_range(-1.0..1.0),
+                cy + rng.gen_range(-1.0..1.0),
+                rng.gen_range(0.2..1.8),
+            )
+        } else {
+            (
+                rng.gen_range(-40.0..40.0),
+                rng.gen_range(-20.0..20.0),
+                rng.gen_range(-0.05..0.05),
+            )
+        };
+        let mut point = [0u8; POINT_STEP];
+        for (offset, value) in [(0, x), (4, y), (8, z), (16, rng.gen_range(0.0..100.0))] {
+            point[offset..offset + 4].copy_from_slice(&(value as f32).to_le_bytes());
+        }
+        data.extend_from_slice(&point);
+    }
+    PointCloud2::new(
+        1634357915,
+        0,
+        "lidar".to_string(),
+        1,
+        POINTS_PER_CLOUD as u32,
+        fields,
+        false,
+        POINT_STEP as u32,
+        (POINTS_PER_CLOUD * POINT_STEP) as u32,
+        data,
+        true,
+    )
+}
+
+fn report(name: &str, elapsed: Duration, points: usize) {
+    let secs = elapsed.as_secs_f64();
+    println!(
+        "{:<32} {:>8.3} s {:>12.1} Mpoints/s",
+        name,
+        secs,
+        points as f64 / secs / 1e6
+    );
+}
+
+fn bench_point_cloud(
+    pc2s: &[Arc<PointCloud2>],
+    leaf_count: Vector3<usize>,
+    min_vec: MinVec,
+    max_vec: MaxVec,
+) {
+    let start = Instant::now();
+    let clouds: Vec<PointCloudXYZIntensity> = pc2s
+        .iter()
+        .map(|pc2| PointCloudXYZIntensity::new(pc2.clone()))
+        .collect();
+    report(
+        "PointCloud decode",
+        start.elapsed(),
+        NUM_CLOUDS * POINTS_PER_CLOUD,
+    );
+
+    let start = Instant::now();
+    let mut cropped = Vec::new();
+    for cloud in clouds.iter() {
+        let point_indices_ref =
+            point_indices_bounding_box(cloud.point_indices_ref(), min_vec, max_vec, false);
+        cropped.push(PointCloudXYZIntensity::from(point_indices_ref));
+    }
+    report(
+        "PointCloud crop",
+        start.elapsed(),
+        NUM_CLOUDS * POINTS_PER_CLOUD,
+    );
+
+    let start = Instant::now();
+    let points: usize = cropped.iter().map(|cloud| cloud.num_points()).sum();
+    let voxels: Vec<PointCloudXYZIntensity> = cropped
+        .iter()
+        .map(|cloud| filter_points(cloud, leaf_count))
+        .collect();
+    report("PointCloud voxel grid", start.elapsed(), points);
+
+    let start = Instant::now();
+    let points: usize = voxels.iter().map(|cloud| cloud.num_points()).sum();
+    let outliers: Vec<PointCloudXYZIntensityRef> = voxels
+        .iter()
+        .map(|cloud| segment_planes_ransac(cloud, 20, 0.2).1)
+        .collect();
+    report("PointCloud ransac", start.elapsed(), points);
+
+    let start = Instant::now();
+    let points: usize = outliers.iter().map(|cloud| cloud.num_points()).sum();
+    let mut num_clusters = 0;
+    for cloud in outliers.iter() {
+        let kdtree = Arc::new(KdTree::from(cloud.point_indices_ref()));
+        // clusters are capped as the recursion of extract overflows the
+        // stack on larger ones
+        let extract = ClustersExtractXYZIntensity::new(cloud.clone(), 0.5, 10, 500, kdtree);
+        num_clusters += extract.extract().len();
+    }
+    report(
+        &format!("PointCloud clusters ({})", num_clusters),
+        start.elapsed(),
+        points,
+    );
+}
+
+fn bench_point_cloud_soa(
+    pc2s: &[Arc<PointCloud2>],
+    leaf_count: Vector3<usize>,
+    min_vec: MinVec,
+    max_vec: MaxVec,
+) {
+    let start = Instant::now();
+    let clouds: Vec<PointCloudSoa> = pc2s
+        .iter()
+        .map(|pc2| PointCloudSoa::new(pc2.clone()))
+        .collect();
+    report(
+        "PointCloudSoa decode",
+        start.elapsed(),
+        NUM_CLOUDS * POINTS_PER_CLOUD,
+    );
+
+    let start = Instant::now();
+    let cropped: Vec<PointCloudSoaView> = clouds
+        .iter()
+        .map(|cloud| crop_box_soa(&cloud.view(), min_vec, max_vec, false))
+        .collect();
+    report(
+        "PointCloudSoa crop",
+        start.elapsed(),
+        NUM_CLOUDS * POINTS_PER_CLOUD,
+    );
+
+    let start = Instant::now();
+    let points: usize = cropped.iter().map(|view| view.len()).sum();
+    let voxels: Vec<PointCloudSoa> = cropped
+        .iter()
+        .map(|view| filter_points_soa(view, leaf_count))
+        .collect();
+    report("PointCloudSoa voxel grid", start.elapsed(), points);
+
+    let start = Instant::now();
+    let points: usize = voxels.iter().map(|cloud| cloud.len()).sum();
+    let outliers: Vec<PointCloudSoaView> = voxels
+        .iter()
+        .map(|cloud| segment_planes_ransac_soa(&cloud.view(), 20, 0.2).1)
+        .collect();
+    report("PointCloudSoa ransac", start.elapsed(), points);
+
+    let start = Instant::now();
+    let points: usize = outliers.iter().map(|view| view.len()).sum();
+    let num_clusters: usize = outliers
+        .iter()
+        .map(|view| extract_clusters_soa(view, 0.5, 10, 500).len())
+        .sum();
+    report(
+        &format!("PointCloudSoa clusters ({})", num_clusters),
+        start.elapsed(),
+        points,
+    );
+}
+
+/// Runs decoding, cropping, voxel grid down sampling, RANSAC plane
+/// segmentation and clustering over generated scans held as a `PointCloud`
+/// of reference counted points and as a columnar `PointCloudSoa`. Run with
+/// `--release`.
+fn main() {
+    let pc2s: Vec<Arc<PointCloud2>> = (0..NUM_CLOUDS as u64)
+        .map(|seed| Arc::new(point_cloud2(seed)))
+        .collect();
+    let leaf_count = Vector3::<usize>::new(400, 200, 20);
+    let min_vec = MinVec::new(-30.0, -10.0, -1.0);
+    let max_vec = MaxVec::new(30.0, 10.0, 3.0);
+
+    bench_point_cloud(&pc2s, leaf_count, min_vec, max_vec);
+    bench_point_cloud_soa(&pc2s, leaf_count, min_vec, max_vec);
+}
@@ -1,6 +1,8 @@
 use crate::bounding_box::*;
+use crate::indices::*;
 use crate::point::*;
 use crate::point_cloud::*;
+use crate::point_cloud_soa::*;
 
 use crate::filters::{MaxVec, MinVec};
 
@@ -42,3 +44,54 @@ where
 
     Arc::new(cropped_point_indices)
 }
+
+/// `point_indices_bounding_box` over a whole cloud, returning the positions
+/// of the points within, or with `drop` outside of, the box.
+pub fn crop_box_indices<T, U>(
+    cloud: &PointCloud<Point<T, U>>,
+    min_vec: MinVec,
+    max_vec: MaxVec,
+    drop: bool, // if true the the bb contained points are excluded otherwise included
+) -> Indices
+where
+    T: PointMeasurable,
+    U: PointDataAccess,
+{
+    let point_min = Point::<T, U>::new(T::new(min_vec.x, min_vec.y, min_vec.z), None);
+    let point_max = Point::<T, U>::new(T::new(max_vec.x, max_vec.y, max_vec.z), None);
+
+    let bb = BoundingBox::<T, U>::new(None, None, None, point_min, point_max);
+    let point_indices_ref = cloud.point_indices_ref();
+    let indices: Vec<usize> = (0..point_indices_ref.len())
+        .into_par_iter()
+        .filter(|&i| bb.bounds(&point_indices_ref[i]) != drop)
+        .collect();
+
+    Indices::from_sorted(indices)
+}
+
+/// The points of `view` within, or with `drop` outside of, the box from
+/// `min_vec` to `max_vec`, as a view of the same cloud.
+pub fn crop_box_soa<'a>(
+    view: &PointCloudSoaView<'a>,
+    min_vec: MinVec,
+    max_vec: MaxVec,
+    drop: bool, // if true the the bb contained points are excluded otherwise included
+) -> PointCloudSoaView<'a> {
+    let cloud = view.cloud();
+    let (x, y, z) = (cloud.x(), cloud.y(), cloud.z());
+    let indices: Vec<usize> = view
+        .indices()
+        .as_slice()
+        .par_iter()
+        .copied()
+        .filter(|&i| {
+            let inbb = (min_vec.x <= x[i] && x[i] <= max_vec.x)
+                && (min_vec.y <= y[i] && y[i] <= max_vec.y)
+                && (min_vec.z <= z[i] && z[i] <= max_vec.z);
+            inbb != drop
+        })
+        .collect();
+
+    PointCloudSoaView::new(cloud, Indices::from_sorted(indices))
+}
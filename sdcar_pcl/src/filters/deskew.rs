@@ -0,0 +1,180 @@
+use crate::point::*;
+use crate::point_cloud::*;
+
+use sdcar_types::kinematics::{normalize_angle, Pose2D};
+use sdcar_types::msg::vehicle_state::VehicleState;
+use sdcar_types::{Error, Result};
+
+use chrono::{DateTime, Duration, Utc};
+use nalgebra::{Isometry3, Point3, Vector3};
+use rayon::prelude::*;
+
+use std::sync::Arc;
+
+/// How the vehicle moves during a sweep.
+pub trait EgoMotion: Sync {
+  /// The pose of the vehicle `t` seconds after `stamp`, relative to its
+  /// pose at `stamp`.
+  fn motion(&self, stamp: DateTime<Utc>, t: f32) -> Result<Isometry3<f32>>;
+}
+
+// a planar pose as a 3D isometry turning about z
+fn planar(x: f32, y: f32, yaw: f32) -> Isometry3<f32> {
+  Isometry3::new(Vector3::new(x, y, 0.0), Vector3::z() * yaw)
+}
+
+/// Motion at a constant speed and yaw rate, along an arc, as measured by a
+/// `VehicleState` close to the sweep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConstantVelocity {
+  v_mps: f32,
+  yaw_rate: f32,
+}
+
+impl ConstantVelocity {
+  /// `v_mps` in metres per second and `yaw_rate` in radians per second,
+  /// positive turning left.
+  pub fn new(v_mps: f32, yaw_rate: f32) -> Self {
+    Self {v_mps, yaw_rate}
+  }
+}
+
+/// Takes `v_mps` and `yaw_rate_radians` as recorded, so the motion turns
+/// left for a positive yaw rate only if the recording takes turning left as
+/// positive, and assumes the yaw rate is in degrees per second as
+/// `yaw_rate_radians` does. Use `new` where the sign is known.
+impl From<&VehicleState> for ConstantVelocity {
+  fn from(state: &VehicleState) -> Self {
+    Self::new(state.v_mps(), state.yaw_rate_radians())
+  }
+}
+
+impl EgoMotion for ConstantVelocity {
+  fn motion(&self, _stamp: DateTime<Utc>, t: f32) -> Result<Isometry3<f32>> {
+    let yaw = self.yaw_rate * t;
+    let distance = self.v_mps * t;
+    // the chord of the arc, or a straight line when barely turning
+    let (x, y) = if yaw.abs() < 1e-6 {
+      (distance, 0.0)
+    } else {
+      let radius = self.v_mps / self.yaw_rate;
+      (radius * yaw.sin(), radius * (1.0 - yaw.cos()))
+    };
+    Ok(planar(x, y, yaw))
+  }
+}
+
+/// Motion interpolated between timestamped poses, such as the track
+/// `DeadReckoning` integrates from `VehicleState` messages.
+#[derive(Debug, Clone)]
+pub struct PoseTrajectory {
+  poses: Vec<Pose2D>,
+}
+
+impl PoseTrajectory {
+  /// `poses` in any order; they are sorted by timestamp.
+  pub fn new(mut poses: Vec<Pose2D>) -> Self {
+    poses.sort_by_key(|pose| pose.timestamp);
+    Self {poses}
+  }
+
+  /// The pose at `timestamp`, linearly interpolated between the poses
+  /// around it.
+  pub fn pose(&self, timestamp: DateTime<Utc>) -> Result<Pose2D> {
+    let next = self.poses.partition_point(|pose| pose.timestamp < timestamp);
+    if next < self.poses.len() && self.poses[next].timestamp == timestamp {
+      return Ok(self.poses[next]);
+    }
+    if next == 0 || next == self.poses.len() {
+      return Err(Error::Deskew(match (self.poses.first(), self.poses.last()) {
+        (Some(first), Some(last)) => format!("{} is outside the trajectory from {} to {}", timestamp, first.timestamp, last.timestamp),
+        _ => "the trajectory has no poses".to_string(),
+      }));
+    }
+    let (a, b) = (&self.poses[next - 1], &self.poses[next]);
+    let span = (b.timestamp - a.timestamp).num_nanoseconds().unwrap_or(i64::MAX) as f64;
+    let f = (timestamp - a.timestamp).num_nanoseconds().unwrap_or(0) as f64 / span;
+    Ok(Pose2D {
+      timestamp,
+      x: a.x + (b.x - a.x) * f,
+      y: a.y + (b.y - a.y) * f,
+      yaw: normalize_angle(a.yaw + normalize_angle(b.yaw - a.yaw) * f),
+    })
+  }
+}
+
+impl EgoMotion for PoseTrajectory {
+  fn motion(&self, stamp: DateTime<Utc>, t: f32) -> Result<Isometry3<f32>> {
+    let at_stamp = self.pose(stamp)?;
+    let at_t = self.pose(stamp + Duration::nanoseconds((t as f64 * 1e9) as i64))?;
+    let relative = at_stamp.isometry().inverse() * at_t.isometry();
+    let t = relative.translation.vector;
+    Ok(planar(t.x as f32, t.y as f32, relative.rotation.angle() as f32))
+  }
+}
+
+/// Removes the smear of a lidar sweep taken while moving by moving each
+/// point to where it would have been measured at the reference time, from
+/// its time field and the motion of the vehicle.
+#[derive(Debug, Clone)]
+pub struct Deskew<M: EgoMotion> {
+  motion: M,
+  sensor_to_vehicle: Isometry3<f32>,
+  reference_time: f32,
+}
+
+impl<M: EgoMotion> Deskew<M> {
+  pub fn new(motion: M) -> Self {
+    Self {motion, sensor_to_vehicle: Isometry3::identity(), reference_time: 0.0}
+  }
+
+  /// The mounting of the lidar on the vehicle, as the motion is that of
+  /// the vehicle's origin. Identity unless given.
+  pub fn with_sensor_to_vehicle(mut self, sensor_to_vehicle: Isometry3<f32>) -> Self {
+    self.sensor_to_vehicle = sensor_to_vehicle;
+    self
+  }
+
+  /// Seconds after the cloud's stamp to deskew to, such as the sweep's
+  /// duration for drivers stamping the start of a sweep and points
+  /// wanted at its end. The stamp itself unless given.
+  pub fn with_reference_time(mut self, reference_time: f32) -> Self {
+    self.reference_time = reference_time;
+    self
+  }
+
+  /// Moves every point of `cloud` to the reference time, keeping its
+  /// frame id and time field. The sweep is stamped by the `PointCloud2` the
+  /// cloud was decoded from, so clouds built from points fail; give their
+  /// stamp to `filter_at`. Fails if the motion is unknown at the time of a
+  /// point.
+  pub fn filter<U>(&self, cloud: &PointCloud<PointXYZ<U>>) -> Result<PointCloud<PointXYZ<U>>>
+  where U: PointDataAccess + HasPointTime
+  {
+    let stamp = cloud.pc2_msg()
+      .map(|pc2_msg| pc2_msg.header.timestamp())
+      .ok_or_else(|| Error::Deskew("the cloud has no PointCloud2 to take the sweep stamp from".to_string()))?;
+    self.filter_at(cloud, stamp)
+  }
+
+  /// As `filter`, for a sweep stamped `stamp`.
+  pub fn filter_at<U>(&self, cloud: &PointCloud<PointXYZ<U>>, stamp: DateTime<Utc>) -> Result<PointCloud<PointXYZ<U>>>
+  where U: PointDataAccess + HasPointTime
+  {
+    let vehicle_to_sensor = self.sensor_to_vehicle.inverse();
+    // the sensor's motion from the reference time
+    let reference = self.motion.motion(stamp, self.reference_time)?.inverse();
+
+    let point_indices = cloud.point_indices_ref()
+      .par_iter()
+      .map(|p| -> Result<Arc<PointXYZ<U>>> {
+        let time = p.data().as_ref().map(|data| data.time()).unwrap_or(0.0);
+        let motion = vehicle_to_sensor * reference * self.motion.motion(stamp, time)? * self.sensor_to_vehicle;
+        let (x, y, z) = p.point().tuple();
+        let moved = motion * Point3::new(x, y, z);
+        Ok(Arc::new(PointXYZ::<U>::new(XYZ::new(moved.x, moved.y, moved.z), p.data().clone())))
+      })
+      .collect::<Result<PointIndices<PointXYZ<U>>>>()?;
+    Ok(cloud.with_point_indices_ref(Arc::new(point_indices)))
+  }
+}
@@ -1,7 +1,9 @@
 pub mod voxel_grid;
 pub mod voxel_grid_par;
 pub mod crop_box;
+pub mod deskew;
 
+use crate::indices::*;
 use crate::point::*;
 use crate::point_cloud::*;
 
@@ -35,6 +37,9 @@ pub type MaxVec = Vector3<f32>;
 // }
 
 
+/// The points of `cloud` not among `inliers_ref`. Points are matched by
+/// value, so a return that equals an inlier is dropped with it; use
+/// `outlier_indices` to tell such returns apart.
 pub fn outlier_filter<T,U> 
 (
     cloud: &PointCloud<Point<T,U>>,
@@ -60,6 +65,12 @@ where T:PointMeasurable,
 
 }
 
+/// `outlier_filter` by position: the points of a cloud of `num_points` not
+/// in `inliers`.
+pub fn outlier_indices(num_points: usize, inliers: &Indices) -> Indices {
+  inliers.complement(num_points)
+}
+
 
 pub fn get_min_max<T,U> (in_cloud: &PointCloud<Point<T,U>>) -> (MinVec, MaxVec) 
 where T:PointMeasurable, 
@@ -2,7 +2,10 @@ extern crate nalgebra;
 
 use crate::point_cloud::*;
 use crate::point::*;
-use crate::filters::get_min_max;
+use crate::point_cloud_soa::*;
+use crate::filters::{get_min_max, MaxVec, MinVec};
+
+use sdcar_types::{Error, Result};
 
 use rayon::prelude::*;
 
@@ -25,15 +28,9 @@ impl PartialOrd for PointBox<PointXYZIntensity> {
 }
 
 
-// used this routine https://github.com/ucanbizon/downsampling-point-cloud/blob/master/downsample.cpp
-pub fn filter_points(in_cloud: &PointCloudXYZIntensity, leaf_count: Vector3<usize>) -> PointCloudXYZIntensity {
-  let mm_pair = get_min_max::<XYZ,Intensity>(in_cloud);
-  let minp = mm_pair.0;
-  let maxp = mm_pair.1;
-
-  
-  // let leaf_count = Vector3::<usize>::new(0,0,0);
-
+/// The leaf of a point among `leaf_count` leaves spanning `minp` to `maxp`,
+/// the index `filter_points` and `filter_points_soa` group points by.
+fn leaf_indexer(minp: MinVec, maxp: MaxVec, leaf_count: Vector3<usize>) -> impl Fn(f32, f32, f32) -> i32 + Sync {
   let leaf_size = Vector3::<f32>::new(
     (maxp.x - minp.x)/leaf_count.x as f32,
     (maxp.y - minp.y)/leaf_count.y as f32,
@@ -58,15 +55,27 @@ pub fn filter_points(in_cloud: &PointCloudXYZIntensity, leaf_count: Vector3<usiz
 
   let divb_mul = Vector3::<i32>::new (1, divb.x, divb.x * divb.y);
 
+  move |x, y, z| {
+    let ijk0 = ((x * inv_leaf_size.x) - minb.x as f32).floor() as i32;
+    let ijk1 = ((y * inv_leaf_size.y) - minb.y as f32).floor() as i32;
+    let ijk2 = ((z * inv_leaf_size.z) - minb.z as f32).floor() as i32;
+    ijk0 * divb_mul.x + ijk1 * divb_mul.y + ijk2 * divb_mul.z
+  }
+}
+
+// used this routine https://github.com/ucanbizon/downsampling-point-cloud/blob/master/downsample.cpp
+/// Leaves split the extent of `in_cloud` `leaf_count` times, so their size
+/// changes with each cloud; `VoxelGrid` downsamples with a fixed leaf size.
+pub fn filter_points(in_cloud: &PointCloudXYZIntensity, leaf_count: Vector3<usize>) -> PointCloudXYZIntensity {
+  let mm_pair = get_min_max::<XYZ,Intensity>(in_cloud);
+  let leaf_index = leaf_indexer(mm_pair.0, mm_pair.1, leaf_count);
+
   let point_indices_ref = in_cloud.point_indices_ref();
   let bb_indices_par_iter = Box::new(point_indices_ref
      .par_iter()
      .cloned()
      .map(|p| -> PointBox<PointXYZIntensity>{
-      let ijk0 = ((p.point().x() * inv_leaf_size.x) - minb.x as f32).floor() as i32;
-      let ijk1 = ((p.point().y() * inv_leaf_size.y) - minb.y as f32).floor() as i32;
-      let ijk2 = ((p.point().z() * inv_leaf_size.z) - minb.z as f32).floor() as i32;
-      let box_idx = ijk0 * divb_mul.x + ijk1 * divb_mul.y + ijk2 * divb_mul.z;
+      let box_idx = leaf_index(p.point().x(), p.point().y(), p.point().z());
       PointBox::<PointXYZIntensity> {point_ref: p, box_idx} 
       }));
 
@@ -106,4 +115,178 @@ pub fn filter_points(in_cloud: &PointCloudXYZIntensity, leaf_count: Vector3<usiz
     cp = i;
   }
   PointCloudXYZIntensity::from(out_cloud_indices)
-}
\ No newline at end of file
+}
+/// `filter_points` over a view of a `PointCloudSoa`: the centroid of the
+/// points of each of `leaf_count` leaves spanning the view, keeping the
+/// intensity of the last point of the leaf as `filter_points` does. The
+/// output keeps the message and frame of the viewed cloud.
+pub fn filter_points_soa(view: &PointCloudSoaView, leaf_count: Vector3<usize>) -> PointCloudSoa {
+  if view.is_empty() {
+    return view.cloud().empty_like();
+  }
+  let (minp, maxp) = view.min_max();
+  let leaf_index = leaf_indexer(minp, maxp, leaf_count);
+
+  let cloud = view.cloud();
+  let (x, y, z, intensity) = (cloud.x(), cloud.y(), cloud.z(), cloud.intensity());
+
+  // (box_idx, point index) pairs rather than reference counted points
+  let mut bb_indices: Vec<(i32, usize)> = view.indices()
+    .as_slice()
+    .par_iter()
+    .map(|&i| (leaf_index(x[i], y[i], z[i]), i))
+    .collect();
+  bb_indices.par_sort_by(|a, b| b.0.cmp(&a.0));
+
+  let mut out_cloud = cloud.empty_like();
+  let num_points = bb_indices.len();
+  let mut cp:usize = 0;
+  while cp < num_points {
+    let (box_idx, first) = bb_indices[cp];
+    let mut centroid = Vector3::<f32>::new(x[first], y[first], z[first]);
+    let mut last = first;
+
+    let mut i = cp + 1;
+    while i < num_points && box_idx == bb_indices[i].0 {
+      last = bb_indices[i].1;
+      centroid.x += x[last];
+      centroid.y += y[last];
+      centroid.z += z[last];
+      i+=1;
+    }
+    centroid /= (i - cp) as f32;
+
+    out_cloud.push(centroid.x, centroid.y, centroid.z, intensity[last]);
+    cp = i;
+  }
+  out_cloud
+}
+
+/// The point `VoxelGrid` keeps for the points of a voxel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoxelMode {
+  /// The centroid of the points, with their data averaged.
+  Centroid,
+  /// The point nearest to the centroid, as it is.
+  NearestToCentroid,
+  /// The point first in the cloud, as it is.
+  First,
+}
+
+/// Downsamples a cloud of any point type to one point per voxel of a
+/// fixed, metric, leaf size, so the resolution does not change with the
+/// extent of each cloud as with `filter_points`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoxelGrid {
+  leaf_size: Vector3<f32>,
+  mode: VoxelMode,
+  min_points_per_voxel: usize,
+}
+
+impl VoxelGrid {
+  /// Voxels of `leaf_size` metres along x, y and z, aligned to the
+  /// origin, keeping centroids of voxels with any points. Fails with
+  /// `Error::InvalidParameter` unless each size is positive and finite.
+  pub fn try_new(leaf_size: Vector3<f32>) -> Result<Self> {
+    if !leaf_size.iter().all(|size| size.is_finite() && *size > 0.0) {
+      return Err(Error::InvalidParameter(format!("voxel leaf size {:?} must be positive and finite", leaf_size.as_slice())));
+    }
+    Ok(Self {leaf_size, mode: VoxelMode::Centroid, min_points_per_voxel: 1})
+  }
+
+  pub fn with_mode(mut self, mode: VoxelMode) -> Self {
+    self.mode = mode;
+    self
+  }
+
+  /// Drops voxels of fewer points, such as those of spurious returns.
+  pub fn with_min_points_per_voxel(mut self, min_points_per_voxel: usize) -> Self {
+    self.min_points_per_voxel = min_points_per_voxel;
+    self
+  }
+
+  pub fn leaf_size(&self) -> Vector3<f32> {
+    self.leaf_size
+  }
+  pub fn mode(&self) -> VoxelMode {
+    self.mode
+  }
+  pub fn min_points_per_voxel(&self) -> usize {
+    self.min_points_per_voxel
+  }
+
+  /// One point for each voxel of `in_cloud`, in the order of their voxels,
+  /// keeping its frame id. Points with coordinates that are not finite are
+  /// dropped.
+  pub fn filter<T,U>(&self, in_cloud: &PointCloud<Point<T,U>>) -> PointCloud<Point<T,U>>
+  where T: PointMeasurable, U: PointDataAccess
+  {
+    let point_indices_ref = in_cloud.point_indices_ref();
+    let inv_leaf_size = self.leaf_size.map(|size| 1.0 / size);
+
+    // (voxel, point index) pairs, sorted so the points of a voxel are
+    // together and in cloud order
+    let mut voxel_indices: Vec<([i64;3], usize)> = point_indices_ref
+      .par_iter()
+      .enumerate()
+      .filter(|(_, p)| p.point().array().iter().all(|value| value.is_finite()))
+      .map(|(i, p)| {
+        let [x, y, z] = p.point().array();
+        let voxel = [
+          (x * inv_leaf_size.x).floor() as i64,
+          (y * inv_leaf_size.y).floor() as i64,
+          (z * inv_leaf_size.z).floor() as i64];
+        (voxel, i)
+      })
+      .collect();
+    voxel_indices.par_sort_unstable();
+
+    let mut voxels = Vec::new();
+    let mut cp:usize = 0;
+    while cp < voxel_indices.len() {
+      let mut i = cp + 1;
+      while i < voxel_indices.len() && voxel_indices[i].0 == voxel_indices[cp].0 {
+        i+=1;
+      }
+      if i - cp >= self.min_points_per_voxel {
+        voxels.push(cp..i);
+      }
+      cp = i;
+    }
+
+    let out_cloud_indices: PointIndices<Point<T,U>> = voxels
+      .into_par_iter()
+      .map(|voxel| {
+        let points: Vec<&Arc<Point<T,U>>> = voxel_indices[voxel].iter().map(|&(_, i)| &point_indices_ref[i]).collect();
+        self.voxel_point(&points)
+      })
+      .collect();
+    in_cloud.with_point_indices_ref(Arc::new(out_cloud_indices))
+  }
+
+  fn voxel_point<T,U>(&self, points: &[&Arc<Point<T,U>>]) -> Arc<Point<T,U>>
+  where T: PointMeasurable, U: PointDataAccess
+  {
+    if self.mode == VoxelMode::First {
+      return points[0].clone();
+    }
+    let centroid = points
+      .iter()
+      .fold(Vector3::<f32>::zeros(), |sum, p| sum + Vector3::from(p.point().array()))
+      / points.len() as f32;
+
+    match self.mode {
+      VoxelMode::NearestToCentroid => points
+        .iter()
+        .map(|p| (Vector3::from(p.point().array()) - centroid).norm_squared())
+        .zip(points.iter())
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, p)| (*p).clone())
+        .unwrap(),
+      _ => {
+        let data: Vec<U> = points.iter().filter_map(|p| p.data().clone()).collect();
+        Arc::new(Point::<T,U>::new(T::new(centroid.x, centroid.y, centroid.z), U::average(&data)))
+      }
+    }
+  }
+}
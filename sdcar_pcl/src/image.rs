@@ -60,7 +60,7 @@ impl From<Image> for ImageFile{
   fn from(image: Image) -> Self{
 
     let image_oid = image.image_oid;
-    let timestamp = image.timestamp;
+    let timestamp = image.header.timestamp();
 
     ImageFile::new(ImageKind::WEBP, image_oid, image.height as usize, image.width as usize,EncodingKind::BGR8, timestamp)
   }
@@ -69,7 +69,7 @@ impl From<Image> for ImageFile{
 impl From<ImageRef> for ImageFile{
   fn from(image: ImageRef) -> Self{
     let image_oid = image.image_oid.to_owned();
-    let timestamp = image.timestamp.to_owned();
+    let timestamp = image.header.timestamp();
     ImageFile::new(ImageKind::WEBP, image_oid, image.height as usize, image.width as usize,EncodingKind::BGR8, timestamp)
   }
 }
\ No newline at end of file
@@ -0,0 +1,117 @@
+use std::iter::FromIterator;
+use std::slice::Iter;
+
+/// A subset of a cloud as the positions of its points in the cloud, kept
+/// sorted and without duplicates, like PCL's `PointIndices`. Points are
+/// identified by where they are rather than by their coordinates, so two
+/// returns at the same XYZ stay distinct.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Indices {
+  indices: Vec<usize>,
+}
+
+impl Indices {
+  pub fn new() -> Self {
+    Self {indices: Vec::new()}
+  }
+
+  /// Every point of a cloud of `num_points`.
+  pub fn all(num_points: usize) -> Self {
+    Self {indices: (0..num_points).collect()}
+  }
+
+  /// Wraps indices already in ascending order without duplicates, as
+  /// filters scanning a cloud in order produce them.
+  pub fn from_sorted(indices: Vec<usize>) -> Self {
+    debug_assert!(indices.windows(2).all(|w| w[0] < w[1]), "indices not sorted and unique");
+    Self {indices}
+  }
+
+  pub fn len(&self) -> usize {
+    self.indices.len()
+  }
+  pub fn is_empty(&self) -> bool {
+    self.indices.is_empty()
+  }
+  pub fn as_slice(&self) -> &[usize] {
+    &self.indices
+  }
+  pub fn into_vec(self) -> Vec<usize> {
+    self.indices
+  }
+  pub fn iter(&self) -> Iter<'_, usize> {
+    self.indices.iter()
+  }
+  pub fn contains(&self, index: usize) -> bool {
+    self.indices.binary_search(&index).is_ok()
+  }
+
+  /// The points of a cloud of `num_points` not in this subset.
+  pub fn complement(&self, num_points: usize) -> Self {
+    Self::all(num_points).difference(self)
+  }
+
+  pub fn union(&self, other: &Indices) -> Self {
+    self.merge(other, true, true, true)
+  }
+
+  pub fn intersection(&self, other: &Indices) -> Self {
+    self.merge(other, false, true, false)
+  }
+
+  /// The points of this subset not in `other`.
+  pub fn difference(&self, other: &Indices) -> Self {
+    self.merge(other, true, false, false)
+  }
+
+  // a single pass over both sorted lists, keeping indices only in self,
+  // in both or only in other as asked
+  fn merge(&self, other: &Indices, only_self: bool, both: bool, only_other: bool) -> Self {
+    let (a, b) = (&self.indices, &other.indices);
+    let mut indices = Vec::with_capacity(a.len().max(b.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+      if a[i] < b[j] {
+        if only_self { indices.push(a[i]); }
+        i += 1;
+      } else if b[j] < a[i] {
+        if only_other { indices.push(b[j]); }
+        j += 1;
+      } else {
+        if both { indices.push(a[i]); }
+        i += 1;
+        j += 1;
+      }
+    }
+    if only_self {
+      indices.extend_from_slice(&a[i..]);
+    }
+    if only_other {
+      indices.extend_from_slice(&b[j..]);
+    }
+    Self {indices}
+  }
+}
+
+impl From<Vec<usize>> for Indices {
+  fn from(mut indices: Vec<usize>) -> Self {
+    indices.sort_unstable();
+    indices.dedup();
+    Self {indices}
+  }
+}
+
+impl FromIterator<usize> for Indices {
+  fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+    Self::from(iter.into_iter().collect::<Vec<usize>>())
+  }
+}
+
+impl<'a> IntoIterator for &'a Indices {
+  type Item = &'a usize;
+  type IntoIter = Iter<'a, usize>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.indices.iter()
+  }
+}
@@ -1,6 +1,9 @@
 pub mod point;
+pub mod indices;
 pub mod point_cloud;
+pub mod point_cloud_soa;
 pub mod bounding_box;
+pub mod transform;
 pub mod filters;
 pub mod segmentation;
 pub mod search;
@@ -5,6 +5,8 @@ use std::cmp::Ordering;
 use std::marker::{Send,Sync};
 use std::ops::{Deref, DerefMut};
 
+use nalgebra::{Matrix3, Vector3};
+
 pub type PointRef<T,U> = Arc<Point<T,U>>;
 pub type PointHashSet<T,U> = HashSet<PointRef<T,U>>;
 pub type PointXYZ<T> = Point<XYZ,T>;
@@ -15,12 +17,32 @@ pub type PointXYZRGB = PointXYZ<RGB>;
 pub type PointXYZRGBRef = Arc<PointXYZRGB>;
 pub type PointXYZPlain = PointXYZ<NoData>;
 pub type PointXYZPlainRef = Arc<PointXYZPlain>;
+pub type PointXYZIR = PointXYZ<IntensityRing>;
+pub type PointXYZIRRef = Arc<PointXYZIR>;
+pub type PointXYZIRT = PointXYZ<IntensityRingTime>;
+pub type PointXYZIRTRef = Arc<PointXYZIRT>;
+pub type PointXYZReflectivity = PointXYZ<Reflectivity>;
+pub type PointXYZReflectivityRef = Arc<PointXYZReflectivity>;
+pub type PointXYZNormal = PointXYZ<Normal>;
+pub type PointXYZNormalRef = Arc<PointXYZNormal>;
 
 
 // Used to access Point coordinates such as XYZ
 pub trait PointMeasurable: Hash + Clone + Ord + HasDimensions + Send + Sync + std::fmt::Debug{}
 // Used to access Data associated with the point such as RGB, Intensity or NoData
-pub trait PointDataAccess: Hash + Clone + Ord + Send + Sync + std::fmt::Debug{}
+pub trait PointDataAccess: Hash + Clone + Ord + Send + Sync + std::fmt::Debug{
+  // the data of a point moved by a transform whose normal matrix, the
+  // inverse transpose of its linear part, is `normal_matrix`, for data with
+  // a direction such as a normal
+  fn transform(&self, _normal_matrix: &Matrix3<f32>) -> Self {
+    self.clone()
+  }
+  // the data standing for all of `data`, such as for the points of a voxel:
+  // the mean of fields that can be averaged, the first of `data` otherwise
+  fn average(data: &[Self]) -> Option<Self> {
+    data.first().cloned()
+  }
+}
 
 #[derive(Debug,Clone,Copy)]
 pub struct Point<T,U>
@@ -52,6 +74,42 @@ pub struct Intensity {
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct NoData {}
 
+// intensity and the laser ring (channel) that measured the point
+#[derive(Debug, Clone, Copy)]
+pub struct IntensityRing {
+  i: f32,
+  ring: u16,
+}
+
+// as IntensityRing plus the time the point was measured, in seconds
+// relative to the stamp of its cloud
+#[derive(Debug, Clone, Copy)]
+pub struct IntensityRingTime {
+  i: f32,
+  ring: u16,
+  time: f32,
+}
+
+// the returns of lidars such as Ouster: signal intensity, surface
+// reflectivity and ambient near infrared light, with ring and time
+#[derive(Debug, Clone, Copy)]
+pub struct Reflectivity {
+  i: f32,
+  reflectivity: u16,
+  ambient: u16,
+  ring: u16,
+  time: f32,
+}
+
+// unit surface normal and curvature estimated at the point
+#[derive(Debug, Clone, Copy)]
+pub struct Normal {
+  normal_x: f32,
+  normal_y: f32,
+  normal_z: f32,
+  curvature: f32,
+}
+
 pub trait HasDimensions {
   fn new (x:f32, y:f32, z:f32 ) -> Self;
   fn dims() -> usize;
@@ -154,7 +212,13 @@ impl RGB {
   }
 }
 
-impl PointDataAccess for RGB {}
+impl PointDataAccess for RGB {
+  fn average(data: &[Self]) -> Option<Self> {
+    let n = data.len() as u32;
+    let mean = |channel: fn(&Self) -> u8| ((data.iter().map(|rgb| channel(rgb) as u32).sum::<u32>() + n / 2) / n) as u8;
+    data.first().map(|_| Self {r: mean(|rgb| rgb.r), g: mean(|rgb| rgb.g), b: mean(|rgb| rgb.b)})
+  }
+}
 
 impl Intensity {
   pub fn i(&self) -> f32 {
@@ -191,10 +255,184 @@ impl Hash for Intensity {
   }
 }
 
-impl PointDataAccess for Intensity {}
+impl PointDataAccess for Intensity {
+  fn average(data: &[Self]) -> Option<Self> {
+    data.first().map(|_| Self {i: mean(data.iter().map(|d| d.i))})
+  }
+}
 
 impl PointDataAccess for NoData {}
 
+fn mean(values: impl Iterator<Item = f32>) -> f32 {
+  let (sum, count) = values.fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+  sum / count as f32
+}
+
+// f32 values compare and hash at a fixed resolution, as for XYZ and Intensity
+fn fixed(value: f32, mul_factor: f32) -> i64 {
+  (value * mul_factor) as i64
+}
+
+impl IntensityRing {
+  pub fn i(&self) -> f32 {
+    self.i
+  }
+  pub fn ring(&self) -> u16 {
+    self.ring
+  }
+  fn key(&self) -> (u16, i64) {
+    (self.ring, fixed(self.i, 10.0))
+  }
+}
+
+impl IntensityRingTime {
+  pub fn i(&self) -> f32 {
+    self.i
+  }
+  pub fn ring(&self) -> u16 {
+    self.ring
+  }
+  pub fn time(&self) -> f32 {
+    self.time
+  }
+  fn key(&self) -> (u16, i64, i64) {
+    // time at microsecond resolution
+    (self.ring, fixed(self.time, 1e6), fixed(self.i, 10.0))
+  }
+}
+
+impl Reflectivity {
+  pub fn i(&self) -> f32 {
+    self.i
+  }
+  pub fn reflectivity(&self) -> u16 {
+    self.reflectivity
+  }
+  pub fn ambient(&self) -> u16 {
+    self.ambient
+  }
+  pub fn ring(&self) -> u16 {
+    self.ring
+  }
+  pub fn time(&self) -> f32 {
+    self.time
+  }
+  fn key(&self) -> (u16, i64, u16, u16, i64) {
+    (self.ring, fixed(self.time, 1e6), self.reflectivity, self.ambient, fixed(self.i, 10.0))
+  }
+}
+
+impl Normal {
+  pub fn normal(&self) -> [f32;3] {
+    [self.normal_x, self.normal_y, self.normal_z]
+  }
+  pub fn curvature(&self) -> f32 {
+    self.curvature
+  }
+  fn key(&self) -> (i64, i64, i64, i64) {
+    (fixed(self.normal_x, 1000.0), fixed(self.normal_y, 1000.0), fixed(self.normal_z, 1000.0), fixed(self.curvature, 1000.0))
+  }
+}
+
+macro_rules! point_data_by_key {
+  ($($data:ty),*) => {
+    $(
+      impl Ord for $data {
+        fn cmp(&self, other: &Self) -> Ordering {
+          self.key().cmp(&other.key())
+        }
+      }
+
+      impl PartialOrd for $data {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+          Some(self.cmp(other))
+        }
+      }
+
+      impl PartialEq for $data {
+        fn eq(&self, other: &Self) -> bool {
+          self.key() == other.key()
+        }
+      }
+
+      impl Eq for $data {}
+
+      impl Hash for $data {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+          self.key().hash(state);
+        }
+      }
+
+    )*
+  };
+}
+
+point_data_by_key!(IntensityRing, IntensityRingTime, Reflectivity, Normal);
+
+// rings are channel numbers, so an average keeps the ring of the first
+impl PointDataAccess for IntensityRing {
+  fn average(data: &[Self]) -> Option<Self> {
+    data.first().map(|first| Self {i: mean(data.iter().map(|d| d.i)), ring: first.ring})
+  }
+}
+
+impl PointDataAccess for IntensityRingTime {
+  fn average(data: &[Self]) -> Option<Self> {
+    data.first().map(|first| Self {
+      i: mean(data.iter().map(|d| d.i)),
+      ring: first.ring,
+      time: mean(data.iter().map(|d| d.time)),
+    })
+  }
+}
+
+impl PointDataAccess for Reflectivity {
+  fn average(data: &[Self]) -> Option<Self> {
+    data.first().map(|first| Self {
+      i: mean(data.iter().map(|d| d.i)),
+      reflectivity: mean(data.iter().map(|d| d.reflectivity as f32)).round() as u16,
+      ambient: mean(data.iter().map(|d| d.ambient as f32)).round() as u16,
+      ring: first.ring,
+      time: mean(data.iter().map(|d| d.time)),
+    })
+  }
+}
+
+// data holding the time its point was measured, in seconds relative to the
+// stamp of its cloud
+pub trait HasPointTime {
+  fn time(&self) -> f32;
+}
+
+impl HasPointTime for IntensityRingTime {
+  fn time(&self) -> f32 {
+    self.time
+  }
+}
+
+impl HasPointTime for Reflectivity {
+  fn time(&self) -> f32 {
+    self.time
+  }
+}
+
+impl PointDataAccess for Normal {
+  // normals turn by the normal matrix, which is the rotation itself for
+  // rigid transforms, and stay of unit length
+  fn transform(&self, normal_matrix: &Matrix3<f32>) -> Self {
+    let n = normal_matrix * Vector3::new(self.normal_x, self.normal_y, self.normal_z);
+    let n = n.try_normalize(f32::EPSILON).unwrap_or(n);
+    Self {normal_x: n.x, normal_y: n.y, normal_z: n.z, curvature: self.curvature}
+  }
+
+  fn average(data: &[Self]) -> Option<Self> {
+    let first = data.first()?;
+    let sum = data.iter().fold(Vector3::zeros(), |sum, d| sum + Vector3::new(d.normal_x, d.normal_y, d.normal_z));
+    let n = sum.try_normalize(f32::EPSILON).unwrap_or_else(|| Vector3::new(first.normal_x, first.normal_y, first.normal_z));
+    Some(Self {normal_x: n.x, normal_y: n.y, normal_z: n.z, curvature: mean(data.iter().map(|d| d.curvature))})
+  }
+}
+
 
 pub trait HasPoint<T,U> {
   fn point(&self)->&T;
@@ -366,3 +604,130 @@ impl Point<XYZ,RGB> {
   }
 
 }
+
+macro_rules! xyz_accessors {
+  () => {
+    pub fn x(&self) -> f32 {
+      self.point.x
+    }
+    pub fn y(&self) -> f32 {
+      self.point.y
+    }
+    pub fn z(&self) -> f32 {
+      self.point.z
+    }
+    pub fn xyz(&self) -> [f32;3]{
+      [self.point.x, self.point.y, self.point.z]
+    }
+    pub fn dist(&self, other:&Self) -> f32 {
+      self.point.dist(&other.point)
+    }
+  };
+}
+
+impl Point<XYZ,IntensityRing> {
+  xyz_accessors!();
+
+  pub fn new(
+    x: f32,
+    y: f32,
+    z: f32,
+    i: f32,
+    ring: u16,
+  ) -> Self {
+    let point = XYZ {x,y,z};
+    let data = Some(IntensityRing {i, ring});
+    Point::<XYZ,IntensityRing> {point, data}
+  }
+  pub fn i(&self) -> f32 {
+    self.data.map_or(0.0, |data| data.i)
+  }
+  pub fn ring(&self) -> u16 {
+    self.data.map_or(0, |data| data.ring)
+  }
+}
+
+impl Point<XYZ,IntensityRingTime> {
+  xyz_accessors!();
+
+  pub fn new(
+    x: f32,
+    y: f32,
+    z: f32,
+    i: f32,
+    ring: u16,
+    time: f32,
+  ) -> Self {
+    let point = XYZ {x,y,z};
+    let data = Some(IntensityRingTime {i, ring, time});
+    Point::<XYZ,IntensityRingTime> {point, data}
+  }
+  pub fn i(&self) -> f32 {
+    self.data.map_or(0.0, |data| data.i)
+  }
+  pub fn ring(&self) -> u16 {
+    self.data.map_or(0, |data| data.ring)
+  }
+  pub fn time(&self) -> f32 {
+    self.data.map_or(0.0, |data| data.time)
+  }
+}
+
+impl Point<XYZ,Reflectivity> {
+  xyz_accessors!();
+
+  #[allow(clippy::too_many_arguments)]
+  pub fn new(
+    x: f32,
+    y: f32,
+    z: f32,
+    i: f32,
+    reflectivity: u16,
+    ambient: u16,
+    ring: u16,
+    time: f32,
+  ) -> Self {
+    let point = XYZ {x,y,z};
+    let data = Some(Reflectivity {i, reflectivity, ambient, ring, time});
+    Point::<XYZ,Reflectivity> {point, data}
+  }
+  pub fn i(&self) -> f32 {
+    self.data.map_or(0.0, |data| data.i)
+  }
+  pub fn reflectivity(&self) -> u16 {
+    self.data.map_or(0, |data| data.reflectivity)
+  }
+  pub fn ambient(&self) -> u16 {
+    self.data.map_or(0, |data| data.ambient)
+  }
+  pub fn ring(&self) -> u16 {
+    self.data.map_or(0, |data| data.ring)
+  }
+  pub fn time(&self) -> f32 {
+    self.data.map_or(0.0, |data| data.time)
+  }
+}
+
+impl Point<XYZ,Normal> {
+  xyz_accessors!();
+
+  pub fn new(
+    x: f32,
+    y: f32,
+    z: f32,
+    normal_x: f32,
+    normal_y: f32,
+    normal_z: f32,
+    curvature: f32,
+  ) -> Self {
+    let point = XYZ {x,y,z};
+    let data = Some(Normal {normal_x, normal_y, normal_z, curvature});
+    Point::<XYZ,Normal> {point, data}
+  }
+  pub fn normal(&self) -> [f32;3] {
+    self.data.map_or([0.0;3], |data| data.normal())
+  }
+  pub fn curvature(&self) -> f32 {
+    self.data.map_or(0.0, |data| data.curvature)
+  }
+}
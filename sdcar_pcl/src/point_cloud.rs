@@ -1,9 +1,10 @@
-use sdcar_types::msg::point_cloud2::PointCloud2;
+use sdcar_types::msg::point_cloud2::{FieldReader, PointCloud2};
+use sdcar_types::Result;
 use chrono::{DateTime, Utc};
 use std::vec::Vec;
 use std::sync::Arc;
-use std::{convert::TryInto, sync::Mutex};
-use crate::{point::*, HasCreationTimestamp};
+use std::sync::Mutex;
+use crate::{indices::Indices, point::*, HasCreationTimestamp};
 use rayon::prelude::*;
 
 pub type PointCloud2Ref = Arc::<PointCloud2>;
@@ -39,92 +40,209 @@ pub type PointCloudXYZIntensityRef = Arc<PointCloudXYZIntensity>;
 #[derive(Debug,Clone)]
 pub struct PointCloud<T>{
   pc2_msg: Option<PointCloud2Ref>,
+  frame_id: Option<String>,
   point_indices_ref: PointIndicesRef<T>,
 }
 
-pub trait NewPointCloud<T> {
-  fn new(pc2_msg: PointCloud2Ref) -> Self;
+pub trait NewPointCloud<T>: Sized {
+  /// Decodes the points of `pc2_msg`, panicking if it lacks a field the
+  /// point type needs. See `try_new`.
+  fn new(pc2_msg: PointCloud2Ref) -> Self {
+    Self::try_new(pc2_msg).unwrap_or_else(|e| panic!("{}", e))
+  }
+  /// Decodes the points of `pc2_msg` by the offset, datatype and byte order
+  /// its `fields` give, so clouds of any lidar driver load.
+  fn try_new(pc2_msg: PointCloud2Ref) -> Result<Self>;
 }
 
-impl NewPointCloud<PointXYZIntensity> for PointCloud<PointXYZIntensity> {
-
-  fn new(pc2_msg: PointCloud2Ref) -> Self {
+impl<T: FromPointCloud2> NewPointCloud<T> for PointCloud<T> {
 
-    let pc_rows = pc2_msg.height; // the number of rows
-    let pc_row_points = pc2_msg.width; // points per rows
-    let point_step = pc2_msg.point_step;
-
-    let num_points = (pc_rows * pc_row_points) as usize;
-
-    // let mut point_indices=PointIndices::<PointXYZIntensity>::with_capacity((pc_rows*pc_row_points) as usize);
-    // TODO revisit this later - am hard coding based on what I capture
-    //  [PointField { name: "x", offset: 0, datatype: 7, count: 1 }, 
-    //   PointField { name: "y", offset: 4, datatype: 7, count: 1 }, 
-    //   PointField { name: "z", offset: 8, datatype: 7, count: 1 }, 
-    //   PointField { name: "intensity", offset: 16, datatype: 7, count: 1 }] 
-    let x_offset = 0;
-    let y_offset = 4;
-    let z_offset = 8;
-    let i_offset = 16;
-
-    let point_build= |point_num| -> Arc<PointXYZIntensity> {
-        let pos = point_num * point_step as usize;
-
-        let x_pos = (pos+x_offset) as usize;
-        let y_pos = (pos+y_offset) as usize;
-        let z_pos = (pos+z_offset) as usize;
-        let i_pos = (pos+i_offset) as usize;
-        let x = f32::from_le_bytes(pc2_msg.data[x_pos..x_pos+4].try_into().unwrap());
-        let y = f32::from_le_bytes(pc2_msg.data[y_pos..y_pos+4].try_into().unwrap());
-        let z = f32::from_le_bytes(pc2_msg.data[z_pos..z_pos+4].try_into().unwrap());
-        let i = f32::from_le_bytes(pc2_msg.data[i_pos..i_pos+4].try_into().unwrap());
-
-        Arc::new(PointXYZIntensity::new(x,y,z,i))
+  fn try_new(pc2_msg: PointCloud2Ref) -> Result<Self> {
+    let point_indices = {
+      let decode = T::decoder(&pc2_msg)?;
+      (0..pc2_msg.num_points()).into_par_iter().map(|point_num| Arc::new(decode(point_num))).collect()
     };
+    let point_indices_ref = PointIndicesRef::<T>::new(point_indices);
+    let frame_id = Some(pc2_msg.header.frame_id.clone()).filter(|frame_id| !frame_id.is_empty());
+    Ok(PointCloud::<T> {pc2_msg: Some(pc2_msg), frame_id, point_indices_ref})
+  }
+}
 
-    let par_iter = (0..num_points).into_par_iter().map(point_build);
-    let point_indices= par_iter.collect();
+pub type PointDecoder<'a, T> = Box<dyn Fn(usize) -> T + Send + Sync + 'a>;
 
-    // let point_indices=PointIndices::<PointXYZIntensity>::with_capacity((pc_rows*pc_row_points) as usize);
-    // let mut append_row_points = |row_num| -> PointCloudXYZIntensity {
-    //   for point_num in 0 .. pc_row_points {
-    //     let pos = (row_num * pc_row_points * point_step) + (point_num * point_step);
+/// Point types decoded from the fields of a `PointCloud2`.
+pub trait FromPointCloud2: Sized + Send + Sync {
+  /// Locates the fields the point type needs, failing if one is absent,
+  /// and returns a function decoding the point at an index.
+  fn decoder(pc2_msg: &PointCloud2) -> Result<PointDecoder<'_, Self>>;
+}
 
-    //     let x_pos = (pos+x_offset) as usize;
-    //     let y_pos = (pos+y_offset) as usize;
-    //     let z_pos = (pos+z_offset) as usize;
-    //     let i_pos = (pos+i_offset) as usize;
-    //     let x = f32::from_le_bytes(pc2_msg.data[x_pos..x_pos+4].try_into().unwrap());
-    //     let y = f32::from_le_bytes(pc2_msg.data[y_pos..y_pos+4].try_into().unwrap());
-    //     let z = f32::from_le_bytes(pc2_msg.data[z_pos..z_pos+4].try_into().unwrap());
-    //     let i = f32::from_le_bytes(pc2_msg.data[i_pos..i_pos+4].try_into().unwrap());
+fn xyz_readers(pc2_msg: &PointCloud2) -> Result<[FieldReader<'_>;3]> {
+  Ok([pc2_msg.field_reader("x")?, pc2_msg.field_reader("y")?, pc2_msg.field_reader("z")?])
+}
 
-    //     let point_ref = Box::new(PointXYZIntensity::new(x,y,z,i));
-    //     // point_indices.push(point_ref);
-    //     point_ref
-    //   }
+fn xyz(readers: &[FieldReader;3], point_num: usize) -> (f32,f32,f32) {
+  (readers[0].get(point_num,0), readers[1].get(point_num,0), readers[2].get(point_num,0))
+}
 
-    // };
+// the first of the names drivers use for a field, such as `ring` or `channel`
+fn any_field_reader<'a>(pc2_msg: &'a PointCloud2, names: &[&str]) -> Result<FieldReader<'a>> {
+  match names.iter().find(|name| pc2_msg.has_field(name)) {
+    Some(name) => pc2_msg.field_reader(name),
+    None => pc2_msg.field_reader(names[0]),
+  }
+}
+
+/// Reads the time of each point relative to the cloud's stamp in seconds,
+/// from Velodyne's `time` in seconds or Ouster's `t` in nanoseconds.
+pub struct TimeReader<'a> {
+  reader: FieldReader<'a>,
+  scale: f64,
+}
+
+impl<'a> TimeReader<'a> {
+  pub fn new(pc2_msg: &'a PointCloud2) -> Result<Self> {
+    if !pc2_msg.has_field("time") && pc2_msg.has_field("t") {
+      Ok(Self {reader: pc2_msg.field_reader("t")?, scale: 1e-9})
+    } else {
+      Ok(Self {reader: pc2_msg.field_reader("time")?, scale: 1.0})
+    }
+  }
+  pub fn time(&self, point_num: usize) -> f32 {
+    (self.reader.get_f64(point_num, 0) * self.scale) as f32
+  }
+}
+
+impl FromPointCloud2 for PointXYZPlain {
+  fn decoder(pc2_msg: &PointCloud2) -> Result<PointDecoder<'_, Self>> {
+    let xyz_readers = xyz_readers(pc2_msg)?;
+    Ok(Box::new(move |point_num| {
+      let (x,y,z) = xyz(&xyz_readers, point_num);
+      PointXYZPlain::new(x,y,z)
+    }))
+  }
+}
 
-    // for row_num in 0 .. pc_rows {
-    //     append_row_points(row_num);
-    // }
-    
-    let point_indices_ref = PointIndicesRef::<PointXYZIntensity>::new(point_indices);
-    PointCloud::<PointXYZIntensity> {pc2_msg: Some(pc2_msg), point_indices_ref}
+impl FromPointCloud2 for PointXYZIntensity {
+  fn decoder(pc2_msg: &PointCloud2) -> Result<PointDecoder<'_, Self>> {
+    let xyz_readers = xyz_readers(pc2_msg)?;
+    let i = pc2_msg.field_reader("intensity")?;
+    Ok(Box::new(move |point_num| {
+      let (x,y,z) = xyz(&xyz_readers, point_num);
+      PointXYZIntensity::new(x,y,z, i.get(point_num,0))
+    }))
+  }
+}
+
+impl FromPointCloud2 for PointXYZIR {
+  fn decoder(pc2_msg: &PointCloud2) -> Result<PointDecoder<'_, Self>> {
+    let xyz_readers = xyz_readers(pc2_msg)?;
+    let i = pc2_msg.field_reader("intensity")?;
+    let ring = any_field_reader(pc2_msg, &["ring", "channel"])?;
+    Ok(Box::new(move |point_num| {
+      let (x,y,z) = xyz(&xyz_readers, point_num);
+      PointXYZIR::new(x,y,z, i.get(point_num,0), ring.get(point_num,0))
+    }))
+  }
+}
+
+impl FromPointCloud2 for PointXYZIRT {
+  fn decoder(pc2_msg: &PointCloud2) -> Result<PointDecoder<'_, Self>> {
+    let xyz_readers = xyz_readers(pc2_msg)?;
+    let i = pc2_msg.field_reader("intensity")?;
+    let ring = any_field_reader(pc2_msg, &["ring", "channel"])?;
+    let time = TimeReader::new(pc2_msg)?;
+    Ok(Box::new(move |point_num| {
+      let (x,y,z) = xyz(&xyz_readers, point_num);
+      PointXYZIRT::new(x,y,z, i.get(point_num,0), ring.get(point_num,0), time.time(point_num))
+    }))
+  }
+}
+
+impl FromPointCloud2 for PointXYZReflectivity {
+  fn decoder(pc2_msg: &PointCloud2) -> Result<PointDecoder<'_, Self>> {
+    let xyz_readers = xyz_readers(pc2_msg)?;
+    let i = any_field_reader(pc2_msg, &["intensity", "signal"])?;
+    let reflectivity = pc2_msg.field_reader("reflectivity")?;
+    // older Ouster firmware names ambient light `noise`
+    let ambient = any_field_reader(pc2_msg, &["ambient", "noise"])?;
+    let ring = any_field_reader(pc2_msg, &["ring", "channel"])?;
+    let time = TimeReader::new(pc2_msg)?;
+    Ok(Box::new(move |point_num| {
+      let (x,y,z) = xyz(&xyz_readers, point_num);
+      PointXYZReflectivity::new(
+        x,y,z,
+        i.get(point_num,0),
+        reflectivity.get(point_num,0),
+        ambient.get(point_num,0),
+        ring.get(point_num,0),
+        time.time(point_num),
+      )
+    }))
+  }
+}
+
+impl FromPointCloud2 for PointXYZNormal {
+  fn decoder(pc2_msg: &PointCloud2) -> Result<PointDecoder<'_, Self>> {
+    let xyz_readers = xyz_readers(pc2_msg)?;
+    let normal_x = pc2_msg.field_reader("normal_x")?;
+    let normal_y = pc2_msg.field_reader("normal_y")?;
+    let normal_z = pc2_msg.field_reader("normal_z")?;
+    let curvature = pc2_msg.field_reader("curvature")?;
+    Ok(Box::new(move |point_num| {
+      let (x,y,z) = xyz(&xyz_readers, point_num);
+      PointXYZNormal::new(
+        x,y,z,
+        normal_x.get(point_num,0),
+        normal_y.get(point_num,0),
+        normal_z.get(point_num,0),
+        curvature.get(point_num,0),
+      )
+    }))
   }
 }
 
 impl<T> HasCreationTimestamp for PointCloud<T> {
   fn timestamp(&self) -> DateTime<Utc> {
     match &self.pc2_msg {
-      Some (pc2_msg) => pc2_msg.timestamp,
+      Some (pc2_msg) => pc2_msg.header.timestamp(),
       None => Utc::now()
     }
   }
 }
 
 
+impl<T> PointCloud<T> {
+  /// The message the cloud was decoded from, `None` if built from points.
+  pub fn pc2_msg(&self) -> Option<PointCloud2Ref> {
+    self.pc2_msg.clone()
+  }
+  /// The coordinate frame of the points: that of the `PointCloud2` they
+  /// were decoded from, unless moved by `transform_to` or set by
+  /// `with_frame_id`. `None` for clouds built from points and for those
+  /// decoded from a `PointCloud2` with an empty frame id.
+  pub fn frame_id(&self) -> Option<&str> {
+    self.frame_id.as_deref()
+  }
+  pub fn with_frame_id(mut self, frame_id: &str) -> Self {
+    self.frame_id = Some(frame_id.to_string());
+    self
+  }
+  /// A cloud of `point_indices_ref` keeping the source message and frame of
+  /// another layout of the same points.
+  pub(crate) fn from_parts(pc2_msg: Option<PointCloud2Ref>, frame_id: Option<String>, point_indices_ref: PointIndicesRef<T>) -> Self {
+    PointCloud::<T> {pc2_msg, frame_id, point_indices_ref}
+  }
+  /// The same cloud with `point_indices_ref` in place of its points.
+  pub fn with_point_indices_ref(&self, point_indices_ref: PointIndicesRef<T>) -> Self {
+    PointCloud::<T> {pc2_msg: self.pc2_msg.clone(), frame_id: self.frame_id.clone(), point_indices_ref}
+  }
+  /// The points of the cloud at `indices`.
+  pub fn select(&self, indices: &Indices) -> PointIndicesRef<T> {
+    Arc::new(indices.iter().map(|&i| self.point_indices_ref[i].clone()).collect())
+  }
+}
+
 pub trait HasPointIndice<T> {
   fn num_points(&self) -> usize;
   fn point(&self, i:usize) -> Arc<T>;
@@ -147,7 +265,7 @@ impl<T> HasPointIndice<T> for PointCloud<T> {
 
 pub trait PointCloudAccess<T>:  HasPointIndice<T> + HasCreationTimestamp + Send + Sync + std::fmt::Debug{}
 
-impl PointCloudAccess<PointXYZIntensity> for PointCloud<PointXYZIntensity> {}
+impl<T: Send + Sync + std::fmt::Debug> PointCloudAccess<T> for PointCloud<T> {}
 
 
 // TODO implement generic versions
@@ -172,7 +290,7 @@ where T: PointMeasurable,
       U: PointDataAccess
 {
   fn from (point_indices: PointIndices<Point<T,U>>) -> Self {
-    PointCloud::<Point::<T,U>> {pc2_msg: None, point_indices_ref: Arc::new(point_indices)}
+    PointCloud::<Point::<T,U>> {pc2_msg: None, frame_id: None, point_indices_ref: Arc::new(point_indices)}
   }
 }
 
@@ -181,6 +299,6 @@ where T: PointMeasurable,
       U: PointDataAccess
 {
   fn from (point_indices_ref: PointIndicesRef<Point<T,U>>) -> Self {
-    PointCloud::<Point::<T,U>> {pc2_msg: None, point_indices_ref: point_indices_ref}
+    PointCloud::<Point::<T,U>> {pc2_msg: None, frame_id: None, point_indices_ref: point_indices_ref}
   }
 }
\ No newline at end of file
@@ -0,0 +1,233 @@
+use sdcar_types::msg::point_cloud2::PointCloud2;
+use sdcar_types::Result;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use crate::{indices::Indices, point::*, point_cloud::*, HasCreationTimestamp};
+use crate::filters::{MaxVec, MinVec};
+use rayon::prelude::*;
+
+pub type PointCloudSoaRef = Arc<PointCloudSoa>;
+
+/// A point cloud of x, y, z and intensity held column by column in
+/// contiguous vectors, rather than as a `PointIndices` of separately
+/// allocated points. Subsets are `PointCloudSoaView`s of indices into it.
+#[derive(Debug,Clone,Default)]
+pub struct PointCloudSoa {
+  pc2_msg: Option<PointCloud2Ref>,
+  frame_id: Option<String>,
+  x: Vec<f32>,
+  y: Vec<f32>,
+  z: Vec<f32>,
+  intensity: Vec<f32>,
+}
+
+impl PointCloudSoa {
+  pub fn with_capacity(capacity: usize) -> Self {
+    Self {
+      pc2_msg: None,
+      frame_id: None,
+      x: Vec::with_capacity(capacity),
+      y: Vec::with_capacity(capacity),
+      z: Vec::with_capacity(capacity),
+      intensity: Vec::with_capacity(capacity),
+    }
+  }
+
+  /// An empty cloud with the message and frame of this one, for the output
+  /// of a filter.
+  pub(crate) fn empty_like(&self) -> Self {
+    Self {pc2_msg: self.pc2_msg.clone(), frame_id: self.frame_id.clone(), ..Self::default()}
+  }
+
+  /// Builds a cloud from its columns, which must be of equal length.
+  pub fn from_columns(x: Vec<f32>, y: Vec<f32>, z: Vec<f32>, intensity: Vec<f32>) -> Self {
+    assert!(
+      x.len() == y.len() && x.len() == z.len() && x.len() == intensity.len(),
+      "columns of unequal length x {} y {} z {} intensity {}", x.len(), y.len(), z.len(), intensity.len()
+    );
+    Self {pc2_msg: None, frame_id: None, x, y, z, intensity}
+  }
+
+  /// The message the cloud was decoded from, `None` if built from points.
+  pub fn pc2_msg(&self) -> Option<PointCloud2Ref> {
+    self.pc2_msg.clone()
+  }
+  /// The coordinate frame of the points, as for `PointCloud::frame_id`.
+  pub fn frame_id(&self) -> Option<&str> {
+    self.frame_id.as_deref()
+  }
+
+  pub fn push(&mut self, x: f32, y: f32, z: f32, intensity: f32) {
+    self.x.push(x);
+    self.y.push(y);
+    self.z.push(z);
+    self.intensity.push(intensity);
+  }
+
+  pub fn len(&self) -> usize {
+    self.x.len()
+  }
+  pub fn is_empty(&self) -> bool {
+    self.x.is_empty()
+  }
+  pub fn x(&self) -> &[f32] {
+    &self.x
+  }
+  pub fn y(&self) -> &[f32] {
+    &self.y
+  }
+  pub fn z(&self) -> &[f32] {
+    &self.z
+  }
+  pub fn intensity(&self) -> &[f32] {
+    &self.intensity
+  }
+  pub fn xyz(&self, i: usize) -> [f32;3] {
+    [self.x[i], self.y[i], self.z[i]]
+  }
+  pub fn point(&self, i: usize) -> PointXYZIntensity {
+    PointXYZIntensity::new(self.x[i], self.y[i], self.z[i], self.intensity[i])
+  }
+
+  /// A view of every point of the cloud.
+  pub fn view(&self) -> PointCloudSoaView<'_> {
+    PointCloudSoaView::new(self, Indices::all(self.len()))
+  }
+
+  /// A view of the points at `indices`.
+  pub fn select(&self, indices: Indices) -> PointCloudSoaView<'_> {
+    PointCloudSoaView::new(self, indices)
+  }
+}
+
+impl NewPointCloud<PointXYZIntensity> for PointCloudSoa {
+  fn try_new(pc2_msg: PointCloud2Ref) -> Result<Self> {
+    let mut columns = ["x", "y", "z", "intensity"]
+      .par_iter()
+      .map(|name| pc2_msg.read_field::<f32>(name))
+      .collect::<Result<Vec<Vec<f32>>>>()?;
+    let intensity = columns.pop().unwrap();
+    let z = columns.pop().unwrap();
+    let y = columns.pop().unwrap();
+    let x = columns.pop().unwrap();
+    let frame_id = Some(pc2_msg.header.frame_id.clone()).filter(|frame_id| !frame_id.is_empty());
+    Ok(Self {pc2_msg: Some(pc2_msg), frame_id, x, y, z, intensity})
+  }
+}
+
+impl HasCreationTimestamp for PointCloudSoa {
+  fn timestamp(&self) -> DateTime<Utc> {
+    match &self.pc2_msg {
+      Some (pc2_msg) => pc2_msg.header.timestamp(),
+      None => Utc::now()
+    }
+  }
+}
+
+impl From <PointCloud2> for PointCloudSoa {
+  fn from (pc2_msg: PointCloud2) -> Self {
+     PointCloudSoa::new(Arc::new(pc2_msg))
+  }
+}
+
+impl From <&PointCloudXYZIntensity> for PointCloudSoa {
+  fn from (cloud: &PointCloudXYZIntensity) -> Self {
+    let point_indices_ref = cloud.point_indices_ref();
+    let mut soa = PointCloudSoa::with_capacity(point_indices_ref.len());
+    for p in point_indices_ref.iter() {
+      let (x,y,z) = p.point().tuple();
+      soa.push(x, y, z, p.data().unwrap().i());
+    }
+    soa.pc2_msg = cloud.pc2_msg();
+    soa.frame_id = cloud.frame_id().map(str::to_string);
+    soa
+  }
+}
+
+impl From <&PointCloudSoa> for PointCloudXYZIntensity {
+  fn from (soa: &PointCloudSoa) -> Self {
+    soa.view().to_cloud()
+  }
+}
+
+/// Points of a `PointCloudSoa` selected by `Indices`, without copying the
+/// points.
+#[derive(Debug,Clone)]
+pub struct PointCloudSoaView<'a> {
+  cloud: &'a PointCloudSoa,
+  indices: Indices,
+}
+
+impl<'a> PointCloudSoaView<'a> {
+  pub fn new(cloud: &'a PointCloudSoa, indices: Indices) -> Self {
+    Self {cloud, indices}
+  }
+  pub fn cloud(&self) -> &'a PointCloudSoa {
+    self.cloud
+  }
+  pub fn indices(&self) -> &Indices {
+    &self.indices
+  }
+  pub fn into_indices(self) -> Indices {
+    self.indices
+  }
+  pub fn len(&self) -> usize {
+    self.indices.len()
+  }
+  pub fn is_empty(&self) -> bool {
+    self.indices.is_empty()
+  }
+  /// The coordinates of the `i`th point of the view.
+  pub fn xyz(&self, i: usize) -> [f32;3] {
+    self.cloud.xyz(self.indices.as_slice()[i])
+  }
+
+  /// A view of the points of this view at positions `positions`, indexing
+  /// the underlying cloud.
+  pub fn select(&self, positions: &[usize]) -> PointCloudSoaView<'a> {
+    PointCloudSoaView::new(self.cloud, positions.iter().map(|&i| self.indices.as_slice()[i]).collect())
+  }
+
+  /// Copies the viewed points into a cloud of their own.
+  pub fn to_soa(&self) -> PointCloudSoa {
+    let column = |values: &[f32]| -> Vec<f32> { self.indices.iter().map(|&i| values[i]).collect() };
+    let c = self.cloud;
+    PointCloudSoa {
+      pc2_msg: c.pc2_msg.clone(),
+      frame_id: c.frame_id.clone(),
+      x: column(&c.x),
+      y: column(&c.y),
+      z: column(&c.z),
+      intensity: column(&c.intensity),
+    }
+  }
+
+  /// Copies the viewed points into a `PointCloud` with the message and frame
+  /// of the underlying cloud.
+  pub fn to_cloud(&self) -> PointCloudXYZIntensity {
+    let point_indices: PointXYZIntensityIndices = self.indices
+      .as_slice()
+      .par_iter()
+      .map(|&i| Arc::new(self.cloud.point(i)))
+      .collect();
+    PointCloudXYZIntensity::from_parts(self.cloud.pc2_msg(), self.cloud.frame_id.clone(), Arc::new(point_indices))
+  }
+
+  pub fn min_max(&self) -> (MinVec, MaxVec) {
+    let c = self.cloud;
+    let mm_pair = self.indices
+      .as_slice()
+      .par_iter()
+      .map(|&i| ((c.x[i], c.y[i], c.z[i]), (c.x[i], c.y[i], c.z[i])))
+      .reduce(|| ((f32::MAX, f32::MAX, f32::MAX), (f32::MIN, f32::MIN, f32::MIN)),
+        |a,b|{
+            let minv = (a.0.0.min(b.0.0), a.0.1.min(b.0.1), a.0.2.min(b.0.2));
+            let maxv = (a.1.0.max(b.1.0), a.1.1.max(b.1.1), a.1.2.max(b.1.2));
+            (minv,maxv)
+        }
+      );
+
+    (MinVec::new(mm_pair.0.0, mm_pair.0.1, mm_pair.0.2),
+     MaxVec::new(mm_pair.1.0, mm_pair.1.1, mm_pair.1.2))
+  }
+}
@@ -7,8 +7,11 @@ pub use crate::segmentation::*;
 pub use crate::segmentation::extract_clusters::*;
 pub use crate::segmentation::sac::*;
 pub use crate::point::*;
+pub use crate::indices::*;
 pub use crate::point_cloud::*;
+pub use crate::point_cloud_soa::*;
 pub use crate::bounding_box::*;
+pub use crate::transform::*;
 pub use crate::image::*;
 
 pub use crate::DataKind;
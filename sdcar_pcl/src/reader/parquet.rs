@@ -1,24 +1,25 @@
 extern crate sdcar_types;
 use sdcar_types::reader::parquet::*;
-use sdcar_types::reader::combined::*;
+use sdcar_types::reader::merged::*;
 use sdcar_types::msg::MsgKind;
 
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::prelude::*;
 
-pub struct PointCloudXYZIntensityRowIter<'a> {
-  point_cloud2_row_iter: PointCloud2ParquetRowIter<'a>,
+pub struct PointCloudXYZIntensityRowIter {
+  point_cloud2_row_iter: PointCloud2ParquetRowIter,
 }
 
-impl <'a> PointCloudXYZIntensityRowIter<'a>{
-  pub fn new(path:&'a str) -> Self{
+impl PointCloudXYZIntensityRowIter{
+  pub fn new<P: AsRef<Path>>(path:P) -> Self{
     let point_cloud2_row_iter = PointCloud2ParquetRowIter::new(path);
     Self {point_cloud2_row_iter}
   }
 }
 
-impl <'a> Iterator for PointCloudXYZIntensityRowIter <'a> {
+impl Iterator for PointCloudXYZIntensityRowIter {
   type Item = PointCloud<PointXYZIntensity>;
 
   fn next(&mut self) -> Option<Self::Item> {
@@ -31,19 +32,22 @@ impl <'a> Iterator for PointCloudXYZIntensityRowIter <'a> {
   }
 }
 
-pub struct ImageRowIter<'a> {
-  image_path: &'a str,
-  image_row_iter: ImageParquetRowIter<'a>,
+pub struct ImageRowIter {
+  image_path: PathBuf,
+  image_row_iter: ImageParquetRowIter,
 }
 
-impl <'a> ImageRowIter<'a>{
-  pub fn new(path:&'a str, image_path:&'a str) -> Self{
+impl ImageRowIter{
+  pub fn new<P: AsRef<Path>, Q: AsRef<Path>>(path:P, image_path:Q) -> Self{
     let image_row_iter = ImageParquetRowIter::new(path);
-    Self {image_row_iter, image_path}
+    Self {image_row_iter, image_path: image_path.as_ref().to_path_buf()}
+  }
+  pub fn image_path(&self) -> &Path {
+    &self.image_path
   }
 }
 
-impl <'a> Iterator for ImageRowIter<'a>{
+impl Iterator for ImageRowIter{
   type Item = ImageFile;
 
   fn next(&mut self) -> Option<Self::Item> {
@@ -57,37 +61,40 @@ impl <'a> Iterator for ImageRowIter<'a>{
 }
 
 
-pub struct PointCloudXYZIntensityImageRowIter<'a>
+pub struct PointCloudXYZIntensityImageRowIter
 {
-  pc2_image_row_iter: Reader2<PointCloud2ParquetRowIter<'static>, ImageParquetRowIter<'static>>,
-  image_data_path: &'a str,
+  pc2_image_row_iter: MergedReader,
+  image_data_path: String,
 }
-impl <'a> PointCloudXYZIntensityImageRowIter<'a>{
-  pub fn new(point_cloud2_row_iter:PointCloud2ParquetRowIter<'static>, image_row_iter:ImageParquetRowIter<'static>, image_data_path:&'a str) -> Self{
+impl PointCloudXYZIntensityImageRowIter{
+  pub fn new<P: AsRef<Path>>(point_cloud2_row_iter:PointCloud2ParquetRowIter, image_row_iter:ImageParquetRowIter, image_data_path:P) -> Self{
     // let mut point_cloud2_row_iter = PointCloud2ParquetRowIter::new(pc2_file);
     // let mut image_row_iter = ImageParquetRowIter::new(image_file);
-    let pc2_image_row_iter = Reader2::new(point_cloud2_row_iter, image_row_iter);
+    let pc2_image_row_iter = MergedReader::new()
+      .with_source("point_cloud2", point_cloud2_row_iter)
+      .with_source("image", image_row_iter);
+    let image_data_path = image_data_path.as_ref().to_string_lossy().to_string();
     Self {pc2_image_row_iter,image_data_path}
   }
   pub fn image_data_path(&self) -> &str {
-    self.image_data_path.clone()
+    &self.image_data_path
   }
 }
 
-impl <'a> Iterator for PointCloudXYZIntensityImageRowIter<'a>{
+impl Iterator for PointCloudXYZIntensityImageRowIter{
 
   type Item = DataKind<XYZ,Intensity>;
 
   fn next(&mut self) -> Option<Self::Item> {
     if let Some(next_row) = self.pc2_image_row_iter.next() {
-      match next_row.msg_kind() {
+      match next_row.msg_kind {
         MsgKind::PointCloud2(pc2_ref)=> {
           let pc = PointCloudXYZIntensity::from(pc2_ref);
           Some(DataKind::<XYZ,Intensity>::PointCloud(Arc::new(pc)))
         },
         MsgKind::Image(image_ref) => {
           let mut image_file = ImageFile::from(image_ref);
-          image_file.path(self.image_data_path.to_string());
+          image_file.path(self.image_data_path.clone());
           Some(DataKind::<XYZ,Intensity>::ImageFile(Arc::new(image_file)))
         },
         _ => None
@@ -1,5 +1,4 @@
 use std::sync::Arc;
-use std::sync::Mutex;
 use std::sync::RwLock;
 
 use rayon::prelude::*;
@@ -88,7 +87,7 @@ where T: PointMeasurable, U:PointDataAccess
     self.insert_node(&self.root, 0, point, id);
   } 
 
-  fn search_nodes(&self, point:PointRef<T,U>, node:&NodeRefLock<T,U>, depth: usize, distance_tol:f32, point_indices_ref: &PointIndicesMutexRef<Point<T,U>>)  {
+  fn search_nodes(&self, point:PointRef<T,U>, node:&NodeRefLock<T,U>, depth: usize, distance_tol:f32, nodes: &mut Vec<NodeArc<T,U>>)  {
     let node_guard = node.read().unwrap();
     match node_guard.clone() {
       Some(node_ref) => {
@@ -106,19 +105,16 @@ where T: PointMeasurable, U:PointDataAccess
 
           let distance = (dx*dx+dy*dy+dz*dz).sqrt();
           if distance < distance_tol {
-            point_indices_ref
-            .lock()
-            .unwrap()
-            .push(node_ref.point());
+            nodes.push(node_ref.clone());
           }
         }
 
         // check across boundary
         if (tp[depth%self.n_dim]-distance_tol) <= np[depth%self.n_dim] {
-          self.search_nodes(point.clone(), &node_ref.left, depth+1, distance_tol, &point_indices_ref)
+          self.search_nodes(point.clone(), &node_ref.left, depth+1, distance_tol, nodes)
         }
         if (tp[depth%self.n_dim]+distance_tol) > np[depth%self.n_dim] {
-          self.search_nodes(point.clone(), &node_ref.right, depth+1, distance_tol, &point_indices_ref)
+          self.search_nodes(point.clone(), &node_ref.right, depth+1, distance_tol, nodes)
         }.clone()
       }
       None => {
@@ -129,15 +125,22 @@ where T: PointMeasurable, U:PointDataAccess
   // return a vector of point indices in the tree 
   // that are within distance of target
   pub fn search(&self, target: PointRef<T,U>, distance_tol: f32) ->PointIndicesRef<Point<T,U>> {
-    let point_indices = Vec::<PointRef<T,U>>::new();
-    let point_indices_ref = PointIndicesMutexRef::<Point<T,U>>::new(Mutex::new(point_indices));
+    let mut nodes = Vec::new();
+    self.search_nodes(target, &self.root, 0, distance_tol, &mut nodes);
 
-    self.search_nodes(target, &self.root, 0, distance_tol, &point_indices_ref);
-
-    let nearest_point_indices = point_indices_ref.lock().unwrap().clone();
+    let nearest_point_indices = nodes.iter().map(|node| node.point()).collect();
     Arc::new(nearest_point_indices)
   }
 
+  // return the ids the points within distance of target were inserted
+  // with, their positions in the cloud when loaded by `load`
+  pub fn search_ids(&self, target: PointRef<T,U>, distance_tol: f32) -> Vec<usize> {
+    let mut nodes = Vec::new();
+    self.search_nodes(target, &self.root, 0, distance_tol, &mut nodes);
+
+    nodes.iter().map(|node| node.id as usize).collect()
+  }
+
   pub fn load (&self, point_indices_ref: PointIndicesRef<Point<T,U>>) {
     // let mut id: u32 = 0;
     // for point in point_indices_ref.iter().cloned() {
@@ -0,0 +1,78 @@
+use crate::point_cloud_soa::*;
+
+/// A balanced KD-tree of the indices of a `PointCloudSoaView`, laid out
+/// implicitly in one vector: each range's median splits it on the axis of
+/// its depth, so no nodes or locks are allocated.
+#[derive(Debug)]
+pub struct KdTreeSoa<'a> {
+  cloud: &'a PointCloudSoa,
+  nodes: Vec<usize>,
+}
+
+impl<'a> KdTreeSoa<'a> {
+  pub fn new(view: &PointCloudSoaView<'a>) -> Self {
+    let cloud = view.cloud();
+    let mut nodes = view.indices().as_slice().to_vec();
+    Self::build(cloud, &mut nodes, 0);
+    Self {cloud, nodes}
+  }
+
+  fn build(cloud: &PointCloudSoa, nodes: &mut [usize], depth: usize) {
+    if nodes.len() <= 1 {
+      return;
+    }
+    let axis = Self::axis(cloud, depth);
+    let mid = nodes.len() / 2;
+    nodes.select_nth_unstable_by(mid, |&a, &b| axis[a].total_cmp(&axis[b]));
+    let (left, right) = nodes.split_at_mut(mid);
+    Self::build(cloud, left, depth + 1);
+    Self::build(cloud, &mut right[1..], depth + 1);
+  }
+
+  fn axis(cloud: &PointCloudSoa, depth: usize) -> &[f32] {
+    match depth % 3 {
+      0 => cloud.x(),
+      1 => cloud.y(),
+      _ => cloud.z(),
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.nodes.len()
+  }
+  pub fn is_empty(&self) -> bool {
+    self.nodes.is_empty()
+  }
+
+  /// Indices into the cloud of the points within `distance_tol` of `target`.
+  pub fn search(&self, target: [f32;3], distance_tol: f32) -> Vec<usize> {
+    let mut ids = Vec::new();
+    self.search_into(target, distance_tol, &mut ids);
+    ids
+  }
+
+  /// `search`, appending to `ids` so a caller can reuse its allocation.
+  pub fn search_into(&self, target: [f32;3], distance_tol: f32, ids: &mut Vec<usize>) {
+    self.search_range(&self.nodes, 0, target, distance_tol, ids);
+  }
+
+  fn search_range(&self, nodes: &[usize], depth: usize, target: [f32;3], distance_tol: f32, ids: &mut Vec<usize>) {
+    if nodes.is_empty() {
+      return;
+    }
+    let mid = nodes.len() / 2;
+    let point = self.cloud.xyz(nodes[mid]);
+    let (dx, dy, dz) = (point[0] - target[0], point[1] - target[1], point[2] - target[2]);
+    if dx * dx + dy * dy + dz * dz <= distance_tol * distance_tol {
+      ids.push(nodes[mid]);
+    }
+
+    let d = depth % 3;
+    if target[d] - distance_tol <= point[d] {
+      self.search_range(&nodes[..mid], depth + 1, target, distance_tol, ids);
+    }
+    if target[d] + distance_tol >= point[d] {
+      self.search_range(&nodes[mid + 1..], depth + 1, target, distance_tol, ids);
+    }
+  }
+}
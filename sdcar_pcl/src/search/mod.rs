@@ -1,2 +1,3 @@
 pub mod kdtree;
+pub mod kdtree_soa;
 
@@ -1,4 +1,7 @@
 use crate::search::kdtree::*;
+use crate::search::kdtree_soa::*;
+use crate::indices::*;
+use crate::point_cloud_soa::*;
 use crate::point_cloud::*;
 use crate::point::*;
 
@@ -6,51 +9,45 @@ use rayon::prelude::*;
 
 use std::sync::Arc;
 use std::sync::RwLock;
-use dashmap::{DashSet,DashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use dashmap::DashMap;
 use rand::distributions::{Distribution,Uniform};
 
-pub type ClusterRef<T,U> = Arc<RwLock<Cluster<T,U>>>;
+pub type ClusterRef = Arc<RwLock<Cluster>>;
 pub type Clusters<T,U>  = Vec<PointIndicesRef<Point<T,U>>>;
 pub type ClustersRef<T,U>  = Arc<Clusters<T,U>>;
-// pub type ClusterPointIndicesRef<T,U>  = Arc<Vec<PointIndicesRef<Point<T,U>>>>;
 
+// whether the point at each position of the cloud is in a cluster
+type PointProcessed = Vec<AtomicBool>;
+type PointProcessedRef = Arc<PointProcessed>;
 
-// type PointProcessed<T,U> = Arc<Mutex<PointHashSet<T,U>>>;
-type PointProcessed<T,U> = DashSet<PointRef<T,U>>;
-type PointProcessedRef<T,U> = Arc<PointProcessed<T,U>>;
-
-type PointLocked<T,U> = DashMap<PointRef<T,U>,usize>;
-type PointLockedRef<T,U> = Arc<PointLocked<T,U>>;
+type PointLocked = DashMap<usize,usize>;
+type PointLockedRef = Arc<PointLocked>;
 
 pub type ClustersExtractXYZIntensity = EuclideanClustersExtract<XYZ,Intensity>;
 
 #[derive(Debug,Clone)]
-pub struct Cluster<T,U>
-where T: PointMeasurable, U: PointDataAccess {
-  point_indices_ref: Arc<RwLock<PointIndices<Point<T,U>>>>,
-  point_processed_ref: PointProcessedRef<T,U>
+pub struct Cluster {
+  indices: Vec<usize>,
+  point_processed_ref: PointProcessedRef
 }
 
-impl<T,U> Cluster<T,U>
-where T: PointMeasurable, U: PointDataAccess {
-  fn new(point_processed_ref:PointProcessedRef<T,U>) -> Self{
-    let point_indices:PointIndices<Point<T,U>> = PointIndices::<Point<T,U>>::new();
-    let point_indices_ref = Arc::new(RwLock::new(point_indices));
-    Self {point_indices_ref, point_processed_ref}
+impl Cluster {
+  fn new(point_processed_ref:PointProcessedRef) -> Self{
+    Self {indices: Vec::new(), point_processed_ref}
   }
-  fn push(&mut self, point_ref: PointRef<T,U>)  {
-    let mut point_indices_guard = self.point_indices_ref.write().unwrap();
-    point_indices_guard.push(point_ref.clone());
+  fn push(&mut self, index: usize)  {
+    self.indices.push(index);
   }
-  fn is_point_processed(&self, point_ref: PointRef<T,U>) -> bool {
-    self.point_processed_ref.contains(&point_ref)
+  // marks the point processed, returning whether it already was
+  fn process_point(&self, index: usize) -> bool {
+    self.point_processed_ref[index].swap(true, Ordering::SeqCst)
   }
-  fn point_indices_ref(&self) -> PointIndicesRef<Point<T,U>> {
-    // let pi:PointIndices<Point<T,U>> = self.point_indices.drain(..).collect();
-    Arc::new(self.point_indices_ref.read().unwrap().clone())
+  fn indices(&self) -> Indices {
+    Indices::from(self.indices.clone())
   }
   fn num_points(&self) -> usize {
-    self.point_indices_ref.read().unwrap().len()
+    self.indices.len()
   }
 }
 
@@ -67,88 +64,85 @@ where T: PointMeasurable, U: PointDataAccess {
 impl<T,U> EuclideanClustersExtract<T, U>
 where T: PointMeasurable, U: PointDataAccess
 {
+    /// `kdtree_ref` must hold the points of `cloud_ref` with their positions
+    /// as ids, as `KdTree::load` inserts them.
     pub fn new(cloud_ref: PointCloudRef<Point<T,U>>, cluster_tolerance: f32, min_cluster_size: usize, max_cluster_size: usize, kdtree_ref: KdTreeRef<T,U>) -> Self {
       Self {cloud_ref, cluster_tolerance, min_cluster_size, max_cluster_size, kdtree_ref }
     }
 
-    fn cluster_builder(&self, point_ref: PointRef<T,U>, cluster_ref: &ClusterRef<T,U>) {
+    fn cluster_builder(&self, index: usize, cluster_ref: &ClusterRef) {
       {
         let mut cluster_guard = cluster_ref.write().unwrap();
-        if cluster_guard.is_point_processed(point_ref.clone()) {
+        if cluster_guard.process_point(index) {
           return;
         } else {
-          cluster_guard.point_processed_ref.insert(point_ref.clone());
-          cluster_guard.push(point_ref.clone());
+          cluster_guard.push(index);
           if cluster_guard.num_points() >= self.max_cluster_size  {
             return;
           }
         }
       }// lock released
 
-      let nearest_points_ref = self.kdtree_ref.search(point_ref.clone(), self.cluster_tolerance);
+      let nearest_ids = self.kdtree_ref.search_ids(self.cloud_ref.point(index), self.cluster_tolerance);
 
       // not interested in loan points not near any other
-      if nearest_points_ref.len() == 1 {
+      if nearest_ids.len() == 1 {
         return;
       }
 
-      (0..nearest_points_ref.len())
+      nearest_ids
       .into_par_iter()
       .for_each(|i| {
-        let p = nearest_points_ref.get(i).unwrap();
-        // println!("{:?} nearest point {:?}", point_ref.clone().point().array(), p.clone().point().array());
-        self.cluster_builder( p.clone(), &cluster_ref);
+        self.cluster_builder(i, cluster_ref);
       });
     }
 
-    fn cluster_search (&self, point_ref: PointRef<T,U>, point_processed_ref:PointProcessedRef<T,U>, point_locked_ref: PointLockedRef<T,U>, thread_id:usize) -> PointIndicesRef<Point<T,U>>{
-      let cluster_ref: ClusterRef<T,U> = Arc::new(RwLock::new(Cluster::<T,U>::new(point_processed_ref.clone())));
+    fn cluster_search (&self, index: usize, point_processed_ref:PointProcessedRef, point_locked_ref: PointLockedRef, thread_id:usize) -> Indices {
+      let cluster_ref: ClusterRef = Arc::new(RwLock::new(Cluster::new(point_processed_ref)));
 
-      if point_locked_ref.get(&point_ref.clone()).unwrap().clone() == thread_id {
-        self.cluster_builder(point_ref, &cluster_ref);
+      if *point_locked_ref.get(&index).unwrap() == thread_id {
+        self.cluster_builder(index, &cluster_ref);
       }
 
       let cluster_guard = cluster_ref.read().unwrap();
-
-      // println!("cluster built with {} points", cluster_guard.num_points());
-//
-      // for point in point_processed_ref.iter() {
-      //   println!("point_processed {:?}", point.clone());
-      // }
-      let point_indices_ref = cluster_guard.point_indices_ref();
-      point_indices_ref
+      cluster_guard.indices()
     }
 
+    fn point_processed(&self) -> PointProcessedRef {
+      let num_points = self.cloud_ref.num_points();
+      Arc::new((0..num_points).map(|_| AtomicBool::new(false)).collect())
+    }
 
     pub fn extract_parallel(&self) -> Clusters<T,U> {
-      // let mut clusters = Clusters::<T,U>::new();
-      let num_points = self.cloud_ref.point_indices_ref().len();
-
-      let point_processed_ref:PointProcessedRef<T,U> =  Arc::new(PointProcessed::<T,U>::with_capacity(num_points));
-      let point_locked_ref: PointLockedRef<T,U> = Arc::new(PointLocked::<T,U>::new());
+      self.extract_parallel_indices().iter().map(|indices| self.cloud_ref.select(indices)).collect()
+    }
 
+    /// `extract_parallel` returning the positions in the cloud of the
+    /// points of each cluster.
+    pub fn extract_parallel_indices(&self) -> Vec<Indices> {
+      let point_processed_ref = self.point_processed();
+      let point_locked_ref: PointLockedRef = Arc::new(PointLocked::new());
 
-      let point_indices_range = 0..self.cloud_ref.point_indices_ref().len();
+      let point_indices_range = 0..self.cloud_ref.num_points();
 
-      let clusters:Clusters<T,U> = point_indices_range.clone()
+      point_indices_range.clone()
         .into_par_iter()
         .map_init(|| {
           let rng = rand::thread_rng();
           let point_indicees_uniform = Uniform::from(point_indices_range.clone());
           (rng, point_indicees_uniform)
-         },|(rng, point_indicees_uniform), i| -> PointIndicesRef<Point<T,U>>{
+         },|(rng, point_indicees_uniform), i| -> Indices {
           let n = point_indicees_uniform.sample(rng);
-          let point_ref = self.cloud_ref.point(n);
 
           // slight chance as running in parallel, that the point has been processed
           // so dont search just return a point indices that will be filtered out
-          match point_processed_ref.contains(&point_ref) || point_locked_ref.contains_key(&point_ref) {
-            true => Arc::new(PointIndices::<Point<T,U>>::new()),
+          match point_processed_ref[n].load(Ordering::SeqCst) || point_locked_ref.contains_key(&n) {
+            true => Indices::new(),
             false => {
-              point_locked_ref.insert(point_ref.clone(), i);
-              let pir = self.cluster_search(point_ref.clone(), point_processed_ref.clone(),point_locked_ref.clone(), i);
-              point_locked_ref.remove(&point_ref);
-              pir
+              point_locked_ref.insert(n, i);
+              let indices = self.cluster_search(n, point_processed_ref.clone(),point_locked_ref.clone(), i);
+              point_locked_ref.remove(&n);
+              indices
             }
 
           }
@@ -156,35 +150,28 @@ where T: PointMeasurable, U: PointDataAccess
         .filter(|x| {
           x.len() >= self.min_cluster_size
         })
-        .collect();
-
-      clusters
+        .collect()
     }
 
     pub fn extract(&self) -> Clusters<T,U>{
+      self.extract_indices().iter().map(|indices| self.cloud_ref.select(indices)).collect()
+    }
 
-      let num_points = self.cloud_ref.point_indices_ref().len();
-
-      let point_processed_ref:PointProcessedRef<T,U> =  Arc::new(PointProcessed::<T,U>::with_capacity(num_points));
-      let point_locked_ref: PointLockedRef<T,U> = Arc::new(PointLocked::<T,U>::new());
-
-
-      let point_indices_range = 0..self.cloud_ref.point_indices_ref().len();
-
-      let clusters:Clusters<T,U> = point_indices_range.clone()
-        .into_iter()
-        .map(|i| -> PointIndicesRef<Point<T,U>>{
-          let point_ref = self.cloud_ref.point(i);
+    /// `extract` returning the positions in the cloud of the points of each
+    /// cluster.
+    pub fn extract_indices(&self) -> Vec<Indices> {
+      let point_processed_ref = self.point_processed();
+      let point_locked_ref: PointLockedRef = Arc::new(PointLocked::new());
 
-          // slight chance as running in parallel, that the point has been processed
-          // so dont search just return a point indices that will be filtered out
-          match point_processed_ref.contains(&point_ref) || point_locked_ref.contains_key(&point_ref) {
-            true => Arc::new(PointIndices::<Point<T,U>>::new()),
+      (0..self.cloud_ref.num_points())
+        .map(|i| -> Indices {
+          match point_processed_ref[i].load(Ordering::SeqCst) || point_locked_ref.contains_key(&i) {
+            true => Indices::new(),
             false => {
-              point_locked_ref.insert(point_ref.clone(), i);
-              let pir = self.cluster_search(point_ref.clone(), point_processed_ref.clone(),point_locked_ref.clone(), i);
-              point_locked_ref.remove(&point_ref);
-              pir
+              point_locked_ref.insert(i, i);
+              let indices = self.cluster_search(i, point_processed_ref.clone(),point_locked_ref.clone(), i);
+              point_locked_ref.remove(&i);
+              indices
             }
 
           }
@@ -192,11 +179,45 @@ where T: PointMeasurable, U: PointDataAccess
         .filter(|x| {
           x.len() >= self.min_cluster_size
         })
-        .collect();
-
-      clusters
+        .collect()
     }
 
 }
 
-
+/// Euclidean clusters of a view of a `PointCloudSoa`: points within
+/// `cluster_tolerance` of a point of a cluster join it until it holds
+/// `max_cluster_size` points, and clusters under `min_cluster_size` are
+/// dropped, as `EuclideanClustersExtract::extract` does.
+pub fn extract_clusters_soa<'a>(view: &PointCloudSoaView<'a>, cluster_tolerance: f32, min_cluster_size: usize, max_cluster_size: usize) -> Vec<PointCloudSoaView<'a>> {
+  let cloud = view.cloud();
+  let kdtree = KdTreeSoa::new(view);
+
+  // a cloud index is processed once it is in a cluster
+  let mut processed = vec![false; cloud.len()];
+  let mut nearest = Vec::new();
+  let mut clusters = Vec::new();
+
+  for &seed in view.indices() {
+    if processed[seed] {
+      continue;
+    }
+    processed[seed] = true;
+    let mut cluster = vec![seed];
+    let mut next = 0;
+    while next < cluster.len() && cluster.len() < max_cluster_size {
+      nearest.clear();
+      kdtree.search_into(cloud.xyz(cluster[next]), cluster_tolerance, &mut nearest);
+      for &i in nearest.iter() {
+        if !processed[i] && cluster.len() < max_cluster_size {
+          processed[i] = true;
+          cluster.push(i);
+        }
+      }
+      next += 1;
+    }
+    if cluster.len() >= min_cluster_size {
+      clusters.push(PointCloudSoaView::new(cloud, Indices::from(cluster)));
+    }
+  }
+  clusters
+}
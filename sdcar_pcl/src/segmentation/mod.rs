@@ -3,10 +3,11 @@ pub mod extract_clusters;
 pub mod sac;
 
 use crate::point_cloud::*;
-use crate::point::*;
+use crate::indices::*;
+use crate::point_cloud_soa::*;
 use crate::filters::*;
 
-use sac::ransac;
+use sac::{ransac_indices, ransac_soa};
 
 use std::sync::Arc;
 
@@ -15,12 +16,35 @@ pub fn segment_planes_ransac(
   max_iterations: usize,
   distance_to_l: f32,
 ) -> (PointCloudXYZIntensityRef, PointCloudXYZIntensityRef) {
-  let inliers_ref = ransac(cloud, max_iterations, distance_to_l);
+  let (inliers, outliers) = segment_planes_ransac_indices(cloud, max_iterations, distance_to_l);
 
-  let outlier_ref = outlier_filter::<XYZ,Intensity>(cloud, inliers_ref.clone());
-
-  let inlier_cloud_ref = Arc::new(PointCloudXYZIntensity::from(inliers_ref));
-  let outlier_cloud_ref = Arc::new(PointCloudXYZIntensity::from(outlier_ref));
+  let inlier_cloud_ref = Arc::new(PointCloudXYZIntensity::from(cloud.select(&inliers)));
+  let outlier_cloud_ref = Arc::new(PointCloudXYZIntensity::from(cloud.select(&outliers)));
 
   (inlier_cloud_ref, outlier_cloud_ref)
-}
\ No newline at end of file
+}
+
+/// `segment_planes_ransac` returning the positions in `cloud` of the
+/// inliers and outliers of the plane.
+pub fn segment_planes_ransac_indices(
+  cloud: &PointCloudXYZIntensity,
+  max_iterations: usize,
+  distance_to_l: f32,
+) -> (Indices, Indices) {
+  let inliers = ransac_indices(cloud, max_iterations, distance_to_l);
+  let outliers = outlier_indices(cloud.num_points(), &inliers);
+  (inliers, outliers)
+}
+
+/// `segment_planes_ransac` over a view of a `PointCloudSoa`, returning the
+/// inliers and outliers of the plane as views of the same cloud.
+pub fn segment_planes_ransac_soa<'a>(
+  view: &PointCloudSoaView<'a>,
+  max_iterations: usize,
+  distance_to_l: f32,
+) -> (PointCloudSoaView<'a>, PointCloudSoaView<'a>) {
+  let inliers = ransac_soa(view, max_iterations, distance_to_l);
+
+  let outliers = view.indices().difference(inliers.indices());
+  (inliers, PointCloudSoaView::new(view.cloud(), outliers))
+}
@@ -1,39 +1,49 @@
 extern crate nalgebra;
 
+use crate::indices::*;
 use crate::point::*;
 use crate::point_cloud::*;
+use crate::point_cloud_soa::*;
 
 use nalgebra::Vector3;
 use rand::distributions::{Distribution, Uniform};
 use rayon::prelude::*;
-use std::sync::Arc;
 
 pub fn ransac(
     in_cloud: &PointCloudXYZIntensity,
     max_iterations: usize,
     distance_to_l: f32,
 ) -> PointXYZIntensityIndicesRef {
+    in_cloud.select(&ransac_indices(in_cloud, max_iterations, distance_to_l))
+}
+
+/// `ransac` returning the positions of the inliers of the best fitting
+/// plane in `in_cloud`.
+pub fn ransac_indices(
+    in_cloud: &PointCloudXYZIntensity,
+    max_iterations: usize,
+    distance_to_l: f32,
+) -> Indices {
     let point_indices_ref = in_cloud.point_indices_ref();
+    if point_indices_ref.is_empty() {
+        return Indices::new();
+    }
 
     let indices_uniform = Uniform::from(0..point_indices_ref.len());
 
-    let random_point_sample = || -> PointXYZIntensityRef {
-        let mut rng = rand::thread_rng();
-        let indices = indices_uniform.sample(&mut rng);
-        point_indices_ref[indices].clone()
-    };
-
-    let sample_and_fit_line = || -> PointXYZIntensityIndicesRef {
+    let sample_and_fit_line = || -> Indices {
         // create 3 random inlier samples
-        let mut inlier_samples = PointXYZIntensityIndices::new();
-        inlier_samples.push(random_point_sample());
-        inlier_samples.push(random_point_sample());
-        inlier_samples.push(random_point_sample());
+        let mut rng = rand::thread_rng();
+        let inlier_samples = [
+            indices_uniform.sample(&mut rng),
+            indices_uniform.sample(&mut rng),
+            indices_uniform.sample(&mut rng),
+        ];
 
         // measure distance between every point and fitted line
-        let (x1, y1, z1) = inlier_samples[0].point().tuple();
-        let (x2, y2, z2) = inlier_samples[1].point().tuple();
-        let (x3, y3, z3) = inlier_samples[2].point().tuple();
+        let (x1, y1, z1) = point_indices_ref[inlier_samples[0]].point().tuple();
+        let (x2, y2, z2) = point_indices_ref[inlier_samples[1]].point().tuple();
+        let (x3, y3, z3) = point_indices_ref[inlier_samples[2]].point().tuple();
 
         let v1 = Vector3::<f32>::new(x2 - x1, y2 - y1, z2 - z1);
         let v2 = Vector3::<f32>::new(x3 - x1, y3 - y1, z3 - z1);
@@ -45,35 +55,87 @@ pub fn ransac(
         let d = -(i * x1 + j * y1 + k * z1);
         let e = (a * a + b * b + c * c).sqrt();
 
-        let inlier_filter = point_indices_ref.par_iter().cloned().filter(|p| {
+        let inlier_filter = (0..point_indices_ref.len()).into_par_iter().filter(|n| {
             // include the inlier sample
-            if inlier_samples.contains(p) {
+            if inlier_samples.contains(n) {
                 return true;
             };
 
-            let (x, y, z) = p.point().tuple();
+            let (x, y, z) = point_indices_ref[*n].point().tuple();
             let distance = (a * x + b * y + c * z + d).abs() / e;
             // if distance is smaller than threshold, count it as inlier
             distance <= distance_to_l
         });
-        let inliers: PointIndices<PointXYZIntensity> = inlier_filter.collect();
-        Arc::new(inliers)
+        Indices::from_sorted(inlier_filter.collect())
     };
 
-    let inlier_point_indices_refs_iter = (0..max_iterations)
+    (0..max_iterations)
         .into_par_iter()
-        .map(|_x| sample_and_fit_line());
+        .map(|_x| sample_and_fit_line())
+        .max_by_key(|x| x.len())
+        .unwrap_or_default()
+}
+
+/// `ransac` over a view of a `PointCloudSoa`, returning the inliers of the
+/// best fitting plane as a view of the same cloud.
+pub fn ransac_soa<'a>(
+    view: &PointCloudSoaView<'a>,
+    max_iterations: usize,
+    distance_to_l: f32,
+) -> PointCloudSoaView<'a> {
+    let cloud = view.cloud();
+    if view.is_empty() {
+        return PointCloudSoaView::new(cloud, Indices::new());
+    }
+    let (xs, ys, zs) = (cloud.x(), cloud.y(), cloud.z());
+    let indices = view.indices().as_slice();
 
-    let inlier_point_indices_refs: Vec<PointXYZIntensityIndicesRef> =
-        inlier_point_indices_refs_iter.collect();
+    let indices_uniform = Uniform::from(0..indices.len());
 
-    match inlier_point_indices_refs
-        .par_iter()
-        .cloned()
+    let sample_and_fit_line = || -> Indices {
+        // create 3 random inlier samples
+        let mut rng = rand::thread_rng();
+        let samples = [
+            indices[indices_uniform.sample(&mut rng)],
+            indices[indices_uniform.sample(&mut rng)],
+            indices[indices_uniform.sample(&mut rng)],
+        ];
+
+        // measure distance between every point and fitted line
+        let [x1, y1, z1] = cloud.xyz(samples[0]);
+        let [x2, y2, z2] = cloud.xyz(samples[1]);
+        let [x3, y3, z3] = cloud.xyz(samples[2]);
+
+        let v1 = Vector3::<f32>::new(x2 - x1, y2 - y1, z2 - z1);
+        let v2 = Vector3::<f32>::new(x3 - x1, y3 - y1, z3 - z1);
+
+        let nv = v1.cross(&v2);
+        let (a, b, c) = (nv[0], nv[1], nv[2]);
+        let d = -(a * x1 + b * y1 + c * z1);
+        let e = (a * a + b * b + c * c).sqrt();
+
+        indices
+            .par_iter()
+            .copied()
+            .filter(|&i| {
+                // include the inlier sample
+                if samples.contains(&i) {
+                    return true;
+                };
+
+                let distance = (a * xs[i] + b * ys[i] + c * zs[i] + d).abs() / e;
+                // if distance is smaller than threshold, count it as inlier
+                distance <= distance_to_l
+            })
+            .collect::<Vec<usize>>()
+            .into()
+    };
+
+    let inliers = (0..max_iterations)
+        .into_par_iter()
+        .map(|_x| sample_and_fit_line())
         .max_by_key(|x| x.len())
-    {
-        Some(a) => a,
+        .unwrap_or_default();
 
-        None => PointXYZIntensityIndicesRef::new(PointXYZIntensityIndices::new()),
-    }
+    PointCloudSoaView::new(cloud, inliers)
 }
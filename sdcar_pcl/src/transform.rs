@@ -0,0 +1,165 @@
+use crate::bounding_box::*;
+use crate::point::*;
+use crate::point_cloud::*;
+
+use sdcar_types::{Error, Result};
+
+use nalgebra::{Isometry3, Matrix3, Matrix3x4, Matrix4, Point3, Rotation3, Translation3, UnitQuaternion, Vector3};
+use rayon::prelude::*;
+
+use std::sync::Arc;
+
+/// A rigid transform taking coordinates in frame `source_frame_id` to
+/// frame `target_frame_id`, such as from `velodyne` to `base_link`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameTransform {
+  source_frame_id: String,
+  target_frame_id: String,
+  isometry: Isometry3<f32>,
+}
+
+impl FrameTransform {
+  pub fn new(source_frame_id: &str, target_frame_id: &str, isometry: Isometry3<f32>) -> Self {
+    Self {source_frame_id: source_frame_id.to_string(), target_frame_id: target_frame_id.to_string(), isometry}
+  }
+
+  /// From a homogeneous matrix `[R t; 0 1]`, taking the rotation nearest
+  /// to `R` should it not be orthonormal.
+  pub fn from_matrix(source_frame_id: &str, target_frame_id: &str, matrix: &Matrix4<f32>) -> Self {
+    let rotation = Rotation3::from_matrix(&matrix.fixed_slice::<3,3>(0,0).into_owned());
+    let translation = Translation3::from(Vector3::<f32>::from(matrix.fixed_slice::<3,1>(0,3)));
+    let isometry = Isometry3::from_parts(translation, UnitQuaternion::from_rotation_matrix(&rotation));
+    Self::new(source_frame_id, target_frame_id, isometry)
+  }
+
+  /// From a `[R|t]` extrinsic such as the lidar to camera one of
+  /// `LiDAR2Camera`.
+  pub fn from_matrix3x4(source_frame_id: &str, target_frame_id: &str, matrix: &Matrix3x4<f64>) -> Self {
+    let mut homogeneous = Matrix4::<f32>::identity();
+    homogeneous.fixed_slice_mut::<3,4>(0,0).copy_from(&matrix.cast::<f32>());
+    Self::from_matrix(source_frame_id, target_frame_id, &homogeneous)
+  }
+
+  pub fn source_frame_id(&self) -> &str {
+    &self.source_frame_id
+  }
+  pub fn target_frame_id(&self) -> &str {
+    &self.target_frame_id
+  }
+  pub fn isometry(&self) -> &Isometry3<f32> {
+    &self.isometry
+  }
+  pub fn to_homogeneous(&self) -> Matrix4<f32> {
+    self.isometry.to_homogeneous()
+  }
+
+  /// The transform from the target frame back to the source frame.
+  pub fn inverse(&self) -> Self {
+    Self::new(&self.target_frame_id, &self.source_frame_id, self.isometry.inverse())
+  }
+
+  /// This transform followed by `next`, which must start in the frame this
+  /// one ends in, such as `velodyne` to `base_link` then `base_link` to
+  /// `map`.
+  pub fn then(&self, next: &FrameTransform) -> Result<Self> {
+    if next.source_frame_id != self.target_frame_id {
+      return Err(Error::FrameMismatch {expected: next.source_frame_id.clone(), found: self.target_frame_id.clone()});
+    }
+    Ok(Self::new(&self.source_frame_id, &next.target_frame_id, next.isometry * self.isometry))
+  }
+}
+
+/// Data that can be moved by a rigid transform or a homogeneous matrix,
+/// keeping whatever frame id it has.
+pub trait Transformable: Sized {
+  fn transform(&self, isometry: &Isometry3<f32>) -> Self;
+  fn transform_matrix(&self, matrix: &Matrix4<f32>) -> Self;
+}
+
+fn transform_point<T,U>(p: &Point<T,U>, transform: impl Fn(&Point3<f32>) -> Point3<f32>, normal_matrix: &Matrix3<f32>) -> Point<T,U>
+where T: PointMeasurable, U: PointDataAccess
+{
+  let (x, y, z) = p.point().tuple();
+  let moved = transform(&Point3::new(x, y, z));
+  let data = p.data().as_ref().map(|data| data.transform(normal_matrix));
+  Point::<T,U>::new(T::new(moved.x, moved.y, moved.z), data)
+}
+
+impl<T,U> PointCloud<Point<T,U>>
+where T: PointMeasurable, U: PointDataAccess
+{
+  // `linear` is the linear part of `transform`; its inverse transpose,
+  // which turns the normals, is found once for the whole cloud
+  fn map_points(&self, transform: impl Fn(&Point3<f32>) -> Point3<f32> + Send + Sync, linear: &Matrix3<f32>) -> Self {
+    let normal_matrix = linear.try_inverse().map(|inverse| inverse.transpose()).unwrap_or(*linear);
+    let point_indices: PointIndices<Point<T,U>> = self.point_indices_ref()
+      .par_iter()
+      .map(|p| Arc::new(transform_point(p, &transform, &normal_matrix)))
+      .collect();
+    self.with_point_indices_ref(Arc::new(point_indices))
+  }
+
+  /// Moves the cloud into the target frame of `frame_transform`, failing
+  /// unless the cloud is in its source frame. A cloud without a frame id,
+  /// such as one built from points, cannot be checked and is taken to be in
+  /// it; check `frame_id` first where that must not pass.
+  pub fn transform_to(&self, frame_transform: &FrameTransform) -> Result<Self> {
+    match self.frame_id() {
+      Some(frame_id) if frame_id != frame_transform.source_frame_id() => Err(Error::FrameMismatch {
+        expected: frame_transform.source_frame_id().to_string(),
+        found: frame_id.to_string(),
+      }),
+      _ => Ok(self.transform(frame_transform.isometry()).with_frame_id(frame_transform.target_frame_id())),
+    }
+  }
+}
+
+impl<T,U> Transformable for PointCloud<Point<T,U>>
+where T: PointMeasurable, U: PointDataAccess
+{
+  fn transform(&self, isometry: &Isometry3<f32>) -> Self {
+    let linear = isometry.rotation.to_rotation_matrix().into_inner();
+    self.map_points(|p| isometry * p, &linear)
+  }
+
+  fn transform_matrix(&self, matrix: &Matrix4<f32>) -> Self {
+    let linear = matrix.fixed_slice::<3,3>(0,0).into_owned();
+    self.map_points(|p| matrix.transform_point(p), &linear)
+  }
+}
+
+impl<T,U> BoundingBox<T,U>
+where T: PointMeasurable, U: PointDataAccess
+{
+  // the axis aligned box holding the moved corners of this one
+  fn map_corners(&self, transform: impl Fn(&Point3<f32>) -> Point3<f32>) -> Self {
+    let a = self.point_min().point().array();
+    let b = self.point_max().point().array();
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+    for corner in 0..8 {
+      let pick = |axis: usize| if corner & (1 << axis) == 0 { a[axis] } else { b[axis] };
+      let moved = transform(&Point3::new(pick(0), pick(1), pick(2)));
+      for axis in 0..3 {
+        min[axis] = min[axis].min(moved[axis]);
+        max[axis] = max[axis].max(moved[axis]);
+      }
+    }
+    let point_min = Point::<T,U>::new(T::new(min[0], min[1], min[2]), None);
+    let point_max = Point::<T,U>::new(T::new(max[0], max[1], max[2]), None);
+    BoundingBox::<T,U>::new(self.box_id, self.cluster_id, self.track_id, point_min, point_max)
+  }
+}
+
+impl<T,U> Transformable for BoundingBox<T,U>
+where T: PointMeasurable, U: PointDataAccess
+{
+  // a rotated box is no longer axis aligned, so it grows to hold its corners
+  fn transform(&self, isometry: &Isometry3<f32>) -> Self {
+    self.map_corners(|p| isometry * p)
+  }
+
+  fn transform_matrix(&self, matrix: &Matrix4<f32>) -> Self {
+    self.map_corners(|p| matrix.transform_point(p))
+  }
+}
@@ -0,0 +1,6 @@
+#![allow(dead_code)]
+
+/// Asserts that each coordinate of `a` is within `tolerance` of `b`.
+pub fn assert_near(a: [f32;3], b: [f32;3], tolerance: f32) {
+  assert!(a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() < tolerance), "{:?} != {:?}", a, b);
+}
@@ -0,0 +1,107 @@
+use sdcar_pcl::filters::deskew::*;
+use sdcar_pcl::point::*;
+use sdcar_pcl::point_cloud::*;
+use sdcar_types::kinematics::Pose2D;
+use sdcar_types::msg::point_cloud2::{PointCloud2, PointField};
+use sdcar_types::msg::vehicle_state::VehicleState;
+use sdcar_types::Error;
+use chrono::{Duration, TimeZone, Utc};
+use nalgebra::{Isometry3, Vector3};
+use std::f32::consts::FRAC_PI_2;
+use std::sync::Arc;
+use common::assert_near;
+mod common;
+
+// a sweep seeing the same spot 10m ahead at each of `times` after its stamp
+fn sweep(times: &[f32]) -> PointCloud<PointXYZIRT> {
+  let fields = vec![
+    PointField::new("x".to_string(), 0, PointField::FLOAT32, 1),
+    PointField::new("y".to_string(), 4, PointField::FLOAT32, 1),
+    PointField::new("z".to_string(), 8, PointField::FLOAT32, 1),
+    PointField::new("intensity".to_string(), 12, PointField::FLOAT32, 1),
+    PointField::new("ring".to_string(), 16, PointField::UINT16, 1),
+    PointField::new("time".to_string(), 18, PointField::FLOAT32, 1),
+  ];
+  let mut data = Vec::new();
+  for (i, time) in times.iter().enumerate() {
+    let mut point = vec![0u8; 22];
+    point[0..4].copy_from_slice(&10.0f32.to_le_bytes());
+    point[12..16].copy_from_slice(&(i as f32).to_le_bytes());
+    point[16..18].copy_from_slice(&(i as u16).to_le_bytes());
+    point[18..22].copy_from_slice(&time.to_le_bytes());
+    data.extend_from_slice(&point);
+  }
+  let pc2 = PointCloud2::new(1635404341, 0, "velodyne".to_string(), 1, times.len() as u32, fields, false, 22, 22 * times.len() as u32, data, true);
+  PointCloud::<PointXYZIRT>::try_new(Arc::new(pc2)).unwrap()
+}
+
+fn vehicle_state(yaw_rate_degrees: f32, v_mps: f32) -> VehicleState {
+  VehicleState::new(1635404341, 0, "base_link".to_string(), 1635404341, 0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, yaw_rate_degrees, 0.0, 0.0, 0.01, 0.0, 0.0, v_mps)
+}
+
+#[test]
+fn constant_velocity_deskew_test() {
+  let cloud = sweep(&[0.0, 0.05, 0.1]);
+
+  // driving straight on at 10m/s the spot was passed by 1m over the sweep
+  let deskew = Deskew::new(ConstantVelocity::from(&vehicle_state(0.0, 10.0)));
+  let deskewed = deskew.filter(&cloud).unwrap();
+  assert_near(deskewed.point(0).xyz(), [10.0, 0.0, 0.0], 1e-4);
+  assert_near(deskewed.point(1).xyz(), [10.5, 0.0, 0.0], 1e-4);
+  assert_near(deskewed.point(2).xyz(), [11.0, 0.0, 0.0], 1e-4);
+  // the data, time included, and frame are kept
+  assert_eq!((deskewed.point(2).ring(), deskewed.point(2).time()), (2, 0.1));
+  assert_eq!(deskewed.frame_id(), Some("velodyne"));
+
+  let at_end = deskew.clone().with_reference_time(0.1).filter(&cloud).unwrap();
+  assert_near(at_end.point(0).xyz(), [9.0, 0.0, 0.0], 1e-4);
+  assert_near(at_end.point(2).xyz(), [10.0, 0.0, 0.0], 1e-4);
+
+  // turning on the spot at 90 degrees a second, a tenth of a sweep turns 9 degrees
+  let turning = Deskew::new(ConstantVelocity::from(&vehicle_state(90.0, 0.0))).filter(&cloud).unwrap();
+  let angle = 0.1 * FRAC_PI_2;
+  assert_near(turning.point(2).xyz(), [10.0 * angle.cos(), 10.0 * angle.sin(), 0.0], 1e-4);
+
+  // a lidar facing the vehicle's left sees the forward motion as going right
+  let facing_left = deskew.with_sensor_to_vehicle(Isometry3::new(Vector3::new(1.0, 0.0, 1.8), Vector3::z() * FRAC_PI_2));
+  assert_near(facing_left.filter(&cloud).unwrap().point(2).xyz(), [10.0, -1.0, 0.0], 1e-4);
+}
+
+#[test]
+fn pose_trajectory_deskew_test() {
+  let stamp = Utc.timestamp_opt(1635404341, 0).unwrap();
+  let pose = |ms: i64, x: f64, yaw: f64| Pose2D {timestamp: stamp + Duration::milliseconds(ms), x, y: 5.0, yaw};
+  // facing along y while sliding along x at 10m/s, given out of order
+  let trajectory = PoseTrajectory::new(vec![pose(200, 3.0, FRAC_PI_2 as f64), pose(-100, 0.0, FRAC_PI_2 as f64)]);
+  assert!((trajectory.pose(stamp).unwrap().x - 1.0).abs() < 1e-9);
+
+  let cloud = sweep(&[0.0, 0.1]);
+  let deskewed = Deskew::new(trajectory.clone()).filter(&cloud).unwrap();
+  assert_near(deskewed.point(0).xyz(), [10.0, 0.0, 0.0], 1e-4);
+  // the map's x is the vehicle's right, its -y
+  assert_near(deskewed.point(1).xyz(), [10.0, -1.0, 0.0], 1e-4);
+
+  match Deskew::new(trajectory).filter(&sweep(&[0.0, 0.25])) {
+    Err(Error::Deskew(message)) => assert!(message.contains("outside the trajectory"), "{}", message),
+    other => panic!("expected a deskew error, got {:?}", other.map(|cloud| cloud.num_points())),
+  }
+  assert!(matches!(PoseTrajectory::new(Vec::new()).pose(stamp), Err(Error::Deskew(_))));
+}
+
+#[test]
+fn deskew_points_test() {
+  // a cloud rebuilt from points, as crop and segmentation give, has no stamp
+  let cloud = sweep(&[0.0, 0.05, 0.1]);
+  let points = PointCloud::<PointXYZIRT>::from(cloud.point_indices_ref().as_ref().clone()).with_frame_id("velodyne");
+  let deskew = Deskew::new(ConstantVelocity::from(&vehicle_state(0.0, 10.0)));
+  match deskew.filter(&points) {
+    Err(Error::Deskew(message)) => assert!(message.contains("PointCloud2"), "{}", message),
+    other => panic!("expected a deskew error, got {:?}", other.map(|cloud| cloud.num_points())),
+  }
+
+  // unless given explicitly
+  let stamp = Utc.timestamp_opt(1635404341, 0).unwrap();
+  let deskewed = deskew.filter_at(&points, stamp).unwrap();
+  assert_near(deskewed.point(2).xyz(), [11.0, 0.0, 0.0], 1e-4);
+  assert_eq!(deskewed.frame_id(), Some("velodyne"));
+}
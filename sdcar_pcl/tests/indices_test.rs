@@ -0,0 +1,88 @@
+use sdcar_pcl::indices::*;
+use sdcar_pcl::point::*;
+use sdcar_pcl::point_cloud::*;
+use sdcar_pcl::filters::*;
+use sdcar_pcl::filters::crop_box::*;
+use sdcar_pcl::search::kdtree::*;
+use sdcar_pcl::segmentation::*;
+use sdcar_pcl::segmentation::extract_clusters::*;
+use std::sync::Arc;
+
+// a ground plane at z = 0 with two returns at each of two spots above it
+fn duplicate_returns_cloud() -> PointCloudXYZIntensity {
+  let mut point_indices = PointXYZIntensityIndices::new();
+  for i in 0..10 {
+    for j in 0..10 {
+      point_indices.push(Arc::new(PointXYZIntensity::new(i as f32, j as f32, 0.0, 1.0)));
+    }
+  }
+  for (x, y) in [(2.0, 2.0), (7.0, 7.0)] {
+    point_indices.push(Arc::new(PointXYZIntensity::new(x, y, 2.0, 5.0)));
+    point_indices.push(Arc::new(PointXYZIntensity::new(x, y, 2.0, 5.0)));
+  }
+  PointCloudXYZIntensity::from(point_indices)
+}
+
+#[test]
+fn indices_set_operations_test() {
+  let a = Indices::from(vec![5, 1, 3, 3, 7]);
+  assert_eq!(a.as_slice(), &[1, 3, 5, 7]);
+  let b: Indices = [2, 3, 4, 5].iter().copied().collect();
+
+  assert_eq!(a.union(&b).as_slice(), &[1, 2, 3, 4, 5, 7]);
+  assert_eq!(a.intersection(&b).as_slice(), &[3, 5]);
+  assert_eq!(a.difference(&b).as_slice(), &[1, 7]);
+  assert_eq!(a.complement(9).as_slice(), &[0, 2, 4, 6, 8]);
+  assert_eq!(Indices::new().complement(3), Indices::all(3));
+  assert_eq!(Indices::all(3).complement(3), Indices::new());
+  assert!(a.contains(5) && !a.contains(4));
+  assert_eq!(a.union(&a.complement(9)), Indices::all(9));
+  assert!(a.intersection(&a.complement(9)).is_empty());
+  assert_eq!((&a).into_iter().sum::<usize>(), 16);
+}
+
+#[test]
+fn indices_filters_test() {
+  let cloud = duplicate_returns_cloud();
+  assert_eq!(cloud.num_points(), 104);
+
+  let raised = crop_box_indices(&cloud, MinVec::new(0.0, 0.0, 1.0), MaxVec::new(10.0, 10.0, 3.0), false);
+  assert_eq!(raised.as_slice(), &[100, 101, 102, 103]);
+  let ground = crop_box_indices(&cloud, MinVec::new(0.0, 0.0, 1.0), MaxVec::new(10.0, 10.0, 3.0), true);
+  assert_eq!(ground, raised.complement(cloud.num_points()));
+  assert_eq!(cloud.select(&raised).len(), 4);
+
+  // matching points by value drops the duplicate return along with the
+  // inlier, while matching by index keeps it
+  let inliers = Indices::from(vec![100, 5]);
+  let outliers = outlier_filter(&cloud, cloud.select(&inliers));
+  assert_eq!(outliers.len(), 101);
+  assert!(!outliers.contains(&cloud.point(101)));
+  let outliers = outlier_indices(cloud.num_points(), &inliers);
+  assert_eq!(outliers.len(), 102);
+  assert!(outliers.contains(101) && !outliers.contains(100));
+
+  let (inliers, outliers) = segment_planes_ransac_indices(&cloud, 30, 0.1);
+  assert_eq!(inliers, Indices::all(100));
+  assert_eq!(outliers, raised);
+  let (inlier_cloud, outlier_cloud) = segment_planes_ransac(&cloud, 30, 0.1);
+  assert_eq!((inlier_cloud.num_points(), outlier_cloud.num_points()), (100, 4));
+}
+
+#[test]
+fn indices_clusters_test() {
+  let cloud = duplicate_returns_cloud();
+  let raised = crop_box_indices(&cloud, MinVec::new(0.0, 0.0, 1.0), MaxVec::new(10.0, 10.0, 3.0), false);
+  let raised_cloud = Arc::new(PointCloudXYZIntensity::from(cloud.select(&raised)));
+
+  let kdtree_ref = Arc::new(KdTree::from(raised_cloud.point_indices_ref()));
+  assert_eq!(kdtree_ref.search_ids(raised_cloud.point(0), 0.5).len(), 2);
+
+  let cluster_extract = ClustersExtractXYZIntensity::new(raised_cloud.clone(), 0.5, 2, 10, kdtree_ref);
+  let mut clusters = cluster_extract.extract_indices();
+  clusters.sort_by_key(|cluster| cluster.as_slice()[0]);
+  // both returns at a spot are in its cluster
+  assert_eq!(clusters, vec![Indices::from(vec![0, 1]), Indices::from(vec![2, 3])]);
+  assert_eq!(cluster_extract.extract().len(), 2);
+  assert!(cluster_extract.extract_parallel().iter().all(|cluster| cluster.len() == 2));
+}
@@ -0,0 +1,157 @@
+use sdcar_pcl::indices::*;
+use sdcar_pcl::point_cloud::*;
+use sdcar_pcl::point_cloud_soa::*;
+use sdcar_pcl::filters::crop_box::*;
+use sdcar_pcl::filters::voxel_grid::*;
+use sdcar_pcl::filters::{MaxVec, MinVec};
+use sdcar_pcl::search::kdtree_soa::*;
+use sdcar_pcl::segmentation::*;
+use sdcar_pcl::segmentation::sac::*;
+use sdcar_pcl::segmentation::extract_clusters::*;
+use sdcar_pcl::HasCreationTimestamp;
+use sdcar_types::msg::point_cloud2::{PointCloud2, PointField};
+use sdcar_types::Error;
+use nalgebra::Vector3;
+use std::sync::Arc;
+
+// a ground plane at z = 0 with two boxes of points above it
+fn scene() -> PointCloudSoa {
+  let mut cloud = PointCloudSoa::default();
+  for i in 0..20 {
+    for j in 0..20 {
+      cloud.push(i as f32 * 0.5 - 5.0, j as f32 * 0.5 - 5.0, 0.0, 1.0);
+    }
+  }
+  for (cx, cy) in [(2.0f32, 2.0f32), (-3.0, -2.0)] {
+    for i in 0..4 {
+      for j in 0..4 {
+        cloud.push(cx + i as f32 * 0.1, cy + j as f32 * 0.1, 1.0 + (i + j) as f32 * 0.05, 50.0);
+      }
+    }
+  }
+  cloud
+}
+
+fn sorted(mut indices: Vec<usize>) -> Vec<usize> {
+  indices.sort_unstable();
+  indices
+}
+
+#[test]
+fn point_cloud_soa_conversion_test() {
+  let mut data = Vec::new();
+  for i in 0..3 {
+    for value in [i as f32, 2.0 * i as f32, -(i as f32), 0.0, 10.0 * i as f32] {
+      data.extend_from_slice(&value.to_le_bytes());
+    }
+  }
+  let mut fields = vec![
+    PointField::new("x".to_string(), 0, PointField::FLOAT32, 1),
+    PointField::new("y".to_string(), 4, PointField::FLOAT32, 1),
+    PointField::new("z".to_string(), 8, PointField::FLOAT32, 1),
+    PointField::new("intensity".to_string(), 16, PointField::FLOAT32, 1),
+  ];
+  let pc2 = PointCloud2::new(1635404341, 0, "lidar".to_string(), 1, 3, fields.clone(), false, 20, 60, data.clone(), true);
+  let soa = PointCloudSoa::try_new(Arc::new(pc2.clone())).unwrap();
+  assert_eq!(soa.len(), 3);
+  assert_eq!(soa.xyz(2), [2.0, 4.0, -2.0]);
+  assert_eq!(soa.intensity(), &[0.0, 10.0, 20.0]);
+
+  // both layouts decode the same points and convert into each other
+  let aos = PointCloudXYZIntensity::from(pc2);
+  let from_aos = PointCloudSoa::from(&aos);
+  assert_eq!(from_aos.x(), soa.x());
+  assert_eq!(from_aos.intensity(), soa.intensity());
+  let back = PointCloudXYZIntensity::from(&soa);
+  assert_eq!(back.num_points(), 3);
+  assert_eq!(back.timestamp(), aos.timestamp());
+  assert_eq!(back.frame_id(), Some("lidar"));
+  let moved = PointCloudSoa::from(&aos.clone().with_frame_id("base_link"));
+  assert_eq!(moved.frame_id(), Some("base_link"));
+  assert_eq!(moved.view().to_cloud().frame_id(), Some("base_link"));
+  assert_eq!(moved.timestamp(), aos.timestamp());
+  for i in 0..3 {
+    assert_eq!(*back.point(i), *aos.point(i));
+  }
+
+  let view = soa.select(Indices::from(vec![2, 0]));
+  assert_eq!(view.xyz(1), [2.0, 4.0, -2.0]);
+  assert_eq!(view.select(&[1]).indices().as_slice(), &[2]);
+  let copy = view.to_soa();
+  assert_eq!(copy.x(), &[0.0, 2.0]);
+  assert_eq!(copy.frame_id(), Some("lidar"));
+  assert_eq!(view.to_cloud().timestamp(), aos.timestamp());
+  assert_eq!(copy.intensity(), &[0.0, 20.0]);
+  let (min_vec, max_vec) = view.min_max();
+  assert_eq!((min_vec.z, max_vec.y), (-2.0, 4.0));
+  let voxels = filter_points_soa(&view, Vector3::<usize>::new(2, 2, 2));
+  assert_eq!(voxels.frame_id(), Some("lidar"));
+  assert_eq!(voxels.timestamp(), soa.timestamp());
+
+  fields.pop();
+  let pc2 = PointCloud2::new(1635404341, 0, "lidar".to_string(), 1, 3, fields, false, 20, 60, data, true);
+  match PointCloudSoa::try_new(Arc::new(pc2)) {
+    Err(Error::Decode { field, .. }) => assert_eq!(field, "intensity"),
+    other => panic!("expected a missing intensity error, got {:?}", other.map(|cloud| cloud.len())),
+  }
+}
+
+#[test]
+fn point_cloud_soa_filters_test() {
+  let soa = scene();
+  let aos = PointCloudXYZIntensity::from(&soa);
+
+  let (min_vec, max_vec) = (MinVec::new(-1.0, -1.0, -0.5), MaxVec::new(2.5, 2.5, 2.0));
+  let cropped = crop_box_soa(&soa.view(), min_vec, max_vec, false);
+  let cropped_aos = point_indices_bounding_box(aos.point_indices_ref(), min_vec, max_vec, false);
+  assert_eq!(cropped.len(), cropped_aos.len());
+  assert_eq!(cropped.len() + crop_box_soa(&soa.view(), min_vec, max_vec, true).len(), soa.len());
+  // the boxes above z = 0.5 out of what was cropped
+  let raised = crop_box_soa(&cropped, MinVec::new(-10.0, -10.0, 0.5), MaxVec::new(10.0, 10.0, 10.0), false);
+  assert_eq!(raised.len(), 16);
+  assert!(raised.indices().iter().all(|&i| soa.intensity()[i] == 50.0));
+
+  // voxel centroids agree with those of the PointCloud
+  let leaf_count = Vector3::<usize>::new(8, 8, 4);
+  let voxels = filter_points_soa(&soa.view(), leaf_count);
+  let voxels_aos = filter_points(&aos, leaf_count);
+  assert_eq!(voxels.len(), voxels_aos.num_points());
+  for i in 0..voxels.len() {
+    assert_eq!(voxels.point(i), *voxels_aos.point(i));
+  }
+  assert!(filter_points_soa(&soa.select(Indices::new()), leaf_count).is_empty());
+}
+
+#[test]
+fn point_cloud_soa_segmentation_test() {
+  let soa = scene();
+
+  let (inliers, outliers) = segment_planes_ransac_soa(&soa.view(), 50, 0.2);
+  assert_eq!(inliers.len(), 400);
+  assert!(inliers.indices().iter().all(|&i| soa.z()[i] == 0.0));
+  assert_eq!(outliers.len(), 32);
+  assert!(ransac_soa(&soa.select(Indices::new()), 10, 0.2).is_empty());
+
+  let view = soa.view();
+  let kdtree = KdTreeSoa::new(&view);
+  assert_eq!(kdtree.len(), soa.len());
+  let target = [0.1, 0.1, 0.0];
+  let brute: Vec<usize> = (0..soa.len())
+    .filter(|&i| {
+      let p = soa.xyz(i);
+      (p[0] - target[0]).powi(2) + (p[1] - target[1]).powi(2) + (p[2] - target[2]).powi(2) <= 0.8 * 0.8
+    })
+    .collect();
+  assert_eq!(sorted(kdtree.search(target, 0.8)), brute);
+
+  let clusters = extract_clusters_soa(&outliers, 0.3, 4, 100);
+  assert_eq!(clusters.len(), 2);
+  for cluster in clusters.iter() {
+    assert_eq!(cluster.len(), 16);
+    let (min_vec, max_vec) = cluster.min_max();
+    assert!(max_vec.x - min_vec.x < 0.5 && max_vec.y - min_vec.y < 0.5);
+  }
+  // clusters stop growing at the maximum size, and smaller ones are dropped
+  assert!(extract_clusters_soa(&outliers, 0.3, 4, 10).iter().all(|cluster| cluster.len() <= 10));
+  assert!(extract_clusters_soa(&outliers, 0.3, 17, 100).is_empty());
+}
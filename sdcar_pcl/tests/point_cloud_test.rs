@@ -0,0 +1,173 @@
+use sdcar_pcl::point::*;
+use sdcar_pcl::point_cloud::*;
+use sdcar_types::msg::point_cloud2::{PointCloud2, PointField};
+use sdcar_types::Error;
+use std::sync::Arc;
+
+// Velodyne style packed points: x y z intensity float32, ring uint16, time float32
+fn velodyne_cloud(with_intensity: bool) -> PointCloud2 {
+  let mut fields = vec![
+    PointField::new("x".to_string(), 0, PointField::FLOAT32, 1),
+    PointField::new("y".to_string(), 4, PointField::FLOAT32, 1),
+    PointField::new("z".to_string(), 8, PointField::FLOAT32, 1),
+    PointField::new("ring".to_string(), 16, PointField::UINT16, 1),
+    PointField::new("time".to_string(), 18, PointField::FLOAT32, 1),
+  ];
+  if with_intensity {
+    fields.insert(3, PointField::new("intensity".to_string(), 12, PointField::FLOAT32, 1));
+  }
+  let mut data = Vec::new();
+  for i in 0..4 {
+    let f = i as f32;
+    for value in [f, f + 0.5, -f, 10.0 * f] {
+      data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(&(i as u16).to_le_bytes());
+    data.extend_from_slice(&(0.001 * f).to_le_bytes());
+  }
+  PointCloud2::new(1635404341, 0, "velodyne".to_string(), 1, 4, fields, false, 22, 88, data, true)
+}
+
+#[test]
+fn point_cloud_decode_test() {
+  let cloud = PointCloud::<PointXYZIntensity>::try_new(Arc::new(velodyne_cloud(true))).unwrap();
+  assert_eq!(cloud.num_points(), 4);
+  let p = cloud.point(3);
+  assert_eq!(p.xyz(), [3.0, 3.5, -3.0]);
+  assert_eq!(p.i(), 30.0);
+
+  let plain = PointCloud::<PointXYZPlain>::try_new(Arc::new(velodyne_cloud(false))).unwrap();
+  assert_eq!(plain.num_points(), 4);
+  assert_eq!(plain.point(2).xyz(), [2.0, 2.5, -2.0]);
+
+  // the layout this crate used to assume, intensity at offset 16
+  let fields = vec![
+    PointField::new("x".to_string(), 0, PointField::FLOAT32, 1),
+    PointField::new("y".to_string(), 4, PointField::FLOAT32, 1),
+    PointField::new("z".to_string(), 8, PointField::FLOAT32, 1),
+    PointField::new("intensity".to_string(), 16, PointField::FLOAT32, 1),
+  ];
+  let mut data = Vec::new();
+  for value in [1.0f32, 2.0, 3.0, 0.0, 42.0] {
+    data.extend_from_slice(&value.to_le_bytes());
+  }
+  let pc2 = PointCloud2::new(1635404341, 0, "lidar".to_string(), 1, 1, fields, false, 20, 20, data, true);
+  let cloud = PointCloudXYZIntensity::from(pc2);
+  assert_eq!(cloud.point(0).xyz(), [1.0, 2.0, 3.0]);
+  assert_eq!(cloud.point(0).i(), 42.0);
+}
+
+#[test]
+fn point_cloud_missing_field_test() {
+  match PointCloud::<PointXYZIntensity>::try_new(Arc::new(velodyne_cloud(false))) {
+    Err(Error::Decode { field, .. }) => assert_eq!(field, "intensity"),
+    other => panic!("expected a missing intensity error, got {:?}", other.map(|cloud| cloud.num_points())),
+  }
+}
+
+#[test]
+#[should_panic(expected = "cannot decode field 'intensity'")]
+fn point_cloud_new_panics_on_missing_field_test() {
+  PointCloud::<PointXYZIntensity>::new(Arc::new(velodyne_cloud(false)));
+}
+
+// Ouster style: x y z, pad, intensity float32, t uint32 ns, reflectivity uint16,
+// ring uint8, ambient uint16
+fn ouster_cloud() -> PointCloud2 {
+  let fields = vec![
+    PointField::new("x".to_string(), 0, PointField::FLOAT32, 1),
+    PointField::new("y".to_string(), 4, PointField::FLOAT32, 1),
+    PointField::new("z".to_string(), 8, PointField::FLOAT32, 1),
+    PointField::new("intensity".to_string(), 16, PointField::FLOAT32, 1),
+    PointField::new("t".to_string(), 20, PointField::UINT32, 1),
+    PointField::new("reflectivity".to_string(), 24, PointField::UINT16, 1),
+    PointField::new("ring".to_string(), 26, PointField::UINT8, 1),
+    PointField::new("ambient".to_string(), 28, PointField::UINT16, 1),
+  ];
+  let mut data = Vec::new();
+  for i in 0..6u32 {
+    let mut point = vec![0u8; 32];
+    point[0..4].copy_from_slice(&(i as f32).to_le_bytes());
+    point[16..20].copy_from_slice(&(5.0 * i as f32).to_le_bytes());
+    point[20..24].copy_from_slice(&(i * 50_000_000).to_le_bytes());
+    point[24..26].copy_from_slice(&(i as u16 + 100).to_le_bytes());
+    point[26] = (i % 2) as u8;
+    point[28..30].copy_from_slice(&(i as u16 + 200).to_le_bytes());
+    data.extend_from_slice(&point);
+  }
+  PointCloud2::new(1635404341, 0, "os_lidar".to_string(), 2, 3, fields, false, 32, 96, data, true)
+}
+
+#[test]
+fn point_types_decode_test() {
+  let velodyne = Arc::new(velodyne_cloud(true));
+  let cloud = PointCloud::<PointXYZIR>::try_new(velodyne.clone()).unwrap();
+  assert_eq!((cloud.point(2).ring(), cloud.point(2).i()), (2, 20.0));
+
+  let cloud = PointCloud::<PointXYZIRT>::try_new(velodyne).unwrap();
+  let p = cloud.point(3);
+  assert_eq!(p.xyz(), [3.0, 3.5, -3.0]);
+  assert_eq!((p.ring(), p.time()), (3, 0.003));
+  assert_eq!(p.data().unwrap().time(), 0.003);
+
+  let ouster = Arc::new(ouster_cloud());
+  let cloud = PointCloud::<PointXYZReflectivity>::try_new(ouster.clone()).unwrap();
+  assert_eq!(cloud.num_points(), 6);
+  let p = cloud.point(5);
+  assert_eq!(p.x(), 5.0);
+  assert_eq!(p.i(), 25.0);
+  assert_eq!((p.reflectivity(), p.ambient(), p.ring()), (105, 205, 1));
+  // t is in nanoseconds
+  assert!((p.time() - 0.25).abs() < 1e-7, "{}", p.time());
+
+  // Ouster clouds decode as ring and time points too
+  let cloud = PointCloud::<PointXYZIRT>::try_new(ouster).unwrap();
+  assert!((cloud.point(2).time() - 0.1).abs() < 1e-7);
+
+  let fields = vec![
+    PointField::new("x".to_string(), 0, PointField::FLOAT32, 1),
+    PointField::new("y".to_string(), 4, PointField::FLOAT32, 1),
+    PointField::new("z".to_string(), 8, PointField::FLOAT32, 1),
+    PointField::new("normal_x".to_string(), 16, PointField::FLOAT32, 1),
+    PointField::new("normal_y".to_string(), 20, PointField::FLOAT32, 1),
+    PointField::new("normal_z".to_string(), 24, PointField::FLOAT32, 1),
+    PointField::new("curvature".to_string(), 32, PointField::FLOAT32, 1),
+  ];
+  let mut data = Vec::new();
+  for value in [1.0f32, 2.0, 3.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.02, 0.0, 0.0, 0.0] {
+    data.extend_from_slice(&value.to_le_bytes());
+  }
+  let pc2 = PointCloud2::new(1635404341, 0, "map".to_string(), 1, 1, fields, false, 48, 48, data, true);
+  let cloud = PointCloud::<PointXYZNormal>::try_new(Arc::new(pc2)).unwrap();
+  assert_eq!(cloud.point(0).xyz(), [1.0, 2.0, 3.0]);
+  assert_eq!(cloud.point(0).normal(), [0.0, 0.0, 1.0]);
+  assert_eq!(cloud.point(0).curvature(), 0.02);
+}
+
+#[test]
+fn point_types_missing_field_test() {
+  let mut no_time = velodyne_cloud(true);
+  no_time.fields.retain(|field| field.name != "time");
+  let no_time = Arc::new(no_time);
+  assert!(PointCloud::<PointXYZIR>::try_new(no_time.clone()).is_ok());
+  match PointCloud::<PointXYZIRT>::try_new(no_time) {
+    Err(Error::Decode { field, .. }) => assert_eq!(field, "time"),
+    other => panic!("expected a missing time error, got {:?}", other.map(|cloud| cloud.num_points())),
+  }
+  match PointCloud::<PointXYZReflectivity>::try_new(Arc::new(velodyne_cloud(true))) {
+    Err(Error::Decode { field, .. }) => assert_eq!(field, "reflectivity"),
+    other => panic!("expected a missing reflectivity error, got {:?}", other.map(|cloud| cloud.num_points())),
+  }
+  assert!(PointCloud::<PointXYZNormal>::try_new(Arc::new(ouster_cloud())).is_err());
+}
+
+#[test]
+fn point_types_hash_test() {
+  use std::collections::HashSet;
+  let a = PointXYZIRT::new(1.0, 2.0, 3.0, 10.0, 4, 0.0125);
+  let b = PointXYZIRT::new(1.0, 2.0, 3.0, 10.0, 5, 0.0125);
+  let set: HashSet<_> = [a, a, b].into_iter().collect();
+  assert_eq!(set.len(), 2);
+  assert!(a < b);
+  assert_eq!(PointXYZNormal::new(0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.1), PointXYZNormal::new(0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.1));
+}
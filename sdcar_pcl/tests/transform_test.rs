@@ -0,0 +1,130 @@
+use sdcar_pcl::bounding_box::*;
+use sdcar_pcl::point::*;
+use sdcar_pcl::point_cloud::*;
+use sdcar_pcl::transform::*;
+use sdcar_types::msg::point_cloud2::{PointCloud2, PointField};
+use sdcar_types::Error;
+use nalgebra::{Isometry3, Matrix3x4, Matrix4, Vector3};
+use std::f32::consts::FRAC_PI_2;
+use std::sync::Arc;
+use common::assert_near;
+mod common;
+
+fn velodyne_cloud() -> PointCloudXYZIntensity {
+  let fields = vec![
+    PointField::new("x".to_string(), 0, PointField::FLOAT32, 1),
+    PointField::new("y".to_string(), 4, PointField::FLOAT32, 1),
+    PointField::new("z".to_string(), 8, PointField::FLOAT32, 1),
+    PointField::new("intensity".to_string(), 12, PointField::FLOAT32, 1),
+  ];
+  let mut data = Vec::new();
+  for value in [1.0f32, 0.0, 0.0, 7.0, 0.0, 2.0, -1.0, 9.0] {
+    data.extend_from_slice(&value.to_le_bytes());
+  }
+  let pc2 = PointCloud2::new(1635404341, 0, "velodyne".to_string(), 1, 2, fields, false, 16, 32, data, true);
+  PointCloudXYZIntensity::from(pc2)
+}
+
+#[test]
+fn point_cloud_transform_test() {
+  let cloud = velodyne_cloud();
+  assert_eq!(cloud.frame_id(), Some("velodyne"));
+
+  // lidar 1.8m up, turned to face the vehicle's left
+  let velodyne_to_base = FrameTransform::new(
+    "velodyne", "base_link",
+    Isometry3::new(Vector3::new(0.5, 0.0, 1.8), Vector3::z() * FRAC_PI_2),
+  );
+  let base = cloud.transform_to(&velodyne_to_base).unwrap();
+  assert_eq!(base.frame_id(), Some("base_link"));
+  assert_near(base.point(0).xyz(), [0.5, 1.0, 1.8], 1e-5);
+  assert_near(base.point(1).xyz(), [-1.5, 0.0, 0.8], 1e-5);
+  assert_eq!(base.point(1).i(), 9.0);
+  // the source cloud is left as it was
+  assert_near(cloud.point(0).xyz(), [1.0, 0.0, 0.0], 1e-5);
+
+  match base.transform_to(&velodyne_to_base) {
+    Err(Error::FrameMismatch { expected, found }) => assert_eq!((expected.as_str(), found.as_str()), ("velodyne", "base_link")),
+    other => panic!("expected a frame mismatch, got {:?}", other.map(|cloud| cloud.num_points())),
+  }
+
+  let back = base.transform_to(&velodyne_to_base.inverse()).unwrap();
+  assert_eq!(back.frame_id(), Some("velodyne"));
+  assert_near(back.point(1).xyz(), cloud.point(1).xyz(), 1e-5);
+
+  let base_to_map = FrameTransform::new("base_link", "map", Isometry3::translation(100.0, 200.0, 0.0));
+  let velodyne_to_map = velodyne_to_base.then(&base_to_map).unwrap();
+  assert_eq!((velodyne_to_map.source_frame_id(), velodyne_to_map.target_frame_id()), ("velodyne", "map"));
+  assert_near(cloud.transform_to(&velodyne_to_map).unwrap().point(0).xyz(), [100.5, 201.0, 1.8], 1e-5);
+  assert!(matches!(base_to_map.then(&velodyne_to_base), Err(Error::FrameMismatch { .. })));
+
+  // the same transform as a homogeneous matrix
+  let matrix = velodyne_to_base.to_homogeneous();
+  assert_near(cloud.transform_matrix(&matrix).point(1).xyz(), [-1.5, 0.0, 0.8], 1e-5);
+  let from_matrix = FrameTransform::from_matrix("velodyne", "base_link", &matrix);
+  assert_near(cloud.transform(from_matrix.isometry()).point(0).xyz(), [0.5, 1.0, 1.8], 1e-5);
+  // transform keeps the frame id, a scaling matrix moves points all the same
+  let scaled = cloud.transform_matrix(&Matrix4::new_scaling(2.0));
+  assert_eq!(scaled.frame_id(), Some("velodyne"));
+  assert_near(scaled.point(1).xyz(), [0.0, 4.0, -2.0], 1e-5);
+
+  // the lidar to left camera extrinsic of LiDAR2Camera
+  let l2c = Matrix3x4::<f64>::new(
+    0.0, -1.0, 0.0, 0.25,
+    0.0, 0.0, -1.0, -0.125,
+    1.0, 0.0, 0.0, -0.115,
+  );
+  let camera = cloud.transform_to(&FrameTransform::from_matrix3x4("velodyne", "camera_left", &l2c)).unwrap();
+  assert_eq!(camera.frame_id(), Some("camera_left"));
+  assert_near(camera.point(0).xyz(), [0.25, -0.125, 0.885], 1e-5);
+  assert_near(camera.point(1).xyz(), [-1.75, 0.875, -0.115], 1e-5);
+
+  // clouds built from points have no frame and take the transform's
+  let built = PointCloudXYZIntensity::from(cloud.point_indices_ref());
+  assert_eq!(built.frame_id(), None);
+  assert_eq!(built.transform_to(&velodyne_to_base).unwrap().frame_id(), Some("base_link"));
+
+  // as do clouds decoded from a PointCloud2 with an empty frame id
+  let mut pc2 = (*cloud.pc2_msg().unwrap()).clone();
+  pc2.header.frame_id.clear();
+  let unnamed = PointCloudXYZIntensity::from(pc2);
+  assert_eq!(unnamed.frame_id(), None);
+  assert_eq!(unnamed.transform_to(&velodyne_to_base).unwrap().frame_id(), Some("base_link"));
+}
+
+#[test]
+fn normal_transform_test() {
+  let point_indices: PointIndices<PointXYZNormal> = vec![Arc::new(PointXYZNormal::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.25))];
+  let cloud = PointCloud::<PointXYZNormal>::from(point_indices);
+  let turned = cloud.transform(&Isometry3::new(Vector3::new(0.0, 0.0, 5.0), Vector3::z() * FRAC_PI_2));
+  assert_near(turned.point(0).xyz(), [0.0, 1.0, 5.0], 1e-5);
+  // normals turn but are not translated
+  assert_near(turned.point(0).normal(), [0.0, 1.0, 0.0], 1e-5);
+  assert_eq!(turned.point(0).curvature(), 0.25);
+
+  // and stay of unit length, perpendicular to surfaces, under scaling
+  let stretched = cloud.transform_matrix(&Matrix4::new_nonuniform_scaling(&Vector3::new(3.0, 1.0, 1.0)));
+  assert_near(stretched.point(0).normal(), [1.0, 0.0, 0.0], 1e-5);
+}
+
+#[test]
+fn bounding_box_transform_test() {
+  let point_min = PointXYZIntensity::new(0.0, 0.0, 0.0, 0.0);
+  let point_max = PointXYZIntensity::new(4.0, 2.0, 1.0, 0.0);
+  let bb = BoundingBoxXYZIntensity::new(Some(1), Some(2), None, point_min, point_max);
+
+  let moved = bb.transform(&Isometry3::translation(10.0, 0.0, -1.0));
+  assert_near(moved.point_min().xyz(), [10.0, 0.0, -1.0], 1e-5);
+  assert_near(moved.point_max().xyz(), [14.0, 2.0, 0.0], 1e-5);
+  assert_eq!((moved.box_id, moved.cluster_id, moved.track_id), (Some(1), Some(2), None));
+
+  // turned a quarter the box's x and y extents swap
+  let turned = bb.transform(&Isometry3::rotation(Vector3::z() * FRAC_PI_2));
+  assert_near(turned.point_min().xyz(), [-2.0, 0.0, 0.0], 1e-5);
+  assert_near(turned.point_max().xyz(), [0.0, 4.0, 1.0], 1e-5);
+  let (dx, dy, dz) = turned.dxyz();
+  assert_near([dx, dy, dz], [2.0, 4.0, 1.0], 1e-5);
+
+  let scaled = bb.transform_matrix(&Matrix4::new_scaling(0.5));
+  assert_near(scaled.point_max().xyz(), [2.0, 1.0, 0.5], 1e-5);
+}
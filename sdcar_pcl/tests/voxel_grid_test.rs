@@ -0,0 +1,82 @@
+use sdcar_pcl::filters::voxel_grid::*;
+use sdcar_pcl::point::*;
+use sdcar_pcl::point_cloud::*;
+use sdcar_types::Error;
+use nalgebra::Vector3;
+use std::sync::Arc;
+use common::assert_near;
+mod common;
+
+// three points in the voxel at the origin, one in the voxel along x, one in
+// the voxel along -y and one that is not finite
+fn cloud() -> PointCloud<PointXYZIRT> {
+  let point_indices: PointIndices<PointXYZIRT> = vec![
+    Arc::new(PointXYZIRT::new(0.1, 0.1, 0.1, 10.0, 3, 0.01)),
+    Arc::new(PointXYZIRT::new(0.5, 0.2, 0.1, 20.0, 4, 0.02)),
+    Arc::new(PointXYZIRT::new(0.3, 0.3, 0.3, 60.0, 5, 0.06)),
+    Arc::new(PointXYZIRT::new(1.5, 0.5, 0.5, 5.0, 6, 0.05)),
+    Arc::new(PointXYZIRT::new(0.5, -0.5, 0.5, 7.0, 7, 0.07)),
+    Arc::new(PointXYZIRT::new(f32::NAN, 0.5, 0.5, 7.0, 7, 0.07)),
+  ];
+  PointCloud::<PointXYZIRT>::from(point_indices).with_frame_id("velodyne")
+}
+
+#[test]
+fn voxel_grid_modes_test() {
+  let cloud = cloud();
+  let voxel_grid = VoxelGrid::try_new(Vector3::new(1.0, 1.0, 1.0)).unwrap();
+  assert_eq!(voxel_grid.mode(), VoxelMode::Centroid);
+
+  // voxels in order, with the point that is not finite dropped
+  let centroids = voxel_grid.filter(&cloud);
+  assert_eq!(centroids.num_points(), 3);
+  assert_eq!(centroids.frame_id(), Some("velodyne"));
+  assert_near(centroids.point(0).xyz(), [0.5, -0.5, 0.5], 1e-5);
+  let origin = centroids.point(1);
+  assert_near(origin.xyz(), [0.3, 0.2, 0.5 / 3.0], 1e-5);
+  // the data is averaged, but for the ring of the first
+  assert!((origin.i() - 30.0).abs() < 1e-5 && (origin.time() - 0.03).abs() < 1e-6);
+  assert_eq!(origin.ring(), 3);
+  assert_near(centroids.point(2).xyz(), [1.5, 0.5, 0.5], 1e-5);
+
+  let nearest = voxel_grid.clone().with_mode(VoxelMode::NearestToCentroid).filter(&cloud);
+  assert!(Arc::ptr_eq(&nearest.point(1), &cloud.point(2)));
+  let first = voxel_grid.clone().with_mode(VoxelMode::First).filter(&cloud);
+  assert!(Arc::ptr_eq(&first.point(1), &cloud.point(0)));
+
+  let dense = voxel_grid.with_min_points_per_voxel(2).filter(&cloud);
+  assert_eq!(dense.num_points(), 1);
+  assert_near(dense.point(0).xyz(), [0.3, 0.2, 0.5 / 3.0], 1e-5);
+}
+
+#[test]
+fn voxel_grid_leaf_size_test() {
+  // the same leaf size gives the same voxels however far the cloud spans
+  let mut point_indices: PointIndices<PointXYZIntensity> = (0..100)
+    .map(|i| Arc::new(PointXYZIntensity::new(i as f32 * 0.1, 0.0, 0.0, i as f32)))
+    .collect();
+  let voxel_grid = VoxelGrid::try_new(Vector3::new(0.5, 0.5, 0.5)).unwrap();
+  assert_eq!(voxel_grid.filter(&PointCloudXYZIntensity::from(point_indices.clone())).num_points(), 20);
+  point_indices.push(Arc::new(PointXYZIntensity::new(100.0, 0.0, 0.0, 0.0)));
+  let spread = voxel_grid.filter(&PointCloudXYZIntensity::from(point_indices));
+  assert_eq!(spread.num_points(), 21);
+  assert_near(spread.point(0).xyz(), [0.2, 0.0, 0.0], 1e-5);
+  assert!((spread.point(0).i() - 2.0).abs() < 1e-5);
+
+  // normals average to unit length
+  let normals: PointIndices<PointXYZNormal> = vec![
+    Arc::new(PointXYZNormal::new(0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.2)),
+    Arc::new(PointXYZNormal::new(0.1, 0.0, 0.0, 0.0, 1.0, 0.0, 0.4)),
+  ];
+  let averaged = voxel_grid.filter(&PointCloud::<PointXYZNormal>::from(normals));
+  let half = std::f32::consts::FRAC_1_SQRT_2;
+  assert_near(averaged.point(0).normal(), [half, half, 0.0], 1e-5);
+  assert!((averaged.point(0).curvature() - 0.3).abs() < 1e-6);
+
+  for leaf_size in [Vector3::new(0.5, 0.0, 0.5), Vector3::new(-1.0, 1.0, 1.0), Vector3::new(1.0, f32::NAN, 1.0), Vector3::new(1.0, 1.0, f32::INFINITY)] {
+    match VoxelGrid::try_new(leaf_size) {
+      Err(Error::InvalidParameter(message)) => assert!(message.contains("leaf size"), "{}", message),
+      other => panic!("expected {:?} to be rejected, got {:?}", leaf_size, other),
+    }
+  }
+}
@@ -0,0 +1,165 @@
+extern crate sdcar_types;
+
+use sdcar_types::msg::point_cloud2::{PointCloud2, PointField};
+use sdcar_types::msg::vehicle_state::VehicleState;
+use sdcar_types::parquet::point_cloud2::PointCloud2Batch;
+use sdcar_types::parquet::ToRecordBatch;
+use sdcar_types::reader::parquet::*;
+use sdcar_types::writer::parquet::*;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const NUM_CLOUDS: i32 = 200;
+const POINTS_PER_CLOUD: usize = 64 * 1024;
+const POINT_STEP: usize = 32;
+const NUM_STATES: i32 = 200_000;
+
+fn point_cloud2(i: i32) -> PointCloud2 {
+    let fields = vec![
+        PointField::new("x".to_string(), 0, 7, 1),
+        PointField::new("y".to_string(), 4, 7, 1),
+        PointField::new("z".to_string(), 8, 7, 1),
+        PointField::new("intensity".to_string(), 16, 7, 1),
+    ];
+    PointCloud2::new(
+        1634357915 + i / 10,
+        (i % 10) as u32 * 100_000_000,
+        "lidar".to_string(),
+        1,
+        POINTS_PER_CLOUD as u32,
+        fields,
+        false,
+        POINT_STEP as u32,
+        (POINTS_PER_CLOUD * POINT_STEP) as u32,
+        (0..POINTS_PER_CLOUD * POINT_STEP)
+            .map(|b| (b as i32 + i) as u8)
+            .collect(),
+        true,
+    )
+}
+
+fn vehicle_state(i: i32) -> VehicleState {
+    let f = i as f32;
+    VehicleState::new(
+        1595047644 + i / 100,
+        (i % 100) as u32 * 10_000_000,
+        "base_link".to_string(),
+        1595047644 + i / 100,
+        (i % 100) as u32 * 10_000_000,
+        f,
+        f,
+        f,
+        f,
+        f,
+        f,
+        f,
+        f,
+        f,
+        f,
+        0.01,
+        f,
+        f,
+        f,
+    )
+}
+
+fn temp_path(name: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("sdcar_types_decode_benchmark_{}.parquet", name))
+        .to_string_lossy()
+        .to_string()
+}
+
+fn report(name: &str, elapsed: Duration, msgs: usize, bytes: usize) {
+    let secs = elapsed.as_secs_f64();
+    println!(
+        "{:<32} {:>8.3} s {:>12.1} msgs/s {:>10.1} MB/s",
+        name,
+        secs,
+        msgs as f64 / secs,
+        bytes as f64 / secs / 1e6
+    );
+}
+
+fn bench_point_clouds(path: &str, batch_size: usize) {
+    let start = Instant::now();
+    let (mut clouds, mut bytes) = (0, 0);
+    for cloud in PointCloud2ParquetRowIter::new(path) {
+        clouds += 1;
+        bytes += cloud.data.len();
+    }
+    report("point_cloud2 rows", start.elapsed(), clouds, bytes);
+
+    let start = Instant::now();
+    let (mut clouds, mut bytes) = (0, 0);
+    let iter = PointCloud2ParquetRowIter::new(path)
+        .with_read_mode(ReadMode::Batches(batch_size))
+        .unwrap();
+    for cloud in iter {
+        clouds += 1;
+        bytes += cloud.data.len();
+    }
+    report(
+        "point_cloud2 record batches",
+        start.elapsed(),
+        clouds,
+        bytes,
+    );
+
+    let start = Instant::now();
+    let (mut clouds, mut bytes) = (0, 0);
+    let batches =
+        ParquetFileBatchIter::new(path, &PointCloud2::arrow_schema(), batch_size).unwrap();
+    for batch in batches {
+        let view = PointCloud2Batch::try_new(batch.unwrap()).unwrap();
+        for i in 0..view.len() {
+            clouds += 1;
+            bytes += view.data(i).len();
+        }
+    }
+    report("point_cloud2 batch views", start.elapsed(), clouds, bytes);
+}
+
+fn bench_vehicle_states(path: &str, batch_size: usize) {
+    let start = Instant::now();
+    let states = VehicleStateParquetRowIter::new(path).count();
+    report("vehicle_state rows", start.elapsed(), states, 0);
+
+    let start = Instant::now();
+    let states = VehicleStateParquetRowIter::new(path)
+        .with_read_mode(ReadMode::Batches(batch_size))
+        .unwrap()
+        .count();
+    report("vehicle_state record batches", start.elapsed(), states, 0);
+}
+
+/// Decodes generated point cloud and vehicle state files through the row
+/// API, the Arrow batch reader and zero-copy batch views. Run with
+/// `--release`.
+///
+/// Point clouds are dominated by copying their data, so small batches that
+/// stay in cache do best; topics of many small scalar rows gain the most
+/// from decoding a column at a time.
+fn main() {
+    let cloud_path = temp_path("point_cloud2");
+    let mut writer = PointCloud2ParquetWriter::new(&cloud_path)
+        .unwrap()
+        .with_batch_size(16);
+    for i in 0..NUM_CLOUDS {
+        writer.write(Arc::new(point_cloud2(i))).unwrap();
+    }
+    writer.close().unwrap();
+    bench_point_clouds(&cloud_path, 1);
+    std::fs::remove_file(&cloud_path).unwrap();
+
+    let state_path = temp_path("vehicle_state");
+    let mut writer = VehicleStateParquetWriter::new(&state_path)
+        .unwrap()
+        .with_batch_size(4096);
+    for i in 0..NUM_STATES {
+        writer.write(Arc::new(vehicle_state(i))).unwrap();
+    }
+    writer.close().unwrap();
+    bench_vehicle_states(&state_path, 1024);
+    std::fs::remove_file(&state_path).unwrap();
+}
@@ -5,9 +5,9 @@ use sdcar_types::msg::*;
 use sdcar_types::reader::combined::*;
 use sdcar_types::reader::parquet::*;
 
-fn print_parquet(path1: &'static str, path2: &'static str) {
-    let image_iter = ImageParquetRowIter::new(&path1);
-    let lidar_iter = PointCloud2ParquetRowIter::new(&path2);
+fn print_parquet(path1: &str, path2: &str) {
+    let image_iter = ImageParquetRowIter::new(path1);
+    let lidar_iter = PointCloud2ParquetRowIter::new(path2);
 
     let reader =
         Reader2::<PointCloud2ParquetRowIter, ImageParquetRowIter>::new(lidar_iter, image_iter);
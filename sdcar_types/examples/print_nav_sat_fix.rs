@@ -12,7 +12,7 @@ fn print_parquet(path: &str) {
     let iter = reader.get_row_iter(None).unwrap();
 
     for row in iter {
-        let gps = NavSatFix::from(row);
+        let gps = NavSatFix::try_from(row).unwrap();
         println!("{:?}", gps);
     }
 }
@@ -9,10 +9,10 @@ fn print_parquet(path: &str) {
     for pc in iter {
         println!(
             "{} {} {} {} {} {} {:?} {} {} {} data.len() = {} {}",
-            pc.stamp_sec,
-            pc.stamp_nanosec,
-            pc.timestamp,
-            pc.frame_id,
+            pc.header.stamp.sec(),
+            pc.header.stamp.nanosec(),
+            pc.header.timestamp(),
+            pc.header.frame_id,
             pc.height,
             pc.width,
             pc.fields,
@@ -12,7 +12,7 @@ fn print_parquet(path: &str) {
     let iter = reader.get_row_iter(None).unwrap();
 
     for row in iter {
-        let vehicle_state = VehicleState::from(row);
+        let vehicle_state = VehicleState::try_from(row).unwrap();
         println!("{:?}", vehicle_state);
     }
 }
@@ -0,0 +1,56 @@
+use crate::cdr::{CdrReader, FromCdr};
+use crate::error::Result;
+use crate::msg::geometry::{Pose, PoseWithCovariance, Twist, TwistWithCovariance};
+use crate::msg::twist_stamped::TwistStamped;
+
+use nalgebra as na;
+
+impl<'d> CdrReader<'d> {
+    /// `geometry_msgs/Vector3` or `geometry_msgs/Point`.
+    pub fn vector3(&mut self, field: &str) -> Result<na::Vector3<f64>> {
+        Ok(na::Vector3::new(
+            self.f64(field)?,
+            self.f64(field)?,
+            self.f64(field)?,
+        ))
+    }
+
+    /// `geometry_msgs/Quaternion`, serialized x, y, z, w.
+    pub fn quaternion(&mut self, field: &str) -> Result<na::Quaternion<f64>> {
+        let xyz = self.vector3(field)?;
+        Ok(na::Quaternion::from_parts(self.f64(field)?, xyz))
+    }
+
+    pub fn pose(&mut self, field: &str) -> Result<Pose> {
+        let position = self.vector3(field)?;
+        Ok(Pose::new(position.into(), self.quaternion(field)?))
+    }
+
+    pub fn twist(&mut self, field: &str) -> Result<Twist> {
+        let linear = self.vector3(field)?;
+        Ok(Twist::new(linear, self.vector3(field)?))
+    }
+
+    pub fn pose_with_covariance(&mut self, field: &str) -> Result<PoseWithCovariance> {
+        Ok(PoseWithCovariance {
+            pose: self.pose(field)?,
+            covariance: self.matrix(field)?,
+        })
+    }
+
+    pub fn twist_with_covariance(&mut self, field: &str) -> Result<TwistWithCovariance> {
+        Ok(TwistWithCovariance {
+            twist: self.twist(field)?,
+            covariance: self.matrix(field)?,
+        })
+    }
+}
+
+impl FromCdr for TwistStamped {
+    const ROS_TYPE: &'static str = "geometry_msgs/msg/TwistStamped";
+
+    fn from_cdr(reader: &mut CdrReader) -> Result<Self> {
+        let header = reader.header()?;
+        Ok(TwistStamped::new(header, reader.twist("twist")?))
+    }
+}
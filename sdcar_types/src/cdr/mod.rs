@@ -0,0 +1,225 @@
+//! Decoding of the CDR serialized messages recorded by rosbag2.
+//!
+//! Payloads start with a four byte encapsulation header selecting the byte
+//! order, followed by the fields in declaration order, each aligned to its
+//! size relative to the end of the encapsulation header.
+
+pub mod geometry_msgs;
+pub mod nav_msgs;
+pub mod sensor_msgs;
+
+use crate::error::{Error, Result};
+use crate::msg::header::{Header, Stamp};
+
+use nalgebra as na;
+
+const ENCAPSULATION_LEN: usize = 4;
+
+/// Decodes a message from its CDR payload.
+pub trait FromCdr: Sized {
+    /// ROS 2 type name of the message, as recorded in the bag, such as
+    /// `sensor_msgs/msg/Image`.
+    const ROS_TYPE: &'static str;
+
+    fn from_cdr(reader: &mut CdrReader) -> Result<Self>;
+
+    fn from_cdr_bytes(data: &[u8]) -> Result<Self> {
+        Self::from_cdr(&mut CdrReader::new(data)?)
+    }
+}
+
+/// Reads the fields of a CDR payload in declaration order. Every read names
+/// the field so a truncated or malformed payload reports where it failed.
+pub struct CdrReader<'d> {
+    data: &'d [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+macro_rules! cdr_primitive {
+    ($name:ident, $ty:ty, $expected:expr) => {
+        pub fn $name(&mut self, field: &str) -> Result<$ty> {
+            const SIZE: usize = std::mem::size_of::<$ty>();
+            let bytes: [u8; SIZE] = self.take(field, $expected, SIZE, SIZE)?.try_into().unwrap();
+            Ok(if self.big_endian {
+                <$ty>::from_be_bytes(bytes)
+            } else {
+                <$ty>::from_le_bytes(bytes)
+            })
+        }
+    };
+}
+
+impl<'d> CdrReader<'d> {
+    pub fn new(data: &'d [u8]) -> Result<Self> {
+        if data.len() < ENCAPSULATION_LEN {
+            return Err(Error::decode(
+                "encapsulation",
+                "cdr",
+                format!("payload of {} bytes is too short", data.len()),
+            ));
+        }
+        // CDR_BE = 0x0000, CDR_LE = 0x0001; the PL_CDR variants of the
+        // second byte are not used for ROS messages
+        let big_endian = match data[1] {
+            0x00 => true,
+            0x01 => false,
+            other => {
+                return Err(Error::decode(
+                    "encapsulation",
+                    "cdr",
+                    format!("unsupported representation {:#04x}", other),
+                ))
+            }
+        };
+        Ok(Self {
+            data: &data[ENCAPSULATION_LEN..],
+            pos: 0,
+            big_endian,
+        })
+    }
+
+    /// Bytes left after the fields read so far.
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn take(
+        &mut self,
+        field: &str,
+        expected: &'static str,
+        align: usize,
+        len: usize,
+    ) -> Result<&'d [u8]> {
+        let start = self.pos.div_ceil(align) * align;
+        let end = start.checked_add(len).filter(|end| *end <= self.data.len());
+        match end {
+            Some(end) => {
+                self.pos = end;
+                Ok(&self.data[start..end])
+            }
+            None => Err(Error::decode(
+                field,
+                expected,
+                format!(
+                    "{} bytes at offset {} overrun the {} byte payload",
+                    len,
+                    start,
+                    self.data.len()
+                ),
+            )),
+        }
+    }
+
+    cdr_primitive!(u8, u8, "uint8");
+    cdr_primitive!(i8, i8, "int8");
+    cdr_primitive!(u16, u16, "uint16");
+    cdr_primitive!(u32, u32, "uint32");
+    cdr_primitive!(i32, i32, "int32");
+    cdr_primitive!(f64, f64, "float64");
+
+    pub fn bool(&mut self, field: &str) -> Result<bool> {
+        Ok(self.u8(field)? != 0)
+    }
+
+    /// A sequence length or string size.
+    fn length(&mut self, field: &str, expected: &'static str) -> Result<usize> {
+        let len = self.u32(field)? as usize;
+        if len > self.remaining() {
+            return Err(Error::decode(
+                field,
+                expected,
+                format!(
+                    "length {} exceeds the remaining {} bytes",
+                    len,
+                    self.remaining()
+                ),
+            ));
+        }
+        Ok(len)
+    }
+
+    /// A string, serialized with its terminating nul.
+    pub fn string(&mut self, field: &str) -> Result<String> {
+        let len = self.length(field, "string")?;
+        let bytes = self.take(field, "string", 1, len)?;
+        let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+        String::from_utf8(bytes.to_vec()).map_err(|e| Error::decode(field, "string", e.to_string()))
+    }
+
+    /// A `uint8[]` sequence, borrowed from the payload.
+    pub fn bytes(&mut self, field: &str) -> Result<&'d [u8]> {
+        let len = self.length(field, "uint8[]")?;
+        self.take(field, "uint8[]", 1, len)
+    }
+
+    /// A `float64[]` sequence.
+    pub fn f64_seq(&mut self, field: &str) -> Result<Vec<f64>> {
+        let len = self.length(field, "float64[]")?;
+        (0..len).map(|_| self.f64(field)).collect()
+    }
+
+    /// A `float64[R * C]` array laid out row major, as ROS lays out
+    /// covariances and camera matrices.
+    pub fn matrix<const R: usize, const C: usize>(
+        &mut self,
+        field: &str,
+    ) -> Result<na::SMatrix<f64, R, C>> {
+        let values = (0..R * C)
+            .map(|_| self.f64(field))
+            .collect::<Result<Vec<f64>>>()?;
+        Ok(na::SMatrix::from_row_slice(&values))
+    }
+
+    /// A sequence of elements read by `element`.
+    pub fn seq<T>(
+        &mut self,
+        field: &str,
+        mut element: impl FnMut(&mut Self) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        let len = self.u32(field)? as usize;
+        // each element takes at least a byte, which bounds the allocation
+        let mut elements = Vec::with_capacity(len.min(self.remaining()));
+        for _ in 0..len {
+            elements.push(element(self)?);
+        }
+        Ok(elements)
+    }
+
+    /// `builtin_interfaces/Time`.
+    pub fn stamp(&mut self, field: &str) -> Result<Stamp> {
+        let sec = self.i32(field)?;
+        let nanosec = self.u32(field)?;
+        Ok(Stamp::new(sec, nanosec))
+    }
+
+    /// `std_msgs/Header`.
+    pub fn header(&mut self) -> Result<Header> {
+        let stamp = self.stamp("header.stamp")?;
+        let frame_id = self.string("header.frame_id")?;
+        Ok(Header::new(stamp, frame_id))
+    }
+}
+
+/// Whether messages of the ROS 2 type `ros_type` can be decoded into a
+/// `crate::msg` struct.
+pub fn is_supported(ros_type: &str) -> bool {
+    use crate::msg::camera_info::CameraInfo;
+    use crate::msg::image::Image;
+    use crate::msg::imu::Imu;
+    use crate::msg::nav_sat_fix::NavSatFix;
+    use crate::msg::odometry::Odometry;
+    use crate::msg::point_cloud2::PointCloud2;
+    use crate::msg::twist_stamped::TwistStamped;
+
+    [
+        PointCloud2::ROS_TYPE,
+        Image::ROS_TYPE,
+        NavSatFix::ROS_TYPE,
+        Imu::ROS_TYPE,
+        CameraInfo::ROS_TYPE,
+        TwistStamped::ROS_TYPE,
+        Odometry::ROS_TYPE,
+    ]
+    .contains(&ros_type)
+}
@@ -0,0 +1,15 @@
+use crate::cdr::{CdrReader, FromCdr};
+use crate::error::Result;
+use crate::msg::odometry::Odometry;
+
+impl FromCdr for Odometry {
+    const ROS_TYPE: &'static str = "nav_msgs/msg/Odometry";
+
+    fn from_cdr(reader: &mut CdrReader) -> Result<Self> {
+        let header = reader.header()?;
+        let child_frame_id = reader.string("child_frame_id")?;
+        let pose = reader.pose_with_covariance("pose")?;
+        let twist = reader.twist_with_covariance("twist")?;
+        Ok(Odometry::new(header, child_frame_id, pose, twist))
+    }
+}
@@ -0,0 +1,170 @@
+use crate::cdr::{CdrReader, FromCdr};
+use crate::error::Result;
+use crate::msg::camera_info::{CameraInfo, RegionOfInterest};
+use crate::msg::image::Image;
+use crate::msg::imu::Imu;
+use crate::msg::nav_sat_fix::NavSatFix;
+use crate::msg::point_cloud2::{PointCloud2, PointField};
+
+use sha2::{Digest, Sha256};
+
+/// Content address of an image blob: the hex SHA-256 of its pixel data.
+pub fn image_oid(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// A `sensor_msgs/Image` with its pixel data. `Image` keeps only the
+/// `image_oid` of the data, which is stored as a separate blob.
+#[derive(Debug, Clone)]
+pub struct ImageData {
+    pub image: Image,
+    pub data: Vec<u8>,
+}
+
+/// Reads an image, hashing the pixel data in place so that callers wanting
+/// only the `Image` row do not copy it.
+fn read_image<'d>(reader: &mut CdrReader<'d>) -> Result<(Image, &'d [u8])> {
+    let header = reader.header()?;
+    let height = reader.u32("height")?;
+    let width = reader.u32("width")?;
+    let encoding = reader.string("encoding")?;
+    let is_bigendian = reader.bool("is_bigendian")?;
+    let step = reader.u32("step")?;
+    let data = reader.bytes("data")?;
+    let image = Image {
+        header,
+        height,
+        width,
+        encoding,
+        is_bigendian,
+        step,
+        image_oid: image_oid(data),
+    };
+    Ok((image, data))
+}
+
+impl FromCdr for ImageData {
+    const ROS_TYPE: &'static str = "sensor_msgs/msg/Image";
+
+    fn from_cdr(reader: &mut CdrReader) -> Result<Self> {
+        let (image, data) = read_image(reader)?;
+        Ok(ImageData {
+            image,
+            data: data.to_vec(),
+        })
+    }
+}
+
+impl FromCdr for Image {
+    const ROS_TYPE: &'static str = ImageData::ROS_TYPE;
+
+    fn from_cdr(reader: &mut CdrReader) -> Result<Self> {
+        Ok(read_image(reader)?.0)
+    }
+}
+
+impl FromCdr for PointCloud2 {
+    const ROS_TYPE: &'static str = "sensor_msgs/msg/PointCloud2";
+
+    fn from_cdr(reader: &mut CdrReader) -> Result<Self> {
+        let header = reader.header()?;
+        let height = reader.u32("height")?;
+        let width = reader.u32("width")?;
+        let fields = reader.seq("fields", |reader| {
+            let name = reader.string("fields.name")?;
+            let offset = reader.u32("fields.offset")?;
+            let datatype = reader.u8("fields.datatype")?;
+            let count = reader.u32("fields.count")?;
+            Ok(PointField::new(name, offset, datatype, count))
+        })?;
+        let is_bigendian = reader.bool("is_bigendian")?;
+        let point_step = reader.u32("point_step")?;
+        let row_step = reader.u32("row_step")?;
+        let data = reader.bytes("data")?.to_vec();
+        let is_dense = reader.bool("is_dense")?;
+        Ok(PointCloud2 {
+            header,
+            height,
+            width,
+            fields,
+            is_bigendian,
+            point_step,
+            row_step,
+            data,
+            is_dense,
+        })
+    }
+}
+
+impl FromCdr for NavSatFix {
+    const ROS_TYPE: &'static str = "sensor_msgs/msg/NavSatFix";
+
+    fn from_cdr(reader: &mut CdrReader) -> Result<Self> {
+        let header = reader.header()?;
+        let status = reader.i8("status.status")?;
+        let service = reader.u16("status.service")?;
+        let latitude = reader.f64("latitude")?;
+        let longitude = reader.f64("longitude")?;
+        let altitude = reader.f64("altitude")?;
+        let mut position_covariance = [0.0; 9];
+        for value in position_covariance.iter_mut() {
+            *value = reader.f64("position_covariance")?;
+        }
+        let position_covariance_type = reader.u8("position_covariance_type")?;
+        Ok(NavSatFix {
+            header,
+            status,
+            service,
+            latitude,
+            longitude,
+            altitude,
+            position_covariance,
+            position_covariance_type,
+        })
+    }
+}
+
+impl FromCdr for Imu {
+    const ROS_TYPE: &'static str = "sensor_msgs/msg/Imu";
+
+    fn from_cdr(reader: &mut CdrReader) -> Result<Self> {
+        Ok(Imu::new(
+            reader.header()?,
+            reader.quaternion("orientation")?,
+            reader.matrix("orientation_covariance")?,
+            reader.vector3("angular_velocity")?,
+            reader.matrix("angular_velocity_covariance")?,
+            reader.vector3("linear_acceleration")?,
+            reader.matrix("linear_acceleration_covariance")?,
+        ))
+    }
+}
+
+impl FromCdr for CameraInfo {
+    const ROS_TYPE: &'static str = "sensor_msgs/msg/CameraInfo";
+
+    fn from_cdr(reader: &mut CdrReader) -> Result<Self> {
+        Ok(CameraInfo::new(
+            reader.header()?,
+            reader.u32("height")?,
+            reader.u32("width")?,
+            reader.string("distortion_model")?,
+            reader.f64_seq("d")?,
+            reader.matrix("k")?,
+            reader.matrix("r")?,
+            reader.matrix("p")?,
+            reader.u32("binning_x")?,
+            reader.u32("binning_y")?,
+            RegionOfInterest {
+                x_offset: reader.u32("roi.x_offset")?,
+                y_offset: reader.u32("roi.y_offset")?,
+                height: reader.u32("roi.height")?,
+                width: reader.u32("roi.width")?,
+                do_rectify: reader.bool("roi.do_rectify")?,
+            },
+        ))
+    }
+}
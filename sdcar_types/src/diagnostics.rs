@@ -0,0 +1,429 @@
+use crate::msg::Msg;
+use crate::reader::merged::MergedReader;
+
+use chrono::{DateTime, Duration, Utc};
+
+/// When an interval between consecutive messages counts as a gap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GapThresholds {
+    factor: f64,
+    max_interval: Option<Duration>,
+}
+
+impl Default for GapThresholds {
+    fn default() -> Self {
+        Self {
+            factor: 1.5,
+            max_interval: None,
+        }
+    }
+}
+
+impl GapThresholds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Intervals longer than `factor` nominal periods are gaps. Defaults to
+    /// 1.5, so a single missing frame is reported.
+    pub fn with_factor(mut self, factor: f64) -> Self {
+        self.factor = factor;
+        self
+    }
+
+    /// Intervals longer than `max_interval` are gaps whatever the rate, for
+    /// streams too irregular to estimate one.
+    pub fn with_max_interval(mut self, max_interval: Duration) -> Self {
+        self.max_interval = Some(max_interval);
+        self
+    }
+
+    pub fn factor(&self) -> f64 {
+        self.factor
+    }
+
+    pub fn max_interval(&self) -> Option<Duration> {
+        self.max_interval
+    }
+
+    fn is_gap(&self, interval: Duration, period: Option<Duration>) -> bool {
+        let relative = period.is_some_and(|period| {
+            period > Duration::zero() && seconds(interval) > self.factor * seconds(period)
+        });
+        relative || self.max_interval.is_some_and(|max| interval > max)
+    }
+}
+
+/// Nominal rate of a stream, from the median interval between messages.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateEstimate {
+    pub period: Duration,
+    pub rate_hz: f64,
+    /// Standard deviation of the intervals that are not gaps.
+    pub jitter: Duration,
+}
+
+/// A run of missing messages between two consecutive timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gap {
+    /// Index of the message after the gap.
+    pub index: usize,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Messages expected at the nominal rate but not recorded.
+    pub missing: usize,
+}
+
+impl Gap {
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+/// Timing summary of one stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamReport {
+    pub messages: usize,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub rate: Option<RateEstimate>,
+    pub mean_interval: Option<Duration>,
+    /// Longest interval and the timestamp it starts at.
+    pub max_interval: Option<(Duration, DateTime<Utc>)>,
+    pub gaps: Vec<Gap>,
+    /// Messages whose timestamp is earlier than the one before.
+    pub out_of_order: usize,
+}
+
+impl StreamReport {
+    pub fn duration(&self) -> Duration {
+        match (self.start, self.end) {
+            (Some(start), Some(end)) => end - start,
+            _ => Duration::zero(),
+        }
+    }
+
+    /// Estimated number of messages lost in all gaps.
+    pub fn dropped(&self) -> usize {
+        self.gaps.iter().map(|gap| gap.missing).sum()
+    }
+}
+
+/// Timestamps of one stream in the order they were recorded.
+#[derive(Debug, Clone, Default)]
+pub struct StreamTimes {
+    stamps: Vec<DateTime<Utc>>,
+}
+
+impl StreamTimes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, timestamp: DateTime<Utc>) {
+        self.stamps.push(timestamp);
+    }
+
+    pub fn push_msg<M: Msg + ?Sized>(&mut self, msg: &M) {
+        self.push(msg.timestamp());
+    }
+
+    pub fn stamps(&self) -> &[DateTime<Utc>] {
+        &self.stamps
+    }
+
+    pub fn len(&self) -> usize {
+        self.stamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stamps.is_empty()
+    }
+
+    /// Non negative intervals between consecutive messages.
+    fn intervals(&self) -> impl Iterator<Item = (usize, Duration)> + '_ {
+        self.stamps
+            .windows(2)
+            .enumerate()
+            .map(|(i, pair)| (i + 1, pair[1] - pair[0]))
+            .filter(|(_, interval)| *interval >= Duration::zero())
+    }
+
+    /// The nominal period is the median interval, so the estimate is not
+    /// skewed by the dropouts it is used to find.
+    pub fn period(&self) -> Option<Duration> {
+        median(self.intervals().map(|(_, interval)| interval).collect())
+    }
+
+    pub fn report(&self, thresholds: &GapThresholds) -> StreamReport {
+        let period = self.period();
+        let mut report = StreamReport {
+            messages: self.stamps.len(),
+            start: self.stamps.iter().min().copied(),
+            end: self.stamps.iter().max().copied(),
+            rate: None,
+            mean_interval: None,
+            max_interval: None,
+            gaps: Vec::new(),
+            out_of_order: self
+                .stamps
+                .windows(2)
+                .filter(|pair| pair[1] < pair[0])
+                .count(),
+        };
+
+        let (mut total, mut count) = (Duration::zero(), 0);
+        let mut regular = Vec::new();
+        for (index, interval) in self.intervals() {
+            total += interval;
+            count += 1;
+            if report.max_interval.is_none_or(|(max, _)| interval > max) {
+                report.max_interval = Some((interval, self.stamps[index - 1]));
+            }
+            if thresholds.is_gap(interval, period) {
+                let missing = match period {
+                    Some(period) if period > Duration::zero() => {
+                        ((seconds(interval) / seconds(period)).round() as usize).saturating_sub(1)
+                    }
+                    _ => 0,
+                };
+                report.gaps.push(Gap {
+                    index,
+                    start: self.stamps[index - 1],
+                    end: self.stamps[index],
+                    missing,
+                });
+            } else {
+                regular.push(seconds(interval));
+            }
+        }
+        if count > 0 {
+            report.mean_interval = Some(total / count);
+        }
+        if let Some(period) = period.filter(|period| *period > Duration::zero()) {
+            report.rate = Some(RateEstimate {
+                period,
+                rate_hz: 1.0 / seconds(period),
+                jitter: from_seconds(std_dev(&regular)),
+            });
+        }
+        report
+    }
+
+    /// Estimates how much later `other` stamps the same events, e.g. camera
+    /// frames relative to the lidar sweeps they were triggered with. See
+    /// `estimate_offset`.
+    pub fn offset_to(&self, other: &StreamTimes, max_offset: Duration) -> Option<OffsetEstimate> {
+        estimate_offset(&self.stamps, &other.stamps, max_offset)
+    }
+}
+
+impl FromIterator<DateTime<Utc>> for StreamTimes {
+    fn from_iter<I: IntoIterator<Item = DateTime<Utc>>>(iter: I) -> Self {
+        Self {
+            stamps: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<DateTime<Utc>> for StreamTimes {
+    fn extend<I: IntoIterator<Item = DateTime<Utc>>>(&mut self, iter: I) {
+        self.stamps.extend(iter)
+    }
+}
+
+/// Constant offset between two streams, `b - a`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OffsetEstimate {
+    /// Median difference between each `b` message and its nearest `a`.
+    pub offset: Duration,
+    /// Median absolute deviation of the differences from `offset`.
+    pub spread: Duration,
+    /// Number of `b` messages with an `a` message within `max_offset`.
+    pub pairs: usize,
+}
+
+/// Pairs every timestamp in `b` with the nearest one in `a` no more than
+/// `max_offset` away and returns the median difference. Taking the median
+/// makes the estimate robust to dropouts in either stream; `max_offset`
+/// should be under half the period of `a` so pairs are unambiguous.
+pub fn estimate_offset(
+    a: &[DateTime<Utc>],
+    b: &[DateTime<Utc>],
+    max_offset: Duration,
+) -> Option<OffsetEstimate> {
+    let mut a = a.to_vec();
+    a.sort();
+    let differences: Vec<Duration> = b
+        .iter()
+        .filter_map(|stamp| {
+            let i = a.partition_point(|a_stamp| a_stamp < stamp);
+            let before = i.checked_sub(1).map(|i| *stamp - a[i]);
+            let after = a.get(i).map(|a_stamp| *stamp - *a_stamp);
+            [before, after]
+                .into_iter()
+                .flatten()
+                .min_by_key(|difference| difference.abs())
+        })
+        .filter(|difference| difference.abs() <= max_offset)
+        .collect();
+    let pairs = differences.len();
+    let offset = median(differences.clone())?;
+    let spread = median(
+        differences
+            .iter()
+            .map(|difference| (*difference - offset).abs())
+            .collect(),
+    )?;
+    Some(OffsetEstimate {
+        offset,
+        spread,
+        pairs,
+    })
+}
+
+/// Offset of one stream relative to another in a `DiagnosticsReport`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamOffset {
+    pub reference: usize,
+    pub source: usize,
+    pub estimate: Option<OffsetEstimate>,
+}
+
+/// Timing of every stream of a recording and their offsets to a reference
+/// stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagnosticsReport {
+    pub names: Vec<String>,
+    pub streams: Vec<StreamReport>,
+    pub offsets: Vec<StreamOffset>,
+}
+
+impl DiagnosticsReport {
+    pub fn stream(&self, name: &str) -> Option<&StreamReport> {
+        let index = self.names.iter().position(|n| n == name)?;
+        self.streams.get(index)
+    }
+
+    /// Smallest slop that lets an `ApproximateTimeSync` pivoting on
+    /// `reference` match every other stream: each stream's offset plus its
+    /// spread and half its period, the furthest its nearest message can be.
+    /// `None` unless the report's offsets were taken against `reference`
+    /// and every stream has a rate and an offset.
+    pub fn suggested_slop(&self, reference: usize) -> Option<Duration> {
+        let mut slop = Duration::zero();
+        for offset in self.offsets.iter() {
+            if offset.reference != reference {
+                return None;
+            }
+            let estimate = offset.estimate?;
+            let period = self.streams[offset.source].rate?.period;
+            slop = slop.max(estimate.offset.abs() + estimate.spread + period / 2);
+        }
+        Some(slop)
+    }
+}
+
+/// Collects the timestamps of several named streams and reports on them.
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    names: Vec<String>,
+    streams: Vec<StreamTimes>,
+    thresholds: GapThresholds,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads `reader` to the end, one stream per source.
+    pub fn from_reader(reader: MergedReader) -> Self {
+        let mut diagnostics = Self::new();
+        for source in 0..reader.num_sources() {
+            diagnostics.add_stream(reader.source_name(source));
+        }
+        for msg in reader {
+            diagnostics.push(msg.source, msg.timestamp());
+        }
+        diagnostics
+    }
+
+    pub fn with_thresholds(mut self, thresholds: GapThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// Adds a stream and returns the index to push its timestamps to.
+    pub fn add_stream(&mut self, name: &str) -> usize {
+        self.names.push(name.to_string());
+        self.streams.push(StreamTimes::new());
+        self.streams.len() - 1
+    }
+
+    pub fn push(&mut self, stream: usize, timestamp: DateTime<Utc>) {
+        self.streams[stream].push(timestamp);
+    }
+
+    pub fn stream(&self, stream: usize) -> &StreamTimes {
+        &self.streams[stream]
+    }
+
+    pub fn num_streams(&self) -> usize {
+        self.streams.len()
+    }
+
+    /// Reports every stream with its offset to stream `reference`, pairing
+    /// messages no more than half the reference period apart.
+    pub fn report(&self, reference: usize) -> DiagnosticsReport {
+        let streams: Vec<StreamReport> = self
+            .streams
+            .iter()
+            .map(|stream| stream.report(&self.thresholds))
+            .collect();
+        let reference_period = self.streams.get(reference).and_then(StreamTimes::period);
+        let offsets = (0..self.streams.len())
+            .filter(|source| *source != reference)
+            .map(|source| StreamOffset {
+                reference,
+                source,
+                estimate: reference_period.and_then(|period| {
+                    self.streams[reference].offset_to(&self.streams[source], period / 2)
+                }),
+            })
+            .collect();
+        DiagnosticsReport {
+            names: self.names.clone(),
+            streams,
+            offsets,
+        }
+    }
+}
+
+fn seconds(duration: Duration) -> f64 {
+    match duration.num_nanoseconds() {
+        Some(nanos) => nanos as f64 / 1e9,
+        None => duration.num_milliseconds() as f64 / 1e3,
+    }
+}
+
+fn from_seconds(seconds: f64) -> Duration {
+    Duration::nanoseconds((seconds * 1e9).round() as i64)
+}
+
+fn median(mut values: Vec<Duration>) -> Option<Duration> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort();
+    Some(values[values.len() / 2])
+}
+
+fn std_dev(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    variance.sqrt()
+}
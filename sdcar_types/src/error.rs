@@ -0,0 +1,167 @@
+extern crate arrow;
+extern crate parquet;
+
+use arrow::error::ArrowError;
+use parquet::errors::ParquetError;
+use std::fmt;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// A column could not be read as the type the decoder expects.
+    Decode {
+        field: String,
+        expected: &'static str,
+        row: Option<usize>,
+        reason: String,
+    },
+    /// The file's schema cannot be decoded as the expected message type.
+    Schema(Vec<SchemaMismatch>),
+    Parquet(ParquetError),
+    Arrow(ArrowError),
+    Io(std::io::Error),
+    Sqlite(rusqlite::Error),
+    /// A rosbag2 recording is malformed or has no topic as requested.
+    Bag(String),
+    /// A run has no file for the requested topic.
+    MissingTopic {
+        run: String,
+        topic: &'static str,
+    },
+    /// Ego motion is unknown at the time of a point being deskewed.
+    Deskew(String),
+    /// A transform from one coordinate frame was applied to data in another.
+    FrameMismatch {
+        expected: String,
+        found: String,
+    },
+    /// A filter was configured with a value it cannot work with, such as a
+    /// voxel leaf size that is not positive.
+    InvalidParameter(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaMismatch {
+    Missing {
+        column: String,
+    },
+    Mistyped {
+        column: String,
+        expected: String,
+        found: String,
+    },
+}
+
+impl fmt::Display for SchemaMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaMismatch::Missing { column } => write!(f, "missing column '{}'", column),
+            SchemaMismatch::Mistyped {
+                column,
+                expected,
+                found,
+            } => write!(
+                f,
+                "column '{}' expected {} found {}",
+                column, expected, found
+            ),
+        }
+    }
+}
+
+impl Error {
+    pub fn decode(field: &str, expected: &'static str, reason: String) -> Self {
+        Error::Decode {
+            field: field.to_string(),
+            expected,
+            row: None,
+            reason,
+        }
+    }
+
+    /// Attaches the index of the row being decoded to a decode error.
+    pub fn with_row(self, row_index: usize) -> Self {
+        match self {
+            Error::Decode {
+                field,
+                expected,
+                reason,
+                ..
+            } => Error::Decode {
+                field,
+                expected,
+                row: Some(row_index),
+                reason,
+            },
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Decode {
+                field,
+                expected,
+                row,
+                reason,
+            } => {
+                write!(f, "cannot decode field '{}' as {}", field, expected)?;
+                if let Some(row) = row {
+                    write!(f, " in row {}", row)?;
+                }
+                write!(f, ": {}", reason)
+            }
+            Error::Schema(mismatches) => {
+                write!(f, "schema mismatch:")?;
+                for mismatch in mismatches.iter() {
+                    write!(f, " {};", mismatch)?;
+                }
+                Ok(())
+            }
+            Error::Parquet(e) => write!(f, "parquet error: {}", e),
+            Error::Arrow(e) => write!(f, "arrow error: {}", e),
+            Error::Io(e) => write!(f, "io error: {}", e),
+            Error::Sqlite(e) => write!(f, "sqlite error: {}", e),
+            Error::Bag(reason) => write!(f, "rosbag2 error: {}", reason),
+            Error::MissingTopic { run, topic } => {
+                write!(f, "run {} has no {} topic", run, topic)
+            }
+            Error::Deskew(reason) => write!(f, "cannot deskew: {}", reason),
+            Error::FrameMismatch { expected, found } => write!(
+                f,
+                "transform from frame '{}' cannot apply to frame '{}'",
+                expected, found
+            ),
+            Error::InvalidParameter(reason) => write!(f, "invalid parameter: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ParquetError> for Error {
+    fn from(e: ParquetError) -> Self {
+        Error::Parquet(e)
+    }
+}
+
+impl From<ArrowError> for Error {
+    fn from(e: ArrowError) -> Self {
+        Error::Arrow(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Sqlite(e)
+    }
+}
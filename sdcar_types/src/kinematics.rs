@@ -0,0 +1,220 @@
+use crate::msg::vehicle_state::VehicleState;
+use crate::msg::Msg;
+
+use chrono::{DateTime, Duration, Utc};
+use nalgebra::{Isometry2, Vector2};
+
+use std::borrow::Borrow;
+use std::f64::consts::PI;
+
+/// Vehicle dimensions needed to turn a steering wheel angle into a path
+/// curvature.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VehicleGeometry {
+    wheelbase: f32,
+    steering_ratio: f32,
+}
+
+impl Default for VehicleGeometry {
+    /// Placeholder values, not measured on the recording vehicle; measure it
+    /// for anything more than a rough track.
+    fn default() -> Self {
+        Self {
+            wheelbase: 2.7,
+            steering_ratio: 14.0,
+        }
+    }
+}
+
+impl VehicleGeometry {
+    /// `wheelbase` in metres and `steering_ratio` as steering wheel angle
+    /// over road wheel angle.
+    pub fn new(wheelbase: f32, steering_ratio: f32) -> Self {
+        Self {
+            wheelbase,
+            steering_ratio,
+        }
+    }
+
+    pub fn wheelbase(&self) -> f32 {
+        self.wheelbase
+    }
+
+    pub fn steering_ratio(&self) -> f32 {
+        self.steering_ratio
+    }
+}
+
+/// Position in metres and heading in radians, counter clockwise from the x
+/// axis, of the vehicle at `timestamp`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose2D {
+    pub timestamp: DateTime<Utc>,
+    pub x: f64,
+    pub y: f64,
+    pub yaw: f64,
+}
+
+impl Pose2D {
+    pub fn isometry(&self) -> Isometry2<f64> {
+        Isometry2::new(Vector2::new(self.x, self.y), self.yaw)
+    }
+}
+
+/// Where the dead reckoning integrator takes the turn rate from.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum HeadingSource {
+    /// The measured yaw rate.
+    #[default]
+    YawRate,
+    /// Speed times the curvature of the steering angle, for when the yaw
+    /// rate sensor is missing or biased.
+    Steering(VehicleGeometry),
+}
+
+/// Where the dead reckoning integrator takes the speed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpeedSource {
+    /// The vehicle speed signal `v_mps`.
+    #[default]
+    VehicleSpeed,
+    /// The mean of the four wheel speeds.
+    WheelSpeed,
+}
+
+/// Integrates speed and turn rate from a sequence of `VehicleState`
+/// messages into a 2D pose track, starting from the origin facing along x
+/// unless given an initial pose.
+///
+/// The heading turns with the sign of the recorded yaw rate or steering
+/// angle. That is counter clockwise only if the recording takes turning left
+/// as positive, which it does not document.
+///
+/// Each step uses the mean speed and turn rate of the two messages it
+/// spans. Steps longer than `max_step` (dropouts) or going back in time are
+/// not integrated; the pose carries over to the next message.
+#[derive(Debug, Clone)]
+pub struct DeadReckoning {
+    heading_source: HeadingSource,
+    speed_source: SpeedSource,
+    max_step: Duration,
+    initial: (f64, f64, f64),
+    pose: Option<Pose2D>,
+    last: (f64, f64),
+}
+
+impl Default for DeadReckoning {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeadReckoning {
+    pub fn new() -> Self {
+        Self {
+            heading_source: HeadingSource::default(),
+            speed_source: SpeedSource::default(),
+            max_step: Duration::seconds(1),
+            initial: (0.0, 0.0, 0.0),
+            pose: None,
+            last: (0.0, 0.0),
+        }
+    }
+
+    pub fn with_heading_source(mut self, heading_source: HeadingSource) -> Self {
+        self.heading_source = heading_source;
+        self
+    }
+
+    pub fn with_speed_source(mut self, speed_source: SpeedSource) -> Self {
+        self.speed_source = speed_source;
+        self
+    }
+
+    pub fn with_max_step(mut self, max_step: Duration) -> Self {
+        self.max_step = max_step;
+        self
+    }
+
+    /// Pose at the first message.
+    pub fn with_initial_pose(mut self, x: f64, y: f64, yaw: f64) -> Self {
+        self.initial = (x, y, yaw);
+        self
+    }
+
+    /// Pose at the last message passed to `update`.
+    pub fn pose(&self) -> Option<Pose2D> {
+        self.pose
+    }
+
+    fn speed(&self, state: &VehicleState) -> f64 {
+        match self.speed_source {
+            SpeedSource::VehicleSpeed => state.v_mps() as f64,
+            SpeedSource::WheelSpeed => state.average_wheel_speed_mps() as f64,
+        }
+    }
+
+    fn turn_rate(&self, state: &VehicleState, speed: f64) -> f64 {
+        match &self.heading_source {
+            HeadingSource::YawRate => state.yaw_rate_radians() as f64,
+            HeadingSource::Steering(geometry) => speed * state.curvature(geometry) as f64,
+        }
+    }
+
+    /// Advances the pose to the timestamp of `state`.
+    pub fn update(&mut self, state: &VehicleState) -> Pose2D {
+        let timestamp = state.timestamp();
+        let speed = self.speed(state);
+        let turn_rate = self.turn_rate(state, speed);
+        let pose = match self.pose {
+            None => {
+                let (x, y, yaw) = self.initial;
+                Pose2D {
+                    timestamp,
+                    x,
+                    y,
+                    yaw,
+                }
+            }
+            Some(pose) => {
+                let step = timestamp - pose.timestamp;
+                let mut next = Pose2D { timestamp, ..pose };
+                if step > Duration::zero() && step <= self.max_step {
+                    let dt = step.num_nanoseconds().unwrap_or(0) as f64 / 1e9;
+                    let v = (self.last.0 + speed) / 2.0;
+                    let w = (self.last.1 + turn_rate) / 2.0;
+                    let heading = pose.yaw + w * dt / 2.0;
+                    next.x += v * dt * heading.cos();
+                    next.y += v * dt * heading.sin();
+                    next.yaw = normalize_angle(pose.yaw + w * dt);
+                }
+                next
+            }
+        };
+        self.last = (speed, turn_rate);
+        self.pose = Some(pose);
+        pose
+    }
+
+    /// Integrates every message, returning the pose at each.
+    pub fn track<I, S>(mut self, states: I) -> Vec<Pose2D>
+    where
+        I: IntoIterator<Item = S>,
+        S: Borrow<VehicleState>,
+    {
+        states
+            .into_iter()
+            .map(|state| self.update(state.borrow()))
+            .collect()
+    }
+}
+
+/// Wraps an angle into (-pi, pi].
+pub fn normalize_angle(angle: f64) -> f64 {
+    let angle = angle.rem_euclid(2.0 * PI);
+    if angle > PI {
+        angle - 2.0 * PI
+    } else {
+        angle
+    }
+}
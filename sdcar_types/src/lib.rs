@@ -1,6 +1,17 @@
+pub mod cdr;
+pub mod diagnostics;
+pub mod error;
+pub mod kinematics;
 pub mod msg;
 pub mod parquet;
 pub mod reader;
+pub mod session;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+pub mod trajectory;
+pub mod writer;
+
+pub use error::{Error, Result, SchemaMismatch};
 
 #[cfg(test)]
 mod tests {
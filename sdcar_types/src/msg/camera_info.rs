@@ -0,0 +1,97 @@
+use crate::msg::header::Header;
+use crate::msg::{Msg, MsgKind};
+use nalgebra as na;
+
+use std::sync::Arc;
+
+pub type CameraInfoRef = Arc<CameraInfo>;
+
+/// `sensor_msgs/RegionOfInterest`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RegionOfInterest {
+    pub x_offset: u32,
+    pub y_offset: u32,
+    pub height: u32,
+    pub width: u32,
+    pub do_rectify: bool,
+}
+
+/// `sensor_msgs/CameraInfo`: the calibration of the camera that took the
+/// images with the same header.
+#[derive(Debug, Clone)]
+pub struct CameraInfo {
+    pub header: Header,
+    pub height: u32,
+    pub width: u32,
+    /// e.g. `plumb_bob` or `rational_polynomial`.
+    pub distortion_model: String,
+    /// Distortion coefficients; their number depends on the model.
+    pub d: Vec<f64>,
+    /// Intrinsic matrix of the raw images.
+    pub k: na::Matrix3<f64>,
+    /// Rectification rotation, identity for a monocular camera.
+    pub r: na::Matrix3<f64>,
+    /// Projection matrix of the rectified images.
+    pub p: na::Matrix3x4<f64>,
+    pub binning_x: u32,
+    pub binning_y: u32,
+    pub roi: RegionOfInterest,
+}
+
+impl CameraInfo {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        header: Header,
+        height: u32,
+        width: u32,
+        distortion_model: String,
+        d: Vec<f64>,
+        k: na::Matrix3<f64>,
+        r: na::Matrix3<f64>,
+        p: na::Matrix3x4<f64>,
+        binning_x: u32,
+        binning_y: u32,
+        roi: RegionOfInterest,
+    ) -> Self {
+        Self {
+            header,
+            height,
+            width,
+            distortion_model,
+            d,
+            k,
+            r,
+            p,
+            binning_x,
+            binning_y,
+            roi,
+        }
+    }
+
+    /// Whether the camera was calibrated; ROS leaves `k` zero otherwise.
+    pub fn is_calibrated(&self) -> bool {
+        self.k[(0, 0)] != 0.0
+    }
+
+    /// Focal lengths in pixels.
+    pub fn focal_length(&self) -> (f64, f64) {
+        (self.k[(0, 0)], self.k[(1, 1)])
+    }
+
+    /// Principal point in pixels.
+    pub fn principal_point(&self) -> (f64, f64) {
+        (self.k[(0, 2)], self.k[(1, 2)])
+    }
+}
+
+impl Msg for CameraInfo {
+    fn header(&self) -> &Header {
+        &self.header
+    }
+    fn msg_kind(&self) -> MsgKind {
+        MsgKind::CameraInfo(Arc::new(self.clone()))
+    }
+    fn into_msg_kind(self: Arc<Self>) -> MsgKind {
+        MsgKind::CameraInfo(self)
+    }
+}
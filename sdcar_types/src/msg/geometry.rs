@@ -0,0 +1,56 @@
+use nalgebra as na;
+
+/// `geometry_msgs/Pose`. The orientation is kept as recorded, so it may not
+/// be a unit quaternion.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose {
+    pub position: na::Point3<f64>,
+    pub orientation: na::Quaternion<f64>,
+}
+
+impl Pose {
+    pub fn new(position: na::Point3<f64>, orientation: na::Quaternion<f64>) -> Self {
+        Self {
+            position,
+            orientation,
+        }
+    }
+
+    /// The pose as a rigid transform, normalizing the orientation.
+    pub fn isometry(&self) -> na::Isometry3<f64> {
+        na::Isometry3::from_parts(
+            na::Translation3::from(self.position.coords),
+            na::UnitQuaternion::from_quaternion(self.orientation),
+        )
+    }
+}
+
+/// `geometry_msgs/Twist`: linear velocity in m/s and angular velocity in
+/// rad/s.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Twist {
+    pub linear: na::Vector3<f64>,
+    pub angular: na::Vector3<f64>,
+}
+
+impl Twist {
+    pub fn new(linear: na::Vector3<f64>, angular: na::Vector3<f64>) -> Self {
+        Self { linear, angular }
+    }
+}
+
+/// `geometry_msgs/PoseWithCovariance`. The covariance is over x, y, z and
+/// rotation about x, y, z.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoseWithCovariance {
+    pub pose: Pose,
+    pub covariance: na::Matrix6<f64>,
+}
+
+/// `geometry_msgs/TwistWithCovariance`. The covariance is over the linear
+/// then angular velocity components.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TwistWithCovariance {
+    pub twist: Twist,
+    pub covariance: na::Matrix6<f64>,
+}
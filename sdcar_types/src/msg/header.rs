@@ -0,0 +1,109 @@
+use chrono::{DateTime, TimeZone, Utc};
+
+use std::fmt;
+
+const NANOS_PER_SEC: i64 = 1_000_000_000;
+
+/// `builtin_interfaces/Time`: seconds and nanoseconds since the Unix epoch.
+///
+/// Nanoseconds are kept below one second, carrying into `sec`, so stamps
+/// order and compare field by field and convert to and from `DateTime<Utc>`
+/// without loss.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Stamp {
+    sec: i32,
+    nanosec: u32,
+}
+
+impl Stamp {
+    /// Carries whole seconds of `nanosec` into `sec`. A carry past
+    /// `i32::MAX` saturates to the latest stamp, `i32::MAX` seconds and
+    /// 999999999 nanoseconds, rather than wrapping to a stamp in 1901; the
+    /// message constructors built on this cannot fail.
+    pub fn new(sec: i32, nanosec: u32) -> Self {
+        let carry = nanosec / NANOS_PER_SEC as u32;
+        match sec.checked_add(carry as i32) {
+            Some(sec) => Self {
+                sec,
+                nanosec: nanosec % NANOS_PER_SEC as u32,
+            },
+            None => Self {
+                sec: i32::MAX,
+                nanosec: NANOS_PER_SEC as u32 - 1,
+            },
+        }
+    }
+
+    /// `None` if `nanos` is outside the range of an `i32` of seconds.
+    pub fn from_nanos(nanos: i64) -> Option<Self> {
+        let sec = i32::try_from(nanos.div_euclid(NANOS_PER_SEC)).ok()?;
+        Some(Self {
+            sec,
+            nanosec: nanos.rem_euclid(NANOS_PER_SEC) as u32,
+        })
+    }
+
+    /// `None` if `timestamp` is outside the range of an `i32` of seconds.
+    pub fn from_datetime(timestamp: DateTime<Utc>) -> Option<Self> {
+        Some(Self {
+            sec: i32::try_from(timestamp.timestamp()).ok()?,
+            nanosec: timestamp.timestamp_subsec_nanos(),
+        })
+    }
+
+    pub fn sec(&self) -> i32 {
+        self.sec
+    }
+
+    pub fn nanosec(&self) -> u32 {
+        self.nanosec
+    }
+
+    /// Nanoseconds since the Unix epoch, as in the recorder's `timestamp`
+    /// column.
+    pub fn as_nanos(&self) -> i64 {
+        self.sec as i64 * NANOS_PER_SEC + self.nanosec as i64
+    }
+
+    pub fn to_datetime(&self) -> DateTime<Utc> {
+        // any i32 of seconds with nanoseconds below one second is in range
+        Utc.timestamp_opt(self.sec.into(), self.nanosec).unwrap()
+    }
+}
+
+impl From<Stamp> for DateTime<Utc> {
+    fn from(stamp: Stamp) -> Self {
+        stamp.to_datetime()
+    }
+}
+
+impl fmt::Display for Stamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{:09}", self.sec, self.nanosec)
+    }
+}
+
+/// `std_msgs/Header` shared by every message.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Header {
+    pub stamp: Stamp,
+    pub frame_id: String,
+}
+
+impl Header {
+    pub fn new(stamp: Stamp, frame_id: String) -> Self {
+        Self { stamp, frame_id }
+    }
+
+    pub fn stamp(&self) -> Stamp {
+        self.stamp
+    }
+
+    pub fn frame_id(&self) -> &str {
+        &self.frame_id
+    }
+
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.stamp.to_datetime()
+    }
+}
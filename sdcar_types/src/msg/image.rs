@@ -1,5 +1,5 @@
+use crate::msg::header::{Header, Stamp};
 use crate::msg::{Msg, MsgKind};
-use chrono::{DateTime, TimeZone, Utc};
 
 use std::sync::Arc;
 
@@ -7,10 +7,7 @@ pub type ImageRef = Arc<Image>;
 
 #[derive(Debug, Clone)]
 pub struct Image {
-    pub stamp_sec: i32,
-    pub stamp_nanosec: u32,
-    pub timestamp: DateTime<Utc>,
-    pub frame_id: String,
+    pub header: Header,
     pub height: u32,
     pub width: u32,
     pub encoding: String,
@@ -32,10 +29,7 @@ impl Image {
         image_oid: String,
     ) -> Self {
         Self {
-            stamp_sec,
-            stamp_nanosec,
-            timestamp: Utc.timestamp(stamp_sec.into(), stamp_nanosec),
-            frame_id,
+            header: Header::new(Stamp::new(stamp_sec, stamp_nanosec), frame_id),
             height,
             width,
             encoding,
@@ -47,10 +41,13 @@ impl Image {
 }
 
 impl Msg for Image {
-    fn timestamp(&self) -> DateTime<Utc> {
-        self.timestamp
+    fn header(&self) -> &Header {
+        &self.header
     }
     fn msg_kind(&self) -> MsgKind {
         MsgKind::Image(Arc::new(self.clone()))
     }
+    fn into_msg_kind(self: Arc<Self>) -> MsgKind {
+        MsgKind::Image(self)
+    }
 }
@@ -0,0 +1,69 @@
+use crate::msg::header::Header;
+use crate::msg::{Msg, MsgKind};
+use nalgebra as na;
+
+use std::sync::Arc;
+
+pub type ImuRef = Arc<Imu>;
+
+/// `sensor_msgs/Imu`. Covariances are about the x, y and z axes; as in ROS
+/// a covariance whose first element is -1 marks the quantity as not
+/// provided by the sensor.
+#[derive(Debug, Clone)]
+pub struct Imu {
+    pub header: Header,
+    pub orientation: na::Quaternion<f64>,
+    pub orientation_covariance: na::Matrix3<f64>,
+    /// Angular velocity in rad/s.
+    pub angular_velocity: na::Vector3<f64>,
+    pub angular_velocity_covariance: na::Matrix3<f64>,
+    /// Linear acceleration in m/s^2.
+    pub linear_acceleration: na::Vector3<f64>,
+    pub linear_acceleration_covariance: na::Matrix3<f64>,
+}
+
+impl Imu {
+    pub fn new(
+        header: Header,
+        orientation: na::Quaternion<f64>,
+        orientation_covariance: na::Matrix3<f64>,
+        angular_velocity: na::Vector3<f64>,
+        angular_velocity_covariance: na::Matrix3<f64>,
+        linear_acceleration: na::Vector3<f64>,
+        linear_acceleration_covariance: na::Matrix3<f64>,
+    ) -> Self {
+        Self {
+            header,
+            orientation,
+            orientation_covariance,
+            angular_velocity,
+            angular_velocity_covariance,
+            linear_acceleration,
+            linear_acceleration_covariance,
+        }
+    }
+
+    pub fn has_orientation(&self) -> bool {
+        self.orientation_covariance[(0, 0)] != -1.0
+    }
+
+    pub fn has_angular_velocity(&self) -> bool {
+        self.angular_velocity_covariance[(0, 0)] != -1.0
+    }
+
+    pub fn has_linear_acceleration(&self) -> bool {
+        self.linear_acceleration_covariance[(0, 0)] != -1.0
+    }
+}
+
+impl Msg for Imu {
+    fn header(&self) -> &Header {
+        &self.header
+    }
+    fn msg_kind(&self) -> MsgKind {
+        MsgKind::Imu(Arc::new(self.clone()))
+    }
+    fn into_msg_kind(self: Arc<Self>) -> MsgKind {
+        MsgKind::Imu(self)
+    }
+}
@@ -1,18 +1,36 @@
+pub mod camera_info;
+pub mod geometry;
+pub mod header;
 pub mod image;
+pub mod imu;
 pub mod nav_sat_fix;
+pub mod odometry;
 pub mod point_cloud2;
+pub mod twist_stamped;
 pub mod vehicle_state;
 
+use camera_info::CameraInfoRef;
+use header::Header;
 use image::ImageRef;
+use imu::ImuRef;
 use nav_sat_fix::NavSatFixRef;
+use odometry::OdometryRef;
 use point_cloud2::PointCloud2Ref;
+use twist_stamped::TwistStampedRef;
 use vehicle_state::VehicleStateRef;
 
 use chrono::{DateTime, Utc};
 
+use std::sync::Arc;
+
 pub trait Msg {
-    fn timestamp(&self) -> DateTime<Utc>;
+    fn header(&self) -> &Header;
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.header().timestamp()
+    }
     fn msg_kind(&self) -> MsgKind;
+    /// Wraps an already shared message without cloning it.
+    fn into_msg_kind(self: Arc<Self>) -> MsgKind;
 }
 
 #[derive(Debug, Clone)]
@@ -21,4 +39,27 @@ pub enum MsgKind {
     VehicleState(VehicleStateRef),
     PointCloud2(PointCloud2Ref),
     Image(ImageRef),
+    Imu(ImuRef),
+    CameraInfo(CameraInfoRef),
+    TwistStamped(TwistStampedRef),
+    Odometry(OdometryRef),
+}
+
+impl MsgKind {
+    pub fn header(&self) -> &Header {
+        match self {
+            MsgKind::NavSatFix(msg) => msg.header(),
+            MsgKind::VehicleState(msg) => msg.header(),
+            MsgKind::PointCloud2(msg) => msg.header(),
+            MsgKind::Image(msg) => msg.header(),
+            MsgKind::Imu(msg) => msg.header(),
+            MsgKind::CameraInfo(msg) => msg.header(),
+            MsgKind::TwistStamped(msg) => msg.header(),
+            MsgKind::Odometry(msg) => msg.header(),
+        }
+    }
+
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.header().timestamp()
+    }
 }
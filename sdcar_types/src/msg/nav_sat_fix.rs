@@ -1,23 +1,83 @@
-use chrono::{DateTime, TimeZone, Utc};
-
 extern crate coord_transforms;
 extern crate nalgebra as na;
 
 use coord_transforms::prelude::*;
 use std::vec::Vec;
 
+use crate::msg::header::{Header, Stamp};
 use crate::msg::{Msg, MsgKind};
 
 use std::sync::Arc;
 
 pub type NavSatFixRef = Arc<NavSatFix>;
 
+/// Typed view of the `position_covariance` and `position_covariance_type` of
+/// a fix: the covariance of the position in metres^2 in the east, north, up
+/// frame at the fix, tagged with how it was obtained. See
+/// `NavSatFix::covariance`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PositionCovariance {
+    /// `COVARIANCE_TYPE_UNKNOWN`; also used for unrecognised type values.
+    Unknown,
+    /// `COVARIANCE_TYPE_APPROXIMATED`, e.g. from the dilution of precision.
+    Approximated(na::Matrix3<f64>),
+    /// `COVARIANCE_TYPE_DIAGONAL_KNOWN`, only the variances are meaningful.
+    DiagonalKnown(na::Matrix3<f64>),
+    /// `COVARIANCE_TYPE_KNOWN`.
+    Known(na::Matrix3<f64>),
+}
+
+impl PositionCovariance {
+    /// From the row major `covariance` and `covariance_type` of the message.
+    pub fn from_raw(covariance: [f64; 9], covariance_type: u8) -> Self {
+        let matrix = na::Matrix3::from_row_slice(&covariance);
+        match covariance_type {
+            1 => PositionCovariance::Approximated(matrix),
+            2 => PositionCovariance::DiagonalKnown(matrix),
+            3 => PositionCovariance::Known(matrix),
+            _ => PositionCovariance::Unknown,
+        }
+    }
+
+    pub fn covariance_type(&self) -> u8 {
+        match self {
+            PositionCovariance::Unknown => 0,
+            PositionCovariance::Approximated(_) => 1,
+            PositionCovariance::DiagonalKnown(_) => 2,
+            PositionCovariance::Known(_) => 3,
+        }
+    }
+
+    pub fn matrix(&self) -> Option<&na::Matrix3<f64>> {
+        match self {
+            PositionCovariance::Unknown => None,
+            PositionCovariance::Approximated(matrix)
+            | PositionCovariance::DiagonalKnown(matrix)
+            | PositionCovariance::Known(matrix) => Some(matrix),
+        }
+    }
+
+    /// Root of the summed east and north variances in metres (DRMS).
+    pub fn horizontal_accuracy(&self) -> Option<f64> {
+        self.matrix()
+            .map(|matrix| (matrix[(0, 0)] + matrix[(1, 1)]).max(0.0).sqrt())
+    }
+
+    /// The larger of the east and north standard deviations in metres.
+    pub fn max_horizontal_std(&self) -> Option<f64> {
+        self.matrix()
+            .map(|matrix| matrix[(0, 0)].max(matrix[(1, 1)]).max(0.0).sqrt())
+    }
+
+    /// Standard deviation of the altitude in metres.
+    pub fn vertical_accuracy(&self) -> Option<f64> {
+        self.matrix().map(|matrix| matrix[(2, 2)].max(0.0).sqrt())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct NavSatFix {
-    pub stamp_sec: i32,
-    pub stamp_nanosec: i64,
-    pub timestamp: DateTime<Utc>,
-    pub frame_id: String,
+    pub header: Header,
     pub status: i8,
     pub service: u16,
     pub latitude: f64,
@@ -30,7 +90,7 @@ pub struct NavSatFix {
 impl NavSatFix {
     pub fn new(
         stamp_sec: i32,
-        stamp_nanosec: i64,
+        stamp_nanosec: u32,
         frame_id: String,
         status: i8,
         service: u16,
@@ -41,10 +101,7 @@ impl NavSatFix {
         position_covariance_type: u8,
     ) -> NavSatFix {
         NavSatFix {
-            stamp_sec,
-            stamp_nanosec,
-            timestamp: Utc.timestamp(stamp_sec.into(), stamp_nanosec as u32),
-            frame_id,
+            header: Header::new(Stamp::new(stamp_sec, stamp_nanosec), frame_id),
             status,
             service,
             latitude,
@@ -54,6 +111,12 @@ impl NavSatFix {
             position_covariance_type,
         }
     }
+
+    /// The covariance as a `PositionCovariance`. A type value outside the
+    /// message's constants reads as `Unknown`, while the raw fields keep it.
+    pub fn covariance(&self) -> PositionCovariance {
+        PositionCovariance::from_raw(self.position_covariance, self.position_covariance_type)
+    }
     pub fn lla_vec(&self) -> Vec<f64> {
         vec![
             self.latitude.to_radians(),
@@ -99,10 +162,13 @@ impl NavSatFix {
 }
 
 impl Msg for NavSatFix {
-    fn timestamp(&self) -> DateTime<Utc> {
-        self.timestamp
+    fn header(&self) -> &Header {
+        &self.header
     }
     fn msg_kind(&self) -> MsgKind {
         MsgKind::NavSatFix(Arc::new(self.clone()))
     }
+    fn into_msg_kind(self: Arc<Self>) -> MsgKind {
+        MsgKind::NavSatFix(self)
+    }
 }
@@ -0,0 +1,45 @@
+use crate::msg::geometry::{PoseWithCovariance, TwistWithCovariance};
+use crate::msg::header::Header;
+use crate::msg::{Msg, MsgKind};
+
+use std::sync::Arc;
+
+pub type OdometryRef = Arc<Odometry>;
+
+/// `nav_msgs/Odometry`: the pose of `child_frame_id` in the header's frame
+/// and its velocity in `child_frame_id`.
+#[derive(Debug, Clone)]
+pub struct Odometry {
+    pub header: Header,
+    pub child_frame_id: String,
+    pub pose: PoseWithCovariance,
+    pub twist: TwistWithCovariance,
+}
+
+impl Odometry {
+    pub fn new(
+        header: Header,
+        child_frame_id: String,
+        pose: PoseWithCovariance,
+        twist: TwistWithCovariance,
+    ) -> Self {
+        Self {
+            header,
+            child_frame_id,
+            pose,
+            twist,
+        }
+    }
+}
+
+impl Msg for Odometry {
+    fn header(&self) -> &Header {
+        &self.header
+    }
+    fn msg_kind(&self) -> MsgKind {
+        MsgKind::Odometry(Arc::new(self.clone()))
+    }
+    fn into_msg_kind(self: Arc<Self>) -> MsgKind {
+        MsgKind::Odometry(self)
+    }
+}
@@ -1,5 +1,5 @@
-use chrono::{DateTime, TimeZone, Utc};
-
+use crate::error::{Error, Result};
+use crate::msg::header::{Header, Stamp};
 use crate::msg::{Msg, MsgKind};
 use std::vec::Vec;
 
@@ -16,6 +16,15 @@ pub struct PointField {
 }
 
 impl PointField {
+    pub const INT8: u8 = 1;
+    pub const UINT8: u8 = 2;
+    pub const INT16: u8 = 3;
+    pub const UINT16: u8 = 4;
+    pub const INT32: u8 = 5;
+    pub const UINT32: u8 = 6;
+    pub const FLOAT32: u8 = 7;
+    pub const FLOAT64: u8 = 8;
+
     pub fn new(name: String, offset: u32, datatype: u8, count: u32) -> PointField {
         PointField {
             name,
@@ -24,14 +33,173 @@ impl PointField {
             count,
         }
     }
+
+    /// The type of the field's values, `None` for a datatype outside 1 to 8.
+    pub fn field_type(&self) -> Option<PointFieldType> {
+        PointFieldType::from_datatype(self.datatype)
+    }
+}
+
+/// The `sensor_msgs/PointField` datatypes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointFieldType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl PointFieldType {
+    pub fn from_datatype(datatype: u8) -> Option<Self> {
+        Some(match datatype {
+            PointField::INT8 => PointFieldType::Int8,
+            PointField::UINT8 => PointFieldType::UInt8,
+            PointField::INT16 => PointFieldType::Int16,
+            PointField::UINT16 => PointFieldType::UInt16,
+            PointField::INT32 => PointFieldType::Int32,
+            PointField::UINT32 => PointFieldType::UInt32,
+            PointField::FLOAT32 => PointFieldType::Float32,
+            PointField::FLOAT64 => PointFieldType::Float64,
+            _ => return None,
+        })
+    }
+
+    pub fn datatype(&self) -> u8 {
+        match self {
+            PointFieldType::Int8 => PointField::INT8,
+            PointFieldType::UInt8 => PointField::UINT8,
+            PointFieldType::Int16 => PointField::INT16,
+            PointFieldType::UInt16 => PointField::UINT16,
+            PointFieldType::Int32 => PointField::INT32,
+            PointFieldType::UInt32 => PointField::UINT32,
+            PointFieldType::Float32 => PointField::FLOAT32,
+            PointFieldType::Float64 => PointField::FLOAT64,
+        }
+    }
+
+    /// Size of one value in bytes.
+    pub fn size(&self) -> usize {
+        match self {
+            PointFieldType::Int8 | PointFieldType::UInt8 => 1,
+            PointFieldType::Int16 | PointFieldType::UInt16 => 2,
+            PointFieldType::Int32 | PointFieldType::UInt32 | PointFieldType::Float32 => 4,
+            PointFieldType::Float64 => 8,
+        }
+    }
+}
+
+/// A type point field values are read as. Values of any datatype convert
+/// with `as`, so an `f32` column can be read from an `int16` field.
+pub trait FieldValue: Copy + Send + Sync {
+    fn from_f64(value: f64) -> Self;
+}
+
+macro_rules! field_value {
+    ($($ty:ty),*) => {
+        $(
+            impl FieldValue for $ty {
+                fn from_f64(value: f64) -> Self {
+                    value as $ty
+                }
+            }
+        )*
+    };
+}
+
+field_value!(i8, u8, i16, u16, i32, u32, f32, f64);
+
+/// Reads one field of every point of a `PointCloud2`. The cloud's layout
+/// is checked when the reader is created, so reads do not fail.
+#[derive(Debug, Clone, Copy)]
+pub struct FieldReader<'a> {
+    cloud: &'a PointCloud2,
+    offset: usize,
+    field_type: PointFieldType,
+    count: usize,
+}
+
+macro_rules! read_value {
+    ($ty:ty, $bytes:expr, $big_endian:expr) => {{
+        let bytes = $bytes[..std::mem::size_of::<$ty>()].try_into().unwrap();
+        if $big_endian {
+            <$ty>::from_be_bytes(bytes) as f64
+        } else {
+            <$ty>::from_le_bytes(bytes) as f64
+        }
+    }};
+}
+
+impl<'a> FieldReader<'a> {
+    pub fn field_type(&self) -> PointFieldType {
+        self.field_type
+    }
+
+    /// Number of values of the field in each point.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Number of points, `height * width`.
+    pub fn len(&self) -> usize {
+        self.cloud.num_points()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Value `element` of the field of point `index`, with points numbered
+    /// row by row. Panics if either is out of range.
+    pub fn get<T: FieldValue>(&self, index: usize, element: usize) -> T {
+        T::from_f64(self.get_f64(index, element))
+    }
+
+    pub fn get_f64(&self, index: usize, element: usize) -> f64 {
+        assert!(
+            element < self.count,
+            "element {} of {}",
+            element,
+            self.count
+        );
+        let width = self.cloud.width as usize;
+        let pos = (index / width) * self.cloud.row_step as usize
+            + (index % width) * self.cloud.point_step as usize
+            + self.offset
+            + element * self.field_type.size();
+        let bytes = &self.cloud.data[pos..pos + self.field_type.size()];
+        let big_endian = self.cloud.is_bigendian;
+        match self.field_type {
+            PointFieldType::Int8 => bytes[0] as i8 as f64,
+            PointFieldType::UInt8 => bytes[0] as f64,
+            PointFieldType::Int16 => read_value!(i16, bytes, big_endian),
+            PointFieldType::UInt16 => read_value!(u16, bytes, big_endian),
+            PointFieldType::Int32 => read_value!(i32, bytes, big_endian),
+            PointFieldType::UInt32 => read_value!(u32, bytes, big_endian),
+            PointFieldType::Float32 => read_value!(f32, bytes, big_endian),
+            PointFieldType::Float64 => read_value!(f64, bytes, big_endian),
+        }
+    }
+
+    /// The first value of the field of every point.
+    pub fn column<T: FieldValue>(&self) -> Vec<T> {
+        (0..self.len()).map(|index| self.get(index, 0)).collect()
+    }
+
+    /// All `count` values of the field of every point, point after point.
+    pub fn values<T: FieldValue>(&self) -> Vec<T> {
+        (0..self.len())
+            .flat_map(|index| (0..self.count).map(move |element| self.get(index, element)))
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct PointCloud2 {
-    pub stamp_sec: i32,
-    pub stamp_nanosec: u32,
-    pub timestamp: DateTime<Utc>,
-    pub frame_id: String,
+    pub header: Header,
     pub height: u32,
     pub width: u32,
     pub fields: Vec<PointField>,
@@ -57,10 +225,7 @@ impl PointCloud2 {
         is_dense: bool,
     ) -> PointCloud2 {
         PointCloud2 {
-            stamp_sec,
-            stamp_nanosec,
-            timestamp: Utc.timestamp(stamp_sec.into(), stamp_nanosec),
-            frame_id,
+            header: Header::new(Stamp::new(stamp_sec, stamp_nanosec), frame_id),
             height,
             width,
             fields,
@@ -71,13 +236,108 @@ impl PointCloud2 {
             is_dense,
         }
     }
+
+    /// Number of points, `height * width`.
+    pub fn num_points(&self) -> usize {
+        self.height as usize * self.width as usize
+    }
+
+    pub fn field(&self, name: &str) -> Option<&PointField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    pub fn has_field(&self, name: &str) -> bool {
+        self.field(name).is_some()
+    }
+
+    /// A reader of the field `name`, located by its `PointField`. Fails if
+    /// the cloud has no such field, its datatype is unknown, or `data` is
+    /// too short for the `height`, `row_step` and `point_step` given.
+    pub fn field_reader(&self, name: &str) -> Result<FieldReader<'_>> {
+        let field = self.field(name).ok_or_else(|| {
+            let names: Vec<&str> = self
+                .fields
+                .iter()
+                .map(|field| field.name.as_str())
+                .collect();
+            Error::decode(
+                name,
+                "point field",
+                format!("the cloud has only fields [{}]", names.join(", ")),
+            )
+        })?;
+        let field_type = field.field_type().ok_or_else(|| {
+            Error::decode(
+                name,
+                "datatype 1 to 8",
+                format!("found datatype {}", field.datatype),
+            )
+        })?;
+        // some drivers leave count 0 for a single value
+        let count = (field.count as usize).max(1);
+        let end = field.offset as usize + count * field_type.size();
+        if end > self.point_step as usize {
+            return Err(Error::decode(
+                name,
+                "field within point_step",
+                format!(
+                    "{} bytes at offset {} overrun point_step {}",
+                    end - field.offset as usize,
+                    field.offset,
+                    self.point_step
+                ),
+            ));
+        }
+        let row_len = self.width as usize * self.point_step as usize;
+        if self.height > 1 && (self.row_step as usize) < row_len {
+            return Err(Error::decode(
+                "row_step",
+                "width * point_step or more",
+                format!(
+                    "row_step {} for {} points of {} bytes",
+                    self.row_step, self.width, self.point_step
+                ),
+            ));
+        }
+        let needed = match self.height {
+            0 => 0,
+            height => (height as usize - 1) * self.row_step as usize + row_len,
+        };
+        if self.data.len() < needed {
+            return Err(Error::decode(
+                "data",
+                "height * row_step bytes",
+                format!(
+                    "{} bytes for {}x{} points, {} needed",
+                    self.data.len(),
+                    self.height,
+                    self.width,
+                    needed
+                ),
+            ));
+        }
+        Ok(FieldReader {
+            cloud: self,
+            offset: field.offset as usize,
+            field_type,
+            count,
+        })
+    }
+
+    /// The first value of the field `name` of every point, as `T`.
+    pub fn read_field<T: FieldValue>(&self, name: &str) -> Result<Vec<T>> {
+        Ok(self.field_reader(name)?.column())
+    }
 }
 
 impl Msg for PointCloud2 {
-    fn timestamp(&self) -> DateTime<Utc> {
-        self.timestamp
+    fn header(&self) -> &Header {
+        &self.header
     }
     fn msg_kind(&self) -> MsgKind {
         MsgKind::PointCloud2(Arc::new(self.clone()))
     }
+    fn into_msg_kind(self: Arc<Self>) -> MsgKind {
+        MsgKind::PointCloud2(self)
+    }
 }
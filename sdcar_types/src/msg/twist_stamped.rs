@@ -0,0 +1,32 @@
+use crate::msg::geometry::Twist;
+use crate::msg::header::Header;
+use crate::msg::{Msg, MsgKind};
+
+use std::sync::Arc;
+
+pub type TwistStampedRef = Arc<TwistStamped>;
+
+/// `geometry_msgs/TwistStamped`: a velocity in the header's frame.
+#[derive(Debug, Clone)]
+pub struct TwistStamped {
+    pub header: Header,
+    pub twist: Twist,
+}
+
+impl TwistStamped {
+    pub fn new(header: Header, twist: Twist) -> Self {
+        Self { header, twist }
+    }
+}
+
+impl Msg for TwistStamped {
+    fn header(&self) -> &Header {
+        &self.header
+    }
+    fn msg_kind(&self) -> MsgKind {
+        MsgKind::TwistStamped(Arc::new(self.clone()))
+    }
+    fn into_msg_kind(self: Arc<Self>) -> MsgKind {
+        MsgKind::TwistStamped(self)
+    }
+}
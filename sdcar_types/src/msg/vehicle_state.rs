@@ -1,41 +1,38 @@
+use crate::kinematics::VehicleGeometry;
+use crate::msg::header::{Header, Stamp};
 use crate::msg::{Msg, MsgKind};
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{DateTime, Utc};
 
 use std::sync::Arc;
 pub type VehicleStateRef = Arc<VehicleState>;
 
 #[derive(Debug, Clone)]
 pub struct VehicleState {
-    stamp_sec: i32,
-    stamp_nanosec: i64,
-    timestamp: DateTime<Utc>,
-    frame_id: String,
-    timeref_sec: i32,
-    timeref_nanosec: i64,
-    timeref_stamp: DateTime<Utc>,
-    vs_eng: f32,
-    wheel_fl: f32,
-    wheel_fr: f32,
-    wheel_rl: f32,
-    wheel_rr: f32,
-    long_accel: f32,
-    lat_accel: f32,
-    yaw_rate: f32,
-    sas_angle: f32,
-    sas_speed: f32,
-    dt_sec: f32,
-    sas_radians: f32,
-    vs: f32,
-    v_mps: f32,
+    pub(crate) header: Header,
+    pub(crate) timeref: Stamp,
+    pub(crate) vs_eng: f32,
+    pub(crate) wheel_fl: f32,
+    pub(crate) wheel_fr: f32,
+    pub(crate) wheel_rl: f32,
+    pub(crate) wheel_rr: f32,
+    pub(crate) long_accel: f32,
+    pub(crate) lat_accel: f32,
+    pub(crate) yaw_rate: f32,
+    pub(crate) sas_angle: f32,
+    pub(crate) sas_speed: f32,
+    pub(crate) dt_sec: f32,
+    pub(crate) sas_radians: f32,
+    pub(crate) vs: f32,
+    pub(crate) v_mps: f32,
 }
 
 impl VehicleState {
     pub fn new(
         stamp_sec: i32,
-        stamp_nanosec: i64,
+        stamp_nanosec: u32,
         frame_id: String,
         timeref_sec: i32,
-        timeref_nanosec: i64,
+        timeref_nanosec: u32,
         vs_eng: f32,
         wheel_fl: f32,
         wheel_fr: f32,
@@ -52,13 +49,8 @@ impl VehicleState {
         v_mps: f32, // velocity in metres per second
     ) -> VehicleState {
         VehicleState {
-            stamp_sec: stamp_sec,
-            stamp_nanosec: stamp_nanosec,
-            timestamp: Utc.timestamp(stamp_sec.into(), stamp_nanosec as u32),
-            frame_id: frame_id,
-            timeref_sec: timeref_sec,
-            timeref_nanosec: timeref_nanosec,
-            timeref_stamp: Utc.timestamp(timeref_sec.into(), timeref_nanosec as u32),
+            header: Header::new(Stamp::new(stamp_sec, stamp_nanosec), frame_id),
+            timeref: Stamp::new(timeref_sec, timeref_nanosec),
             vs_eng: vs_eng,
             wheel_fl: wheel_fl,
             wheel_fr: wheel_fr,
@@ -75,13 +67,123 @@ impl VehicleState {
             v_mps: v_mps,
         }
     }
+
+    pub fn stamp_sec(&self) -> i32 {
+        self.header.stamp.sec()
+    }
+    pub fn stamp_nanosec(&self) -> u32 {
+        self.header.stamp.nanosec()
+    }
+    pub fn frame_id(&self) -> &str {
+        &self.header.frame_id
+    }
+    pub fn timeref_sec(&self) -> i32 {
+        self.timeref.sec()
+    }
+    pub fn timeref_nanosec(&self) -> u32 {
+        self.timeref.nanosec()
+    }
+    /// The `timeref` field as recorded.
+    pub fn timeref(&self) -> Stamp {
+        self.timeref
+    }
+    pub fn timeref_stamp(&self) -> DateTime<Utc> {
+        self.timeref.to_datetime()
+    }
+    pub fn vs_eng(&self) -> f32 {
+        self.vs_eng
+    }
+    /// Front left wheel speed.
+    pub fn wheel_fl(&self) -> f32 {
+        self.wheel_fl
+    }
+    /// Front right wheel speed.
+    pub fn wheel_fr(&self) -> f32 {
+        self.wheel_fr
+    }
+    /// Rear left wheel speed.
+    pub fn wheel_rl(&self) -> f32 {
+        self.wheel_rl
+    }
+    /// Rear right wheel speed.
+    pub fn wheel_rr(&self) -> f32 {
+        self.wheel_rr
+    }
+    /// Longitudinal acceleration.
+    pub fn long_accel(&self) -> f32 {
+        self.long_accel
+    }
+    /// Lateral acceleration.
+    pub fn lat_accel(&self) -> f32 {
+        self.lat_accel
+    }
+    /// Yaw rate as recorded. The recording does not document its unit or
+    /// sign convention; `yaw_rate_radians` assumes degrees per second.
+    pub fn yaw_rate(&self) -> f32 {
+        self.yaw_rate
+    }
+    /// Steering wheel angle as recorded.
+    pub fn sas_angle(&self) -> f32 {
+        self.sas_angle
+    }
+    /// Steering wheel speed.
+    pub fn sas_speed(&self) -> f32 {
+        self.sas_speed
+    }
+    pub fn dt_sec(&self) -> f32 {
+        self.dt_sec
+    }
+    /// Steering wheel angle in radians.
+    pub fn sas_radians(&self) -> f32 {
+        self.sas_radians
+    }
+    /// Vehicle speed as recorded.
+    pub fn vs(&self) -> f32 {
+        self.vs
+    }
+    /// Vehicle speed in metres per second.
+    pub fn v_mps(&self) -> f32 {
+        self.v_mps
+    }
+
+    /// Mean of the four wheel speeds, in their recorded unit.
+    pub fn average_wheel_speed(&self) -> f32 {
+        (self.wheel_fl + self.wheel_fr + self.wheel_rl + self.wheel_rr) / 4.0
+    }
+
+    /// Mean of the four wheel speeds in metres per second, assuming they are
+    /// recorded in km/h. The recording does not document their unit, so
+    /// check this against `v_mps` before relying on it.
+    pub fn average_wheel_speed_mps(&self) -> f32 {
+        self.average_wheel_speed() / 3.6
+    }
+
+    /// Yaw rate in radians per second, assuming `yaw_rate` is recorded in
+    /// degrees per second. The sign is as recorded.
+    pub fn yaw_rate_radians(&self) -> f32 {
+        self.yaw_rate.to_radians()
+    }
+
+    /// Road wheel angle in radians from the steering wheel angle.
+    pub fn road_wheel_angle(&self, geometry: &VehicleGeometry) -> f32 {
+        self.sas_radians / geometry.steering_ratio()
+    }
+
+    /// Path curvature in 1/m from the steering angle using a kinematic
+    /// bicycle model, with the sign of the steering angle.
+    pub fn curvature(&self, geometry: &VehicleGeometry) -> f32 {
+        self.road_wheel_angle(geometry).tan() / geometry.wheelbase()
+    }
 }
 
 impl Msg for VehicleState {
-    fn timestamp(&self) -> DateTime<Utc> {
-        self.timestamp
+    fn header(&self) -> &Header {
+        &self.header
     }
     fn msg_kind(&self) -> MsgKind {
         MsgKind::VehicleState(Arc::new(self.clone()))
     }
+    fn into_msg_kind(self: Arc<Self>) -> MsgKind {
+        MsgKind::VehicleState(self)
+    }
 }
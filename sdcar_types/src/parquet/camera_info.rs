@@ -0,0 +1,204 @@
+extern crate arrow;
+extern crate parquet;
+
+use arrow::array::{ArrayRef, BooleanArray, ListArray, StringArray, UInt32Array};
+use arrow::datatypes::{DataType, Field, Float64Type, Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+use nalgebra as na;
+
+use crate::error::{Error, Result};
+use crate::msg::camera_info::{CameraInfo, CameraInfoRef, RegionOfInterest};
+use crate::msg::header::{Header, Stamp};
+use crate::parquet::geometry::{
+    matrix_array, matrix_field, matrix_from_row, matrix_value, struct_array,
+};
+use crate::parquet::schema::{NamedColumns, NamedRow};
+use crate::parquet::{
+    header_array, header_field, timestamp_array, timestamp_field, FromRecordBatch, ToRecordBatch,
+};
+
+use std::sync::Arc;
+
+impl TryFrom<parquet::record::Row> for CameraInfo {
+    type Error = Error;
+
+    fn try_from(row: parquet::record::Row) -> Result<Self> {
+        let row = NamedRow::new(&row);
+        let header = row.get_group("header")?;
+        let stamp = header.get_group("stamp")?;
+        let sec = stamp.get_int("sec")?;
+        let nanosec = stamp.get_uint("nanosec")?;
+        let frame_id = header.get_string("frame_id")?.to_string();
+        let height = row.get_uint("height")?;
+        let width = row.get_uint("width")?;
+        let distortion_model = row.get_string("distortion_model")?.to_string();
+        let d = row.get_double_list("d")?;
+        let k = matrix_from_row(&row, "k")?;
+        let r = matrix_from_row(&row, "r")?;
+        let p = matrix_from_row(&row, "p")?;
+        let binning_x = row.get_uint("binning_x")?;
+        let binning_y = row.get_uint("binning_y")?;
+        let roi = row.get_group("roi")?;
+        let roi = RegionOfInterest {
+            x_offset: roi.get_uint("x_offset")?,
+            y_offset: roi.get_uint("y_offset")?,
+            height: roi.get_uint("height")?,
+            width: roi.get_uint("width")?,
+            do_rectify: roi.get_bool("do_rectify")?,
+        };
+
+        Ok(CameraInfo::new(
+            Header::new(Stamp::new(sec, nanosec), frame_id),
+            height,
+            width,
+            distortion_model,
+            d,
+            k,
+            r,
+            p,
+            binning_x,
+            binning_y,
+            roi,
+        ))
+    }
+}
+
+impl FromRecordBatch for CameraInfo {
+    fn from_record_batch(batch: &RecordBatch) -> Result<Vec<Result<Self>>> {
+        let columns = NamedColumns::new(batch);
+        let header = columns.get_struct("header")?;
+        let stamp = header.get_struct("stamp")?;
+        let sec = stamp.get_int("sec")?;
+        let nanosec = stamp.get_uint("nanosec")?;
+        let frame_id = header.get_string("frame_id")?;
+        let height = columns.get_uint("height")?;
+        let width = columns.get_uint("width")?;
+        let distortion_model = columns.get_string("distortion_model")?;
+        let d = columns.get_double_list("d")?;
+        let k = columns.get_double_list("k")?;
+        let r = columns.get_double_list("r")?;
+        let p = columns.get_double_list("p")?;
+        let binning_x = columns.get_uint("binning_x")?;
+        let binning_y = columns.get_uint("binning_y")?;
+        let roi = columns.get_struct("roi")?;
+        let x_offset = roi.get_uint("x_offset")?;
+        let y_offset = roi.get_uint("y_offset")?;
+        let roi_height = roi.get_uint("height")?;
+        let roi_width = roi.get_uint("width")?;
+        let do_rectify = roi.get_bool("do_rectify")?;
+
+        Ok((0..batch.num_rows())
+            .map(|i| {
+                Ok(CameraInfo::new(
+                    Header::new(
+                        Stamp::new(sec.value(i)?, nanosec.value(i)?),
+                        frame_id.value(i)?.to_string(),
+                    ),
+                    height.value(i)?,
+                    width.value(i)?,
+                    distortion_model.value(i)?.to_string(),
+                    d.value(i)?.to_vec(),
+                    matrix_value(&k, i)?,
+                    matrix_value(&r, i)?,
+                    matrix_value(&p, i)?,
+                    binning_x.value(i)?,
+                    binning_y.value(i)?,
+                    RegionOfInterest {
+                        x_offset: x_offset.value(i)?,
+                        y_offset: y_offset.value(i)?,
+                        height: roi_height.value(i)?,
+                        width: roi_width.value(i)?,
+                        do_rectify: do_rectify.value(i)?,
+                    },
+                ))
+            })
+            .collect())
+    }
+}
+
+/// `sensor_msgs/RegionOfInterest` group.
+fn roi_field() -> Field {
+    Field::new(
+        "roi",
+        DataType::Struct(vec![
+            Field::new("x_offset", DataType::UInt32, true),
+            Field::new("y_offset", DataType::UInt32, true),
+            Field::new("height", DataType::UInt32, true),
+            Field::new("width", DataType::UInt32, true),
+            Field::new("do_rectify", DataType::Boolean, true),
+        ]),
+        true,
+    )
+}
+
+impl ToRecordBatch for CameraInfo {
+    fn arrow_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            timestamp_field(),
+            header_field(DataType::UInt32),
+            Field::new("height", DataType::UInt32, true),
+            Field::new("width", DataType::UInt32, true),
+            Field::new("distortion_model", DataType::Utf8, true),
+            matrix_field("d"),
+            matrix_field("k"),
+            matrix_field("r"),
+            matrix_field("p"),
+            Field::new("binning_x", DataType::UInt32, true),
+            Field::new("binning_y", DataType::UInt32, true),
+            roi_field(),
+        ]))
+    }
+
+    fn to_record_batch(msgs: &[CameraInfoRef]) -> ArrowResult<RecordBatch> {
+        let stamps: Vec<Stamp> = msgs.iter().map(|m| m.header.stamp).collect();
+        let nanosecs: Vec<u32> = stamps.iter().map(|stamp| stamp.nanosec()).collect();
+        let uint_column = |f: fn(&CameraInfo) -> u32| -> ArrayRef {
+            Arc::new(UInt32Array::from(
+                msgs.iter().map(|m| f(m.as_ref())).collect::<Vec<u32>>(),
+            ))
+        };
+        let d = ListArray::from_iter_primitive::<Float64Type, _, _>(
+            msgs.iter()
+                .map(|m| Some(m.d.iter().map(|value| Some(*value)).collect::<Vec<_>>())),
+        );
+        let k: Vec<na::Matrix3<f64>> = msgs.iter().map(|m| m.k).collect();
+        let r: Vec<na::Matrix3<f64>> = msgs.iter().map(|m| m.r).collect();
+        let p: Vec<na::Matrix3x4<f64>> = msgs.iter().map(|m| m.p).collect();
+        let columns: Vec<ArrayRef> = vec![
+            timestamp_array(&stamps),
+            header_array(
+                DataType::UInt32,
+                stamps.iter().map(|stamp| stamp.sec()).collect(),
+                Arc::new(UInt32Array::from(nanosecs)),
+                msgs.iter().map(|m| m.header.frame_id.as_str()).collect(),
+            ),
+            uint_column(|m| m.height),
+            uint_column(|m| m.width),
+            Arc::new(StringArray::from(
+                msgs.iter()
+                    .map(|m| m.distortion_model.as_str())
+                    .collect::<Vec<&str>>(),
+            )),
+            Arc::new(d),
+            matrix_array(&k),
+            matrix_array(&r),
+            matrix_array(&p),
+            uint_column(|m| m.binning_x),
+            uint_column(|m| m.binning_y),
+            struct_array(
+                &roi_field(),
+                vec![
+                    uint_column(|m| m.roi.x_offset),
+                    uint_column(|m| m.roi.y_offset),
+                    uint_column(|m| m.roi.height),
+                    uint_column(|m| m.roi.width),
+                    Arc::new(BooleanArray::from(
+                        msgs.iter().map(|m| m.roi.do_rectify).collect::<Vec<bool>>(),
+                    )),
+                ],
+            ),
+        ];
+        RecordBatch::try_new(Self::arrow_schema(), columns)
+    }
+}
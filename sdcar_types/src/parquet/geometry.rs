@@ -0,0 +1,307 @@
+//! Encoding and decoding of the `geometry_msgs` groups and fixed size
+//! covariance lists shared by several message types.
+
+extern crate arrow;
+
+use arrow::array::{ArrayRef, Float64Array, ListArray, StructArray};
+use arrow::datatypes::{DataType, Field, Float64Type};
+use nalgebra as na;
+
+use crate::error::{Error, Result};
+use crate::msg::geometry::{Pose, PoseWithCovariance, Twist, TwistWithCovariance};
+use crate::parquet::schema::{Column, DoubleListColumn, NamedColumns, NamedRow};
+
+use std::sync::Arc;
+
+/// A matrix from its row major elements, as ROS lays out covariances.
+pub(crate) fn matrix_from_row_major<const R: usize, const C: usize>(
+    field: &str,
+    values: &[f64],
+) -> Result<na::SMatrix<f64, R, C>> {
+    if values.len() != R * C {
+        return Err(Error::decode(
+            field,
+            "list<double>",
+            format!("expected {} elements, found {}", R * C, values.len()),
+        ));
+    }
+    Ok(na::SMatrix::from_row_slice(values))
+}
+
+fn row_major<const R: usize, const C: usize>(matrix: &na::SMatrix<f64, R, C>) -> Vec<Option<f64>> {
+    matrix
+        .transpose()
+        .iter()
+        .map(|value| Some(*value))
+        .collect()
+}
+
+pub(crate) fn matrix_from_row<const R: usize, const C: usize>(
+    row: &NamedRow,
+    name: &str,
+) -> Result<na::SMatrix<f64, R, C>> {
+    matrix_from_row_major(&row.path(name), &row.get_double_list(name)?)
+}
+
+pub(crate) fn vector3_from_row(row: &NamedRow, name: &str) -> Result<na::Vector3<f64>> {
+    let group = row.get_group(name)?;
+    Ok(na::Vector3::new(
+        group.get_double("x")?,
+        group.get_double("y")?,
+        group.get_double("z")?,
+    ))
+}
+
+pub(crate) fn quaternion_from_row(row: &NamedRow, name: &str) -> Result<na::Quaternion<f64>> {
+    let group = row.get_group(name)?;
+    Ok(na::Quaternion::new(
+        group.get_double("w")?,
+        group.get_double("x")?,
+        group.get_double("y")?,
+        group.get_double("z")?,
+    ))
+}
+
+pub(crate) fn twist_from_row(row: &NamedRow, name: &str) -> Result<Twist> {
+    let group = row.get_group(name)?;
+    Ok(Twist::new(
+        vector3_from_row(&group, "linear")?,
+        vector3_from_row(&group, "angular")?,
+    ))
+}
+
+pub(crate) fn pose_with_covariance_from_row(
+    row: &NamedRow,
+    name: &str,
+) -> Result<PoseWithCovariance> {
+    let group = row.get_group(name)?;
+    let pose = group.get_group("pose")?;
+    Ok(PoseWithCovariance {
+        pose: Pose::new(
+            vector3_from_row(&pose, "position")?.into(),
+            quaternion_from_row(&pose, "orientation")?,
+        ),
+        covariance: matrix_from_row(&group, "covariance")?,
+    })
+}
+
+pub(crate) fn twist_with_covariance_from_row(
+    row: &NamedRow,
+    name: &str,
+) -> Result<TwistWithCovariance> {
+    let group = row.get_group(name)?;
+    Ok(TwistWithCovariance {
+        twist: twist_from_row(&group, "twist")?,
+        covariance: matrix_from_row(&group, "covariance")?,
+    })
+}
+
+/// The columns of an `x`, `y`, `z` group.
+pub(crate) struct Vector3Columns<'c> {
+    x: Column<'c, Float64Array>,
+    y: Column<'c, Float64Array>,
+    z: Column<'c, Float64Array>,
+}
+
+impl<'c> Vector3Columns<'c> {
+    pub fn new(group: &'c NamedColumns) -> Result<Self> {
+        Ok(Self {
+            x: group.get_double("x")?,
+            y: group.get_double("y")?,
+            z: group.get_double("z")?,
+        })
+    }
+
+    pub fn value(&self, i: usize) -> Result<na::Vector3<f64>> {
+        Ok(na::Vector3::new(
+            self.x.value(i)?,
+            self.y.value(i)?,
+            self.z.value(i)?,
+        ))
+    }
+}
+
+/// The columns of an `x`, `y`, `z`, `w` group.
+pub(crate) struct QuaternionColumns<'c> {
+    xyz: Vector3Columns<'c>,
+    w: Column<'c, Float64Array>,
+}
+
+impl<'c> QuaternionColumns<'c> {
+    pub fn new(group: &'c NamedColumns) -> Result<Self> {
+        Ok(Self {
+            xyz: Vector3Columns::new(group)?,
+            w: group.get_double("w")?,
+        })
+    }
+
+    pub fn value(&self, i: usize) -> Result<na::Quaternion<f64>> {
+        Ok(na::Quaternion::from_parts(
+            self.w.value(i)?,
+            self.xyz.value(i)?,
+        ))
+    }
+}
+
+pub(crate) fn matrix_value<const R: usize, const C: usize>(
+    column: &DoubleListColumn,
+    i: usize,
+) -> Result<na::SMatrix<f64, R, C>> {
+    matrix_from_row_major(column.path(), column.value(i)?)
+}
+
+fn struct_fields(field: &Field) -> Vec<Field> {
+    match field.data_type() {
+        DataType::Struct(fields) => fields.clone(),
+        _ => unreachable!(),
+    }
+}
+
+/// Pairs the children of the struct `field` with their arrays.
+pub(crate) fn struct_array(field: &Field, arrays: Vec<ArrayRef>) -> ArrayRef {
+    Arc::new(StructArray::from(
+        struct_fields(field)
+            .into_iter()
+            .zip(arrays)
+            .collect::<Vec<(Field, ArrayRef)>>(),
+    ))
+}
+
+fn double_fields(names: &[&str]) -> Vec<Field> {
+    names
+        .iter()
+        .map(|name| Field::new(name, DataType::Float64, true))
+        .collect()
+}
+
+fn double_array(values: impl Iterator<Item = f64>) -> ArrayRef {
+    Arc::new(Float64Array::from(values.collect::<Vec<f64>>()))
+}
+
+pub(crate) fn vector3_field(name: &str) -> Field {
+    Field::new(
+        name,
+        DataType::Struct(double_fields(&["x", "y", "z"])),
+        true,
+    )
+}
+
+pub(crate) fn vector3_array(name: &str, vectors: &[na::Vector3<f64>]) -> ArrayRef {
+    struct_array(
+        &vector3_field(name),
+        (0..3)
+            .map(|axis| double_array(vectors.iter().map(|vector| vector[axis])))
+            .collect(),
+    )
+}
+
+pub(crate) fn quaternion_field(name: &str) -> Field {
+    Field::new(
+        name,
+        DataType::Struct(double_fields(&["x", "y", "z", "w"])),
+        true,
+    )
+}
+
+pub(crate) fn quaternion_array(name: &str, quaternions: &[na::Quaternion<f64>]) -> ArrayRef {
+    // coords are stored x, y, z, w
+    struct_array(
+        &quaternion_field(name),
+        (0..4)
+            .map(|i| double_array(quaternions.iter().map(|quaternion| quaternion.coords[i])))
+            .collect(),
+    )
+}
+
+pub(crate) fn matrix_field(name: &str) -> Field {
+    Field::new(
+        name,
+        DataType::List(Box::new(Field::new("item", DataType::Float64, true))),
+        true,
+    )
+}
+
+pub(crate) fn matrix_array<const R: usize, const C: usize>(
+    matrices: &[na::SMatrix<f64, R, C>],
+) -> ArrayRef {
+    Arc::new(ListArray::from_iter_primitive::<Float64Type, _, _>(
+        matrices.iter().map(|matrix| Some(row_major(matrix))),
+    ))
+}
+
+pub(crate) fn twist_field(name: &str) -> Field {
+    Field::new(
+        name,
+        DataType::Struct(vec![vector3_field("linear"), vector3_field("angular")]),
+        true,
+    )
+}
+
+pub(crate) fn twist_array(name: &str, twists: &[Twist]) -> ArrayRef {
+    let linear: Vec<na::Vector3<f64>> = twists.iter().map(|twist| twist.linear).collect();
+    let angular: Vec<na::Vector3<f64>> = twists.iter().map(|twist| twist.angular).collect();
+    struct_array(
+        &twist_field(name),
+        vec![
+            vector3_array("linear", &linear),
+            vector3_array("angular", &angular),
+        ],
+    )
+}
+
+fn pose_field(name: &str) -> Field {
+    Field::new(
+        name,
+        DataType::Struct(vec![
+            vector3_field("position"),
+            quaternion_field("orientation"),
+        ]),
+        true,
+    )
+}
+
+pub(crate) fn pose_with_covariance_field(name: &str) -> Field {
+    Field::new(
+        name,
+        DataType::Struct(vec![pose_field("pose"), matrix_field("covariance")]),
+        true,
+    )
+}
+
+pub(crate) fn pose_with_covariance_array(name: &str, poses: &[PoseWithCovariance]) -> ArrayRef {
+    let positions: Vec<na::Vector3<f64>> =
+        poses.iter().map(|pose| pose.pose.position.coords).collect();
+    let orientations: Vec<na::Quaternion<f64>> =
+        poses.iter().map(|pose| pose.pose.orientation).collect();
+    let covariances: Vec<na::Matrix6<f64>> = poses.iter().map(|pose| pose.covariance).collect();
+    struct_array(
+        &pose_with_covariance_field(name),
+        vec![
+            struct_array(
+                &pose_field("pose"),
+                vec![
+                    vector3_array("position", &positions),
+                    quaternion_array("orientation", &orientations),
+                ],
+            ),
+            matrix_array(&covariances),
+        ],
+    )
+}
+
+pub(crate) fn twist_with_covariance_field(name: &str) -> Field {
+    Field::new(
+        name,
+        DataType::Struct(vec![twist_field("twist"), matrix_field("covariance")]),
+        true,
+    )
+}
+
+pub(crate) fn twist_with_covariance_array(name: &str, twists: &[TwistWithCovariance]) -> ArrayRef {
+    let inner: Vec<Twist> = twists.iter().map(|twist| twist.twist).collect();
+    let covariances: Vec<na::Matrix6<f64>> = twists.iter().map(|twist| twist.covariance).collect();
+    struct_array(
+        &twist_with_covariance_field(name),
+        vec![twist_array("twist", &inner), matrix_array(&covariances)],
+    )
+}
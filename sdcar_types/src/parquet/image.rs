@@ -1,24 +1,39 @@
+extern crate arrow;
 extern crate parquet;
 
-use parquet::record::RowAccessor;
-
-use crate::msg::image::Image;
-
-impl From<parquet::record::Row> for Image {
-    fn from(row: parquet::record::Row) -> Self {
-        let header = row.get_group(1).unwrap();
-        let stamp = header.get_group(0).unwrap();
-        let sec = stamp.get_int(0).unwrap();
-        let nanosec = stamp.get_uint(1).unwrap();
-        let frame_id = header.get_string(1).unwrap().to_string();
-        let height = row.get_uint(2).unwrap();
-        let width = row.get_uint(3).unwrap();
-        let encoding = row.get_string(4).unwrap().to_string();
-        let is_bigendian = row.get_bool(5).unwrap();
-        let step = row.get_uint(6).unwrap();
-        let image_oid = row.get_string(7).unwrap().to_string();
-
-        Image::new(
+use arrow::array::{ArrayRef, BooleanArray, StringArray, UInt32Array};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+
+use crate::error::{Error, Result};
+use crate::msg::header::Stamp;
+use crate::msg::image::{Image, ImageRef};
+use crate::parquet::schema::{NamedColumns, NamedRow};
+use crate::parquet::{
+    header_array, header_field, timestamp_array, timestamp_field, FromRecordBatch, ToRecordBatch,
+};
+
+use std::sync::Arc;
+
+impl TryFrom<parquet::record::Row> for Image {
+    type Error = Error;
+
+    fn try_from(row: parquet::record::Row) -> Result<Self> {
+        let row = NamedRow::new(&row);
+        let header = row.get_group("header")?;
+        let stamp = header.get_group("stamp")?;
+        let sec = stamp.get_int("sec")?;
+        let nanosec = stamp.get_uint("nanosec")?;
+        let frame_id = header.get_string("frame_id")?.to_string();
+        let height = row.get_uint("height")?;
+        let width = row.get_uint("width")?;
+        let encoding = row.get_string("encoding")?.to_string();
+        let is_bigendian = row.get_bool("is_bigendian")?;
+        let step = row.get_uint("step")?;
+        let image_oid = row.get_string("image_oid")?.to_string();
+
+        Ok(Image::new(
             sec,
             nanosec,
             frame_id,
@@ -28,6 +43,87 @@ impl From<parquet::record::Row> for Image {
             is_bigendian,
             step,
             image_oid,
-        )
+        ))
+    }
+}
+
+impl FromRecordBatch for Image {
+    fn from_record_batch(batch: &RecordBatch) -> Result<Vec<Result<Self>>> {
+        let columns = NamedColumns::new(batch);
+        let header = columns.get_struct("header")?;
+        let stamp = header.get_struct("stamp")?;
+        let sec = stamp.get_int("sec")?;
+        let nanosec = stamp.get_uint("nanosec")?;
+        let frame_id = header.get_string("frame_id")?;
+        let height = columns.get_uint("height")?;
+        let width = columns.get_uint("width")?;
+        let encoding = columns.get_string("encoding")?;
+        let is_bigendian = columns.get_bool("is_bigendian")?;
+        let step = columns.get_uint("step")?;
+        let image_oid = columns.get_string("image_oid")?;
+
+        Ok((0..batch.num_rows())
+            .map(|i| {
+                Ok(Image::new(
+                    sec.value(i)?,
+                    nanosec.value(i)?,
+                    frame_id.value(i)?.to_string(),
+                    height.value(i)?,
+                    width.value(i)?,
+                    encoding.value(i)?.to_string(),
+                    is_bigendian.value(i)?,
+                    step.value(i)?,
+                    image_oid.value(i)?.to_string(),
+                ))
+            })
+            .collect())
+    }
+}
+
+impl ToRecordBatch for Image {
+    fn arrow_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            timestamp_field(),
+            header_field(DataType::UInt32),
+            Field::new("height", DataType::UInt32, true),
+            Field::new("width", DataType::UInt32, true),
+            Field::new("encoding", DataType::Utf8, true),
+            Field::new("is_bigendian", DataType::Boolean, true),
+            Field::new("step", DataType::UInt32, true),
+            Field::new("image_oid", DataType::Utf8, true),
+        ]))
+    }
+
+    fn to_record_batch(msgs: &[ImageRef]) -> ArrowResult<RecordBatch> {
+        let stamps: Vec<Stamp> = msgs.iter().map(|m| m.header.stamp).collect();
+        let nanosecs: Vec<u32> = stamps.iter().map(|stamp| stamp.nanosec()).collect();
+        let columns: Vec<ArrayRef> = vec![
+            timestamp_array(&stamps),
+            header_array(
+                DataType::UInt32,
+                stamps.iter().map(|stamp| stamp.sec()).collect(),
+                Arc::new(UInt32Array::from(nanosecs)),
+                msgs.iter().map(|m| m.header.frame_id.as_str()).collect(),
+            ),
+            Arc::new(UInt32Array::from(
+                msgs.iter().map(|m| m.height).collect::<Vec<u32>>(),
+            )),
+            Arc::new(UInt32Array::from(
+                msgs.iter().map(|m| m.width).collect::<Vec<u32>>(),
+            )),
+            Arc::new(StringArray::from(
+                msgs.iter().map(|m| m.encoding.as_str()).collect::<Vec<&str>>(),
+            )),
+            Arc::new(BooleanArray::from(
+                msgs.iter().map(|m| m.is_bigendian).collect::<Vec<bool>>(),
+            )),
+            Arc::new(UInt32Array::from(
+                msgs.iter().map(|m| m.step).collect::<Vec<u32>>(),
+            )),
+            Arc::new(StringArray::from(
+                msgs.iter().map(|m| m.image_oid.as_str()).collect::<Vec<&str>>(),
+            )),
+        ];
+        RecordBatch::try_new(Self::arrow_schema(), columns)
     }
 }
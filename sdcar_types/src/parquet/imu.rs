@@ -0,0 +1,127 @@
+extern crate arrow;
+extern crate parquet;
+
+use arrow::array::{ArrayRef, UInt32Array};
+use arrow::datatypes::{DataType, Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+use nalgebra as na;
+
+use crate::error::{Error, Result};
+use crate::msg::header::{Header, Stamp};
+use crate::msg::imu::{Imu, ImuRef};
+use crate::parquet::geometry::{
+    matrix_array, matrix_field, matrix_from_row, matrix_value, quaternion_array, quaternion_field,
+    quaternion_from_row, vector3_array, vector3_field, vector3_from_row, QuaternionColumns,
+    Vector3Columns,
+};
+use crate::parquet::schema::{NamedColumns, NamedRow};
+use crate::parquet::{
+    header_array, header_field, timestamp_array, timestamp_field, FromRecordBatch, ToRecordBatch,
+};
+
+use std::sync::Arc;
+
+impl TryFrom<parquet::record::Row> for Imu {
+    type Error = Error;
+
+    fn try_from(row: parquet::record::Row) -> Result<Self> {
+        let row = NamedRow::new(&row);
+        let header = row.get_group("header")?;
+        let stamp = header.get_group("stamp")?;
+        let sec = stamp.get_int("sec")?;
+        let nanosec = stamp.get_uint("nanosec")?;
+        let frame_id = header.get_string("frame_id")?.to_string();
+
+        Ok(Imu::new(
+            Header::new(Stamp::new(sec, nanosec), frame_id),
+            quaternion_from_row(&row, "orientation")?,
+            matrix_from_row(&row, "orientation_covariance")?,
+            vector3_from_row(&row, "angular_velocity")?,
+            matrix_from_row(&row, "angular_velocity_covariance")?,
+            vector3_from_row(&row, "linear_acceleration")?,
+            matrix_from_row(&row, "linear_acceleration_covariance")?,
+        ))
+    }
+}
+
+impl FromRecordBatch for Imu {
+    fn from_record_batch(batch: &RecordBatch) -> Result<Vec<Result<Self>>> {
+        let columns = NamedColumns::new(batch);
+        let header = columns.get_struct("header")?;
+        let stamp = header.get_struct("stamp")?;
+        let sec = stamp.get_int("sec")?;
+        let nanosec = stamp.get_uint("nanosec")?;
+        let frame_id = header.get_string("frame_id")?;
+        let orientation_group = columns.get_struct("orientation")?;
+        let orientation = QuaternionColumns::new(&orientation_group)?;
+        let orientation_covariance = columns.get_double_list("orientation_covariance")?;
+        let angular_velocity_group = columns.get_struct("angular_velocity")?;
+        let angular_velocity = Vector3Columns::new(&angular_velocity_group)?;
+        let angular_velocity_covariance = columns.get_double_list("angular_velocity_covariance")?;
+        let linear_acceleration_group = columns.get_struct("linear_acceleration")?;
+        let linear_acceleration = Vector3Columns::new(&linear_acceleration_group)?;
+        let linear_acceleration_covariance =
+            columns.get_double_list("linear_acceleration_covariance")?;
+
+        Ok((0..batch.num_rows())
+            .map(|i| {
+                Ok(Imu::new(
+                    Header::new(
+                        Stamp::new(sec.value(i)?, nanosec.value(i)?),
+                        frame_id.value(i)?.to_string(),
+                    ),
+                    orientation.value(i)?,
+                    matrix_value(&orientation_covariance, i)?,
+                    angular_velocity.value(i)?,
+                    matrix_value(&angular_velocity_covariance, i)?,
+                    linear_acceleration.value(i)?,
+                    matrix_value(&linear_acceleration_covariance, i)?,
+                ))
+            })
+            .collect())
+    }
+}
+
+impl ToRecordBatch for Imu {
+    fn arrow_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            timestamp_field(),
+            header_field(DataType::UInt32),
+            quaternion_field("orientation"),
+            matrix_field("orientation_covariance"),
+            vector3_field("angular_velocity"),
+            matrix_field("angular_velocity_covariance"),
+            vector3_field("linear_acceleration"),
+            matrix_field("linear_acceleration_covariance"),
+        ]))
+    }
+
+    fn to_record_batch(msgs: &[ImuRef]) -> ArrowResult<RecordBatch> {
+        let stamps: Vec<Stamp> = msgs.iter().map(|m| m.header.stamp).collect();
+        let nanosecs: Vec<u32> = stamps.iter().map(|stamp| stamp.nanosec()).collect();
+        let orientations: Vec<na::Quaternion<f64>> = msgs.iter().map(|m| m.orientation).collect();
+        let vectors = |f: fn(&Imu) -> na::Vector3<f64>| -> Vec<na::Vector3<f64>> {
+            msgs.iter().map(|m| f(m.as_ref())).collect()
+        };
+        let covariances = |f: fn(&Imu) -> na::Matrix3<f64>| -> ArrayRef {
+            matrix_array(&msgs.iter().map(|m| f(m.as_ref())).collect::<Vec<_>>())
+        };
+        let columns: Vec<ArrayRef> = vec![
+            timestamp_array(&stamps),
+            header_array(
+                DataType::UInt32,
+                stamps.iter().map(|stamp| stamp.sec()).collect(),
+                Arc::new(UInt32Array::from(nanosecs)),
+                msgs.iter().map(|m| m.header.frame_id.as_str()).collect(),
+            ),
+            quaternion_array("orientation", &orientations),
+            covariances(|m| m.orientation_covariance),
+            vector3_array("angular_velocity", &vectors(|m| m.angular_velocity)),
+            covariances(|m| m.angular_velocity_covariance),
+            vector3_array("linear_acceleration", &vectors(|m| m.linear_acceleration)),
+            covariances(|m| m.linear_acceleration_covariance),
+        ];
+        RecordBatch::try_new(Self::arrow_schema(), columns)
+    }
+}
@@ -1,4 +1,98 @@
+pub mod camera_info;
+mod geometry;
 pub mod image;
+pub mod imu;
 pub mod nav_sat_fix;
+pub mod odometry;
 pub mod point_cloud2;
+pub mod schema;
+pub mod twist_stamped;
 pub mod vehicle_state;
+
+extern crate arrow;
+
+use arrow::array::{ArrayRef, Int32Array, Int64Array, StringArray, StructArray};
+use arrow::datatypes::{DataType, Field, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+
+use crate::error::{Error, Result};
+use crate::msg::header::Stamp;
+use std::sync::Arc;
+
+/// Encodes messages into Arrow record batches laid out with the same
+/// schema the `TryFrom<Row>` decoders in this module expect.
+pub trait ToRecordBatch: Sized {
+    fn arrow_schema() -> SchemaRef;
+    fn to_record_batch(msgs: &[Arc<Self>]) -> ArrowResult<RecordBatch>;
+}
+
+/// Decodes the messages of a record batch read from parquet, a column at a
+/// time instead of through `parquet::record::Row`.
+pub trait FromRecordBatch: Sized {
+    /// Fails if a column is missing or mistyped; otherwise returns one
+    /// result per row, so a single bad row does not lose the whole batch.
+    fn from_record_batch(batch: &RecordBatch) -> Result<Vec<Result<Self>>>;
+}
+
+/// Leading column written by the recorder: the header stamp in nanoseconds.
+pub(crate) fn timestamp_field() -> Field {
+    Field::new("timestamp", DataType::Int64, true)
+}
+
+pub(crate) fn timestamp_array(stamps: &[Stamp]) -> ArrayRef {
+    let nanos: Vec<i64> = stamps.iter().map(|stamp| stamp.as_nanos()).collect();
+    Arc::new(Int64Array::from(nanos))
+}
+
+/// Checks a nanosec recorded as `int64`, as by the `NavSatFix` and
+/// `VehicleState` topics, fits the `u32` of a `Stamp`.
+pub(crate) fn nanosec_from_long(field: &str, nanosec: i64) -> Result<u32> {
+    u32::try_from(nanosec)
+        .map_err(|_| Error::decode(field, "uint32", format!("{} is out of range", nanosec)))
+}
+
+/// `builtin_interfaces/Time` group; the nanosec type differs between topics.
+pub(crate) fn time_fields(nanosec_type: DataType) -> Vec<Field> {
+    vec![
+        Field::new("sec", DataType::Int32, true),
+        Field::new("nanosec", nanosec_type, true),
+    ]
+}
+
+pub(crate) fn time_array(nanosec_type: DataType, secs: Vec<i32>, nanosecs: ArrayRef) -> StructArray {
+    let fields = time_fields(nanosec_type);
+    StructArray::from(vec![
+        (fields[0].clone(), Arc::new(Int32Array::from(secs)) as ArrayRef),
+        (fields[1].clone(), nanosecs),
+    ])
+}
+
+/// `std_msgs/Header` group.
+pub(crate) fn header_field(nanosec_type: DataType) -> Field {
+    Field::new(
+        "header",
+        DataType::Struct(vec![
+            Field::new("stamp", DataType::Struct(time_fields(nanosec_type)), true),
+            Field::new("frame_id", DataType::Utf8, true),
+        ]),
+        true,
+    )
+}
+
+pub(crate) fn header_array(
+    nanosec_type: DataType,
+    secs: Vec<i32>,
+    nanosecs: ArrayRef,
+    frame_ids: Vec<&str>,
+) -> ArrayRef {
+    let stamp = time_array(nanosec_type.clone(), secs, nanosecs);
+    let fields = match header_field(nanosec_type).data_type() {
+        DataType::Struct(fields) => fields.clone(),
+        _ => unreachable!(),
+    };
+    Arc::new(StructArray::from(vec![
+        (fields[0].clone(), Arc::new(stamp) as ArrayRef),
+        (fields[1].clone(), Arc::new(StringArray::from(frame_ids)) as ArrayRef),
+    ]))
+}
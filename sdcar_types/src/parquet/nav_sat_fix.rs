@@ -1,31 +1,188 @@
+extern crate arrow;
 extern crate parquet;
 
-use parquet::record::{ListAccessor, RowAccessor};
-
-use crate::msg::nav_sat_fix::NavSatFix;
-
-impl From<parquet::record::Row> for NavSatFix {
-    fn from(row: parquet::record::Row) -> Self {
-        let header = row.get_group(1).unwrap();
-        let stamp = header.get_group(0).unwrap();
-        let sec = stamp.get_int(0).unwrap();
-        let nanosec = stamp.get_long(1).unwrap();
-        let frame = header.get_string(1).unwrap().to_string();
-        let status_group = row.get_group(2).unwrap();
-        let status = status_group.get_byte(0).unwrap();
-        let service = status_group.get_ushort(1).unwrap();
-        let latitude = row.get_double(3).unwrap();
-        let longitude = row.get_double(4).unwrap();
-        let altitude = row.get_double(5).unwrap();
-        let mut covar: [f64; 9] = [0.0; 9];
-        let covar_row = row.get_list(6).unwrap();
-        for i in 0..covar_row.len() {
-            covar[i] = covar_row.get_double(i).unwrap();
-        }
-        let covar_type = row.get_ubyte(7).unwrap();
-
-        NavSatFix::new(
-            sec, nanosec, frame, status, service, latitude, longitude, altitude, covar, covar_type,
-        )
+use arrow::array::{
+    Array, ArrayRef, Float64Array, Int64Array, Int8Array, ListArray, StructArray, UInt16Array,
+    UInt8Array,
+};
+use arrow::datatypes::{DataType, Field, Float64Type, Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+
+use crate::error::{Error, Result};
+use crate::msg::header::Stamp;
+use crate::msg::nav_sat_fix::{NavSatFix, NavSatFixRef};
+use crate::parquet::schema::{NamedColumns, NamedRow};
+use crate::parquet::{
+    header_array, header_field, nanosec_from_long, timestamp_array, timestamp_field,
+    FromRecordBatch, ToRecordBatch,
+};
+
+use std::sync::Arc;
+
+impl TryFrom<parquet::record::Row> for NavSatFix {
+    type Error = Error;
+
+    fn try_from(row: parquet::record::Row) -> Result<Self> {
+        let row = NamedRow::new(&row);
+        let header = row.get_group("header")?;
+        let stamp = header.get_group("stamp")?;
+        let sec = stamp.get_int("sec")?;
+        let nanosec = nanosec_from_long("header.stamp.nanosec", stamp.get_long("nanosec")?)?;
+        let frame_id = header.get_string("frame_id")?.to_string();
+        let status_group = row.get_group("status")?;
+        let status = status_group.get_byte("status")?;
+        let service = status_group.get_ushort("service")?;
+        let latitude = row.get_double("latitude")?;
+        let longitude = row.get_double("longitude")?;
+        let altitude = row.get_double("altitude")?;
+        let covar_list = row.get_double_list("position_covariance")?;
+        let covar: [f64; 9] = covar_list.as_slice().try_into().map_err(|_| {
+            Error::decode(
+                "position_covariance",
+                "list<double>",
+                format!("expected 9 elements, found {}", covar_list.len()),
+            )
+        })?;
+        let covar_type = row.get_ubyte("position_covariance_type")?;
+
+        Ok(NavSatFix::new(
+            sec, nanosec, frame_id, status, service, latitude, longitude, altitude, covar,
+            covar_type,
+        ))
+    }
+}
+
+impl FromRecordBatch for NavSatFix {
+    fn from_record_batch(batch: &RecordBatch) -> Result<Vec<Result<Self>>> {
+        let columns = NamedColumns::new(batch);
+        let header = columns.get_struct("header")?;
+        let stamp = header.get_struct("stamp")?;
+        let sec = stamp.get_int("sec")?;
+        let nanosec = stamp.get_long("nanosec")?;
+        let frame_id = header.get_string("frame_id")?;
+        let status_group = columns.get_struct("status")?;
+        let status = status_group.get_byte("status")?;
+        let service = status_group.get_ushort("service")?;
+        let latitude = columns.get_double("latitude")?;
+        let longitude = columns.get_double("longitude")?;
+        let altitude = columns.get_double("altitude")?;
+        let covar_list = columns.get_list("position_covariance")?;
+        let covar_values = covar_list.array().values();
+        let covar_values = covar_values
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .ok_or_else(|| {
+                Error::decode(
+                    "position_covariance",
+                    "list<double>",
+                    format!("found list<{:?}>", covar_values.data_type()),
+                )
+            })?;
+        let covar_type = columns.get_ubyte("position_covariance_type")?;
+
+        Ok((0..batch.num_rows())
+            .map(|i| {
+                let range = covar_list.value_range(i)?;
+                let covar: [f64; 9] =
+                    covar_values.values()[range.clone()]
+                        .try_into()
+                        .map_err(|_| {
+                            Error::decode(
+                                "position_covariance",
+                                "list<double>",
+                                format!("expected 9 elements, found {}", range.len()),
+                            )
+                        })?;
+                Ok(NavSatFix::new(
+                    sec.value(i)?,
+                    nanosec_from_long("header.stamp.nanosec", nanosec.value(i)?)?,
+                    frame_id.value(i)?.to_string(),
+                    status.value(i)?,
+                    service.value(i)?,
+                    latitude.value(i)?,
+                    longitude.value(i)?,
+                    altitude.value(i)?,
+                    covar,
+                    covar_type.value(i)?,
+                ))
+            })
+            .collect())
+    }
+}
+
+impl ToRecordBatch for NavSatFix {
+    fn arrow_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            timestamp_field(),
+            header_field(DataType::Int64),
+            Field::new("status", DataType::Struct(status_fields()), true),
+            Field::new("latitude", DataType::Float64, true),
+            Field::new("longitude", DataType::Float64, true),
+            Field::new("altitude", DataType::Float64, true),
+            Field::new(
+                "position_covariance",
+                DataType::List(Box::new(Field::new("item", DataType::Float64, true))),
+                true,
+            ),
+            Field::new("position_covariance_type", DataType::UInt8, true),
+        ]))
     }
+
+    fn to_record_batch(msgs: &[NavSatFixRef]) -> ArrowResult<RecordBatch> {
+        let stamps: Vec<Stamp> = msgs.iter().map(|m| m.header.stamp).collect();
+        let nanosecs: Vec<i64> = stamps.iter().map(|stamp| stamp.nanosec() as i64).collect();
+        let status_fields = status_fields();
+        let status = StructArray::from(vec![
+            (
+                status_fields[0].clone(),
+                Arc::new(Int8Array::from(
+                    msgs.iter().map(|m| m.status).collect::<Vec<i8>>(),
+                )) as ArrayRef,
+            ),
+            (
+                status_fields[1].clone(),
+                Arc::new(UInt16Array::from(
+                    msgs.iter().map(|m| m.service).collect::<Vec<u16>>(),
+                )) as ArrayRef,
+            ),
+        ]);
+        let covariance = ListArray::from_iter_primitive::<Float64Type, _, _>(
+            msgs.iter().map(|m| Some(m.position_covariance.map(Some))),
+        );
+        let columns: Vec<ArrayRef> = vec![
+            timestamp_array(&stamps),
+            header_array(
+                DataType::Int64,
+                stamps.iter().map(|stamp| stamp.sec()).collect(),
+                Arc::new(Int64Array::from(nanosecs)),
+                msgs.iter().map(|m| m.header.frame_id.as_str()).collect(),
+            ),
+            Arc::new(status),
+            Arc::new(Float64Array::from(
+                msgs.iter().map(|m| m.latitude).collect::<Vec<f64>>(),
+            )),
+            Arc::new(Float64Array::from(
+                msgs.iter().map(|m| m.longitude).collect::<Vec<f64>>(),
+            )),
+            Arc::new(Float64Array::from(
+                msgs.iter().map(|m| m.altitude).collect::<Vec<f64>>(),
+            )),
+            Arc::new(covariance),
+            Arc::new(UInt8Array::from(
+                msgs.iter()
+                    .map(|m| m.position_covariance_type)
+                    .collect::<Vec<u8>>(),
+            )),
+        ];
+        RecordBatch::try_new(Self::arrow_schema(), columns)
+    }
+}
+
+/// `sensor_msgs/NavSatStatus` group.
+fn status_fields() -> Vec<Field> {
+    vec![
+        Field::new("status", DataType::Int8, true),
+        Field::new("service", DataType::UInt16, true),
+    ]
 }
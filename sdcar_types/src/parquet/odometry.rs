@@ -0,0 +1,128 @@
+extern crate arrow;
+extern crate parquet;
+
+use arrow::array::{ArrayRef, StringArray, UInt32Array};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+
+use crate::error::{Error, Result};
+use crate::msg::geometry::{Pose, PoseWithCovariance, Twist, TwistWithCovariance};
+use crate::msg::header::{Header, Stamp};
+use crate::msg::odometry::{Odometry, OdometryRef};
+use crate::parquet::geometry::{
+    matrix_value, pose_with_covariance_array, pose_with_covariance_field,
+    pose_with_covariance_from_row, twist_with_covariance_array, twist_with_covariance_field,
+    twist_with_covariance_from_row, QuaternionColumns, Vector3Columns,
+};
+use crate::parquet::schema::{NamedColumns, NamedRow};
+use crate::parquet::{
+    header_array, header_field, timestamp_array, timestamp_field, FromRecordBatch, ToRecordBatch,
+};
+
+use std::sync::Arc;
+
+impl TryFrom<parquet::record::Row> for Odometry {
+    type Error = Error;
+
+    fn try_from(row: parquet::record::Row) -> Result<Self> {
+        let row = NamedRow::new(&row);
+        let header = row.get_group("header")?;
+        let stamp = header.get_group("stamp")?;
+        let sec = stamp.get_int("sec")?;
+        let nanosec = stamp.get_uint("nanosec")?;
+        let frame_id = header.get_string("frame_id")?.to_string();
+        let child_frame_id = row.get_string("child_frame_id")?.to_string();
+
+        Ok(Odometry::new(
+            Header::new(Stamp::new(sec, nanosec), frame_id),
+            child_frame_id,
+            pose_with_covariance_from_row(&row, "pose")?,
+            twist_with_covariance_from_row(&row, "twist")?,
+        ))
+    }
+}
+
+impl FromRecordBatch for Odometry {
+    fn from_record_batch(batch: &RecordBatch) -> Result<Vec<Result<Self>>> {
+        let columns = NamedColumns::new(batch);
+        let header = columns.get_struct("header")?;
+        let stamp = header.get_struct("stamp")?;
+        let sec = stamp.get_int("sec")?;
+        let nanosec = stamp.get_uint("nanosec")?;
+        let frame_id = header.get_string("frame_id")?;
+        let child_frame_id = columns.get_string("child_frame_id")?;
+
+        let pose_group = columns.get_struct("pose")?;
+        let pose = pose_group.get_struct("pose")?;
+        let position_group = pose.get_struct("position")?;
+        let position = Vector3Columns::new(&position_group)?;
+        let orientation_group = pose.get_struct("orientation")?;
+        let orientation = QuaternionColumns::new(&orientation_group)?;
+        let pose_covariance = pose_group.get_double_list("covariance")?;
+
+        let twist_group = columns.get_struct("twist")?;
+        let twist = twist_group.get_struct("twist")?;
+        let linear_group = twist.get_struct("linear")?;
+        let linear = Vector3Columns::new(&linear_group)?;
+        let angular_group = twist.get_struct("angular")?;
+        let angular = Vector3Columns::new(&angular_group)?;
+        let twist_covariance = twist_group.get_double_list("covariance")?;
+
+        Ok((0..batch.num_rows())
+            .map(|i| {
+                Ok(Odometry::new(
+                    Header::new(
+                        Stamp::new(sec.value(i)?, nanosec.value(i)?),
+                        frame_id.value(i)?.to_string(),
+                    ),
+                    child_frame_id.value(i)?.to_string(),
+                    PoseWithCovariance {
+                        pose: Pose::new(position.value(i)?.into(), orientation.value(i)?),
+                        covariance: matrix_value(&pose_covariance, i)?,
+                    },
+                    TwistWithCovariance {
+                        twist: Twist::new(linear.value(i)?, angular.value(i)?),
+                        covariance: matrix_value(&twist_covariance, i)?,
+                    },
+                ))
+            })
+            .collect())
+    }
+}
+
+impl ToRecordBatch for Odometry {
+    fn arrow_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            timestamp_field(),
+            header_field(DataType::UInt32),
+            Field::new("child_frame_id", DataType::Utf8, true),
+            pose_with_covariance_field("pose"),
+            twist_with_covariance_field("twist"),
+        ]))
+    }
+
+    fn to_record_batch(msgs: &[OdometryRef]) -> ArrowResult<RecordBatch> {
+        let stamps: Vec<Stamp> = msgs.iter().map(|m| m.header.stamp).collect();
+        let nanosecs: Vec<u32> = stamps.iter().map(|stamp| stamp.nanosec()).collect();
+        let poses: Vec<PoseWithCovariance> = msgs.iter().map(|m| m.pose).collect();
+        let twists: Vec<TwistWithCovariance> = msgs.iter().map(|m| m.twist).collect();
+        let columns: Vec<ArrayRef> = vec![
+            timestamp_array(&stamps),
+            header_array(
+                DataType::UInt32,
+                stamps.iter().map(|stamp| stamp.sec()).collect(),
+                Arc::new(UInt32Array::from(nanosecs)),
+                msgs.iter().map(|m| m.header.frame_id.as_str()).collect(),
+            ),
+            Arc::new(StringArray::from(
+                msgs.iter()
+                    .map(|m| m.child_frame_id.as_str())
+                    .collect::<Vec<&str>>(),
+            )),
+            pose_with_covariance_array("pose", &poses),
+            twist_with_covariance_array("twist", &twists),
+        ];
+        RecordBatch::try_new(Self::arrow_schema(), columns)
+    }
+}
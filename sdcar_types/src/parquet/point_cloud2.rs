@@ -1,40 +1,59 @@
+extern crate arrow;
 extern crate parquet;
 
-use parquet::record::{ListAccessor, RowAccessor};
+use arrow::array::{
+    Array, ArrayData, ArrayRef, BinaryArray, BooleanArray, Int32Array, ListArray, StringArray,
+    StructArray, UInt32Array, UInt8Array,
+};
+use arrow::buffer::Buffer;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
 
-use crate::msg::point_cloud2::{PointCloud2, PointField};
+use chrono::{DateTime, Utc};
+
+use crate::error::{Error, Result};
+use crate::msg::header::Stamp;
+use crate::msg::point_cloud2::{PointCloud2, PointCloud2Ref, PointField};
+use crate::parquet::schema::{Column, NamedColumns, NamedRow};
+use crate::parquet::{
+    header_array, header_field, timestamp_array, timestamp_field, FromRecordBatch, ToRecordBatch,
+};
+use std::sync::Arc;
 use std::vec::Vec;
 
-impl From<parquet::record::Row> for PointCloud2 {
-    fn from(row: parquet::record::Row) -> Self {
-        let header = row.get_group(1).unwrap();
-        let stamp = header.get_group(0).unwrap();
-        let sec = stamp.get_int(0).unwrap();
-        let nanosec = stamp.get_uint(1).unwrap();
-        let frame_id = header.get_string(1).unwrap().to_string();
-        let height = row.get_uint(2).unwrap();
-        let width = row.get_uint(3).unwrap();
-        let fields_row = row.get_list(4).unwrap();
-        let mut fields: Vec<PointField> = Vec::with_capacity(fields_row.len());
-        for i in 0..fields_row.len() {
-            let field_group = fields_row.get_group(i).unwrap();
-
-            let field = PointField::new(
-                field_group.get_string(0).unwrap().to_string(),
-                field_group.get_uint(1).unwrap(),
-                field_group.get_ubyte(2).unwrap(),
-                field_group.get_uint(3).unwrap(),
-            );
-            fields.push(field);
-        }
-        let is_bigendian = row.get_bool(5).unwrap();
-        let point_step = row.get_uint(6).unwrap();
-        let row_step = row.get_uint(7).unwrap();
-        let data = row.get_bytes(8).unwrap().data();
+impl TryFrom<parquet::record::Row> for PointCloud2 {
+    type Error = Error;
 
-        let is_dense = row.get_bool(9).unwrap();
+    fn try_from(row: parquet::record::Row) -> Result<Self> {
+        let row = NamedRow::new(&row);
+        let header = row.get_group("header")?;
+        let stamp = header.get_group("stamp")?;
+        let sec = stamp.get_int("sec")?;
+        let nanosec = stamp.get_uint("nanosec")?;
+        let frame_id = header.get_string("frame_id")?.to_string();
+        let height = row.get_uint("height")?;
+        let width = row.get_uint("width")?;
+        let fields = row
+            .get_group_list("fields")?
+            .iter()
+            .map(|field_group| {
+                Ok(PointField::new(
+                    field_group.get_string("name")?.to_string(),
+                    field_group.get_uint("offset")?,
+                    field_group.get_ubyte("datatype")?,
+                    field_group.get_uint("count")?,
+                ))
+            })
+            .collect::<Result<Vec<PointField>>>()?;
+        let is_bigendian = row.get_bool("is_bigendian")?;
+        let point_step = row.get_uint("point_step")?;
+        let row_step = row.get_uint("row_step")?;
+        let data = row.get_bytes("data")?;
 
-        PointCloud2::new(
+        let is_dense = row.get_bool("is_dense")?;
+
+        Ok(PointCloud2::new(
             sec,
             nanosec,
             frame_id,
@@ -46,6 +65,297 @@ impl From<parquet::record::Row> for PointCloud2 {
             row_step,
             data.to_vec(),
             is_dense,
-        )
+        ))
+    }
+}
+
+impl FromRecordBatch for PointCloud2 {
+    fn from_record_batch(batch: &RecordBatch) -> Result<Vec<Result<Self>>> {
+        let columns = NamedColumns::new(batch);
+        let header = columns.get_struct("header")?;
+        let stamp = header.get_struct("stamp")?;
+        let sec = stamp.get_int("sec")?;
+        let nanosec = stamp.get_uint("nanosec")?;
+        let frame_id = header.get_string("frame_id")?;
+        let height = columns.get_uint("height")?;
+        let width = columns.get_uint("width")?;
+        let fields = columns.get_list("fields")?;
+        let field_columns = point_field_columns(&fields.array().values())?;
+        let point_fields = PointFieldColumns::new(&field_columns)?;
+        let is_bigendian = columns.get_bool("is_bigendian")?;
+        let point_step = columns.get_uint("point_step")?;
+        let row_step = columns.get_uint("row_step")?;
+        let data = columns.get_bytes("data")?;
+        let is_dense = columns.get_bool("is_dense")?;
+
+        Ok((0..batch.num_rows())
+            .map(|i| {
+                Ok(PointCloud2::new(
+                    sec.value(i)?,
+                    nanosec.value(i)?,
+                    frame_id.value(i)?.to_string(),
+                    height.value(i)?,
+                    width.value(i)?,
+                    point_fields.get(fields.value_range(i)?)?,
+                    is_bigendian.value(i)?,
+                    point_step.value(i)?,
+                    row_step.value(i)?,
+                    data.value(i)?.to_vec(),
+                    is_dense.value(i)?,
+                ))
+            })
+            .collect())
+    }
+}
+
+/// Columns of the struct array holding the `fields` of every message.
+struct PointFieldColumns<'b> {
+    name: Column<'b, StringArray>,
+    offset: Column<'b, UInt32Array>,
+    datatype: Column<'b, UInt8Array>,
+    count: Column<'b, UInt32Array>,
+}
+
+fn point_field_columns(values: &ArrayRef) -> Result<NamedColumns> {
+    let values = values
+        .as_any()
+        .downcast_ref::<StructArray>()
+        .ok_or_else(|| {
+            Error::decode(
+                "fields",
+                "list<group>",
+                format!("found list<{:?}>", values.data_type()),
+            )
+        })?;
+    Ok(NamedColumns::from_struct(values, "fields".to_string()))
+}
+
+impl<'b> PointFieldColumns<'b> {
+    fn new(columns: &'b NamedColumns) -> Result<Self> {
+        Ok(Self {
+            name: columns.get_string("name")?,
+            offset: columns.get_uint("offset")?,
+            datatype: columns.get_ubyte("datatype")?,
+            count: columns.get_uint("count")?,
+        })
+    }
+
+    fn get(&self, range: std::ops::Range<usize>) -> Result<Vec<PointField>> {
+        range
+            .map(|j| {
+                Ok(PointField::new(
+                    self.name.value(j)?.to_string(),
+                    self.offset.value(j)?,
+                    self.datatype.value(j)?,
+                    self.count.value(j)?,
+                ))
+            })
+            .collect()
+    }
+}
+
+/// A batch of point clouds read with the Arrow reader, giving access to the
+/// `data` of each message without copying it out of the Arrow buffers.
+pub struct PointCloud2Batch {
+    batch: RecordBatch,
+    sec: Int32Array,
+    nanosec: UInt32Array,
+    frame_id: StringArray,
+    height: UInt32Array,
+    width: UInt32Array,
+    is_bigendian: BooleanArray,
+    point_step: UInt32Array,
+    row_step: UInt32Array,
+    data: BinaryArray,
+}
+
+impl PointCloud2Batch {
+    /// Checks `batch` holds the point cloud columns. Null values are not
+    /// checked here; accessors other than `timestamp` treat them as zero or
+    /// empty.
+    pub fn try_new(batch: RecordBatch) -> Result<Self> {
+        let columns = NamedColumns::new(&batch);
+        let header = columns.get_struct("header")?;
+        let stamp = header.get_struct("stamp")?;
+        let sec = Int32Array::from(stamp.get_int("sec")?.array().data().clone());
+        let nanosec = UInt32Array::from(stamp.get_uint("nanosec")?.array().data().clone());
+        let frame_id = StringArray::from(header.get_string("frame_id")?.array().data().clone());
+        let height = UInt32Array::from(columns.get_uint("height")?.array().data().clone());
+        let width = UInt32Array::from(columns.get_uint("width")?.array().data().clone());
+        let is_bigendian =
+            BooleanArray::from(columns.get_bool("is_bigendian")?.array().data().clone());
+        let point_step = UInt32Array::from(columns.get_uint("point_step")?.array().data().clone());
+        let row_step = UInt32Array::from(columns.get_uint("row_step")?.array().data().clone());
+        let data = BinaryArray::from(columns.get_bytes("data")?.array().data().clone());
+        Ok(Self {
+            batch,
+            sec,
+            nanosec,
+            frame_id,
+            height,
+            width,
+            is_bigendian,
+            point_step,
+            row_step,
+            data,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.batch.num_rows()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.batch.num_rows() == 0
+    }
+    pub fn batch(&self) -> &RecordBatch {
+        &self.batch
+    }
+    /// The header stamp of message `i`, `None` if it is null.
+    pub fn timestamp(&self, i: usize) -> Option<DateTime<Utc>> {
+        if self.sec.is_null(i) || self.nanosec.is_null(i) {
+            return None;
+        }
+        Some(Stamp::new(self.sec.value(i), self.nanosec.value(i)).to_datetime())
+    }
+    pub fn frame_id(&self, i: usize) -> &str {
+        self.frame_id.value(i)
+    }
+    pub fn height(&self, i: usize) -> u32 {
+        self.height.value(i)
+    }
+    pub fn width(&self, i: usize) -> u32 {
+        self.width.value(i)
+    }
+    pub fn is_bigendian(&self, i: usize) -> bool {
+        self.is_bigendian.value(i)
+    }
+    pub fn point_step(&self, i: usize) -> u32 {
+        self.point_step.value(i)
+    }
+    pub fn row_step(&self, i: usize) -> u32 {
+        self.row_step.value(i)
+    }
+    /// The point data of message `i`, borrowed from the batch.
+    pub fn data(&self, i: usize) -> &[u8] {
+        self.data.value(i)
+    }
+    /// Decodes message `i`, copying its data.
+    pub fn msg(&self, i: usize) -> Result<PointCloud2> {
+        PointCloud2::from_record_batch(&self.batch.slice(i, 1))?.remove(0)
+    }
+}
+
+impl ToRecordBatch for PointCloud2 {
+    fn arrow_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            timestamp_field(),
+            header_field(DataType::UInt32),
+            Field::new("height", DataType::UInt32, true),
+            Field::new("width", DataType::UInt32, true),
+            Field::new("fields", fields_data_type(), true),
+            Field::new("is_bigendian", DataType::Boolean, true),
+            Field::new("point_step", DataType::UInt32, true),
+            Field::new("row_step", DataType::UInt32, true),
+            Field::new("data", DataType::Binary, true),
+            Field::new("is_dense", DataType::Boolean, true),
+        ]))
+    }
+
+    fn to_record_batch(msgs: &[PointCloud2Ref]) -> ArrowResult<RecordBatch> {
+        let stamps: Vec<Stamp> = msgs.iter().map(|m| m.header.stamp).collect();
+        let nanosecs: Vec<u32> = stamps.iter().map(|stamp| stamp.nanosec()).collect();
+        let columns: Vec<ArrayRef> = vec![
+            timestamp_array(&stamps),
+            header_array(
+                DataType::UInt32,
+                stamps.iter().map(|stamp| stamp.sec()).collect(),
+                Arc::new(UInt32Array::from(nanosecs)),
+                msgs.iter().map(|m| m.header.frame_id.as_str()).collect(),
+            ),
+            Arc::new(UInt32Array::from(
+                msgs.iter().map(|m| m.height).collect::<Vec<u32>>(),
+            )),
+            Arc::new(UInt32Array::from(
+                msgs.iter().map(|m| m.width).collect::<Vec<u32>>(),
+            )),
+            fields_array(msgs)?,
+            Arc::new(BooleanArray::from(
+                msgs.iter().map(|m| m.is_bigendian).collect::<Vec<bool>>(),
+            )),
+            Arc::new(UInt32Array::from(
+                msgs.iter().map(|m| m.point_step).collect::<Vec<u32>>(),
+            )),
+            Arc::new(UInt32Array::from(
+                msgs.iter().map(|m| m.row_step).collect::<Vec<u32>>(),
+            )),
+            Arc::new(BinaryArray::from(
+                msgs.iter().map(|m| m.data.as_slice()).collect::<Vec<&[u8]>>(),
+            )),
+            Arc::new(BooleanArray::from(
+                msgs.iter().map(|m| m.is_dense).collect::<Vec<bool>>(),
+            )),
+        ];
+        RecordBatch::try_new(Self::arrow_schema(), columns)
+    }
+}
+
+/// `sensor_msgs/PointField` group.
+fn point_field_fields() -> Vec<Field> {
+    vec![
+        Field::new("name", DataType::Utf8, true),
+        Field::new("offset", DataType::UInt32, true),
+        Field::new("datatype", DataType::UInt8, true),
+        Field::new("count", DataType::UInt32, true),
+    ]
+}
+
+fn fields_data_type() -> DataType {
+    DataType::List(Box::new(Field::new(
+        "item",
+        DataType::Struct(point_field_fields()),
+        true,
+    )))
+}
+
+fn fields_array(msgs: &[PointCloud2Ref]) -> ArrowResult<ArrayRef> {
+    let point_fields: Vec<&PointField> = msgs.iter().flat_map(|m| m.fields.iter()).collect();
+    let struct_fields = point_field_fields();
+    let values = StructArray::from(vec![
+        (
+            struct_fields[0].clone(),
+            Arc::new(StringArray::from(
+                point_fields.iter().map(|f| f.name.as_str()).collect::<Vec<&str>>(),
+            )) as ArrayRef,
+        ),
+        (
+            struct_fields[1].clone(),
+            Arc::new(UInt32Array::from(
+                point_fields.iter().map(|f| f.offset).collect::<Vec<u32>>(),
+            )) as ArrayRef,
+        ),
+        (
+            struct_fields[2].clone(),
+            Arc::new(UInt8Array::from(
+                point_fields.iter().map(|f| f.datatype).collect::<Vec<u8>>(),
+            )) as ArrayRef,
+        ),
+        (
+            struct_fields[3].clone(),
+            Arc::new(UInt32Array::from(
+                point_fields.iter().map(|f| f.count).collect::<Vec<u32>>(),
+            )) as ArrayRef,
+        ),
+    ]);
+
+    let mut offsets: Vec<i32> = Vec::with_capacity(msgs.len() + 1);
+    offsets.push(0);
+    for m in msgs.iter() {
+        offsets.push(offsets[offsets.len() - 1] + m.fields.len() as i32);
     }
+    let list_data = ArrayData::builder(fields_data_type())
+        .len(msgs.len())
+        .add_buffer(Buffer::from_slice_ref(&offsets))
+        .add_child_data(values.data().clone())
+        .build()?;
+    Ok(Arc::new(ListArray::from(list_data)))
 }
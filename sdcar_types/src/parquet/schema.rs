@@ -0,0 +1,482 @@
+extern crate arrow;
+extern crate parquet;
+
+use arrow::array::{
+    Array, ArrayRef, BinaryArray, BooleanArray, Float32Array, Float64Array, Int32Array, Int64Array,
+    Int8Array, ListArray, PrimitiveArray, StringArray, StructArray, UInt16Array, UInt32Array,
+    UInt8Array,
+};
+use arrow::datatypes::{ArrowPrimitiveType, DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use chrono::{DateTime, TimeZone, Utc};
+use parquet::arrow::arrow_to_parquet_schema;
+use parquet::basic::{ConvertedType, Type as PhysicalType};
+use parquet::record::{Field, Row};
+use parquet::schema::types::{ColumnDescriptor, SchemaDescriptor};
+
+use crate::error::{Error, Result, SchemaMismatch};
+use crate::parquet::nanosec_from_long;
+
+/// Path segments parquet inserts for list columns; they vary between
+/// writers (`list.item`, `list.element`, `bag.array`) so are ignored when
+/// matching columns.
+const LIST_SEGMENTS: [&str; 5] = ["list", "item", "element", "bag", "array"];
+
+fn column_name(column: &ColumnDescriptor) -> String {
+    column
+        .path()
+        .parts()
+        .iter()
+        .filter(|part| !LIST_SEGMENTS.contains(&part.as_str()))
+        .cloned()
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+fn column_type(column: &ColumnDescriptor) -> (PhysicalType, ConvertedType) {
+    (column.physical_type(), column.converted_type())
+}
+
+fn type_name((physical_type, converted_type): (PhysicalType, ConvertedType)) -> String {
+    match converted_type {
+        ConvertedType::NONE => format!("{}", physical_type),
+        _ => format!("{} ({})", physical_type, converted_type),
+    }
+}
+
+/// Checks every column a decoder reads is present in `file_schema` with the
+/// physical and converted type of `expected`. Columns in the file that the
+/// decoder does not read, such as the recorder's leading timestamp, are
+/// ignored.
+pub fn validate_schema(expected: &Schema, file_schema: &SchemaDescriptor) -> Result<()> {
+    let expected = arrow_to_parquet_schema(expected)?;
+    let mut mismatches = Vec::new();
+    for expected_column in expected.columns().iter() {
+        let name = column_name(expected_column);
+        if name == "timestamp" {
+            continue;
+        }
+        let found = file_schema
+            .columns()
+            .iter()
+            .find(|column| column_name(column) == name);
+        match found {
+            None => mismatches.push(SchemaMismatch::Missing { column: name }),
+            Some(column) => {
+                let expected_type = column_type(expected_column);
+                let found_type = column_type(column);
+                if expected_type != found_type {
+                    mismatches.push(SchemaMismatch::Mistyped {
+                        column: name,
+                        expected: type_name(expected_type),
+                        found: type_name(found_type),
+                    });
+                }
+            }
+        }
+    }
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Schema(mismatches))
+    }
+}
+
+fn field_type_name(field: &Field) -> &'static str {
+    match field {
+        Field::Null => "null",
+        Field::Bool(_) => "bool",
+        Field::Byte(_) => "int8",
+        Field::Short(_) => "int16",
+        Field::Int(_) => "int32",
+        Field::Long(_) => "int64",
+        Field::UByte(_) => "uint8",
+        Field::UShort(_) => "uint16",
+        Field::UInt(_) => "uint32",
+        Field::ULong(_) => "uint64",
+        Field::Float(_) => "float",
+        Field::Double(_) => "double",
+        Field::Decimal(_) => "decimal",
+        Field::Str(_) => "string",
+        Field::Bytes(_) => "bytes",
+        Field::Date(_) => "date",
+        Field::TimestampMillis(_) => "timestamp_millis",
+        Field::TimestampMicros(_) => "timestamp_micros",
+        Field::Group(_) => "group",
+        Field::ListInternal(_) => "list",
+        Field::MapInternal(_) => "map",
+    }
+}
+
+/// Index of the `header.stamp.sec` leaf column, if the file has one.
+pub(crate) fn stamp_sec_column(file_schema: &SchemaDescriptor) -> Option<usize> {
+    file_schema
+        .columns()
+        .iter()
+        .position(|column| column_name(column) == "header.stamp.sec")
+}
+
+/// Reads `header.stamp` of a row without decoding the rest of the message.
+/// The nanosec column is uint32 or int64 depending on the message type.
+pub(crate) fn header_stamp(row: &Row) -> Result<DateTime<Utc>> {
+    let stamp = NamedRow::new(row).get_group("header")?.get_group("stamp")?;
+    let sec = stamp.get_int("sec")?;
+    let nanosec = match stamp.get_uint("nanosec") {
+        Ok(nanosec) => nanosec,
+        Err(_) => nanosec_from_long("header.stamp.nanosec", stamp.get_long("nanosec")?)?,
+    };
+    Utc.timestamp_opt(sec.into(), nanosec)
+        .single()
+        .ok_or_else(|| Error::decode("header.stamp", "timestamp", "out of range".to_string()))
+}
+
+/// Looks up the columns of a row by name, so decoding does not depend on
+/// the column order of the recorder's schema.
+pub(crate) struct NamedRow<'r> {
+    row: &'r Row,
+    prefix: String,
+}
+
+macro_rules! named_getter {
+    ($name:ident, $variant:ident, $ty:ty, $expected:expr) => {
+        pub fn $name(&self, name: &str) -> Result<$ty> {
+            match self.field(name, $expected)? {
+                Field::$variant(value) => Ok(*value),
+                other => Err(self.mistyped(name, $expected, other)),
+            }
+        }
+    };
+}
+
+impl<'r> NamedRow<'r> {
+    pub fn new(row: &'r Row) -> Self {
+        Self {
+            row,
+            prefix: String::new(),
+        }
+    }
+
+    pub fn path(&self, name: &str) -> String {
+        if self.prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", self.prefix, name)
+        }
+    }
+
+    fn field(&self, name: &str, expected: &'static str) -> Result<&'r Field> {
+        self.row
+            .get_column_iter()
+            .find(|(column, _)| column.as_str() == name)
+            .map(|(_, field)| field)
+            .ok_or_else(|| {
+                Error::decode(&self.path(name), expected, "column not found".to_string())
+            })
+    }
+
+    fn mistyped(&self, name: &str, expected: &'static str, found: &Field) -> Error {
+        Error::decode(
+            &self.path(name),
+            expected,
+            format!("found {}", field_type_name(found)),
+        )
+    }
+
+    named_getter!(get_bool, Bool, bool, "bool");
+    named_getter!(get_byte, Byte, i8, "int8");
+    named_getter!(get_int, Int, i32, "int32");
+    named_getter!(get_long, Long, i64, "int64");
+    named_getter!(get_ubyte, UByte, u8, "uint8");
+    named_getter!(get_ushort, UShort, u16, "uint16");
+    named_getter!(get_uint, UInt, u32, "uint32");
+    named_getter!(get_float, Float, f32, "float");
+    named_getter!(get_double, Double, f64, "double");
+
+    pub fn get_string(&self, name: &str) -> Result<&'r str> {
+        match self.field(name, "string")? {
+            Field::Str(value) => Ok(value.as_str()),
+            other => Err(self.mistyped(name, "string", other)),
+        }
+    }
+
+    pub fn get_bytes(&self, name: &str) -> Result<&'r [u8]> {
+        match self.field(name, "bytes")? {
+            Field::Bytes(value) => Ok(value.data()),
+            other => Err(self.mistyped(name, "bytes", other)),
+        }
+    }
+
+    pub fn get_group(&self, name: &str) -> Result<NamedRow<'r>> {
+        match self.field(name, "group")? {
+            Field::Group(row) => Ok(NamedRow {
+                row,
+                prefix: self.path(name),
+            }),
+            other => Err(self.mistyped(name, "group", other)),
+        }
+    }
+
+    pub fn get_double_list(&self, name: &str) -> Result<Vec<f64>> {
+        match self.field(name, "list<double>")? {
+            Field::ListInternal(list) => list
+                .elements()
+                .iter()
+                .map(|element| match element {
+                    Field::Double(value) => Ok(*value),
+                    other => Err(self.mistyped(name, "list<double>", other)),
+                })
+                .collect(),
+            other => Err(self.mistyped(name, "list<double>", other)),
+        }
+    }
+
+    pub fn get_group_list(&self, name: &str) -> Result<Vec<NamedRow<'r>>> {
+        match self.field(name, "list<group>")? {
+            Field::ListInternal(list) => list
+                .elements()
+                .iter()
+                .map(|element| match element {
+                    Field::Group(row) => Ok(NamedRow {
+                        row,
+                        prefix: self.path(name),
+                    }),
+                    other => Err(self.mistyped(name, "list<group>", other)),
+                })
+                .collect(),
+            other => Err(self.mistyped(name, "list<group>", other)),
+        }
+    }
+}
+
+/// Looks up the columns of a record batch by name, the columnar
+/// counterpart of `NamedRow`.
+pub(crate) struct NamedColumns {
+    columns: Vec<(String, ArrayRef)>,
+    prefix: String,
+}
+
+/// A column of a record batch downcast to its Arrow array type. Values are
+/// read by row index; nulls are decode errors.
+pub(crate) struct Column<'c, A> {
+    array: &'c A,
+    path: String,
+    expected: &'static str,
+}
+
+macro_rules! columns_getter {
+    ($name:ident, $array:ty, $expected:expr) => {
+        pub fn $name(&self, name: &str) -> Result<Column<'_, $array>> {
+            self.typed(name, $expected)
+        }
+    };
+}
+
+impl NamedColumns {
+    pub fn new(batch: &RecordBatch) -> Self {
+        Self {
+            columns: batch
+                .schema()
+                .fields()
+                .iter()
+                .map(|field| field.name().clone())
+                .zip(batch.columns().iter().cloned())
+                .collect(),
+            prefix: String::new(),
+        }
+    }
+
+    /// The fields of a struct array, `prefix` naming it in errors.
+    pub fn from_struct(array: &StructArray, prefix: String) -> Self {
+        Self {
+            columns: array
+                .column_names()
+                .into_iter()
+                .map(String::from)
+                .zip(array.columns().into_iter().cloned())
+                .collect(),
+            prefix,
+        }
+    }
+
+    pub fn path(&self, name: &str) -> String {
+        if self.prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", self.prefix, name)
+        }
+    }
+
+    fn typed<A: Array + 'static>(
+        &self,
+        name: &str,
+        expected: &'static str,
+    ) -> Result<Column<'_, A>> {
+        let path = self.path(name);
+        let column = self
+            .columns
+            .iter()
+            .find(|(column, _)| column == name)
+            .map(|(_, array)| array)
+            .ok_or_else(|| Error::decode(&path, expected, "column not found".to_string()))?;
+        match column.as_any().downcast_ref::<A>() {
+            Some(array) => Ok(Column {
+                array,
+                path,
+                expected,
+            }),
+            None => Err(Error::decode(
+                &path,
+                expected,
+                format!("found {}", data_type_name(column.data_type())),
+            )),
+        }
+    }
+
+    pub fn get_struct(&self, name: &str) -> Result<NamedColumns> {
+        let column: Column<'_, StructArray> = self.typed(name, "group")?;
+        Ok(NamedColumns::from_struct(column.array, column.path))
+    }
+
+    columns_getter!(get_bool, BooleanArray, "bool");
+    columns_getter!(get_byte, Int8Array, "int8");
+    columns_getter!(get_int, Int32Array, "int32");
+    columns_getter!(get_long, Int64Array, "int64");
+    columns_getter!(get_ubyte, UInt8Array, "uint8");
+    columns_getter!(get_ushort, UInt16Array, "uint16");
+    columns_getter!(get_uint, UInt32Array, "uint32");
+    columns_getter!(get_float, Float32Array, "float");
+    columns_getter!(get_double, Float64Array, "double");
+    columns_getter!(get_string, StringArray, "string");
+    columns_getter!(get_bytes, BinaryArray, "bytes");
+    columns_getter!(get_list, ListArray, "list");
+
+    pub fn get_double_list(&self, name: &str) -> Result<DoubleListColumn<'_>> {
+        let list = self.typed::<ListArray>(name, "list<double>")?;
+        let values = list.array.values();
+        let values = values
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .ok_or_else(|| {
+                Error::decode(
+                    &list.path,
+                    "list<double>",
+                    format!("found list<{}>", data_type_name(values.data_type())),
+                )
+            })?;
+        Ok(DoubleListColumn {
+            values: Float64Array::from(values.data().clone()),
+            list,
+        })
+    }
+}
+
+fn data_type_name(data_type: &DataType) -> String {
+    match data_type {
+        DataType::Struct(_) => "group".to_string(),
+        DataType::List(_) | DataType::LargeList(_) => "list".to_string(),
+        DataType::Utf8 | DataType::LargeUtf8 => "string".to_string(),
+        DataType::Binary | DataType::LargeBinary => "bytes".to_string(),
+        other => format!("{:?}", other).to_lowercase(),
+    }
+}
+
+impl<'c, A: Array> Column<'c, A> {
+    pub fn array(&self) -> &'c A {
+        self.array
+    }
+
+    fn check(&self, i: usize) -> Result<()> {
+        if self.array.is_null(i) {
+            Err(Error::decode(
+                &self.path,
+                self.expected,
+                "found null".to_string(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<'c, T: ArrowPrimitiveType> Column<'c, PrimitiveArray<T>> {
+    pub fn value(&self, i: usize) -> Result<T::Native> {
+        self.check(i)?;
+        Ok(self.array.value(i))
+    }
+}
+
+impl<'c> Column<'c, BooleanArray> {
+    pub fn value(&self, i: usize) -> Result<bool> {
+        self.check(i)?;
+        Ok(self.array.value(i))
+    }
+}
+
+impl<'c> Column<'c, StringArray> {
+    pub fn value(&self, i: usize) -> Result<&'c str> {
+        self.check(i)?;
+        Ok(self.array.value(i))
+    }
+}
+
+impl<'c> Column<'c, BinaryArray> {
+    pub fn value(&self, i: usize) -> Result<&'c [u8]> {
+        self.check(i)?;
+        Ok(self.array.value(i))
+    }
+}
+
+impl<'c> Column<'c, ListArray> {
+    /// Range of the list at row `i` within the list's child array.
+    pub fn value_range(&self, i: usize) -> Result<std::ops::Range<usize>> {
+        self.check(i)?;
+        let offsets = self.array.value_offsets();
+        Ok(offsets[i] as usize..offsets[i + 1] as usize)
+    }
+}
+
+/// A `list<double>` column, read a list at a time.
+pub(crate) struct DoubleListColumn<'c> {
+    list: Column<'c, ListArray>,
+    values: Float64Array,
+}
+
+impl<'c> DoubleListColumn<'c> {
+    pub fn path(&self) -> &str {
+        &self.list.path
+    }
+
+    pub fn value(&self, i: usize) -> Result<&[f64]> {
+        let range = self.list.value_range(i)?;
+        Ok(&self.values.values()[range])
+    }
+}
+
+/// Stamps of the `header.stamp` columns of a batch; null stamps are `None`.
+pub(crate) fn header_stamps(batch: &RecordBatch) -> Result<Vec<Option<DateTime<Utc>>>> {
+    let stamp = NamedColumns::new(batch)
+        .get_struct("header")?
+        .get_struct("stamp")?;
+    let sec = stamp.get_int("sec")?;
+    let nanosecs: Vec<Result<u32>> = match stamp.get_uint("nanosec") {
+        Ok(nanosec) => (0..batch.num_rows()).map(|i| nanosec.value(i)).collect(),
+        Err(_) => {
+            let nanosec = stamp.get_long("nanosec")?;
+            (0..batch.num_rows())
+                .map(|i| {
+                    nanosec
+                        .value(i)
+                        .and_then(|nanosec| nanosec_from_long("header.stamp.nanosec", nanosec))
+                })
+                .collect()
+        }
+    };
+    Ok(nanosecs
+        .into_iter()
+        .enumerate()
+        .map(|(i, nanosec)| {
+            let sec = sec.value(i).ok()?;
+            Utc.timestamp_opt(sec.into(), nanosec.ok()?).single()
+        })
+        .collect())
+}
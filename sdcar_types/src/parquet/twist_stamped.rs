@@ -0,0 +1,92 @@
+extern crate arrow;
+extern crate parquet;
+
+use arrow::array::{ArrayRef, UInt32Array};
+use arrow::datatypes::{DataType, Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+
+use crate::error::{Error, Result};
+use crate::msg::geometry::Twist;
+use crate::msg::header::{Header, Stamp};
+use crate::msg::twist_stamped::{TwistStamped, TwistStampedRef};
+use crate::parquet::geometry::{twist_array, twist_field, twist_from_row, Vector3Columns};
+use crate::parquet::schema::{NamedColumns, NamedRow};
+use crate::parquet::{
+    header_array, header_field, timestamp_array, timestamp_field, FromRecordBatch, ToRecordBatch,
+};
+
+use std::sync::Arc;
+
+impl TryFrom<parquet::record::Row> for TwistStamped {
+    type Error = Error;
+
+    fn try_from(row: parquet::record::Row) -> Result<Self> {
+        let row = NamedRow::new(&row);
+        let header = row.get_group("header")?;
+        let stamp = header.get_group("stamp")?;
+        let sec = stamp.get_int("sec")?;
+        let nanosec = stamp.get_uint("nanosec")?;
+        let frame_id = header.get_string("frame_id")?.to_string();
+
+        Ok(TwistStamped::new(
+            Header::new(Stamp::new(sec, nanosec), frame_id),
+            twist_from_row(&row, "twist")?,
+        ))
+    }
+}
+
+impl FromRecordBatch for TwistStamped {
+    fn from_record_batch(batch: &RecordBatch) -> Result<Vec<Result<Self>>> {
+        let columns = NamedColumns::new(batch);
+        let header = columns.get_struct("header")?;
+        let stamp = header.get_struct("stamp")?;
+        let sec = stamp.get_int("sec")?;
+        let nanosec = stamp.get_uint("nanosec")?;
+        let frame_id = header.get_string("frame_id")?;
+        let twist = columns.get_struct("twist")?;
+        let linear_group = twist.get_struct("linear")?;
+        let linear = Vector3Columns::new(&linear_group)?;
+        let angular_group = twist.get_struct("angular")?;
+        let angular = Vector3Columns::new(&angular_group)?;
+
+        Ok((0..batch.num_rows())
+            .map(|i| {
+                Ok(TwistStamped::new(
+                    Header::new(
+                        Stamp::new(sec.value(i)?, nanosec.value(i)?),
+                        frame_id.value(i)?.to_string(),
+                    ),
+                    Twist::new(linear.value(i)?, angular.value(i)?),
+                ))
+            })
+            .collect())
+    }
+}
+
+impl ToRecordBatch for TwistStamped {
+    fn arrow_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            timestamp_field(),
+            header_field(DataType::UInt32),
+            twist_field("twist"),
+        ]))
+    }
+
+    fn to_record_batch(msgs: &[TwistStampedRef]) -> ArrowResult<RecordBatch> {
+        let stamps: Vec<Stamp> = msgs.iter().map(|m| m.header.stamp).collect();
+        let nanosecs: Vec<u32> = stamps.iter().map(|stamp| stamp.nanosec()).collect();
+        let twists: Vec<Twist> = msgs.iter().map(|m| m.twist).collect();
+        let columns: Vec<ArrayRef> = vec![
+            timestamp_array(&stamps),
+            header_array(
+                DataType::UInt32,
+                stamps.iter().map(|stamp| stamp.sec()).collect(),
+                Arc::new(UInt32Array::from(nanosecs)),
+                msgs.iter().map(|m| m.header.frame_id.as_str()).collect(),
+            ),
+            twist_array("twist", &twists),
+        ];
+        RecordBatch::try_new(Self::arrow_schema(), columns)
+    }
+}
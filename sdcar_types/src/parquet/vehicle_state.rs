@@ -1,38 +1,54 @@
+extern crate arrow;
 extern crate parquet;
 
-use parquet::record::RowAccessor;
-
-use crate::msg::vehicle_state::VehicleState;
-
-impl From<parquet::record::Row> for VehicleState {
-    fn from(row: parquet::record::Row) -> Self {
-        let header = row.get_group(1).unwrap();
-        let stamp = header.get_group(0).unwrap();
-        let sec = stamp.get_int(0).unwrap();
-        let nanosec = stamp.get_long(1).unwrap();
-        let frame = header.get_string(1).unwrap().to_string();
-        let timeref = row.get_group(2).unwrap();
-        let timeref_sec = timeref.get_int(0).unwrap();
-        let timeref_nanosec = timeref.get_long(1).unwrap();
-        let vs_eng = row.get_float(3).unwrap();
-        let wheel_fl = row.get_float(4).unwrap();
-        let wheel_fr = row.get_float(5).unwrap();
-        let wheel_rl = row.get_float(6).unwrap();
-        let wheel_rr = row.get_float(7).unwrap();
-        let long_accel = row.get_float(8).unwrap();
-        let lat_accel = row.get_float(9).unwrap();
-        let yaw_rate = row.get_float(10).unwrap();
-        let sas_angle = row.get_float(11).unwrap();
-        let sas_speed = row.get_float(12).unwrap();
-        let dt_sec = row.get_float(13).unwrap();
-        let sas_radian = row.get_float(14).unwrap();
-        let vs = row.get_float(15).unwrap();
-        let v_mps = row.get_float(16).unwrap();
-
-        VehicleState::new(
+use arrow::array::{ArrayRef, Float32Array, Int64Array};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+
+use crate::error::{Error, Result};
+use crate::msg::header::Stamp;
+use crate::msg::vehicle_state::{VehicleState, VehicleStateRef};
+use crate::parquet::schema::{NamedColumns, NamedRow};
+use crate::parquet::{
+    header_array, header_field, nanosec_from_long, time_array, time_fields, timestamp_array,
+    timestamp_field, FromRecordBatch, ToRecordBatch,
+};
+
+use std::sync::Arc;
+
+impl TryFrom<parquet::record::Row> for VehicleState {
+    type Error = Error;
+
+    fn try_from(row: parquet::record::Row) -> Result<Self> {
+        let row = NamedRow::new(&row);
+        let header = row.get_group("header")?;
+        let stamp = header.get_group("stamp")?;
+        let sec = stamp.get_int("sec")?;
+        let nanosec = nanosec_from_long("header.stamp.nanosec", stamp.get_long("nanosec")?)?;
+        let frame_id = header.get_string("frame_id")?.to_string();
+        let timeref = row.get_group("timeref")?;
+        let timeref_sec = timeref.get_int("sec")?;
+        let timeref_nanosec = nanosec_from_long("timeref.nanosec", timeref.get_long("nanosec")?)?;
+        let vs_eng = row.get_float("vs_eng")?;
+        let wheel_fl = row.get_float("wheel_fl")?;
+        let wheel_fr = row.get_float("wheel_fr")?;
+        let wheel_rl = row.get_float("wheel_rl")?;
+        let wheel_rr = row.get_float("wheel_rr")?;
+        let long_accel = row.get_float("long_accel")?;
+        let lat_accel = row.get_float("lat_accel")?;
+        let yaw_rate = row.get_float("yaw_rate")?;
+        let sas_angle = row.get_float("sas_angle")?;
+        let sas_speed = row.get_float("sas_speed")?;
+        let dt_sec = row.get_float("dt_sec")?;
+        let sas_radians = row.get_float("sas_radians")?;
+        let vs = row.get_float("vs")?;
+        let v_mps = row.get_float("v_mps")?;
+
+        Ok(VehicleState::new(
             sec,
             nanosec,
-            frame,
+            frame_id,
             timeref_sec,
             timeref_nanosec,
             vs_eng,
@@ -46,9 +62,131 @@ impl From<parquet::record::Row> for VehicleState {
             sas_angle,
             sas_speed,
             dt_sec,
-            sas_radian,
+            sas_radians,
             vs,
             v_mps,
-        )
+        ))
+    }
+}
+
+impl FromRecordBatch for VehicleState {
+    fn from_record_batch(batch: &RecordBatch) -> Result<Vec<Result<Self>>> {
+        let columns = NamedColumns::new(batch);
+        let header = columns.get_struct("header")?;
+        let stamp = header.get_struct("stamp")?;
+        let sec = stamp.get_int("sec")?;
+        let nanosec = stamp.get_long("nanosec")?;
+        let frame_id = header.get_string("frame_id")?;
+        let timeref = columns.get_struct("timeref")?;
+        let timeref_sec = timeref.get_int("sec")?;
+        let timeref_nanosec = timeref.get_long("nanosec")?;
+        let vs_eng = columns.get_float("vs_eng")?;
+        let wheel_fl = columns.get_float("wheel_fl")?;
+        let wheel_fr = columns.get_float("wheel_fr")?;
+        let wheel_rl = columns.get_float("wheel_rl")?;
+        let wheel_rr = columns.get_float("wheel_rr")?;
+        let long_accel = columns.get_float("long_accel")?;
+        let lat_accel = columns.get_float("lat_accel")?;
+        let yaw_rate = columns.get_float("yaw_rate")?;
+        let sas_angle = columns.get_float("sas_angle")?;
+        let sas_speed = columns.get_float("sas_speed")?;
+        let dt_sec = columns.get_float("dt_sec")?;
+        let sas_radians = columns.get_float("sas_radians")?;
+        let vs = columns.get_float("vs")?;
+        let v_mps = columns.get_float("v_mps")?;
+
+        Ok((0..batch.num_rows())
+            .map(|i| {
+                Ok(VehicleState::new(
+                    sec.value(i)?,
+                    nanosec_from_long("header.stamp.nanosec", nanosec.value(i)?)?,
+                    frame_id.value(i)?.to_string(),
+                    timeref_sec.value(i)?,
+                    nanosec_from_long("timeref.nanosec", timeref_nanosec.value(i)?)?,
+                    vs_eng.value(i)?,
+                    wheel_fl.value(i)?,
+                    wheel_fr.value(i)?,
+                    wheel_rl.value(i)?,
+                    wheel_rr.value(i)?,
+                    long_accel.value(i)?,
+                    lat_accel.value(i)?,
+                    yaw_rate.value(i)?,
+                    sas_angle.value(i)?,
+                    sas_speed.value(i)?,
+                    dt_sec.value(i)?,
+                    sas_radians.value(i)?,
+                    vs.value(i)?,
+                    v_mps.value(i)?,
+                ))
+            })
+            .collect())
+    }
+}
+
+impl ToRecordBatch for VehicleState {
+    fn arrow_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            timestamp_field(),
+            header_field(DataType::Int64),
+            Field::new(
+                "timeref",
+                DataType::Struct(time_fields(DataType::Int64)),
+                true,
+            ),
+            Field::new("vs_eng", DataType::Float32, true),
+            Field::new("wheel_fl", DataType::Float32, true),
+            Field::new("wheel_fr", DataType::Float32, true),
+            Field::new("wheel_rl", DataType::Float32, true),
+            Field::new("wheel_rr", DataType::Float32, true),
+            Field::new("long_accel", DataType::Float32, true),
+            Field::new("lat_accel", DataType::Float32, true),
+            Field::new("yaw_rate", DataType::Float32, true),
+            Field::new("sas_angle", DataType::Float32, true),
+            Field::new("sas_speed", DataType::Float32, true),
+            Field::new("dt_sec", DataType::Float32, true),
+            Field::new("sas_radians", DataType::Float32, true),
+            Field::new("vs", DataType::Float32, true),
+            Field::new("v_mps", DataType::Float32, true),
+        ]))
+    }
+
+    fn to_record_batch(msgs: &[VehicleStateRef]) -> ArrowResult<RecordBatch> {
+        let stamps: Vec<Stamp> = msgs.iter().map(|m| m.header.stamp).collect();
+        let nanosecs: Vec<i64> = stamps.iter().map(|stamp| stamp.nanosec() as i64).collect();
+        let timeref_nanosecs: Vec<i64> = msgs.iter().map(|m| m.timeref.nanosec() as i64).collect();
+        let float_column = |f: fn(&VehicleState) -> f32| -> ArrayRef {
+            Arc::new(Float32Array::from(
+                msgs.iter().map(|m| f(m.as_ref())).collect::<Vec<f32>>(),
+            ))
+        };
+        let columns: Vec<ArrayRef> = vec![
+            timestamp_array(&stamps),
+            header_array(
+                DataType::Int64,
+                stamps.iter().map(|stamp| stamp.sec()).collect(),
+                Arc::new(Int64Array::from(nanosecs)),
+                msgs.iter().map(|m| m.header.frame_id.as_str()).collect(),
+            ),
+            Arc::new(time_array(
+                DataType::Int64,
+                msgs.iter().map(|m| m.timeref.sec()).collect(),
+                Arc::new(Int64Array::from(timeref_nanosecs)),
+            )),
+            float_column(|m| m.vs_eng),
+            float_column(|m| m.wheel_fl),
+            float_column(|m| m.wheel_fr),
+            float_column(|m| m.wheel_rl),
+            float_column(|m| m.wheel_rr),
+            float_column(|m| m.long_accel),
+            float_column(|m| m.lat_accel),
+            float_column(|m| m.yaw_rate),
+            float_column(|m| m.sas_angle),
+            float_column(|m| m.sas_speed),
+            float_column(|m| m.dt_sec),
+            float_column(|m| m.sas_radians),
+            float_column(|m| m.vs),
+            float_column(|m| m.v_mps),
+        ];
+        RecordBatch::try_new(Self::arrow_schema(), columns)
     }
 }
@@ -4,24 +4,69 @@ use crate::msg::Msg;
 use crate::reader::parquet::HasRowIter;
 use chrono::{DateTime, Utc};
 
+use std::cmp::Ordering;
 use std::sync::Arc;
 
+/// Which stream of a `Reader2` is emitted first when both hold a message
+/// with the same timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TiePriority {
+    #[default]
+    A,
+    B,
+}
+
+/// Merges two timestamp ordered streams. Every message of both streams is
+/// emitted exactly once, in timestamp order; equal timestamps are emitted
+/// in `TiePriority` order, A first by default.
+///
+/// `a_last` and `b_last` hold the next message of each stream that has not
+/// been emitted yet.
 pub struct Reader2<T, U>
 where
-    T: HasRowIter<'static> + 'static,
-    U: HasRowIter<'static> + 'static,
+    T: HasRowIter,
+    U: HasRowIter,
 {
     // a_iter: &'a mut T,
     a_iter: T,
     a_last: Option<Arc<dyn Msg>>,
     b_iter: U,
     b_last: Option<Arc<dyn Msg>>,
+    tie_priority: TiePriority,
+}
+
+impl<T, U> Reader2<T, U>
+where
+    T: HasRowIter,
+    U: HasRowIter,
+{
+    pub fn with_tie_priority(mut self, tie_priority: TiePriority) -> Self {
+        self.tie_priority = tie_priority;
+        self
+    }
+
+    pub fn tie_priority(&self) -> TiePriority {
+        self.tie_priority
+    }
+
+    // true when the pending A message is due before the pending B message
+    fn a_is_next(&self) -> bool {
+        match (&self.a_last, &self.b_last) {
+            (Some(a), Some(b)) => match a.timestamp().cmp(&b.timestamp()) {
+                Ordering::Less => true,
+                Ordering::Greater => false,
+                Ordering::Equal => self.tie_priority == TiePriority::A,
+            },
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
 }
 
 pub trait HasReader2Iter<T, U>
 where
-    T: HasRowIter<'static> + 'static,
-    U: HasRowIter<'static> + 'static,
+    T: HasRowIter,
+    U: HasRowIter,
 {
     fn new(a_iter: T, b_iter: U) -> Self;
     fn a_last(&self) -> Option<Arc<dyn Msg>>;
@@ -33,8 +78,8 @@ where
 
 impl<T, U> HasReader2Iter<T, U> for Reader2<T, U>
 where
-    T: HasRowIter<'static> + 'static,
-    U: HasRowIter<'static> + 'static,
+    T: HasRowIter,
+    U: HasRowIter,
 {
     fn new(a_iter: T, b_iter: U) -> Self {
         // initialise with first rows of each
@@ -47,6 +92,7 @@ where
             a_last,
             b_iter,
             b_last,
+            tie_priority: TiePriority::default(),
         }
     }
 
@@ -66,23 +112,19 @@ where
         self.b_last.clone()
     }
 
+    /// Advances stream A and returns its new pending message.
     fn a_next(&mut self) -> Option<Arc<dyn Msg>> {
-        match self.a_last.clone() {
-            Some(a_last) => {
-                self.a_last = self.a_iter.next_msg();
-                self.a_last.clone()
-            },
-            None => None,
+        if self.a_last.is_some() {
+            self.a_last = self.a_iter.next_msg();
         }
+        self.a_last.clone()
     }
+    /// Advances stream B and returns its new pending message.
     fn b_next(&mut self) -> Option<Arc<dyn Msg>> {
-        match self.b_last.clone() {
-            Some(b_last) => {
-                self.b_last = self.b_iter.next_msg();
-                self.b_last.clone()
-            },
-            None => None,
+        if self.b_last.is_some() {
+            self.b_last = self.b_iter.next_msg();
         }
+        self.b_last.clone()
     }
 
     fn timestamp(&self, msg: Option<Arc<dyn Msg>>) -> DateTime<Utc> {
@@ -95,31 +137,20 @@ where
 
 impl<T, U> Iterator for Reader2<T, U>
 where
-    T: HasRowIter<'static> + Iterator + 'static,
-    U: HasRowIter<'static> + Iterator + 'static,
+    T: HasRowIter + Iterator,
+    U: HasRowIter + Iterator,
 {
     type Item = Arc<dyn Msg>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.a_last.is_none() && self.b_last.is_none() {
-            return None;
-        }
-
-        if self.timestamp(self.a_last.clone()) < self.timestamp(self.b_last.clone()) {
+        if self.a_is_next() {
+            let msg = self.a_last.clone();
             self.a_next();
+            msg
         } else {
+            let msg = self.b_last.clone();
             self.b_next();
-        }
-
-        let a_timestamp = self.timestamp(self.a_last.clone());
-        let b_timestamp = self.timestamp(self.b_last.clone());
-
-
-        if a_timestamp < b_timestamp
-        {
-            self.a_last.clone()
-        } else {
-            self.b_last.clone()
+            msg
         }
     }
 }
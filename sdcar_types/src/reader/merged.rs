@@ -0,0 +1,134 @@
+use crate::msg::MsgKind;
+use crate::reader::parquet::HasRowIter;
+use chrono::{DateTime, Utc};
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+/// A message from a `MergedReader` tagged with the index of the source it
+/// was read from, as returned by `MergedReader::add_source`.
+#[derive(Debug, Clone)]
+pub struct MergedMsg {
+    pub source: usize,
+    pub msg_kind: MsgKind,
+}
+
+impl MergedMsg {
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.msg_kind.timestamp()
+    }
+}
+
+struct HeapEntry {
+    timestamp: DateTime<Utc>,
+    source: usize,
+    msg_kind: MsgKind,
+}
+
+impl PartialEq for HeapEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for HeapEntry {}
+
+impl PartialOrd for HeapEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeapEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.timestamp
+            .cmp(&other.timestamp)
+            .then(self.source.cmp(&other.source))
+    }
+}
+
+/// Merges any number of message streams, each already in timestamp order,
+/// into a single stream in timestamp order. Messages with equal timestamps
+/// are emitted in the order their sources were added.
+pub struct MergedReader {
+    sources: Vec<Box<dyn HasRowIter>>,
+    names: Vec<String>,
+    heap: BinaryHeap<Reverse<HeapEntry>>,
+}
+
+impl Default for MergedReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MergedReader {
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+            names: Vec::new(),
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    /// Adds a source and returns the index that tags its messages.
+    pub fn add_source<T>(&mut self, name: &str, source: T) -> usize
+    where
+        T: HasRowIter + 'static,
+    {
+        let index = self.sources.len();
+        self.sources.push(Box::new(source));
+        self.names.push(name.to_string());
+        self.fill(index);
+        index
+    }
+
+    pub fn with_source<T>(mut self, name: &str, source: T) -> Self
+    where
+        T: HasRowIter + 'static,
+    {
+        self.add_source(name, source);
+        self
+    }
+
+    pub fn num_sources(&self) -> usize {
+        self.sources.len()
+    }
+
+    pub fn source_name(&self, source: usize) -> &str {
+        &self.names[source]
+    }
+
+    pub fn source(&self, source: usize) -> &dyn HasRowIter {
+        self.sources[source].as_ref()
+    }
+
+    /// Timestamp of the message the next call to `next` will return.
+    pub fn peek_timestamp(&self) -> Option<DateTime<Utc>> {
+        self.heap.peek().map(|Reverse(entry)| entry.timestamp)
+    }
+
+    fn fill(&mut self, source: usize) {
+        if let Some(msg) = self.sources[source].next_msg() {
+            let msg_kind = msg.into_msg_kind();
+            self.heap.push(Reverse(HeapEntry {
+                timestamp: msg_kind.timestamp(),
+                source,
+                msg_kind,
+            }));
+        }
+    }
+}
+
+impl Iterator for MergedReader {
+    type Item = MergedMsg;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse(entry) = self.heap.pop()?;
+        self.fill(entry.source);
+        Some(MergedMsg {
+            source: entry.source,
+            msg_kind: entry.msg_kind,
+        })
+    }
+}
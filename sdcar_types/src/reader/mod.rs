@@ -1,2 +1,5 @@
 pub mod combined;
+pub mod merged;
 pub mod parquet;
+pub mod rosbag2;
+pub mod sync;
@@ -1,80 +1,672 @@
+extern crate arrow;
 extern crate parquet;
-use crate::msg::image::{Image, ImageRef};
-use crate::msg::point_cloud2::{PointCloud2, PointCloud2Ref};
+use crate::error::{Error, Result};
+use crate::msg::camera_info::CameraInfo;
+use crate::msg::image::Image;
+use crate::msg::imu::Imu;
+use crate::msg::nav_sat_fix::NavSatFix;
+use crate::msg::odometry::Odometry;
+use crate::msg::point_cloud2::PointCloud2;
+use crate::msg::twist_stamped::TwistStamped;
+use crate::msg::vehicle_state::VehicleState;
 use crate::msg::Msg;
-use parquet::file::reader::SerializedFileReader;
+use crate::parquet::schema::{header_stamp, header_stamps, stamp_sec_column, validate_schema};
+use crate::parquet::{FromRecordBatch, ToRecordBatch};
+use arrow::array::BooleanArray;
+use arrow::compute::filter_record_batch;
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use chrono::{DateTime, Utc};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReader;
+use parquet::arrow::{ArrowReader, ParquetFileArrowReader};
+use parquet::file::metadata::{ParquetMetaData, RowGroupMetaData};
+use parquet::file::reader::{ChunkReader, FileReader, Length, SerializedFileReader};
+use parquet::file::serialized_reader::{ReadOptions, ReadOptionsBuilder};
+use parquet::file::statistics::Statistics;
 use parquet::record::reader::RowIter;
 use parquet::record::Row;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
 use std::fs::File;
-use std::path::Path;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-pub struct ParquetFileReaderRowIter<'a> {
-    path: &'a str,
-    row_iter: RowIter<'a>,
+/// What a row iterator does with a row that fails to decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RowErrorPolicy {
+    /// Stop iterating and keep the error, see `HasRowIter::error`.
+    /// `try_next_msg` returns the error to the caller instead.
+    #[default]
+    Surface,
+    /// Skip the row and continue with the next one.
+    Skip,
 }
 
-pub trait HasRowIter<'a> {
-    fn new(path: &'a str) -> Self;
+type OpenFn = dyn Fn(ReadOptions) -> Result<Box<dyn FileReader>> + Send + Sync;
+
+/// Where the rows of a reader come from, kept so `seek` and `range` can open
+/// it again with different row groups.
+#[derive(Clone)]
+struct ParquetSource {
+    path: Option<PathBuf>,
+    open: Arc<OpenFn>,
+}
+
+/// Shares one chunk reader between the file readers of a `ParquetSource`.
+struct SharedChunkReader<R>(Arc<R>);
+
+impl<R: ChunkReader> Length for SharedChunkReader<R> {
+    fn len(&self) -> u64 {
+        self.0.len()
+    }
+}
+
+impl<R: ChunkReader> ChunkReader for SharedChunkReader<R> {
+    type T = R::T;
+
+    fn get_read(&self, start: u64, length: usize) -> parquet::errors::Result<Self::T> {
+        self.0.get_read(start, length)
+    }
+}
+
+impl ParquetSource {
+    fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let file_path = path.clone();
+        Self {
+            path: Some(path),
+            open: Arc::new(move |options| {
+                let file = File::open(&file_path)?;
+                Ok(Box::new(SerializedFileReader::new_with_options(
+                    file, options,
+                )?))
+            }),
+        }
+    }
+
+    fn from_reader<R: ChunkReader + 'static>(reader: R) -> Self {
+        let reader = Arc::new(reader);
+        Self {
+            path: None,
+            open: Arc::new(move |options| {
+                let reader = SharedChunkReader(reader.clone());
+                Ok(Box::new(SerializedFileReader::new_with_options(
+                    reader, options,
+                )?))
+            }),
+        }
+    }
+
+    fn open(&self) -> Result<Box<dyn FileReader>> {
+        (self.open)(ReadOptionsBuilder::new().build())
+    }
+
+    fn open_with_options(&self, options: ReadOptions) -> Result<Box<dyn FileReader>> {
+        (self.open)(options)
+    }
+}
+
+pub struct ParquetFileReaderRowIter {
+    source: ParquetSource,
+    row_iter: RowIter<'static>,
+    row_groups: RowGroups,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    row_index: usize,
+    error_policy: RowErrorPolicy,
+    skipped_rows: usize,
+    error: Option<Error>,
+}
+
+pub trait HasRowIter {
+    /// Opens `path`, panicking if it cannot be read or its schema does not
+    /// match the message type. See `try_new`.
+    fn new<P: AsRef<Path>>(path: P) -> Self
+    where
+        Self: Sized,
+    {
+        let path = path.as_ref();
+        Self::try_new(path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+    }
+    /// Opens `path` and validates its schema before any row is read.
+    fn try_new<P: AsRef<Path>>(path: P) -> Result<Self>
+    where
+        Self: Sized;
+    /// Reads an already opened `File`, or any other parquet `ChunkReader`,
+    /// validating its schema before any row is read.
+    fn try_from_reader<R: ChunkReader + 'static>(reader: R) -> Result<Self>
+    where
+        Self: Sized;
     fn next_msg(&mut self) -> Option<Arc<dyn Msg>>;
+    /// Like `next_msg` but returns decode errors to the caller.
+    fn try_next_msg(&mut self) -> Option<Result<Arc<dyn Msg>>>;
+    fn set_error_policy(&mut self, error_policy: RowErrorPolicy);
+    /// The error that ended iteration under `RowErrorPolicy::Surface`.
+    fn error(&self) -> Option<&Error>;
+    fn skipped_rows(&self) -> usize;
 }
 
-impl<'a> ParquetFileReaderRowIter<'a> {
-    fn new(path: &'a str) -> Self {
-        let file: File = File::open(&Path::new(path)).unwrap();
-        let reader = SerializedFileReader::new(file).unwrap();
-        let row_iter = reader.into_iter();
-        Self { path, row_iter }
+/// Whether a row group may hold stamps in `[start, end)`, judged from the
+/// min and max of its `header.stamp.sec` column. Row groups without
+/// statistics are always read.
+fn row_group_overlaps(
+    row_group: &RowGroupMetaData,
+    sec_column: usize,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> bool {
+    match row_group.column(sec_column).statistics() {
+        Some(Statistics::Int32(stats)) if stats.has_min_max_set() => {
+            let (min, max) = (i64::from(*stats.min()), i64::from(*stats.max()));
+            start.is_none_or(|start| max >= start.timestamp())
+                && end.is_none_or(|end| min <= end.timestamp())
+        }
+        _ => true,
     }
-    pub fn path(&self) -> &str {
-        self.path
+}
+
+/// The row groups of a file selected for reading, mapping the position of
+/// a row in the selection back to its index in the file.
+struct RowGroups {
+    // (first row in the file, number of rows) of every selected row group
+    offsets: Vec<(usize, usize)>,
+    num_row_groups: usize,
+    row_group: usize,
+    row_in_group: usize,
+}
+
+impl RowGroups {
+    fn new<F>(metadata: &ParquetMetaData, mut keep: F) -> Self
+    where
+        F: FnMut(&RowGroupMetaData) -> bool,
+    {
+        let mut first_row = 0;
+        let mut offsets = Vec::new();
+        for row_group in metadata.row_groups().iter() {
+            let num_rows = row_group.num_rows() as usize;
+            if keep(row_group) {
+                offsets.push((first_row, num_rows));
+            }
+            first_row += num_rows;
+        }
+        Self {
+            offsets,
+            num_row_groups: metadata.num_row_groups(),
+            row_group: 0,
+            row_in_group: 0,
+        }
+    }
+
+    fn skipped(&self) -> usize {
+        self.num_row_groups - self.offsets.len()
+    }
+
+    /// Index in the file of the next row read from the selection.
+    fn next_row_index(&mut self) -> usize {
+        while self.row_in_group >= self.offsets[self.row_group].1 {
+            self.row_group += 1;
+            self.row_in_group = 0;
+        }
+        self.row_in_group += 1;
+        self.offsets[self.row_group].0 + self.row_in_group - 1
     }
 }
 
-impl<'a> Iterator for ParquetFileReaderRowIter<'a> {
+/// Opens `source` reading only the row groups that may hold stamps in
+/// `[start, end)`.
+fn open_range(
+    source: &ParquetSource,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Result<(Box<dyn FileReader>, RowGroups)> {
+    let reader = source.open()?;
+    let sec_column = stamp_sec_column(reader.metadata().file_metadata().schema_descr());
+    let overlaps = move |row_group: &RowGroupMetaData| {
+        sec_column.is_none_or(|column| row_group_overlaps(row_group, column, start, end))
+    };
+    let row_groups = RowGroups::new(reader.metadata(), overlaps);
+    let options = ReadOptionsBuilder::new()
+        .with_predicate(Box::new(move |row_group, _| overlaps(row_group)))
+        .build();
+    Ok((source.open_with_options(options)?, row_groups))
+}
+
+fn in_range(
+    stamp: DateTime<Utc>,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> bool {
+    start.is_none_or(|start| stamp >= start) && end.is_none_or(|end| stamp < end)
+}
+
+impl ParquetFileReaderRowIter {
+    /// Opens `source` and checks its schema holds every column of `expected`.
+    fn new(source: ParquetSource, expected: &Schema) -> Result<Self> {
+        let reader = source.open()?;
+        validate_schema(expected, reader.metadata().file_metadata().schema_descr())?;
+        let row_groups = RowGroups::new(reader.metadata(), |_| true);
+        Ok(Self {
+            source,
+            row_iter: RowIter::from_file_into(reader),
+            row_groups,
+            start: None,
+            end: None,
+            row_index: 0,
+            error_policy: RowErrorPolicy::default(),
+            skipped_rows: 0,
+            error: None,
+        })
+    }
+
+    /// Restarts iteration at the first message stamped at or after
+    /// `timestamp`. See `range`.
+    pub fn seek(&mut self, timestamp: DateTime<Utc>) -> Result<()> {
+        self.set_range(Some(timestamp), None)
+    }
+
+    /// Restarts iteration over the messages stamped in `[start, end)`.
+    ///
+    /// Row groups whose `header.stamp.sec` statistics lie outside the range
+    /// are not read at all; rows of the remaining groups are filtered on
+    /// their stamp before they are decoded into messages.
+    pub fn range(&mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<()> {
+        self.set_range(Some(start), Some(end))
+    }
+
+    fn set_range(
+        &mut self,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        let (reader, row_groups) = open_range(&self.source, start, end)?;
+        self.row_iter = RowIter::from_file_into(reader);
+        self.row_groups = row_groups;
+        self.start = start;
+        self.end = end;
+        self.row_index = 0;
+        self.error = None;
+        Ok(())
+    }
+
+    /// Number of row groups `seek` or `range` did not need to read.
+    pub fn skipped_row_groups(&self) -> usize {
+        self.row_groups.skipped()
+    }
+
+    /// Index in the file of the next row, counting rows of skipped row
+    /// groups.
+    pub fn row_index(&self) -> usize {
+        self.row_index
+    }
+
+    fn row_in_range(&self, row: &Row) -> bool {
+        if self.start.is_none() && self.end.is_none() {
+            return true;
+        }
+        match header_stamp(row) {
+            Ok(stamp) => in_range(stamp, self.start, self.end),
+            // let the decoder report rows without a readable stamp
+            Err(_) => true,
+        }
+    }
+
+    /// The path the rows are read from, `None` when read from a
+    /// `ChunkReader`.
+    pub fn path(&self) -> Option<&Path> {
+        self.source.path.as_deref()
+    }
+    pub fn error_policy(&self) -> RowErrorPolicy {
+        self.error_policy
+    }
+    pub fn set_error_policy(&mut self, error_policy: RowErrorPolicy) {
+        self.error_policy = error_policy;
+    }
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+    pub fn skipped_rows(&self) -> usize {
+        self.skipped_rows
+    }
+
+    /// Decodes the next row, skipping bad rows under `RowErrorPolicy::Skip`.
+    pub fn try_next_decoded<M>(&mut self) -> Option<Result<M>>
+    where
+        M: TryFrom<Row, Error = Error>,
+    {
+        loop {
+            let row = self.next()?;
+            match M::try_from(row) {
+                Ok(msg) => return Some(Ok(msg)),
+                Err(e) => {
+                    let e = e.with_row(self.row_index - 1);
+                    match self.error_policy {
+                        RowErrorPolicy::Surface => return Some(Err(e)),
+                        RowErrorPolicy::Skip => self.skipped_rows += 1,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Decodes the next row; under `RowErrorPolicy::Surface` a bad row ends
+    /// iteration and its error is kept in `error`.
+    pub fn next_decoded<M>(&mut self) -> Option<M>
+    where
+        M: TryFrom<Row, Error = Error>,
+    {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.try_next_decoded() {
+            Some(Ok(msg)) => Some(msg),
+            Some(Err(e)) => {
+                self.error = Some(e);
+                None
+            }
+            None => None,
+        }
+    }
+}
+
+impl Iterator for ParquetFileReaderRowIter {
     type Item = Row;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.row_iter.next()
+        loop {
+            let row = self.row_iter.next()?;
+            self.row_index = self.row_groups.next_row_index() + 1;
+            if self.row_in_range(&row) {
+                return Some(row);
+            }
+        }
     }
 }
 
-pub struct PointCloud2ParquetRowIter<'a> {
-    parquet_row_iter: ParquetFileReaderRowIter<'a>,
+/// Reads a parquet file as Arrow record batches, for decoding a column at a
+/// time instead of a row at a time.
+pub struct ParquetFileBatchIter {
+    source: ParquetSource,
+    batch_size: usize,
+    batch_reader: ParquetRecordBatchReader,
+    row_groups: RowGroups,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    row_indices: Vec<usize>,
 }
 
-impl<'a> HasRowIter<'a> for PointCloud2ParquetRowIter<'a> {
-    fn new(path: &'a str) -> Self {
-        let parquet_row_iter = ParquetFileReaderRowIter::new(path);
-        Self { parquet_row_iter }
+impl ParquetFileBatchIter {
+    /// Opens `path` and checks its schema holds every column of `expected`.
+    pub fn new<P: AsRef<Path>>(path: P, expected: &Schema, batch_size: usize) -> Result<Self> {
+        Self::from_source(ParquetSource::from_path(path), expected, batch_size)
     }
-    fn next_msg(&mut self) -> Option<Arc<dyn Msg>> {
-        match self.next() {
-            Some(msg) => Some(msg),
-            None => None,
+
+    /// Reads an already opened `File`, or any other parquet `ChunkReader`.
+    pub fn from_reader<R: ChunkReader + 'static>(
+        reader: R,
+        expected: &Schema,
+        batch_size: usize,
+    ) -> Result<Self> {
+        Self::from_source(ParquetSource::from_reader(reader), expected, batch_size)
+    }
+
+    fn from_source(source: ParquetSource, expected: &Schema, batch_size: usize) -> Result<Self> {
+        let (reader, row_groups) = open_range(&source, None, None)?;
+        validate_schema(expected, reader.metadata().file_metadata().schema_descr())?;
+        Ok(Self {
+            source,
+            batch_size: batch_size.max(1),
+            batch_reader: Self::batch_reader(reader, batch_size.max(1))?,
+            row_groups,
+            start: None,
+            end: None,
+            row_indices: Vec::new(),
+        })
+    }
+
+    fn batch_reader(
+        reader: Box<dyn FileReader>,
+        batch_size: usize,
+    ) -> Result<ParquetRecordBatchReader> {
+        Ok(ParquetFileArrowReader::new(Arc::from(reader)).get_record_reader(batch_size)?)
+    }
+
+    /// The path the batches are read from, `None` when read from a
+    /// `ChunkReader`.
+    pub fn path(&self) -> Option<&Path> {
+        self.source.path.as_deref()
+    }
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Restarts iteration at the first row stamped at or after `timestamp`.
+    pub fn seek(&mut self, timestamp: DateTime<Utc>) -> Result<()> {
+        self.set_range(Some(timestamp), None)
+    }
+
+    /// Restarts iteration over the rows stamped in `[start, end)`, skipping
+    /// row groups outside the range as `ParquetFileReaderRowIter::range`
+    /// does.
+    pub fn range(&mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<()> {
+        self.set_range(Some(start), Some(end))
+    }
+
+    fn set_range(
+        &mut self,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        let (reader, row_groups) = open_range(&self.source, start, end)?;
+        self.batch_reader = Self::batch_reader(reader, self.batch_size)?;
+        self.row_groups = row_groups;
+        self.start = start;
+        self.end = end;
+        self.row_indices.clear();
+        Ok(())
+    }
+
+    pub fn skipped_row_groups(&self) -> usize {
+        self.row_groups.skipped()
+    }
+
+    /// Index in the file of every row of the last batch returned.
+    pub fn row_indices(&self) -> &[usize] {
+        &self.row_indices
+    }
+
+    fn next_batch(&mut self) -> Option<Result<RecordBatch>> {
+        loop {
+            let batch = match self.batch_reader.next()? {
+                Ok(batch) => batch,
+                Err(e) => return Some(Err(e.into())),
+            };
+            let row_indices = (0..batch.num_rows()).map(|_| self.row_groups.next_row_index());
+            if self.start.is_none() && self.end.is_none() {
+                self.row_indices = row_indices.collect();
+                return Some(Ok(batch));
+            }
+
+            let stamps = match header_stamps(&batch) {
+                Ok(stamps) => stamps,
+                Err(e) => return Some(Err(e)),
+            };
+            // let the decoder report rows without a readable stamp
+            let keep: Vec<bool> = stamps
+                .iter()
+                .map(|stamp| stamp.is_none_or(|stamp| in_range(stamp, self.start, self.end)))
+                .collect();
+            self.row_indices = row_indices
+                .zip(keep.iter())
+                .filter(|(_, keep)| **keep)
+                .map(|(row_index, _)| row_index)
+                .collect();
+            if self.row_indices.is_empty() {
+                continue;
+            }
+            return Some(
+                filter_record_batch(&batch, &BooleanArray::from(keep)).map_err(Error::from),
+            );
         }
     }
 }
 
-impl<'a> Iterator for PointCloud2ParquetRowIter<'a> {
-    type Item = PointCloud2Ref;
+impl Iterator for ParquetFileBatchIter {
+    type Item = Result<RecordBatch>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.parquet_row_iter.next() {
-            Some(row) => Some(Arc::new(PointCloud2::from(row))),
-            None => None,
-        }
+        self.next_batch()
     }
 }
 
-pub struct ImageParquetRowIter<'a> {
-    parquet_row_iter: ParquetFileReaderRowIter<'a>,
+/// How a message iterator decodes its file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReadMode {
+    /// A row at a time through `parquet::record::Row`.
+    #[default]
+    Rows,
+    /// Through Arrow record batches of the given number of rows, a column
+    /// at a time. Much faster on large binary columns such as point cloud
+    /// data.
+    Batches(usize),
+}
+
+/// Message types that can be decoded from the rows of a parquet file.
+pub trait ParquetMsg:
+    Msg + TryFrom<Row, Error = Error> + FromRecordBatch + ToRecordBatch + 'static
+{
+}
+
+impl<M> ParquetMsg for M where
+    M: Msg + TryFrom<Row, Error = Error> + FromRecordBatch + ToRecordBatch + 'static
+{
+}
+
+/// Iterates the messages of one type stored in a parquet file.
+pub struct MsgParquetRowIter<M: ParquetMsg> {
+    parquet_row_iter: ParquetFileReaderRowIter,
+    batch_iter: Option<ParquetFileBatchIter>,
+    // decoded rows of the current batch with their index in the file
+    decoded: VecDeque<(usize, Result<M>)>,
+    msg_type: PhantomData<M>,
+}
+
+impl<M: ParquetMsg> MsgParquetRowIter<M> {
+    fn from_source(source: ParquetSource) -> Result<Self> {
+        let parquet_row_iter = ParquetFileReaderRowIter::new(source, &M::arrow_schema())?;
+        Ok(Self {
+            parquet_row_iter,
+            batch_iter: None,
+            decoded: VecDeque::new(),
+            msg_type: PhantomData,
+        })
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.parquet_row_iter.path()
+    }
+
+    pub fn read_mode(&self) -> ReadMode {
+        match &self.batch_iter {
+            Some(batch_iter) => ReadMode::Batches(batch_iter.batch_size()),
+            None => ReadMode::Rows,
+        }
+    }
+
+    /// Switches how rows are decoded. Iteration restarts from the start of
+    /// the range set by `seek` or `range`, or of the file.
+    pub fn set_read_mode(&mut self, read_mode: ReadMode) -> Result<()> {
+        self.decoded.clear();
+        self.batch_iter = None;
+        let (start, end) = (self.parquet_row_iter.start, self.parquet_row_iter.end);
+        match read_mode {
+            ReadMode::Rows => self.parquet_row_iter.set_range(start, end),
+            ReadMode::Batches(batch_size) => {
+                let mut batch_iter = ParquetFileBatchIter::from_source(
+                    self.parquet_row_iter.source.clone(),
+                    &M::arrow_schema(),
+                    batch_size,
+                )?;
+                if start.is_some() || end.is_some() {
+                    batch_iter.set_range(start, end)?;
+                }
+                self.parquet_row_iter.error = None;
+                self.batch_iter = Some(batch_iter);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn with_read_mode(mut self, read_mode: ReadMode) -> Result<Self> {
+        self.set_read_mode(read_mode)?;
+        Ok(self)
+    }
+
+    /// Restarts iteration at the first message stamped at or after
+    /// `timestamp`, skipping row groups that end before it.
+    pub fn seek(&mut self, timestamp: DateTime<Utc>) -> Result<()> {
+        self.parquet_row_iter.seek(timestamp)?;
+        self.decoded.clear();
+        match &mut self.batch_iter {
+            Some(batch_iter) => batch_iter.seek(timestamp),
+            None => Ok(()),
+        }
+    }
+    /// Restarts iteration over the messages stamped in `[start, end)`,
+    /// skipping row groups outside the range.
+    pub fn range(&mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<()> {
+        self.parquet_row_iter.range(start, end)?;
+        self.decoded.clear();
+        match &mut self.batch_iter {
+            Some(batch_iter) => batch_iter.range(start, end),
+            None => Ok(()),
+        }
+    }
+    pub fn skipped_row_groups(&self) -> usize {
+        self.parquet_row_iter.skipped_row_groups()
+    }
+    pub fn try_next(&mut self) -> Option<Result<Arc<M>>> {
+        let msg = match self.batch_iter {
+            Some(_) => self.try_next_batched(),
+            None => self.parquet_row_iter.try_next_decoded::<M>(),
+        };
+        msg.map(|msg| msg.map(Arc::new))
+    }
+
+    // the batch counterpart of `ParquetFileReaderRowIter::try_next_decoded`
+    fn try_next_batched(&mut self) -> Option<Result<M>> {
+        loop {
+            if let Some((row_index, decoded)) = self.decoded.pop_front() {
+                match decoded {
+                    Ok(msg) => return Some(Ok(msg)),
+                    Err(e) => match self.parquet_row_iter.error_policy {
+                        RowErrorPolicy::Surface => return Some(Err(e.with_row(row_index))),
+                        RowErrorPolicy::Skip => self.parquet_row_iter.skipped_rows += 1,
+                    },
+                }
+                continue;
+            }
+            let batch_iter = self.batch_iter.as_mut()?;
+            let batch = match batch_iter.next()? {
+                Ok(batch) => batch,
+                Err(e) => return Some(Err(e)),
+            };
+            match M::from_record_batch(&batch) {
+                Ok(msgs) => self
+                    .decoded
+                    .extend(batch_iter.row_indices().iter().copied().zip(msgs)),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
 }
 
-impl<'a> HasRowIter<'a> for ImageParquetRowIter<'a> {
-    fn new(path: &'a str) -> Self {
-        let parquet_row_iter = ParquetFileReaderRowIter::new(path);
-        Self { parquet_row_iter }
+impl<M: ParquetMsg> HasRowIter for MsgParquetRowIter<M> {
+    fn try_new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_source(ParquetSource::from_path(path))
+    }
+    fn try_from_reader<R: ChunkReader + 'static>(reader: R) -> Result<Self> {
+        Self::from_source(ParquetSource::from_reader(reader))
     }
     fn next_msg(&mut self) -> Option<Arc<dyn Msg>> {
         match self.next() {
@@ -82,15 +674,47 @@ impl<'a> HasRowIter<'a> for ImageParquetRowIter<'a> {
             None => None,
         }
     }
+    fn try_next_msg(&mut self) -> Option<Result<Arc<dyn Msg>>> {
+        self.try_next()
+            .map(|msg| msg.map(|msg| msg as Arc<dyn Msg>))
+    }
+    fn set_error_policy(&mut self, error_policy: RowErrorPolicy) {
+        self.parquet_row_iter.set_error_policy(error_policy);
+    }
+    fn error(&self) -> Option<&Error> {
+        self.parquet_row_iter.error()
+    }
+    fn skipped_rows(&self) -> usize {
+        self.parquet_row_iter.skipped_rows()
+    }
 }
 
-impl<'a> Iterator for ImageParquetRowIter<'a> {
-    type Item = ImageRef;
+impl<M: ParquetMsg> Iterator for MsgParquetRowIter<M> {
+    type Item = Arc<M>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.parquet_row_iter.next() {
-            Some(row) => Some(Arc::new(Image::from(row))),
+        if self.batch_iter.is_none() {
+            return self.parquet_row_iter.next_decoded::<M>().map(Arc::new);
+        }
+        if self.parquet_row_iter.error.is_some() {
+            return None;
+        }
+        match self.try_next_batched() {
+            Some(Ok(msg)) => Some(Arc::new(msg)),
+            Some(Err(e)) => {
+                self.parquet_row_iter.error = Some(e);
+                None
+            }
             None => None,
         }
     }
 }
+
+pub type PointCloud2ParquetRowIter = MsgParquetRowIter<PointCloud2>;
+pub type ImageParquetRowIter = MsgParquetRowIter<Image>;
+pub type NavSatFixParquetRowIter = MsgParquetRowIter<NavSatFix>;
+pub type VehicleStateParquetRowIter = MsgParquetRowIter<VehicleState>;
+pub type ImuParquetRowIter = MsgParquetRowIter<Imu>;
+pub type CameraInfoParquetRowIter = MsgParquetRowIter<CameraInfo>;
+pub type TwistStampedParquetRowIter = MsgParquetRowIter<TwistStamped>;
+pub type OdometryParquetRowIter = MsgParquetRowIter<Odometry>;
@@ -0,0 +1,881 @@
+//! Reads rosbag2 recordings directly, without exporting them to parquet
+//! first. Both storage plugins are supported: sqlite3 databases (`.db3`)
+//! and MCAP files (`.mcap`). CDR payloads of the message types in
+//! `crate::cdr` are decoded into the `crate::msg` structs.
+
+use crate::cdr::FromCdr;
+use crate::error::{Error, Result};
+use crate::msg::camera_info::CameraInfo;
+use crate::msg::image::Image;
+use crate::msg::imu::Imu;
+use crate::msg::nav_sat_fix::NavSatFix;
+use crate::msg::odometry::Odometry;
+use crate::msg::point_cloud2::PointCloud2;
+use crate::msg::twist_stamped::TwistStamped;
+use crate::msg::Msg;
+use crate::reader::merged::MergedReader;
+use crate::reader::parquet::{HasRowIter, RowErrorPolicy};
+use parquet::file::reader::ChunkReader;
+use rusqlite::{params, Connection, OpenFlags};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File};
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const MCAP_MAGIC: &[u8; 8] = b"\x89MCAP0\r\n";
+const SQLITE_MAGIC: &[u8; 16] = b"SQLite format 3\0";
+
+/// Largest decompressed MCAP chunk accepted. rosbag2 writes chunks of a
+/// few MB, so a size above this is taken to be a corrupt file.
+const MAX_CHUNK_SIZE: u64 = 1 << 30;
+
+/// Messages fetched from a sqlite3 database per query.
+const SQLITE_PAGE_SIZE: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageFormat {
+    Sqlite3,
+    Mcap,
+}
+
+/// A topic recorded in a bag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BagTopic {
+    pub name: String,
+    /// ROS 2 type name, such as `sensor_msgs/msg/Image`.
+    pub msg_type: String,
+    /// `cdr` for every topic recorded by ROS 2.
+    pub serialization_format: String,
+}
+
+/// A message as recorded, before its payload is decoded.
+#[derive(Debug, Clone)]
+pub struct BagMessage {
+    /// Index of the message's topic in `Rosbag2::topics`.
+    pub topic: usize,
+    /// Time the recorder received the message, in nanoseconds since the
+    /// Unix epoch.
+    pub log_time: i64,
+    pub data: Vec<u8>,
+}
+
+/// An in memory MCAP recording shared by the readers of a `Rosbag2`.
+#[derive(Clone)]
+struct SharedBytes(Arc<Vec<u8>>);
+
+impl AsRef<[u8]> for SharedBytes {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
+#[derive(Clone)]
+enum StorageFile {
+    Path(PathBuf),
+    Bytes(SharedBytes),
+}
+
+/// A rosbag2 recording: either a bag directory holding the split files
+/// written by the recorder, or a single `.db3` or `.mcap` file.
+#[derive(Clone)]
+pub struct Rosbag2 {
+    path: Option<PathBuf>,
+    format: StorageFormat,
+    files: Vec<StorageFile>,
+    topics: Vec<BagTopic>,
+}
+
+impl Rosbag2 {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let (format, files) = if path.is_dir() {
+            storage_files(path)?
+        } else {
+            (storage_format(path)?, vec![path.to_path_buf()])
+        };
+        let files: Vec<StorageFile> = files.into_iter().map(StorageFile::Path).collect();
+        let topics = read_topics(format, &files)?;
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            format,
+            files,
+            topics,
+        })
+    }
+
+    /// A single MCAP file already read into memory.
+    pub fn from_mcap_bytes(bytes: Vec<u8>) -> Result<Self> {
+        let files = vec![StorageFile::Bytes(SharedBytes(Arc::new(bytes)))];
+        let topics = read_topics(StorageFormat::Mcap, &files)?;
+        Ok(Self {
+            path: None,
+            format: StorageFormat::Mcap,
+            files,
+            topics,
+        })
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn format(&self) -> StorageFormat {
+        self.format
+    }
+
+    /// Topics of every storage file, in the order first recorded.
+    pub fn topics(&self) -> &[BagTopic] {
+        &self.topics
+    }
+
+    pub fn topic_index(&self, name: &str) -> Option<usize> {
+        self.topics.iter().position(|topic| topic.name == name)
+    }
+
+    /// The messages of every topic, in recorded order.
+    pub fn messages(&self) -> BagMessageIter {
+        self.topic_messages(&(0..self.topics.len()).collect::<Vec<_>>())
+    }
+
+    /// The messages of the topics with the given indices, in recorded order.
+    pub fn topic_messages(&self, topics: &[usize]) -> BagMessageIter {
+        let mut wanted = vec![false; self.topics.len()];
+        for topic in topics {
+            wanted[*topic] = true;
+        }
+        BagMessageIter {
+            format: self.format,
+            files: self.files.iter().cloned().collect(),
+            topics: self.topics.clone(),
+            wanted,
+            current: None,
+        }
+    }
+
+    /// Decodes the messages of `topic`, which must be recorded as `M`.
+    pub fn msg_reader<M: BagMsg>(&self, topic: &str) -> Result<MsgBagRowIter<M>> {
+        let index = self
+            .topic_index(topic)
+            .ok_or_else(|| Error::Bag(format!("no topic {}", topic)))?;
+        let msg_type = &self.topics[index].msg_type;
+        if msg_type != M::ROS_TYPE {
+            return Err(Error::Bag(format!(
+                "topic {} is recorded as {}, not {}",
+                topic,
+                msg_type,
+                M::ROS_TYPE
+            )));
+        }
+        Ok(MsgBagRowIter::from_messages(self.topic_messages(&[index])))
+    }
+
+    /// Decodes the messages of the only topic recorded as `M`.
+    fn unique_msg_reader<M: BagMsg>(&self) -> Result<MsgBagRowIter<M>> {
+        let mut topics = self
+            .topics
+            .iter()
+            .filter(|topic| topic.msg_type == M::ROS_TYPE);
+        match (topics.next(), topics.next()) {
+            (Some(topic), None) => self.msg_reader(&topic.name),
+            (None, _) => Err(Error::Bag(format!("no topic of type {}", M::ROS_TYPE))),
+            (Some(_), Some(_)) => Err(Error::Bag(format!(
+                "more than one topic of type {}, open it with Rosbag2::msg_reader",
+                M::ROS_TYPE
+            ))),
+        }
+    }
+
+    /// Merges every topic of a supported type, each source named after its
+    /// topic and added in `topics` order. Each source reads the storage
+    /// files separately.
+    pub fn merged_reader(&self) -> Result<MergedReader> {
+        let mut reader = MergedReader::new();
+        for topic in self.topics.iter() {
+            let name = topic.name.as_str();
+            match topic.msg_type.as_str() {
+                PointCloud2::ROS_TYPE => {
+                    reader.add_source(name, self.msg_reader::<PointCloud2>(name)?)
+                }
+                Image::ROS_TYPE => reader.add_source(name, self.msg_reader::<Image>(name)?),
+                NavSatFix::ROS_TYPE => reader.add_source(name, self.msg_reader::<NavSatFix>(name)?),
+                Imu::ROS_TYPE => reader.add_source(name, self.msg_reader::<Imu>(name)?),
+                CameraInfo::ROS_TYPE => {
+                    reader.add_source(name, self.msg_reader::<CameraInfo>(name)?)
+                }
+                TwistStamped::ROS_TYPE => {
+                    reader.add_source(name, self.msg_reader::<TwistStamped>(name)?)
+                }
+                Odometry::ROS_TYPE => reader.add_source(name, self.msg_reader::<Odometry>(name)?),
+                _ => continue,
+            };
+        }
+        Ok(reader)
+    }
+}
+
+fn storage_format(path: &Path) -> Result<StorageFormat> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("db3") => return Ok(StorageFormat::Sqlite3),
+        Some("mcap") => return Ok(StorageFormat::Mcap),
+        _ => {}
+    }
+    let mut magic = [0; 16];
+    let len = File::open(path)?.read(&mut magic)?;
+    if magic[..len].starts_with(MCAP_MAGIC) {
+        Ok(StorageFormat::Mcap)
+    } else if magic[..len] == SQLITE_MAGIC[..] {
+        Ok(StorageFormat::Sqlite3)
+    } else {
+        Err(Error::Bag(format!(
+            "{} is neither a sqlite3 nor an MCAP file",
+            path.display()
+        )))
+    }
+}
+
+/// Index of a split file such as `run_12.mcap`, so `run_10` sorts after
+/// `run_9`.
+fn split_index(path: &Path) -> Option<u64> {
+    let stem = path.file_stem()?.to_str()?;
+    stem.rsplit('_').next()?.parse().ok()
+}
+
+/// The storage files of a bag directory, in split order.
+fn storage_files(dir: &Path) -> Result<(StorageFormat, Vec<PathBuf>)> {
+    let mut files: Vec<(StorageFormat, PathBuf)> = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let format = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("db3") => StorageFormat::Sqlite3,
+            Some("mcap") => StorageFormat::Mcap,
+            _ => continue,
+        };
+        files.push((format, path));
+    }
+    let format = match files.first() {
+        Some((format, _)) => *format,
+        None => {
+            return Err(Error::Bag(format!(
+                "no .db3 or .mcap files in {}",
+                dir.display()
+            )))
+        }
+    };
+    if files.iter().any(|(other, _)| *other != format) {
+        return Err(Error::Bag(format!(
+            "{} mixes .db3 and .mcap files",
+            dir.display()
+        )));
+    }
+    let mut files: Vec<PathBuf> = files.into_iter().map(|(_, path)| path).collect();
+    files.sort_by(|a, b| (split_index(a), a).cmp(&(split_index(b), b)));
+    Ok((format, files))
+}
+
+fn read_topics(format: StorageFormat, files: &[StorageFile]) -> Result<Vec<BagTopic>> {
+    let mut topics: Vec<BagTopic> = Vec::new();
+    for file in files {
+        let file_topics = match format {
+            StorageFormat::Sqlite3 => sqlite3_topics(&open_sqlite3(file)?)?,
+            StorageFormat::Mcap => McapMessages::open(file)?.topics()?,
+        };
+        for topic in file_topics {
+            if !topics.iter().any(|known| known.name == topic.name) {
+                topics.push(topic);
+            }
+        }
+    }
+    Ok(topics)
+}
+
+/// Iterates the messages of a `Rosbag2`, one storage file after another.
+/// Iteration ends after the first error.
+pub struct BagMessageIter {
+    format: StorageFormat,
+    files: VecDeque<StorageFile>,
+    topics: Vec<BagTopic>,
+    wanted: Vec<bool>,
+    current: Option<Box<dyn StorageReader>>,
+}
+
+impl BagMessageIter {
+    pub fn topics(&self) -> &[BagTopic] {
+        &self.topics
+    }
+
+    fn open_next(&mut self) -> Result<bool> {
+        let file = match self.files.pop_front() {
+            Some(file) => file,
+            None => return Ok(false),
+        };
+        self.current = Some(match self.format {
+            StorageFormat::Sqlite3 => {
+                Box::new(Sqlite3Messages::open(&file, &self.topics, &self.wanted)?)
+            }
+            StorageFormat::Mcap => {
+                let mut messages = McapMessages::open(&file)?;
+                messages.select(&self.topics, &self.wanted);
+                Box::new(messages)
+            }
+        });
+        Ok(true)
+    }
+
+    fn try_next(&mut self) -> Result<Option<BagMessage>> {
+        loop {
+            if let Some(current) = self.current.as_mut() {
+                if let Some(message) = current.next_message()? {
+                    return Ok(Some(message));
+                }
+                self.current = None;
+            }
+            if !self.open_next()? {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+impl Iterator for BagMessageIter {
+    type Item = Result<BagMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.try_next() {
+            Ok(message) => message.map(Ok),
+            Err(e) => {
+                self.current = None;
+                self.files.clear();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+trait StorageReader {
+    fn next_message(&mut self) -> Result<Option<BagMessage>>;
+}
+
+fn open_sqlite3(file: &StorageFile) -> Result<Connection> {
+    match file {
+        StorageFile::Path(path) => Ok(Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?),
+        StorageFile::Bytes(_) => Err(Error::Bag(
+            "sqlite3 bags can only be read from a file".to_string(),
+        )),
+    }
+}
+
+/// The `topics` table, keyed by its id.
+fn sqlite3_topic_rows(connection: &Connection) -> Result<Vec<(i64, BagTopic)>> {
+    let mut statement = connection
+        .prepare("SELECT id, name, type, serialization_format FROM topics ORDER BY id")?;
+    let rows = statement.query_map([], |row| {
+        Ok((
+            row.get(0)?,
+            BagTopic {
+                name: row.get(1)?,
+                msg_type: row.get(2)?,
+                serialization_format: row.get(3)?,
+            },
+        ))
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+fn sqlite3_topics(connection: &Connection) -> Result<Vec<BagTopic>> {
+    Ok(sqlite3_topic_rows(connection)?
+        .into_iter()
+        .map(|(_, topic)| topic)
+        .collect())
+}
+
+/// Pages through the `messages` table in `(timestamp, id)` order, the order
+/// `ros2 bag play` uses.
+struct Sqlite3Messages {
+    connection: Connection,
+    // topic id in this database to index in the bag's topics
+    topic_indices: HashMap<i64, usize>,
+    first_query: String,
+    next_query: String,
+    // (timestamp, id) of the last message read
+    last: Option<(i64, i64)>,
+    page: VecDeque<BagMessage>,
+    done: bool,
+}
+
+impl Sqlite3Messages {
+    fn open(file: &StorageFile, topics: &[BagTopic], wanted: &[bool]) -> Result<Self> {
+        let connection = open_sqlite3(file)?;
+        let topic_indices: HashMap<i64, usize> = sqlite3_topic_rows(&connection)?
+            .into_iter()
+            .filter_map(|(id, topic)| {
+                let index = topics.iter().position(|known| known.name == topic.name)?;
+                Some((id, index)).filter(|_| wanted[index])
+            })
+            .collect();
+        let ids: Vec<String> = topic_indices.keys().map(|id| id.to_string()).collect();
+        let select = format!(
+            "SELECT id, topic_id, timestamp, data FROM messages WHERE topic_id IN ({})",
+            ids.join(", ")
+        );
+        let order = format!("ORDER BY timestamp, id LIMIT {}", SQLITE_PAGE_SIZE);
+        Ok(Self {
+            connection,
+            first_query: format!("{} {}", select, order),
+            next_query: format!("{} AND (timestamp, id) > (?1, ?2) {}", select, order),
+            done: topic_indices.is_empty(),
+            topic_indices,
+            last: None,
+            page: VecDeque::new(),
+        })
+    }
+
+    fn fetch_page(&mut self) -> Result<()> {
+        let (query, last) = match self.last {
+            Some(last) => (&self.next_query, last),
+            None => (&self.first_query, (0, 0)),
+        };
+        let mut statement = self.connection.prepare_cached(query)?;
+        let map_row = |row: &rusqlite::Row| -> rusqlite::Result<(i64, i64, i64, Vec<u8>)> {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        };
+        let rows = match self.last {
+            Some(_) => statement.query_map(params![last.0, last.1], map_row)?,
+            None => statement.query_map([], map_row)?,
+        };
+        for row in rows {
+            let (id, topic_id, timestamp, data) = row?;
+            self.last = Some((timestamp, id));
+            self.page.push_back(BagMessage {
+                topic: self.topic_indices[&topic_id],
+                log_time: timestamp,
+                data,
+            });
+        }
+        self.done = self.page.len() < SQLITE_PAGE_SIZE;
+        Ok(())
+    }
+}
+
+impl StorageReader for Sqlite3Messages {
+    fn next_message(&mut self) -> Result<Option<BagMessage>> {
+        if self.page.is_empty() && !self.done {
+            self.fetch_page()?;
+        }
+        Ok(self.page.pop_front())
+    }
+}
+
+trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+mod opcode {
+    pub const FOOTER: u8 = 0x02;
+    pub const SCHEMA: u8 = 0x03;
+    pub const CHANNEL: u8 = 0x04;
+    pub const MESSAGE: u8 = 0x05;
+    pub const CHUNK: u8 = 0x06;
+    pub const DATA_END: u8 = 0x0f;
+}
+
+fn malformed(record: &str) -> Error {
+    Error::Bag(format!("malformed MCAP {} record", record))
+}
+
+/// Reads the little endian fields of an MCAP record body.
+struct McapFields<'b> {
+    record: &'static str,
+    body: &'b [u8],
+    pos: usize,
+}
+
+impl<'b> McapFields<'b> {
+    fn new(record: &'static str, body: &'b [u8]) -> Self {
+        Self {
+            record,
+            body,
+            pos: 0,
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'b [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.body.len())
+            .ok_or_else(|| malformed(self.record))?;
+        let bytes = &self.body[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| malformed(self.record))
+    }
+
+    fn rest(&mut self) -> &'b [u8] {
+        let bytes = &self.body[self.pos..];
+        self.pos = self.body.len();
+        bytes
+    }
+}
+
+/// Reads the next record, or `None` at the end of the input.
+fn read_record(input: &mut dyn Read) -> Result<Option<(u8, Vec<u8>)>> {
+    let mut opcode = [0; 1];
+    if input.read(&mut opcode)? == 0 {
+        return Ok(None);
+    }
+    let mut len = [0; 8];
+    input.read_exact(&mut len)?;
+    let len = u64::from_le_bytes(len);
+    let mut body = Vec::new();
+    input.take(len).read_to_end(&mut body)?;
+    if body.len() as u64 != len {
+        return Err(Error::Bag("truncated MCAP record".to_string()));
+    }
+    Ok(Some((opcode[0], body)))
+}
+
+fn decompress_chunk(body: &[u8]) -> Result<Vec<u8>> {
+    let mut fields = McapFields::new("chunk", body);
+    let _message_start_time = fields.u64()?;
+    let _message_end_time = fields.u64()?;
+    let uncompressed_size = fields.u64()?;
+    let _uncompressed_crc = fields.u32()?;
+    let compression = fields.string()?;
+    let records_len = fields.u64()? as usize;
+    let records = fields.take(records_len)?;
+    if uncompressed_size > MAX_CHUNK_SIZE {
+        return Err(Error::Bag(format!(
+            "MCAP chunk of {} bytes is above the {} byte limit",
+            uncompressed_size, MAX_CHUNK_SIZE
+        )));
+    }
+    let decoder: Box<dyn Read + '_> = match compression.as_str() {
+        "" => Box::new(records),
+        "zstd" => Box::new(zstd::stream::read::Decoder::with_buffer(records)?),
+        "lz4" => Box::new(lz4::Decoder::new(records)?),
+        other => {
+            return Err(Error::Bag(format!(
+                "unsupported MCAP chunk compression {}",
+                other
+            )))
+        }
+    };
+    // The recorded size is not trusted for allocation; the buffer grows
+    // with the data, and reading one byte past the size catches a chunk
+    // that decompresses to more.
+    let mut records = Vec::new();
+    decoder
+        .take(uncompressed_size + 1)
+        .read_to_end(&mut records)?;
+    if records.len() as u64 != uncompressed_size {
+        return Err(malformed("chunk"));
+    }
+    Ok(records)
+}
+
+/// Streams the data section of an MCAP file, decompressing one chunk at a
+/// time. Messages are returned in file order.
+struct McapMessages {
+    input: Box<dyn ReadSeek>,
+    // schema id to type name
+    schemas: HashMap<u16, String>,
+    // channel id to topic, from the channel records read so far
+    channels: HashMap<u16, BagTopic>,
+    // channel id to index in the bag's topics, for the selected topics
+    selected: HashMap<u16, usize>,
+    topics: Vec<BagTopic>,
+    wanted: Vec<bool>,
+    chunk: Option<Cursor<Vec<u8>>>,
+    done: bool,
+}
+
+impl McapMessages {
+    fn open(file: &StorageFile) -> Result<Self> {
+        let mut input: Box<dyn ReadSeek> = match file {
+            StorageFile::Path(path) => Box::new(BufReader::new(File::open(path)?)),
+            StorageFile::Bytes(bytes) => Box::new(Cursor::new(bytes.clone())),
+        };
+        let mut magic = [0; 8];
+        input.read_exact(&mut magic)?;
+        if &magic != MCAP_MAGIC {
+            return Err(Error::Bag("not an MCAP file".to_string()));
+        }
+        Ok(Self {
+            input,
+            schemas: HashMap::new(),
+            channels: HashMap::new(),
+            selected: HashMap::new(),
+            topics: Vec::new(),
+            wanted: Vec::new(),
+            chunk: None,
+            done: false,
+        })
+    }
+
+    fn select(&mut self, topics: &[BagTopic], wanted: &[bool]) {
+        self.topics = topics.to_vec();
+        self.wanted = wanted.to_vec();
+    }
+
+    /// The channels of the file, from its summary section if it has one,
+    /// otherwise by reading the whole file.
+    fn topics(mut self) -> Result<Vec<BagTopic>> {
+        let mut channels: Vec<(u16, BagTopic)> = match self.summary_start()? {
+            Some(summary_start) => {
+                self.input.seek(SeekFrom::Start(summary_start))?;
+                while let Some((opcode, body)) = read_record(&mut self.input)? {
+                    if opcode == opcode::FOOTER {
+                        break;
+                    }
+                    self.read_definition(opcode, &body)?;
+                }
+                self.channels.into_iter().collect()
+            }
+            None => {
+                while self.next_message()?.is_some() {}
+                self.channels.into_iter().collect()
+            }
+        };
+        channels.sort_by_key(|(id, _)| *id);
+        Ok(channels.into_iter().map(|(_, topic)| topic).collect())
+    }
+
+    /// Offset of the summary section given by the footer, if any.
+    fn summary_start(&mut self) -> Result<Option<u64>> {
+        // footer record: opcode, length, summary_start, summary_offset_start
+        // and summary_crc, followed by the closing magic
+        const FOOTER_LEN: i64 = 1 + 8 + 8 + 8 + 4;
+        let position = self.input.stream_position()?;
+        let end = self.input.seek(SeekFrom::End(0))?;
+        if end < (MCAP_MAGIC.len() as u64) * 2 + FOOTER_LEN as u64 {
+            self.input.seek(SeekFrom::Start(position))?;
+            return Ok(None);
+        }
+        self.input
+            .seek(SeekFrom::End(-(FOOTER_LEN + MCAP_MAGIC.len() as i64)))?;
+        let mut footer = [0; FOOTER_LEN as usize];
+        self.input.read_exact(&mut footer)?;
+        self.input.seek(SeekFrom::Start(position))?;
+        if footer[0] != opcode::FOOTER {
+            return Ok(None);
+        }
+        let summary_start = u64::from_le_bytes(footer[9..17].try_into().unwrap());
+        Ok(Some(summary_start).filter(|start| *start != 0))
+    }
+
+    /// Records a schema or channel definition.
+    fn read_definition(&mut self, opcode: u8, body: &[u8]) -> Result<()> {
+        match opcode {
+            opcode::SCHEMA => {
+                let mut fields = McapFields::new("schema", body);
+                let id = fields.u16()?;
+                let name = fields.string()?;
+                self.schemas.insert(id, name);
+            }
+            opcode::CHANNEL => {
+                let mut fields = McapFields::new("channel", body);
+                let id = fields.u16()?;
+                let schema_id = fields.u16()?;
+                let name = fields.string()?;
+                let serialization_format = fields.string()?;
+                let msg_type = self.schemas.get(&schema_id).cloned().unwrap_or_default();
+                if let Some(index) = self.topics.iter().position(|topic| topic.name == name) {
+                    if self.wanted[index] {
+                        self.selected.insert(id, index);
+                    }
+                }
+                self.channels.insert(
+                    id,
+                    BagTopic {
+                        name,
+                        msg_type,
+                        serialization_format,
+                    },
+                );
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn next_record(&mut self) -> Result<Option<(u8, Vec<u8>)>> {
+        loop {
+            if let Some(chunk) = self.chunk.as_mut() {
+                if let Some(record) = read_record(chunk)? {
+                    return Ok(Some(record));
+                }
+                self.chunk = None;
+            }
+            if self.done {
+                return Ok(None);
+            }
+            match read_record(&mut self.input)? {
+                Some((opcode::CHUNK, body)) => {
+                    self.chunk = Some(Cursor::new(decompress_chunk(&body)?));
+                }
+                Some((opcode::DATA_END, _)) | Some((opcode::FOOTER, _)) | None => {
+                    self.done = true;
+                }
+                Some(record) => return Ok(Some(record)),
+            }
+        }
+    }
+}
+
+impl StorageReader for McapMessages {
+    fn next_message(&mut self) -> Result<Option<BagMessage>> {
+        while let Some((opcode, body)) = self.next_record()? {
+            match opcode {
+                opcode::SCHEMA | opcode::CHANNEL => self.read_definition(opcode, &body)?,
+                opcode::MESSAGE => {
+                    let mut fields = McapFields::new("message", &body);
+                    let channel_id = fields.u16()?;
+                    let topic = match self.selected.get(&channel_id) {
+                        Some(topic) => *topic,
+                        None => continue,
+                    };
+                    let _sequence = fields.u32()?;
+                    let log_time = fields.u64()? as i64;
+                    let _publish_time = fields.u64()?;
+                    return Ok(Some(BagMessage {
+                        topic,
+                        log_time,
+                        data: fields.rest().to_vec(),
+                    }));
+                }
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// A message type that can be read from a bag.
+pub trait BagMsg: Msg + FromCdr + 'static {}
+
+impl<M> BagMsg for M where M: Msg + FromCdr + 'static {}
+
+/// Iterates the messages of one topic of a bag, decoded as `M`.
+pub struct MsgBagRowIter<M: BagMsg> {
+    messages: BagMessageIter,
+    row_index: usize,
+    error_policy: RowErrorPolicy,
+    skipped_rows: usize,
+    error: Option<Error>,
+    msg_type: PhantomData<M>,
+}
+
+impl<M: BagMsg> MsgBagRowIter<M> {
+    fn from_messages(messages: BagMessageIter) -> Self {
+        Self {
+            messages,
+            row_index: 0,
+            error_policy: RowErrorPolicy::default(),
+            skipped_rows: 0,
+            error: None,
+            msg_type: PhantomData,
+        }
+    }
+
+    pub fn try_next(&mut self) -> Option<Result<Arc<M>>> {
+        loop {
+            let message = match self.messages.next()? {
+                Ok(message) => message,
+                Err(e) => return Some(Err(e)),
+            };
+            let row_index = self.row_index;
+            self.row_index += 1;
+            match M::from_cdr_bytes(&message.data) {
+                Ok(msg) => return Some(Ok(Arc::new(msg))),
+                Err(e) => match self.error_policy {
+                    RowErrorPolicy::Surface => return Some(Err(e.with_row(row_index))),
+                    RowErrorPolicy::Skip => self.skipped_rows += 1,
+                },
+            }
+        }
+    }
+}
+
+impl<M: BagMsg> HasRowIter for MsgBagRowIter<M> {
+    /// Opens the bag at `path`, which must have exactly one topic of type
+    /// `M`. Use `Rosbag2::msg_reader` to pick a topic by name.
+    fn try_new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Rosbag2::open(path)?.unique_msg_reader()
+    }
+    /// Reads an MCAP recording into memory; sqlite3 databases can only be
+    /// opened by path.
+    fn try_from_reader<R: ChunkReader + 'static>(reader: R) -> Result<Self> {
+        let mut bytes = Vec::new();
+        reader
+            .get_read(0, reader.len() as usize)?
+            .read_to_end(&mut bytes)?;
+        Rosbag2::from_mcap_bytes(bytes)?.unique_msg_reader()
+    }
+    fn next_msg(&mut self) -> Option<Arc<dyn Msg>> {
+        match self.next() {
+            Some(msg) => Some(msg),
+            None => None,
+        }
+    }
+    fn try_next_msg(&mut self) -> Option<Result<Arc<dyn Msg>>> {
+        self.try_next()
+            .map(|msg| msg.map(|msg| msg as Arc<dyn Msg>))
+    }
+    fn set_error_policy(&mut self, error_policy: RowErrorPolicy) {
+        self.error_policy = error_policy;
+    }
+    fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+    fn skipped_rows(&self) -> usize {
+        self.skipped_rows
+    }
+}
+
+impl<M: BagMsg> Iterator for MsgBagRowIter<M> {
+    type Item = Arc<M>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.try_next() {
+            Some(Ok(msg)) => Some(msg),
+            Some(Err(e)) => {
+                self.error = Some(e);
+                None
+            }
+            None => None,
+        }
+    }
+}
+
+pub type PointCloud2BagRowIter = MsgBagRowIter<PointCloud2>;
+pub type ImageBagRowIter = MsgBagRowIter<Image>;
+pub type NavSatFixBagRowIter = MsgBagRowIter<NavSatFix>;
+pub type ImuBagRowIter = MsgBagRowIter<Imu>;
+pub type CameraInfoBagRowIter = MsgBagRowIter<CameraInfo>;
+pub type TwistStampedBagRowIter = MsgBagRowIter<TwistStamped>;
+pub type OdometryBagRowIter = MsgBagRowIter<Odometry>;
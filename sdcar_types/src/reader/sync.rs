@@ -0,0 +1,252 @@
+use crate::msg::camera_info::CameraInfoRef;
+use crate::msg::image::ImageRef;
+use crate::msg::imu::ImuRef;
+use crate::msg::nav_sat_fix::NavSatFixRef;
+use crate::msg::odometry::OdometryRef;
+use crate::msg::point_cloud2::PointCloud2Ref;
+use crate::msg::twist_stamped::TwistStampedRef;
+use crate::msg::vehicle_state::VehicleStateRef;
+use crate::msg::MsgKind;
+use crate::reader::merged::MergedReader;
+use chrono::{DateTime, Duration, Utc};
+
+use std::collections::VecDeque;
+
+/// What happens to a message once it has been placed in a set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StalePolicy {
+    /// A message is used in at most one set; it and any older messages of
+    /// its source are discarded once used.
+    #[default]
+    Drop,
+    /// A message may be reused by later sets while it is the closest one to
+    /// their pivot, e.g. a slow GPS fix shared by several lidar sweeps.
+    Reuse,
+}
+
+/// One message from every source of the synchronizer, indexed by source.
+#[derive(Debug, Clone)]
+pub struct SyncedSet {
+    timestamp: DateTime<Utc>,
+    msgs: Vec<MsgKind>,
+}
+
+impl SyncedSet {
+    /// Timestamp of the pivot message the set was built around.
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+    pub fn len(&self) -> usize {
+        self.msgs.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.msgs.is_empty()
+    }
+    pub fn get(&self, source: usize) -> &MsgKind {
+        &self.msgs[source]
+    }
+    pub fn msgs(&self) -> &[MsgKind] {
+        &self.msgs
+    }
+    /// Difference between the latest and the earliest message in the set.
+    pub fn spread(&self) -> Duration {
+        let stamps = self.msgs.iter().map(|msg| msg.timestamp());
+        match (stamps.clone().min(), stamps.max()) {
+            (Some(min), Some(max)) => max - min,
+            _ => Duration::zero(),
+        }
+    }
+    pub fn point_cloud2(&self, source: usize) -> Option<PointCloud2Ref> {
+        match &self.msgs[source] {
+            MsgKind::PointCloud2(msg) => Some(msg.clone()),
+            _ => None,
+        }
+    }
+    pub fn image(&self, source: usize) -> Option<ImageRef> {
+        match &self.msgs[source] {
+            MsgKind::Image(msg) => Some(msg.clone()),
+            _ => None,
+        }
+    }
+    pub fn nav_sat_fix(&self, source: usize) -> Option<NavSatFixRef> {
+        match &self.msgs[source] {
+            MsgKind::NavSatFix(msg) => Some(msg.clone()),
+            _ => None,
+        }
+    }
+    pub fn vehicle_state(&self, source: usize) -> Option<VehicleStateRef> {
+        match &self.msgs[source] {
+            MsgKind::VehicleState(msg) => Some(msg.clone()),
+            _ => None,
+        }
+    }
+    pub fn imu(&self, source: usize) -> Option<ImuRef> {
+        match &self.msgs[source] {
+            MsgKind::Imu(msg) => Some(msg.clone()),
+            _ => None,
+        }
+    }
+    pub fn camera_info(&self, source: usize) -> Option<CameraInfoRef> {
+        match &self.msgs[source] {
+            MsgKind::CameraInfo(msg) => Some(msg.clone()),
+            _ => None,
+        }
+    }
+    pub fn twist_stamped(&self, source: usize) -> Option<TwistStampedRef> {
+        match &self.msgs[source] {
+            MsgKind::TwistStamped(msg) => Some(msg.clone()),
+            _ => None,
+        }
+    }
+    pub fn odometry(&self, source: usize) -> Option<OdometryRef> {
+        match &self.msgs[source] {
+            MsgKind::Odometry(msg) => Some(msg.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Groups the messages of every source of a `MergedReader` into sets whose
+/// timestamps lie within `slop` of a pivot message, similar to the ROS
+/// message_filters ApproximateTime policy.
+///
+/// Each message of the pivot source (source 0 unless set by `with_pivot`)
+/// yields at most one set, built from the message of every other source
+/// closest to it in time. Pivot messages without a match within `slop` for
+/// every source are dropped and counted in `dropped_pivots`.
+/// `DiagnosticsReport::suggested_slop` estimates a slop from a recording.
+pub struct ApproximateTimeSync {
+    reader: MergedReader,
+    slop: Duration,
+    pivot: usize,
+    stale_policy: StalePolicy,
+    queues: Vec<VecDeque<MsgKind>>,
+    dropped_pivots: usize,
+}
+
+impl ApproximateTimeSync {
+    pub fn new(reader: MergedReader, slop: Duration) -> Self {
+        let queues = (0..reader.num_sources()).map(|_| VecDeque::new()).collect();
+        Self {
+            reader,
+            slop,
+            pivot: 0,
+            stale_policy: StalePolicy::default(),
+            queues,
+            dropped_pivots: 0,
+        }
+    }
+
+    pub fn with_pivot(mut self, pivot: usize) -> Self {
+        assert!(
+            pivot < self.queues.len(),
+            "pivot source {} out of range",
+            pivot
+        );
+        self.pivot = pivot;
+        self
+    }
+
+    pub fn with_stale_policy(mut self, stale_policy: StalePolicy) -> Self {
+        self.stale_policy = stale_policy;
+        self
+    }
+
+    pub fn slop(&self) -> Duration {
+        self.slop
+    }
+
+    pub fn dropped_pivots(&self) -> usize {
+        self.dropped_pivots
+    }
+
+    pub fn reader(&self) -> &MergedReader {
+        &self.reader
+    }
+
+    // discard non pivot messages too old to match any pivot still to come
+    fn prune(&mut self, oldest_pivot: DateTime<Utc>) {
+        let threshold = oldest_pivot - self.slop;
+        for (source, queue) in self.queues.iter_mut().enumerate() {
+            if source == self.pivot {
+                continue;
+            }
+            while queue.front().is_some_and(|msg| msg.timestamp() < threshold) {
+                queue.pop_front();
+            }
+        }
+    }
+
+    fn match_pivot(&mut self, pivot_msg: MsgKind) -> Option<SyncedSet> {
+        let timestamp = pivot_msg.timestamp();
+        let mut chosen = Vec::with_capacity(self.queues.len());
+        for (source, queue) in self.queues.iter().enumerate() {
+            if source == self.pivot {
+                chosen.push(None);
+                continue;
+            }
+            let closest = queue
+                .iter()
+                .enumerate()
+                .map(|(i, msg)| (i, (msg.timestamp() - timestamp).abs()))
+                .filter(|(_, offset)| *offset <= self.slop)
+                .min_by_key(|(_, offset)| *offset)
+                .map(|(i, _)| i);
+            match closest {
+                Some(i) => chosen.push(Some(i)),
+                None => return None,
+            }
+        }
+
+        let mut msgs = Vec::with_capacity(self.queues.len());
+        for (source, i) in chosen.into_iter().enumerate() {
+            match i {
+                None => msgs.push(pivot_msg.clone()),
+                Some(i) => {
+                    let msg = self.queues[source][i].clone();
+                    if self.stale_policy == StalePolicy::Drop {
+                        self.queues[source].drain(..=i);
+                    }
+                    msgs.push(msg);
+                }
+            }
+        }
+        Some(SyncedSet { timestamp, msgs })
+    }
+}
+
+impl Iterator for ApproximateTimeSync {
+    type Item = SyncedSet;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(pivot_msg) = self.queues[self.pivot].front() {
+                let window_end = pivot_msg.timestamp() + self.slop;
+                // every message that could match the pivot has been read
+                let ready = self
+                    .reader
+                    .peek_timestamp()
+                    .is_none_or(|next| next > window_end);
+                if ready {
+                    let pivot_msg = self.queues[self.pivot].pop_front()?;
+                    self.prune(pivot_msg.timestamp());
+                    match self.match_pivot(pivot_msg) {
+                        Some(set) => return Some(set),
+                        None => {
+                            self.dropped_pivots += 1;
+                            continue;
+                        }
+                    }
+                }
+            }
+
+            let merged_msg = self.reader.next()?;
+            let timestamp = merged_msg.timestamp();
+            self.queues[merged_msg.source].push_back(merged_msg.msg_kind);
+            let oldest_pivot = self.queues[self.pivot]
+                .front()
+                .map_or(timestamp, |msg| msg.timestamp());
+            self.prune(oldest_pivot);
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use crate::reader::merged::MergedReader;
+use crate::reader::parquet::*;
+use crate::{Error, Result};
+
+use chrono::NaiveDateTime;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Topics recorded for every run, named as in `<topic>_<run>.parquet`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Topic {
+    PointCloud2,
+    LeftImage,
+    RightImage,
+    NavSatFix,
+    VehicleState,
+    Imu,
+    LeftCameraInfo,
+    RightCameraInfo,
+}
+
+impl Topic {
+    pub const ALL: [Topic; 8] = [
+        Topic::PointCloud2,
+        Topic::LeftImage,
+        Topic::RightImage,
+        Topic::NavSatFix,
+        Topic::VehicleState,
+        Topic::Imu,
+        Topic::LeftCameraInfo,
+        Topic::RightCameraInfo,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Topic::PointCloud2 => "point_cloud2",
+            Topic::LeftImage => "left_image",
+            Topic::RightImage => "right_image",
+            Topic::NavSatFix => "nav_sat_fix",
+            Topic::VehicleState => "vehicle_state",
+            Topic::Imu => "imu",
+            Topic::LeftCameraInfo => "left_camera_info",
+            Topic::RightCameraInfo => "right_camera_info",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Topic> {
+        Topic::ALL
+            .iter()
+            .copied()
+            .find(|topic| topic.name() == name)
+    }
+
+    /// Splits a file name such as `left_image_20211028165901.parquet` into
+    /// its topic and run id.
+    fn parse_file_name(file_name: &str) -> Option<(Topic, &str)> {
+        let stem = file_name.strip_suffix(".parquet")?;
+        Topic::ALL.iter().find_map(|topic| {
+            let run = stem.strip_prefix(topic.name())?.strip_prefix('_')?;
+            if !run.is_empty() && run.bytes().all(|b| b.is_ascii_digit()) {
+                Some((*topic, run))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A data collection directory laid out as
+///
+/// ```text
+/// <collection>/parquet/<topic>_<run>.parquet
+/// <collection>/images/<oid>.webp
+/// <collection>/images/rectified/<oid>.webp
+/// ```
+#[derive(Debug, Clone)]
+pub struct Session {
+    path: PathBuf,
+    runs: Vec<Run>,
+}
+
+impl Session {
+    /// Scans `<path>/parquet` for topic files and groups them into runs
+    /// ordered by run id. Files not named `<topic>_<run>.parquet` are ignored.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut runs: BTreeMap<String, Run> = BTreeMap::new();
+        for entry in fs::read_dir(path.join("parquet"))? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let (topic, id) = match file_name.to_str().and_then(Topic::parse_file_name) {
+                Some(parsed) => parsed,
+                None => continue,
+            };
+            runs.entry(id.to_string())
+                .or_insert_with(|| Run::new(&path, id))
+                .topics
+                .insert(topic, entry.path());
+        }
+        Ok(Self {
+            path,
+            runs: runs.into_values().collect(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn parquet_dir(&self) -> PathBuf {
+        self.path.join("parquet")
+    }
+
+    /// Directory of the content addressed `<oid>.webp` image blobs.
+    pub fn image_dir(&self) -> PathBuf {
+        self.path.join("images")
+    }
+
+    pub fn rectified_image_dir(&self) -> PathBuf {
+        self.image_dir().join("rectified")
+    }
+
+    pub fn runs(&self) -> &[Run] {
+        &self.runs
+    }
+
+    pub fn run(&self, id: &str) -> Option<&Run> {
+        self.runs.iter().find(|run| run.id == id)
+    }
+}
+
+/// One recording within a `Session`, identified by its timestamp id such as
+/// `20211028165901`.
+#[derive(Debug, Clone)]
+pub struct Run {
+    id: String,
+    session_path: PathBuf,
+    topics: BTreeMap<Topic, PathBuf>,
+}
+
+impl Run {
+    fn new(session_path: &Path, id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            session_path: session_path.to_path_buf(),
+            topics: BTreeMap::new(),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The recorder's local start time encoded in the id, if it is in
+    /// `%Y%m%d%H%M%S` form.
+    pub fn start_time(&self) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(&self.id, "%Y%m%d%H%M%S").ok()
+    }
+
+    /// Topics that have a file in this run, in `Topic::ALL` order.
+    pub fn topics(&self) -> impl Iterator<Item = Topic> + '_ {
+        self.topics.keys().copied()
+    }
+
+    pub fn has_topic(&self, topic: Topic) -> bool {
+        self.topics.contains_key(&topic)
+    }
+
+    pub fn topic_path(&self, topic: Topic) -> Option<&Path> {
+        self.topics.get(&topic).map(PathBuf::as_path)
+    }
+
+    pub fn image_dir(&self) -> PathBuf {
+        self.session_path.join("images")
+    }
+
+    pub fn rectified_image_dir(&self) -> PathBuf {
+        self.image_dir().join("rectified")
+    }
+
+    fn require(&self, topic: Topic) -> Result<&Path> {
+        self.topic_path(topic).ok_or_else(|| Error::MissingTopic {
+            run: self.id.clone(),
+            topic: topic.name(),
+        })
+    }
+
+    pub fn point_cloud2(&self) -> Result<PointCloud2ParquetRowIter> {
+        PointCloud2ParquetRowIter::try_new(self.require(Topic::PointCloud2)?)
+    }
+
+    pub fn left_image(&self) -> Result<ImageParquetRowIter> {
+        ImageParquetRowIter::try_new(self.require(Topic::LeftImage)?)
+    }
+
+    pub fn right_image(&self) -> Result<ImageParquetRowIter> {
+        ImageParquetRowIter::try_new(self.require(Topic::RightImage)?)
+    }
+
+    pub fn nav_sat_fix(&self) -> Result<NavSatFixParquetRowIter> {
+        NavSatFixParquetRowIter::try_new(self.require(Topic::NavSatFix)?)
+    }
+
+    pub fn vehicle_state(&self) -> Result<VehicleStateParquetRowIter> {
+        VehicleStateParquetRowIter::try_new(self.require(Topic::VehicleState)?)
+    }
+
+    pub fn imu(&self) -> Result<ImuParquetRowIter> {
+        ImuParquetRowIter::try_new(self.require(Topic::Imu)?)
+    }
+
+    pub fn left_camera_info(&self) -> Result<CameraInfoParquetRowIter> {
+        CameraInfoParquetRowIter::try_new(self.require(Topic::LeftCameraInfo)?)
+    }
+
+    pub fn right_camera_info(&self) -> Result<CameraInfoParquetRowIter> {
+        CameraInfoParquetRowIter::try_new(self.require(Topic::RightCameraInfo)?)
+    }
+
+    /// Merges every topic present in the run, each source named after its
+    /// topic and added in `Topic::ALL` order.
+    pub fn merged_reader(&self) -> Result<MergedReader> {
+        let mut reader = MergedReader::new();
+        for topic in self.topics() {
+            match topic {
+                Topic::PointCloud2 => reader.add_source(topic.name(), self.point_cloud2()?),
+                Topic::LeftImage => reader.add_source(topic.name(), self.left_image()?),
+                Topic::RightImage => reader.add_source(topic.name(), self.right_image()?),
+                Topic::NavSatFix => reader.add_source(topic.name(), self.nav_sat_fix()?),
+                Topic::VehicleState => reader.add_source(topic.name(), self.vehicle_state()?),
+                Topic::Imu => reader.add_source(topic.name(), self.imu()?),
+                Topic::LeftCameraInfo => reader.add_source(topic.name(), self.left_camera_info()?),
+                Topic::RightCameraInfo => {
+                    reader.add_source(topic.name(), self.right_camera_info()?)
+                }
+            };
+        }
+        Ok(reader)
+    }
+}
@@ -0,0 +1,270 @@
+//! Fixtures for tests that read rosbag2 recordings: CDR payloads
+//! serialized the way rmw does and MCAP files holding them. Built only with
+//! the `test-support` feature, for the tests of this crate and of the
+//! crates converting bags through it.
+
+use std::io::Write;
+
+/// Serializes CDR the way rmw does, little endian unless `big_endian`.
+pub struct CdrWriter {
+    pub buf: Vec<u8>,
+    big_endian: bool,
+}
+
+impl CdrWriter {
+    pub fn new(big_endian: bool) -> Self {
+        let kind = if big_endian { 0x00 } else { 0x01 };
+        Self {
+            buf: vec![0x00, kind, 0x00, 0x00],
+            big_endian,
+        }
+    }
+    fn align(&mut self, size: usize) {
+        while !(self.buf.len() - 4).is_multiple_of(size) {
+            self.buf.push(0);
+        }
+    }
+    pub fn u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+    pub fn u16(&mut self, value: u16) {
+        self.align(2);
+        let bytes = if self.big_endian {
+            value.to_be_bytes()
+        } else {
+            value.to_le_bytes()
+        };
+        self.buf.extend_from_slice(&bytes);
+    }
+    pub fn u32(&mut self, value: u32) {
+        self.align(4);
+        let bytes = if self.big_endian {
+            value.to_be_bytes()
+        } else {
+            value.to_le_bytes()
+        };
+        self.buf.extend_from_slice(&bytes);
+    }
+    pub fn f64(&mut self, value: f64) {
+        self.align(8);
+        let bytes = if self.big_endian {
+            value.to_be_bytes()
+        } else {
+            value.to_le_bytes()
+        };
+        self.buf.extend_from_slice(&bytes);
+    }
+    pub fn f64s(&mut self, values: &[f64]) {
+        for value in values {
+            self.f64(*value);
+        }
+    }
+    pub fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32 + 1);
+        self.buf.extend_from_slice(value.as_bytes());
+        self.buf.push(0);
+    }
+    pub fn bytes(&mut self, value: &[u8]) {
+        self.u32(value.len() as u32);
+        self.buf.extend_from_slice(value);
+    }
+    pub fn header(&mut self, sec: i32, nanosec: u32, frame_id: &str) {
+        self.u32(sec as u32);
+        self.u32(nanosec);
+        self.string(frame_id);
+    }
+}
+
+pub const NAV_SAT_FIX: &str = "sensor_msgs/msg/NavSatFix";
+pub const IMU: &str = "sensor_msgs/msg/Imu";
+pub const IMAGE: &str = "sensor_msgs/msg/Image";
+pub const CAMERA_INFO: &str = "sensor_msgs/msg/CameraInfo";
+pub const TWIST_STAMPED: &str = "geometry_msgs/msg/TwistStamped";
+pub const TF: &str = "tf2_msgs/msg/TFMessage";
+
+/// The second the i-th message of a fixture is stamped after.
+pub const START_SEC: i32 = 1634357915;
+
+/// The log time of a message recorded `nanosec` after the i-th second.
+pub fn log_time(i: i32, nanosec: i64) -> i64 {
+    (START_SEC + i) as i64 * 1_000_000_000 + nanosec
+}
+
+pub fn nav_sat_fix_cdr(i: i32, big_endian: bool) -> Vec<u8> {
+    let mut cdr = CdrWriter::new(big_endian);
+    cdr.header(START_SEC + i, 500_000_000, "gps");
+    cdr.u8(2);
+    cdr.u16(1);
+    cdr.f64s(&[-27.55 + i as f64 * 1e-5, 153.05, 45.5]);
+    cdr.f64s(&[1.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 3.0]);
+    cdr.u8(2);
+    cdr.buf
+}
+
+/// An imu without an orientation, turning at `0.03 * i` about z.
+pub fn imu_cdr(i: i32) -> Vec<u8> {
+    let mut cdr = CdrWriter::new(false);
+    cdr.header(START_SEC + i, 250_000_000, "imu_link");
+    cdr.f64s(&[0.0, 0.0, 0.1, 0.99]);
+    cdr.f64s(&[-1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+    cdr.f64s(&[0.01, 0.02, 0.03 * i as f64]);
+    cdr.f64s(&[0.001; 9]);
+    cdr.f64s(&[0.1, 0.2, 9.81]);
+    cdr.f64s(&[0.01; 9]);
+    cdr.buf
+}
+
+/// A 2x2 image of `encoding` with rows padded to `step` bytes.
+pub fn image_cdr(i: i32, frame_id: &str, encoding: &str, step: u32, data: &[u8]) -> Vec<u8> {
+    let mut cdr = CdrWriter::new(false);
+    cdr.header(START_SEC + i, 750_000_000, frame_id);
+    cdr.u32(2);
+    cdr.u32(2);
+    cdr.string(encoding);
+    cdr.u8(0);
+    cdr.u32(step);
+    cdr.bytes(data);
+    cdr.buf
+}
+
+pub fn twist_stamped_cdr(i: i32) -> Vec<u8> {
+    let mut cdr = CdrWriter::new(false);
+    cdr.header(START_SEC + i, 0, "base_link");
+    cdr.f64s(&[10.0 + i as f64, 0.0, 0.0, 0.0, 0.0, 0.05]);
+    cdr.buf
+}
+
+/// (topic, type, log time, payload) of messages in recorded order.
+pub type Recorded = Vec<(&'static str, &'static str, i64, Vec<u8>)>;
+
+fn record(buf: &mut Vec<u8>, opcode: u8, body: &[u8]) {
+    buf.push(opcode);
+    buf.extend_from_slice(&(body.len() as u64).to_le_bytes());
+    buf.extend_from_slice(body);
+}
+
+fn mcap_string(buf: &mut Vec<u8>, value: &str) {
+    buf.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    buf.extend_from_slice(value.as_bytes());
+}
+
+/// The channel id of `topic`, writing its schema and channel records to
+/// `definitions` the first time it is seen.
+fn define<'a>(
+    definitions: &mut Vec<u8>,
+    channels: &mut Vec<&'a str>,
+    topic: &'a str,
+    msg_type: &str,
+) -> u16 {
+    if let Some(index) = channels.iter().position(|known| *known == topic) {
+        return index as u16 + 1;
+    }
+    channels.push(topic);
+    let id = channels.len() as u16;
+    let mut schema = Vec::new();
+    schema.extend_from_slice(&id.to_le_bytes());
+    mcap_string(&mut schema, msg_type);
+    mcap_string(&mut schema, "ros2msg");
+    schema.extend_from_slice(&0u32.to_le_bytes());
+    record(definitions, 0x03, &schema);
+    let mut channel = Vec::new();
+    channel.extend_from_slice(&id.to_le_bytes());
+    channel.extend_from_slice(&id.to_le_bytes());
+    mcap_string(&mut channel, topic);
+    mcap_string(&mut channel, "cdr");
+    channel.extend_from_slice(&0u32.to_le_bytes());
+    record(definitions, 0x04, &channel);
+    id
+}
+
+/// An unchunked MCAP file of `recorded`. Topics without messages are given
+/// as `(topic, type)` in `empty`.
+pub fn mcap_bytes(recorded: &Recorded, empty: &[(&'static str, &str)]) -> Vec<u8> {
+    chunked_mcap_bytes(recorded, empty, "", 0, false)
+}
+
+/// An MCAP file of `recorded` with one chunk per `chunk_size` messages, each
+/// compressed as `compression` (`""`, `"zstd"` or `"lz4"`), or no chunks if
+/// `chunk_size` is 0, and a summary section if `summary`. The schema and
+/// channel records of every topic, those of `empty` first, go ahead of the
+/// first message, as the ros2 writer puts them.
+pub fn chunked_mcap_bytes(
+    recorded: &Recorded,
+    empty: &[(&'static str, &str)],
+    compression: &str,
+    chunk_size: usize,
+    summary: bool,
+) -> Vec<u8> {
+    let mut definitions = Vec::new();
+    let mut channels: Vec<&str> = Vec::new();
+    for (topic, msg_type) in empty {
+        define(&mut definitions, &mut channels, topic, msg_type);
+    }
+    let mut messages = Vec::new();
+    for (topic, msg_type, log_time, data) in recorded {
+        let id = define(&mut definitions, &mut channels, topic, msg_type);
+        let mut message = Vec::new();
+        message.extend_from_slice(&id.to_le_bytes());
+        message.extend_from_slice(&0u32.to_le_bytes());
+        message.extend_from_slice(&(*log_time as u64).to_le_bytes());
+        message.extend_from_slice(&(*log_time as u64).to_le_bytes());
+        message.extend_from_slice(data);
+        messages.push(message);
+    }
+
+    let mut buf = b"\x89MCAP0\r\n".to_vec();
+    let mut header = Vec::new();
+    mcap_string(&mut header, "ros2");
+    mcap_string(&mut header, "sdcar_types test");
+    record(&mut buf, 0x01, &header);
+    if chunk_size == 0 {
+        buf.extend_from_slice(&definitions);
+        for message in messages.iter() {
+            record(&mut buf, 0x05, message);
+        }
+    } else {
+        for (i, chunk_messages) in messages.chunks(chunk_size).enumerate() {
+            let mut records = Vec::new();
+            if i == 0 {
+                records.extend_from_slice(&definitions);
+            }
+            for message in chunk_messages {
+                record(&mut records, 0x05, message);
+            }
+            let compressed = match compression {
+                "" => records.clone(),
+                "zstd" => zstd::bulk::compress(&records, 3).unwrap(),
+                "lz4" => {
+                    let mut encoder = lz4::EncoderBuilder::new().build(Vec::new()).unwrap();
+                    encoder.write_all(&records).unwrap();
+                    encoder.finish().0
+                }
+                other => panic!("unknown compression {}", other),
+            };
+            let mut chunk = Vec::new();
+            chunk.extend_from_slice(&0u64.to_le_bytes());
+            chunk.extend_from_slice(&0u64.to_le_bytes());
+            chunk.extend_from_slice(&(records.len() as u64).to_le_bytes());
+            chunk.extend_from_slice(&0u32.to_le_bytes());
+            mcap_string(&mut chunk, compression);
+            chunk.extend_from_slice(&(compressed.len() as u64).to_le_bytes());
+            chunk.extend_from_slice(&compressed);
+            record(&mut buf, 0x06, &chunk);
+        }
+    }
+    record(&mut buf, 0x0f, &0u32.to_le_bytes());
+    let summary_start = if summary {
+        let start = buf.len() as u64;
+        buf.extend_from_slice(&definitions);
+        start
+    } else {
+        0
+    };
+    let mut footer = Vec::new();
+    footer.extend_from_slice(&summary_start.to_le_bytes());
+    footer.extend_from_slice(&0u64.to_le_bytes());
+    footer.extend_from_slice(&0u32.to_le_bytes());
+    record(&mut buf, 0x02, &footer);
+    buf.extend_from_slice(b"\x89MCAP0\r\n");
+    buf
+}
@@ -0,0 +1,391 @@
+use crate::kinematics::Pose2D;
+use crate::msg::nav_sat_fix::NavSatFix;
+use crate::msg::Msg;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use coord_transforms::prelude::*;
+use nalgebra as na;
+
+use std::borrow::Borrow;
+use std::fmt::Write;
+
+/// `NavSatStatus::STATUS_FIX`, an unaugmented fix.
+pub const STATUS_FIX: i8 = 0;
+
+/// Segments shorter than this, in metres, are too short to give a heading.
+const MIN_HEADING_DISTANCE: f64 = 0.05;
+
+/// Which fixes a `Trajectory` keeps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixFilter {
+    min_status: i8,
+    max_horizontal_std: Option<f64>,
+    max_vertical_std: Option<f64>,
+}
+
+impl Default for FixFilter {
+    fn default() -> Self {
+        Self {
+            min_status: STATUS_FIX,
+            max_horizontal_std: None,
+            max_vertical_std: None,
+        }
+    }
+}
+
+impl FixFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rejects fixes whose status is below `min_status`, e.g. 2 to keep only
+    /// ground based augmented (RTK) fixes. Defaults to `STATUS_FIX`.
+    pub fn with_min_status(mut self, min_status: i8) -> Self {
+        self.min_status = min_status;
+        self
+    }
+
+    /// Rejects fixes whose east or north standard deviation in metres is
+    /// above `max_std`. Fixes with an unknown covariance are kept.
+    pub fn with_max_horizontal_std(mut self, max_std: f64) -> Self {
+        self.max_horizontal_std = Some(max_std);
+        self
+    }
+
+    /// Rejects fixes whose up standard deviation in metres is above
+    /// `max_std`. Fixes with an unknown covariance are kept.
+    pub fn with_max_vertical_std(mut self, max_std: f64) -> Self {
+        self.max_vertical_std = Some(max_std);
+        self
+    }
+
+    pub fn accepts(&self, fix: &NavSatFix) -> bool {
+        if fix.status < self.min_status {
+            return false;
+        }
+        let covariance = fix.covariance();
+        match (
+            covariance.max_horizontal_std(),
+            covariance.vertical_accuracy(),
+        ) {
+            (Some(horizontal), Some(vertical)) => {
+                self.max_horizontal_std.is_none_or(|max| horizontal <= max)
+                    && self.max_vertical_std.is_none_or(|max| vertical <= max)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// One accepted fix with its position in the trajectory's local east, north,
+/// up frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrajectoryPoint {
+    pub timestamp: DateTime<Utc>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: f64,
+    pub enu: na::Vector3<f64>,
+    pub status: i8,
+}
+
+/// A GNSS track built from a `NavSatFix` stream, in a local ENU frame around
+/// an origin that is either given or taken from the first accepted fix.
+///
+/// Fixes must be pushed in timestamp order; fixes not after the last point
+/// are rejected along with those the `FixFilter` rejects.
+#[derive(Debug, Clone)]
+pub struct Trajectory {
+    origin: Option<(f64, f64, f64)>,
+    filter: FixFilter,
+    points: Vec<TrajectoryPoint>,
+    rejected: usize,
+}
+
+impl Default for Trajectory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Trajectory {
+    pub fn new() -> Self {
+        Self {
+            origin: None,
+            filter: FixFilter::default(),
+            points: Vec::new(),
+            rejected: 0,
+        }
+    }
+
+    /// Uses `latitude` and `longitude` in degrees and `altitude` in metres as
+    /// the ENU origin, e.g. to put several runs in one frame.
+    pub fn with_origin(mut self, latitude: f64, longitude: f64, altitude: f64) -> Self {
+        self.origin = Some((latitude, longitude, altitude));
+        self
+    }
+
+    pub fn with_filter(mut self, filter: FixFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Pushes every fix, see `push`.
+    pub fn with_fixes<I, F>(mut self, fixes: I) -> Self
+    where
+        I: IntoIterator<Item = F>,
+        F: Borrow<NavSatFix>,
+    {
+        for fix in fixes {
+            self.push(fix.borrow());
+        }
+        self
+    }
+
+    /// Adds `fix` if the filter accepts it, returning whether it was added.
+    pub fn push(&mut self, fix: &NavSatFix) -> bool {
+        let in_order = self
+            .points
+            .last()
+            .is_none_or(|last| fix.timestamp() > last.timestamp);
+        if !in_order || !self.filter.accepts(fix) {
+            self.rejected += 1;
+            return false;
+        }
+        let (latitude, longitude, altitude) =
+            *self
+                .origin
+                .get_or_insert((fix.latitude, fix.longitude, fix.altitude));
+        let origin = na::Vector3::new(latitude.to_radians(), longitude.to_radians(), altitude);
+        let ellipsoid = geo_ellipsoid::geo_ellipsoid::new(
+            geo_ellipsoid::WGS84_SEMI_MAJOR_AXIS_METERS,
+            geo_ellipsoid::WGS84_FLATTENING,
+        );
+        self.points.push(TrajectoryPoint {
+            timestamp: fix.timestamp(),
+            latitude: fix.latitude,
+            longitude: fix.longitude,
+            altitude: fix.altitude,
+            enu: fix.enu(&ellipsoid, &origin),
+            status: fix.status,
+        });
+        true
+    }
+
+    /// Latitude and longitude in degrees and altitude in metres of the ENU
+    /// origin, once known.
+    pub fn origin(&self) -> Option<(f64, f64, f64)> {
+        self.origin
+    }
+
+    pub fn points(&self) -> &[TrajectoryPoint] {
+        &self.points
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Number of fixes not added to the trajectory.
+    pub fn rejected(&self) -> usize {
+        self.rejected
+    }
+
+    pub fn start(&self) -> Option<DateTime<Utc>> {
+        self.points.first().map(|point| point.timestamp)
+    }
+
+    pub fn end(&self) -> Option<DateTime<Utc>> {
+        self.points.last().map(|point| point.timestamp)
+    }
+
+    /// Total distance travelled in the ENU plane, in metres.
+    pub fn length(&self) -> f64 {
+        self.points
+            .windows(2)
+            .map(|pair| (pair[1].enu - pair[0].enu).xy().norm())
+            .sum()
+    }
+
+    /// Index of the point at or before `timestamp` and the fraction of the
+    /// way to the next point, `None` outside the trajectory.
+    fn bracket(&self, timestamp: DateTime<Utc>) -> Option<(usize, f64)> {
+        let (first, last) = (self.points.first()?, self.points.last()?);
+        if timestamp < first.timestamp || timestamp > last.timestamp {
+            return None;
+        }
+        let after = self
+            .points
+            .partition_point(|point| point.timestamp <= timestamp);
+        let before = after - 1;
+        if after == self.points.len() {
+            return Some((before, 0.0));
+        }
+        let span = self.points[after].timestamp - self.points[before].timestamp;
+        let elapsed = timestamp - self.points[before].timestamp;
+        let fraction = elapsed.num_nanoseconds().unwrap_or(0) as f64
+            / span.num_nanoseconds().unwrap_or(1) as f64;
+        Some((before, fraction))
+    }
+
+    fn interpolate<T, F>(&self, timestamp: DateTime<Utc>, value: F) -> Option<T>
+    where
+        F: Fn(&TrajectoryPoint, Option<(&TrajectoryPoint, f64)>) -> T,
+    {
+        let (i, fraction) = self.bracket(timestamp)?;
+        let next = self.points.get(i + 1).map(|next| (next, fraction));
+        Some(value(&self.points[i], next))
+    }
+
+    /// ENU position at `timestamp`, linearly interpolated between fixes.
+    pub fn position_at(&self, timestamp: DateTime<Utc>) -> Option<na::Vector3<f64>> {
+        self.interpolate(timestamp, |point, next| match next {
+            Some((next, fraction)) => point.enu.lerp(&next.enu, fraction),
+            None => point.enu,
+        })
+    }
+
+    /// Latitude, longitude and altitude at `timestamp`, linearly
+    /// interpolated between fixes.
+    pub fn lla_at(&self, timestamp: DateTime<Utc>) -> Option<(f64, f64, f64)> {
+        self.interpolate(timestamp, |point, next| {
+            let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+            match next {
+                Some((next, t)) => (
+                    lerp(point.latitude, next.latitude, t),
+                    lerp(point.longitude, next.longitude, t),
+                    lerp(point.altitude, next.altitude, t),
+                ),
+                None => (point.latitude, point.longitude, point.altitude),
+            }
+        })
+    }
+
+    /// Heading in radians counter clockwise from east of the segment from
+    /// point `i`, or of the nearest earlier segment long enough to have one.
+    fn heading(&self, i: usize) -> f64 {
+        let segment = |j: usize| {
+            let delta = self.points.get(j + 1)?.enu - self.points[j].enu;
+            if delta.xy().norm() >= MIN_HEADING_DISTANCE {
+                Some(delta.y.atan2(delta.x))
+            } else {
+                None
+            }
+        };
+        (0..=i.min(self.points.len().saturating_sub(2)))
+            .rev()
+            .find_map(segment)
+            .or_else(|| (i..self.points.len()).find_map(segment))
+            .unwrap_or(0.0)
+    }
+
+    /// ENU pose at `timestamp` with the heading taken from the direction of
+    /// travel, for joining positions onto lidar or camera frames.
+    pub fn pose_at(&self, timestamp: DateTime<Utc>) -> Option<Pose2D> {
+        let (i, _) = self.bracket(timestamp)?;
+        let position = self.position_at(timestamp)?;
+        Some(Pose2D {
+            timestamp,
+            x: position.x,
+            y: position.y,
+            yaw: self.heading(i),
+        })
+    }
+
+    /// A GeoJSON `FeatureCollection` holding the track as one `LineString`.
+    pub fn to_geojson(&self, name: &str) -> String {
+        let coordinates: Vec<String> = self
+            .points
+            .iter()
+            .map(|p| format!("[{:.9},{:.9},{:.3}]", p.longitude, p.latitude, p.altitude))
+            .collect();
+        let times: Vec<String> = self
+            .points
+            .iter()
+            .map(|p| format!("\"{}\"", format_time(p.timestamp)))
+            .collect();
+        format!(
+            concat!(
+                "{{\"type\":\"FeatureCollection\",\"features\":[{{\"type\":\"Feature\",",
+                "\"properties\":{{\"name\":\"{}\",\"times\":[{}]}},",
+                "\"geometry\":{{\"type\":\"LineString\",\"coordinates\":[{}]}}}}]}}"
+            ),
+            escape_json(name),
+            times.join(","),
+            coordinates.join(",")
+        )
+    }
+
+    /// A GPX 1.1 document holding the track as one segment.
+    pub fn to_gpx(&self, name: &str) -> String {
+        let mut gpx = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<gpx version=\"1.1\" creator=\"sdcar_types\" ",
+            "xmlns=\"http://www.topografix.com/GPX/1/1\">\n"
+        ));
+        let _ = writeln!(gpx, "<trk><name>{}</name><trkseg>", escape_xml(name));
+        for p in self.points.iter() {
+            let _ = writeln!(
+                gpx,
+                "<trkpt lat=\"{:.9}\" lon=\"{:.9}\"><ele>{:.3}</ele><time>{}</time></trkpt>",
+                p.latitude,
+                p.longitude,
+                p.altitude,
+                format_time(p.timestamp)
+            );
+        }
+        gpx.push_str("</trkseg></trk>\n</gpx>\n");
+        gpx
+    }
+
+    /// A KML document holding the track as one `LineString` placemark.
+    pub fn to_kml(&self, name: &str) -> String {
+        let coordinates: Vec<String> = self
+            .points
+            .iter()
+            .map(|p| format!("{:.9},{:.9},{:.3}", p.longitude, p.latitude, p.altitude))
+            .collect();
+        format!(
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<kml xmlns=\"http://www.opengis.net/kml/2.2\"><Document>",
+                "<Placemark><name>{}</name><LineString>",
+                "<altitudeMode>absolute</altitudeMode>",
+                "<coordinates>{}</coordinates>",
+                "</LineString></Placemark></Document></kml>\n"
+            ),
+            escape_xml(name),
+            coordinates.join(" ")
+        )
+    }
+}
+
+fn format_time(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
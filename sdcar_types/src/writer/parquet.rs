@@ -0,0 +1,204 @@
+extern crate parquet;
+
+use crate::msg::camera_info::CameraInfo;
+use crate::msg::image::Image;
+use crate::msg::imu::Imu;
+use crate::msg::nav_sat_fix::NavSatFix;
+use crate::msg::odometry::Odometry;
+use crate::msg::point_cloud2::PointCloud2;
+use crate::msg::twist_stamped::TwistStamped;
+use crate::msg::vehicle_state::VehicleState;
+use crate::msg::MsgKind;
+use crate::parquet::ToRecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::errors::{ParquetError, Result};
+use parquet::file::properties::{WriterProperties, WriterPropertiesBuilder};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Number of messages buffered before they are encoded into a record batch.
+pub const DEFAULT_BATCH_SIZE: usize = 64;
+
+/// Writes messages of one type to a parquet file using the same schema the
+/// readers in `crate::reader::parquet` decode. `close` must be called to
+/// write the file footer.
+pub struct ParquetFileWriter<M: ToRecordBatch> {
+    path: PathBuf,
+    writer: ArrowWriter<File>,
+    buffer: Vec<Arc<M>>,
+    batch_size: usize,
+    num_rows: usize,
+}
+
+impl<M: ToRecordBatch> ParquetFileWriter<M> {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::with_props(path, WriterProperties::builder())
+    }
+
+    /// Like `new` but starts a new row group every `row_group_size` rows.
+    /// Smaller row groups let readers `seek` with a finer granularity.
+    pub fn new_with_row_group_size<P: AsRef<Path>>(path: P, row_group_size: usize) -> Result<Self> {
+        Self::with_props(
+            path,
+            WriterProperties::builder().set_max_row_group_size(row_group_size.max(1)),
+        )
+    }
+
+    fn with_props<P: AsRef<Path>>(path: P, props: WriterPropertiesBuilder) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::create(&path)?;
+        // dictionary encoding gains little on unique stamps and data blobs
+        let props = props.set_dictionary_enabled(false).build();
+        let writer = ArrowWriter::try_new(file, M::arrow_schema(), Some(props))?;
+        Ok(Self {
+            path,
+            writer,
+            buffer: Vec::with_capacity(DEFAULT_BATCH_SIZE),
+            batch_size: DEFAULT_BATCH_SIZE,
+            num_rows: 0,
+        })
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of messages written so far, including those still buffered.
+    pub fn num_rows(&self) -> usize {
+        self.num_rows + self.buffer.len()
+    }
+
+    pub fn write(&mut self, msg: Arc<M>) -> Result<()> {
+        self.buffer.push(msg);
+        if self.buffer.len() >= self.batch_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn write_all<I: IntoIterator<Item = Arc<M>>>(&mut self, msgs: I) -> Result<()> {
+        for msg in msgs {
+            self.write(msg)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let batch = M::to_record_batch(&self.buffer)?;
+        self.writer.write(&batch)?;
+        self.num_rows += self.buffer.len();
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Flushes any buffered messages, writes the footer and returns the
+    /// number of rows in the file.
+    pub fn close(mut self) -> Result<usize> {
+        self.flush()?;
+        self.writer.close()?;
+        Ok(self.num_rows)
+    }
+}
+
+pub type ImageParquetWriter = ParquetFileWriter<Image>;
+pub type NavSatFixParquetWriter = ParquetFileWriter<NavSatFix>;
+pub type PointCloud2ParquetWriter = ParquetFileWriter<PointCloud2>;
+pub type VehicleStateParquetWriter = ParquetFileWriter<VehicleState>;
+pub type ImuParquetWriter = ParquetFileWriter<Imu>;
+pub type CameraInfoParquetWriter = ParquetFileWriter<CameraInfo>;
+pub type TwistStampedParquetWriter = ParquetFileWriter<TwistStamped>;
+pub type OdometryParquetWriter = ParquetFileWriter<Odometry>;
+
+/// Writes `MsgKind` values to a parquet file. The message type of the file is
+/// fixed by the kind passed to `new`; writing any other kind is an error.
+pub enum MsgKindParquetWriter {
+    NavSatFix(NavSatFixParquetWriter),
+    VehicleState(VehicleStateParquetWriter),
+    PointCloud2(PointCloud2ParquetWriter),
+    Image(ImageParquetWriter),
+    Imu(ImuParquetWriter),
+    CameraInfo(CameraInfoParquetWriter),
+    TwistStamped(TwistStampedParquetWriter),
+    Odometry(OdometryParquetWriter),
+}
+
+impl MsgKindParquetWriter {
+    pub fn new<P: AsRef<Path>>(path: P, msg_kind: &MsgKind) -> Result<Self> {
+        Ok(match msg_kind {
+            MsgKind::NavSatFix(_) => Self::NavSatFix(ParquetFileWriter::new(path)?),
+            MsgKind::VehicleState(_) => Self::VehicleState(ParquetFileWriter::new(path)?),
+            MsgKind::PointCloud2(_) => Self::PointCloud2(ParquetFileWriter::new(path)?),
+            MsgKind::Image(_) => Self::Image(ParquetFileWriter::new(path)?),
+            MsgKind::Imu(_) => Self::Imu(ParquetFileWriter::new(path)?),
+            MsgKind::CameraInfo(_) => Self::CameraInfo(ParquetFileWriter::new(path)?),
+            MsgKind::TwistStamped(_) => Self::TwistStamped(ParquetFileWriter::new(path)?),
+            MsgKind::Odometry(_) => Self::Odometry(ParquetFileWriter::new(path)?),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::NavSatFix(writer) => writer.path(),
+            Self::VehicleState(writer) => writer.path(),
+            Self::PointCloud2(writer) => writer.path(),
+            Self::Image(writer) => writer.path(),
+            Self::Imu(writer) => writer.path(),
+            Self::CameraInfo(writer) => writer.path(),
+            Self::TwistStamped(writer) => writer.path(),
+            Self::Odometry(writer) => writer.path(),
+        }
+    }
+
+    pub fn write(&mut self, msg_kind: MsgKind) -> Result<()> {
+        match (self, msg_kind) {
+            (Self::NavSatFix(writer), MsgKind::NavSatFix(msg)) => writer.write(msg),
+            (Self::VehicleState(writer), MsgKind::VehicleState(msg)) => writer.write(msg),
+            (Self::PointCloud2(writer), MsgKind::PointCloud2(msg)) => writer.write(msg),
+            (Self::Image(writer), MsgKind::Image(msg)) => writer.write(msg),
+            (Self::Imu(writer), MsgKind::Imu(msg)) => writer.write(msg),
+            (Self::CameraInfo(writer), MsgKind::CameraInfo(msg)) => writer.write(msg),
+            (Self::TwistStamped(writer), MsgKind::TwistStamped(msg)) => writer.write(msg),
+            (Self::Odometry(writer), MsgKind::Odometry(msg)) => writer.write(msg),
+            (writer, msg_kind) => Err(ParquetError::General(format!(
+                "cannot write {} message to {}",
+                kind_name(&msg_kind),
+                writer.path().display()
+            ))),
+        }
+    }
+
+    pub fn close(self) -> Result<usize> {
+        match self {
+            Self::NavSatFix(writer) => writer.close(),
+            Self::VehicleState(writer) => writer.close(),
+            Self::PointCloud2(writer) => writer.close(),
+            Self::Image(writer) => writer.close(),
+            Self::Imu(writer) => writer.close(),
+            Self::CameraInfo(writer) => writer.close(),
+            Self::TwistStamped(writer) => writer.close(),
+            Self::Odometry(writer) => writer.close(),
+        }
+    }
+}
+
+fn kind_name(msg_kind: &MsgKind) -> &'static str {
+    match msg_kind {
+        MsgKind::NavSatFix(_) => "NavSatFix",
+        MsgKind::VehicleState(_) => "VehicleState",
+        MsgKind::PointCloud2(_) => "PointCloud2",
+        MsgKind::Image(_) => "Image",
+        MsgKind::Imu(_) => "Imu",
+        MsgKind::CameraInfo(_) => "CameraInfo",
+        MsgKind::TwistStamped(_) => "TwistStamped",
+        MsgKind::Odometry(_) => "Odometry",
+    }
+}
@@ -0,0 +1,212 @@
+#![allow(dead_code)]
+
+use arrow::array::{Array, ArrayRef, Int64Array, StructArray};
+use arrow::datatypes::{DataType, Field};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use sdcar_types::msg::image::Image;
+use sdcar_types::msg::nav_sat_fix::NavSatFix;
+use sdcar_types::msg::point_cloud2::PointCloud2;
+use sdcar_types::parquet::ToRecordBatch;
+use sdcar_types::writer::parquet::*;
+use std::fs::File;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A parquet file path under the system temp dir. The file is removed when
+/// the path is dropped, so a failing test does not leave it behind.
+pub struct TempPath(PathBuf);
+
+impl TempPath {
+    pub fn new(name: &str) -> Self {
+        Self(std::env::temp_dir().join(format!(
+            "sdcar_types_{}_{}.parquet",
+            name,
+            std::process::id()
+        )))
+    }
+}
+
+impl Deref for TempPath {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempPath {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// An empty directory under the system temp dir, removed with its contents
+/// when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("sdcar_types_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+pub fn sec(ms: i64) -> i32 {
+    (ms / 1000) as i32
+}
+
+pub fn nanosec(ms: i64) -> u32 {
+    (ms % 1000 * 1_000_000) as u32
+}
+
+/// Empty lidar sweeps stamped at `stamps_ms`.
+pub fn write_point_clouds<P: AsRef<Path>>(path: P, stamps_ms: &[i64]) {
+    let mut writer = PointCloud2ParquetWriter::new(path).unwrap();
+    for ms in stamps_ms.iter() {
+        writer
+            .write(Arc::new(PointCloud2::new(
+                sec(*ms),
+                nanosec(*ms),
+                "lidar".to_string(),
+                1,
+                0,
+                Vec::new(),
+                false,
+                32,
+                0,
+                Vec::new(),
+                true,
+            )))
+            .unwrap();
+    }
+    writer.close().unwrap();
+}
+
+/// Images of `frame_id` stamped at `stamps_ms`, the i-th with the image_oid
+/// `{frame_id}{i}`.
+pub fn write_images<P: AsRef<Path>>(path: P, frame_id: &str, stamps_ms: &[i64]) {
+    let mut writer = ImageParquetWriter::new(path).unwrap();
+    for (i, ms) in stamps_ms.iter().enumerate() {
+        writer
+            .write(Arc::new(Image::new(
+                sec(*ms),
+                nanosec(*ms),
+                frame_id.to_string(),
+                1536,
+                2048,
+                "bgr8".to_string(),
+                false,
+                6144,
+                format!("{}{}", frame_id, i),
+            )))
+            .unwrap();
+    }
+    writer.close().unwrap();
+}
+
+/// Fixes at the same spot stamped at `stamps_ms`.
+pub fn write_nav_sat_fixes<P: AsRef<Path>>(path: P, stamps_ms: &[i64]) {
+    let mut writer = NavSatFixParquetWriter::new(path).unwrap();
+    for ms in stamps_ms.iter() {
+        writer
+            .write(Arc::new(NavSatFix::new(
+                sec(*ms),
+                nanosec(*ms),
+                "gps".to_string(),
+                0,
+                1,
+                -27.55,
+                153.05,
+                45.5,
+                [0.0; 9],
+                0,
+            )))
+            .unwrap();
+    }
+    writer.close().unwrap();
+}
+
+fn struct_fields(array: &StructArray) -> Vec<Field> {
+    match array.data_type() {
+        DataType::Struct(fields) => fields.clone(),
+        _ => unreachable!(),
+    }
+}
+
+/// Fixes stamped one second apart from the epoch, the i-th with the raw
+/// int64 `header.stamp.nanosec` of `nanosecs[i]`, which need not fit the
+/// message's uint32.
+pub fn write_nav_sat_fixes_with_nanosecs<P: AsRef<Path>>(path: P, nanosecs: &[i64]) {
+    let fixes: Vec<Arc<NavSatFix>> = (0..nanosecs.len())
+        .map(|i| {
+            Arc::new(NavSatFix::new(
+                i as i32,
+                0,
+                "gps".to_string(),
+                0,
+                1,
+                -27.55,
+                153.05,
+                45.5,
+                [0.0; 9],
+                0,
+            ))
+        })
+        .collect();
+    let batch = NavSatFix::to_record_batch(&fixes).unwrap();
+    let mut columns: Vec<ArrayRef> = batch.columns().to_vec();
+    let header = columns[1].as_any().downcast_ref::<StructArray>().unwrap();
+    let stamp = header.column(0).as_any().downcast_ref::<StructArray>().unwrap();
+    let stamp_fields = struct_fields(stamp);
+    let stamp = StructArray::from(vec![
+        (stamp_fields[0].clone(), stamp.column(0).clone()),
+        (
+            stamp_fields[1].clone(),
+            Arc::new(Int64Array::from(nanosecs.to_vec())) as ArrayRef,
+        ),
+    ]);
+    let header_fields = struct_fields(header);
+    columns[1] = Arc::new(StructArray::from(vec![
+        (header_fields[0].clone(), Arc::new(stamp) as ArrayRef),
+        (header_fields[1].clone(), header.column(1).clone()),
+    ]));
+    let batch = RecordBatch::try_new(batch.schema(), columns).unwrap();
+    let props = WriterProperties::builder()
+        .set_dictionary_enabled(false)
+        .build();
+    let mut writer =
+        ArrowWriter::try_new(File::create(path).unwrap(), batch.schema(), Some(props)).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
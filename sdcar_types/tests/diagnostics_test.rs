@@ -0,0 +1,137 @@
+extern crate chrono;
+extern crate sdcar_types;
+
+mod common;
+
+use common::*;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use sdcar_types::diagnostics::*;
+use sdcar_types::reader::merged::*;
+use sdcar_types::reader::parquet::*;
+use sdcar_types::reader::sync::*;
+use std::path::Path;
+
+fn stamp(ms: i64) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(1634357915000 + ms).unwrap()
+}
+
+// a 10 Hz stream missing the messages at `missing` (multiples of 100 ms)
+fn stamps_10hz(count: i64, offset_ms: i64, missing: &[i64]) -> Vec<DateTime<Utc>> {
+    (0..count)
+        .map(|i| i * 100)
+        .filter(|ms| !missing.contains(ms))
+        .map(|ms| stamp(ms + offset_ms))
+        .collect()
+}
+
+fn millis(stamps: &[DateTime<Utc>]) -> Vec<i64> {
+    stamps.iter().map(|stamp| stamp.timestamp_millis()).collect()
+}
+
+#[test]
+fn rate_and_gap_test() {
+    let times: StreamTimes = stamps_10hz(50, 0, &[1000, 1100, 3000]).into_iter().collect();
+    let report = times.report(&GapThresholds::default());
+    assert_eq!(report.messages, 47);
+    assert_eq!(report.start, Some(stamp(0)));
+    assert_eq!(report.end, Some(stamp(4900)));
+    assert_eq!(report.duration(), Duration::milliseconds(4900));
+
+    let rate = report.rate.unwrap();
+    assert_eq!(rate.period, Duration::milliseconds(100));
+    assert!((rate.rate_hz - 10.0).abs() < 1e-9);
+    assert_eq!(rate.jitter, Duration::zero());
+
+    assert_eq!(report.gaps.len(), 2);
+    assert_eq!(report.gaps[0].start, stamp(900));
+    assert_eq!(report.gaps[0].end, stamp(1200));
+    assert_eq!(report.gaps[0].duration(), Duration::milliseconds(300));
+    assert_eq!(report.gaps[0].missing, 2);
+    assert_eq!(report.gaps[1].missing, 1);
+    assert_eq!(report.dropped(), 3);
+    assert_eq!(
+        report.max_interval,
+        Some((Duration::milliseconds(300), stamp(900)))
+    );
+    assert_eq!(report.out_of_order, 0);
+
+    // only intervals over half a second count
+    let report = times.report(&GapThresholds::new().with_factor(5.0));
+    assert!(report.gaps.is_empty());
+    let report = times.report(
+        &GapThresholds::new()
+            .with_factor(f64::INFINITY)
+            .with_max_interval(Duration::milliseconds(250)),
+    );
+    assert_eq!(report.gaps.len(), 1);
+    assert_eq!(report.gaps[0].index, 10);
+}
+
+#[test]
+fn jitter_and_order_test() {
+    let mut times = StreamTimes::new();
+    times.extend([0, 100, 190, 300, 400, 380, 500].into_iter().map(stamp));
+    let report = times.report(&GapThresholds::default());
+    assert_eq!(report.out_of_order, 1);
+    let rate = report.rate.unwrap();
+    assert_eq!(rate.period, Duration::milliseconds(100));
+    assert!(rate.jitter > Duration::milliseconds(5));
+    assert!(rate.jitter < Duration::milliseconds(15));
+    assert!(report.gaps.is_empty());
+
+    let report = StreamTimes::new().report(&GapThresholds::default());
+    assert_eq!(report.messages, 0);
+    assert!(report.rate.is_none());
+    assert!(report.start.is_none());
+}
+
+#[test]
+fn offset_test() {
+    let lidar = stamps_10hz(100, 0, &[2000]);
+    let camera = stamps_10hz(100, 30, &[500, 4100]);
+    let estimate = estimate_offset(&lidar, &camera, Duration::milliseconds(50)).unwrap();
+    assert_eq!(estimate.offset, Duration::milliseconds(30));
+    assert_eq!(estimate.spread, Duration::zero());
+    // the camera frame at 2030 has no lidar sweep within 50 ms
+    assert_eq!(estimate.pairs, 97);
+
+    let estimate = estimate_offset(&camera, &lidar, Duration::milliseconds(50)).unwrap();
+    assert_eq!(estimate.offset, Duration::milliseconds(-30));
+    assert!(estimate_offset(&lidar, &camera, Duration::milliseconds(10)).is_none());
+}
+
+fn merged(lidar_path: &Path, camera_path: &Path) -> MergedReader {
+    MergedReader::new()
+        .with_source("lidar", PointCloud2ParquetRowIter::new(lidar_path))
+        .with_source("camera", ImageParquetRowIter::new(camera_path))
+}
+
+#[test]
+fn diagnostics_report_test() {
+    let lidar_path = TempPath::new("diagnostics_lidar");
+    let camera_path = TempPath::new("diagnostics_camera");
+    write_point_clouds(&lidar_path, &millis(&stamps_10hz(30, 0, &[])));
+    write_images(&camera_path, "left_camera", &millis(&stamps_10hz(30, 40, &[1200])));
+
+    let diagnostics = Diagnostics::from_reader(merged(&lidar_path, &camera_path));
+    assert_eq!(diagnostics.num_streams(), 2);
+    let report = diagnostics.report(0);
+    assert_eq!(report.names, vec!["lidar", "camera"]);
+    assert_eq!(report.stream("lidar").unwrap().dropped(), 0);
+    assert_eq!(report.stream("camera").unwrap().dropped(), 1);
+    assert_eq!(report.offsets.len(), 1);
+    let estimate = report.offsets[0].estimate.unwrap();
+    assert_eq!(estimate.offset, Duration::milliseconds(40));
+
+    let slop = report.suggested_slop(0).unwrap();
+    assert_eq!(slop, Duration::milliseconds(90));
+    assert!(report.suggested_slop(1).is_none());
+
+    // every sweep but the one whose frame was dropped gets a set
+    let mut sync = ApproximateTimeSync::new(merged(&lidar_path, &camera_path), slop);
+    let sets: Vec<SyncedSet> = sync.by_ref().collect();
+    assert_eq!(sets.len(), 29);
+    assert!(sets.iter().all(|set| set.spread() <= slop));
+    assert_eq!(sync.dropped_pivots(), 1);
+}
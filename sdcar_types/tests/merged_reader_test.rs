@@ -0,0 +1,70 @@
+extern crate sdcar_types;
+
+mod common;
+
+use common::*;
+
+use sdcar_types::msg::MsgKind;
+use sdcar_types::reader::merged::*;
+use sdcar_types::reader::parquet::*;
+
+#[test]
+fn merged_reader_test() {
+    let left_path = TempPath::new("merged_left");
+    let right_path = TempPath::new("merged_right");
+    let gps_path = TempPath::new("merged_gps");
+    write_images(&left_path, "left", &[1000, 1100, 1200, 1300]);
+    write_images(&right_path, "right", &[1050, 1100, 1250]);
+    write_nav_sat_fixes(&gps_path, &[900, 1200, 1400]);
+
+    let reader = MergedReader::new()
+        .with_source("left_image", ImageParquetRowIter::new(&left_path))
+        .with_source("right_image", ImageParquetRowIter::new(&right_path))
+        .with_source("nav_sat_fix", NavSatFixParquetRowIter::new(&gps_path));
+    assert_eq!(reader.num_sources(), 3);
+    assert_eq!(reader.source_name(2), "nav_sat_fix");
+
+    let merged: Vec<MergedMsg> = reader.collect();
+    let order: Vec<(usize, i64)> = merged
+        .iter()
+        .map(|msg| (msg.source, msg.timestamp().timestamp_millis()))
+        .collect();
+    assert_eq!(
+        order,
+        vec![
+            (2, 900),
+            (0, 1000),
+            (1, 1050),
+            (0, 1100),
+            (1, 1100),
+            (0, 1200),
+            (2, 1200),
+            (1, 1250),
+            (0, 1300),
+            (2, 1400),
+        ]
+    );
+    for msg in merged.iter() {
+        match (&msg.msg_kind, msg.source) {
+            (MsgKind::Image(image), 0) => assert_eq!(image.header.frame_id, "left"),
+            (MsgKind::Image(image), 1) => assert_eq!(image.header.frame_id, "right"),
+            (MsgKind::NavSatFix(_), 2) => {}
+            (_, source) => panic!("unexpected message from source {}", source),
+        }
+    }
+}
+
+#[test]
+fn merged_reader_empty_source_test() {
+    let empty_path = TempPath::new("merged_empty");
+    let gps_path = TempPath::new("merged_empty_gps");
+    write_images(&empty_path, "left", &[]);
+    write_nav_sat_fixes(&gps_path, &[100, 200]);
+
+    let mut reader = MergedReader::new();
+    reader.add_source("left_image", ImageParquetRowIter::new(&empty_path));
+    let gps = reader.add_source("nav_sat_fix", NavSatFixParquetRowIter::new(&gps_path));
+    assert_eq!(reader.peek_timestamp().unwrap().timestamp_millis(), 100);
+    let sources: Vec<usize> = reader.map(|msg| msg.source).collect();
+    assert_eq!(sources, vec![gps, gps]);
+}
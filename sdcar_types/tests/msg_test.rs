@@ -0,0 +1,220 @@
+extern crate chrono;
+extern crate nalgebra;
+extern crate sdcar_types;
+
+use chrono::{DateTime, TimeZone, Utc};
+use nalgebra::Matrix3;
+use sdcar_types::msg::header::{Header, Stamp};
+use sdcar_types::msg::image::Image;
+use sdcar_types::msg::nav_sat_fix::{NavSatFix, PositionCovariance};
+use sdcar_types::msg::point_cloud2::{PointCloud2, PointField, PointFieldType};
+use sdcar_types::msg::Msg;
+use sdcar_types::Error;
+
+#[test]
+fn stamp_test() {
+    let stamp = Stamp::new(1634357915, 123_456_789);
+    assert_eq!(stamp.sec(), 1634357915);
+    assert_eq!(stamp.nanosec(), 123_456_789);
+    assert_eq!(stamp.as_nanos(), 1_634_357_915_123_456_789);
+    assert_eq!(Stamp::from_nanos(stamp.as_nanos()), Some(stamp));
+    assert_eq!(stamp.to_string(), "1634357915.123456789");
+
+    let timestamp = Utc.timestamp_opt(1634357915, 123_456_789).unwrap();
+    assert_eq!(stamp.to_datetime(), timestamp);
+    assert_eq!(DateTime::<Utc>::from(stamp), timestamp);
+    assert_eq!(Stamp::from_datetime(timestamp), Some(stamp));
+    assert!(Stamp::from_datetime(Utc.timestamp_opt(1 << 40, 0).unwrap()).is_none());
+
+    // nanoseconds carry into seconds, also before the epoch
+    assert_eq!(Stamp::new(1, 1_500_000_000), Stamp::new(2, 500_000_000));
+    let before_epoch = Stamp::from_nanos(-1).unwrap();
+    assert_eq!((before_epoch.sec(), before_epoch.nanosec()), (-1, 999_999_999));
+    assert!(Stamp::from_nanos(i64::MAX).is_none());
+    // and saturate at the latest stamp rather than overflow
+    let latest = Stamp::new(i32::MAX, 999_999_999);
+    assert_eq!(Stamp::new(i32::MAX, 1_000_000_000), latest);
+    assert_eq!(Stamp::new(i32::MAX - 1, u32::MAX), latest);
+    assert_eq!(Stamp::new(i32::MAX - 4, u32::MAX), Stamp::new(i32::MAX, 294_967_295));
+
+    let mut stamps = vec![
+        Stamp::new(2, 0),
+        Stamp::new(1, 999_999_999),
+        before_epoch,
+        Stamp::new(1, 1),
+    ];
+    stamps.sort();
+    assert_eq!(
+        stamps,
+        vec![before_epoch, Stamp::new(1, 1), Stamp::new(1, 999_999_999), Stamp::new(2, 0)]
+    );
+}
+
+#[test]
+fn header_test() {
+    let image = Image::new(
+        1634357915,
+        999_999_999,
+        "left".to_string(),
+        1536,
+        2048,
+        "bgr8".to_string(),
+        false,
+        6144,
+        "oid".to_string(),
+    );
+    let header = Header::new(Stamp::new(1634357915, 999_999_999), "left".to_string());
+    assert_eq!(image.header(), &header);
+    assert_eq!(image.header.frame_id(), "left");
+    assert_eq!(image.timestamp(), header.timestamp());
+    assert_eq!(image.msg_kind().header(), &header);
+}
+
+#[test]
+fn position_covariance_test() {
+    let raw = [9.0, 0.5, 0.0, 0.5, 16.0, 0.0, 0.0, 0.0, 4.0];
+    let fix = NavSatFix::new(
+        1595047644, 0, "gps".to_string(), 0, 1, -27.55, 153.05, 45.5, raw, 3,
+    );
+    let covariance = fix.covariance();
+    assert_eq!(covariance.covariance_type(), 3);
+    let matrix = covariance.matrix().unwrap();
+    assert_eq!(matrix[(0, 1)], 0.5);
+    assert_eq!(matrix[(1, 1)], 16.0);
+    assert_eq!(covariance.horizontal_accuracy(), Some(5.0));
+    assert_eq!(covariance.vertical_accuracy(), Some(2.0));
+
+    let diagonal = PositionCovariance::from_raw(raw, 2);
+    assert_eq!(diagonal, PositionCovariance::DiagonalKnown(Matrix3::from_row_slice(&raw)));
+    assert!(matches!(PositionCovariance::from_raw(raw, 1), PositionCovariance::Approximated(_)));
+
+    for covariance_type in [0, 7] {
+        let unknown = PositionCovariance::from_raw(raw, covariance_type);
+        assert_eq!(unknown, PositionCovariance::Unknown);
+        assert_eq!(unknown.covariance_type(), 0);
+        assert!(unknown.horizontal_accuracy().is_none());
+        assert!(unknown.vertical_accuracy().is_none());
+    }
+
+    // the message keeps a type it does not know, and its values
+    let fix = NavSatFix::new(
+        1595047644, 0, "gps".to_string(), 0, 1, -27.55, 153.05, 45.5, raw, 7,
+    );
+    assert_eq!(fix.covariance(), PositionCovariance::Unknown);
+    assert_eq!((fix.position_covariance, fix.position_covariance_type), (raw, 7));
+}
+
+// Ouster style points: x y z float32, 4 pad bytes, intensity float32,
+// t uint32, reflectivity uint16, ring uint8, then a float64 pair
+fn ouster_cloud(big_endian: bool, height: u32, width: u32, row_pad: usize) -> PointCloud2 {
+    let fields = vec![
+        PointField::new("x".to_string(), 0, PointField::FLOAT32, 1),
+        PointField::new("y".to_string(), 4, PointField::FLOAT32, 1),
+        PointField::new("z".to_string(), 8, PointField::FLOAT32, 1),
+        PointField::new("intensity".to_string(), 16, PointField::FLOAT32, 1),
+        PointField::new("t".to_string(), 20, PointField::UINT32, 1),
+        PointField::new("reflectivity".to_string(), 24, PointField::UINT16, 1),
+        PointField::new("ring".to_string(), 26, PointField::UINT8, 1),
+        PointField::new("offset".to_string(), 27, PointField::INT8, 1),
+        PointField::new("pair".to_string(), 32, PointField::FLOAT64, 2),
+    ];
+    let point_step = 48;
+    let row_step = width * point_step + row_pad as u32;
+    let mut data = Vec::new();
+    for row in 0..height {
+        for col in 0..width {
+            let index = (row * width + col) as f32;
+            let mut point = Vec::new();
+            macro_rules! push {
+                ($value:expr) => {
+                    if big_endian {
+                        point.extend_from_slice(&$value.to_be_bytes())
+                    } else {
+                        point.extend_from_slice(&$value.to_le_bytes())
+                    }
+                };
+            }
+            push!(index);
+            push!(-index);
+            push!(0.5f32 * index);
+            push!(0u32);
+            push!(100.0f32 + index);
+            push!(1_000u32 * index as u32);
+            push!(300u16 + index as u16);
+            point.push(row as u8);
+            point.push((-(index as i8)) as u8);
+            point.extend_from_slice(&[0; 4]);
+            push!(index as f64 * 0.25);
+            push!(-1.5f64);
+            assert_eq!(point.len(), point_step as usize);
+            data.extend_from_slice(&point);
+        }
+        data.extend(std::iter::repeat_n(0xff, row_pad));
+    }
+    PointCloud2::new(
+        1635404341, 0, "os_lidar".to_string(), height, width, fields, big_endian, point_step,
+        row_step, data, true,
+    )
+}
+
+#[test]
+fn point_cloud2_field_reader_test() {
+    for big_endian in [false, true] {
+        let cloud = ouster_cloud(big_endian, 2, 3, 8);
+        assert_eq!(cloud.num_points(), 6);
+        assert_eq!(cloud.read_field::<f32>("x").unwrap(), vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(cloud.read_field::<f32>("y").unwrap()[4], -4.0);
+        assert_eq!(cloud.read_field::<f32>("intensity").unwrap()[5], 105.0);
+        assert_eq!(cloud.read_field::<u32>("t").unwrap()[3], 3_000);
+        assert_eq!(cloud.read_field::<u16>("reflectivity").unwrap()[2], 302);
+        assert_eq!(cloud.read_field::<u8>("ring").unwrap(), vec![0, 0, 0, 1, 1, 1]);
+        assert_eq!(cloud.read_field::<i8>("offset").unwrap()[5], -5);
+        // any datatype converts to the requested type
+        assert_eq!(cloud.read_field::<f64>("t").unwrap()[1], 1_000.0);
+        assert_eq!(cloud.read_field::<f32>("ring").unwrap()[4], 1.0);
+
+        let pair = cloud.field_reader("pair").unwrap();
+        assert_eq!(pair.field_type(), PointFieldType::Float64);
+        assert_eq!((pair.count(), pair.len()), (2, 6));
+        assert_eq!(pair.get::<f64>(2, 0), 0.5);
+        assert_eq!(pair.get_f64(2, 1), -1.5);
+        assert_eq!(pair.values::<f64>().len(), 12);
+        assert_eq!(pair.column::<f64>(), cloud.read_field::<f64>("pair").unwrap());
+    }
+    assert_eq!(PointFieldType::from_datatype(4), Some(PointFieldType::UInt16));
+    assert_eq!(PointFieldType::UInt16.size(), 2);
+    assert_eq!(PointFieldType::Float64.datatype(), PointField::FLOAT64);
+    assert!(PointFieldType::from_datatype(9).is_none());
+}
+
+#[test]
+fn point_cloud2_field_reader_error_test() {
+    let cloud = ouster_cloud(false, 2, 3, 0);
+    match cloud.field_reader("time") {
+        Err(Error::Decode { field, reason, .. }) => {
+            assert_eq!(field, "time");
+            assert!(reason.contains("x, y, z, intensity, t"), "{}", reason);
+        }
+        other => panic!("expected a missing field error, got {:?}", other.map(|_| ())),
+    }
+
+    let mut bad_type = cloud.clone();
+    bad_type.fields[0].datatype = 9;
+    assert!(matches!(bad_type.field_reader("x"), Err(Error::Decode { .. })));
+    assert!(bad_type.field_reader("y").is_ok());
+
+    let mut overrun = cloud.clone();
+    overrun.fields[8].count = 3;
+    assert!(overrun.field_reader("pair").is_err());
+
+    let mut truncated = cloud.clone();
+    truncated.data.truncate(truncated.data.len() - 1);
+    match truncated.field_reader("x") {
+        Err(Error::Decode { field, .. }) => assert_eq!(field, "data"),
+        other => panic!("expected a data length error, got {:?}", other.map(|_| ())),
+    }
+
+    let mut short_rows = cloud;
+    short_rows.row_step = 48;
+    assert!(short_rows.field_reader("x").is_err());
+}
@@ -0,0 +1,425 @@
+extern crate arrow;
+extern crate chrono;
+extern crate nalgebra as na;
+extern crate parquet;
+extern crate sdcar_types;
+
+mod common;
+
+use common::*;
+
+use arrow::array::{Array, ArrayRef, Int32Array, ListArray, StringArray, StructArray};
+use arrow::datatypes::{DataType, Float64Type};
+use arrow::record_batch::RecordBatch;
+use chrono::{TimeZone, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use sdcar_types::msg::camera_info::{CameraInfo, RegionOfInterest};
+use sdcar_types::msg::geometry::{Pose, PoseWithCovariance, Twist, TwistWithCovariance};
+use sdcar_types::msg::header::{Header, Stamp};
+use sdcar_types::msg::image::Image;
+use sdcar_types::msg::imu::Imu;
+use sdcar_types::msg::nav_sat_fix::NavSatFix;
+use sdcar_types::msg::odometry::Odometry;
+use sdcar_types::msg::point_cloud2::{PointCloud2, PointField};
+use sdcar_types::msg::twist_stamped::TwistStamped;
+use sdcar_types::msg::vehicle_state::VehicleState;
+use sdcar_types::parquet::point_cloud2::PointCloud2Batch;
+use sdcar_types::parquet::ToRecordBatch;
+use sdcar_types::reader::parquet::*;
+use sdcar_types::writer::parquet::*;
+use sdcar_types::Error;
+use std::fmt::Debug;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+fn image(i: i32) -> Image {
+    Image::new(
+        1634357915 + i,
+        123_456_789,
+        "left_camera".to_string(),
+        1536,
+        2048,
+        "bgr8".to_string(),
+        false,
+        6144,
+        format!("oid{:04}", i),
+    )
+}
+
+fn point_cloud2(i: i32) -> PointCloud2 {
+    let fields = [
+        PointField::new("x".to_string(), 0, 7, 1),
+        PointField::new("y".to_string(), 4, 7, 1),
+        PointField::new("z".to_string(), 8, 7, 1),
+        PointField::new("intensity".to_string(), 16, 7, 1),
+    ];
+    PointCloud2::new(
+        1634357915 + i,
+        999_999_999,
+        "lidar".to_string(),
+        1,
+        2,
+        fields[..(i as usize % 4 + 1)].to_vec(),
+        false,
+        32,
+        64,
+        (0..64).map(|b| (b + i) as u8).collect(),
+        true,
+    )
+}
+
+fn nav_sat_fix(i: i32) -> NavSatFix {
+    let mut covariance = [0.0; 9];
+    covariance[0] = i as f64;
+    NavSatFix::new(
+        1595047644 + i,
+        500_000_000,
+        "gps".to_string(),
+        2,
+        1,
+        -27.55,
+        153.05,
+        45.5,
+        covariance,
+        2,
+    )
+}
+
+fn vehicle_state(i: i32) -> VehicleState {
+    VehicleState::new(
+        1595047644 + i,
+        250_000_000,
+        "base_link".to_string(),
+        1595047643 + i,
+        750_000_000,
+        10.0,
+        2.1,
+        2.2,
+        2.3,
+        2.4,
+        0.1,
+        -0.2,
+        0.05,
+        12.5,
+        0.3,
+        0.02,
+        0.218,
+        36.0,
+        i as f32,
+    )
+}
+
+// writes `msgs` then reads them back in both read modes
+fn assert_batch_matches_rows<M: ParquetMsg + Debug>(name: &str, msgs: Vec<Arc<M>>) {
+    let path = TempPath::new(name);
+    let mut writer = ParquetFileWriter::<M>::new(&path).unwrap();
+    writer.write_all(msgs.iter().cloned()).unwrap();
+    writer.close().unwrap();
+
+    let rows: Vec<Arc<M>> = MsgParquetRowIter::<M>::new(&path).collect();
+    let iter = MsgParquetRowIter::<M>::new(&path)
+        .with_read_mode(ReadMode::Batches(4))
+        .unwrap();
+    assert_eq!(iter.read_mode(), ReadMode::Batches(4));
+    let batches: Vec<Arc<M>> = iter.collect();
+    assert_eq!(rows.len(), msgs.len());
+    assert_eq!(batches.len(), msgs.len());
+    for ((msg, row), batch) in msgs.iter().zip(rows.iter()).zip(batches.iter()) {
+        assert_eq!(format!("{:?}", msg), format!("{:?}", row));
+        assert_eq!(format!("{:?}", msg), format!("{:?}", batch));
+    }
+}
+
+#[test]
+fn batch_round_trip_test() {
+    assert_batch_matches_rows("batch_image", (0..10).map(|i| Arc::new(image(i))).collect());
+    assert_batch_matches_rows(
+        "batch_point_cloud2",
+        (0..10).map(|i| Arc::new(point_cloud2(i))).collect(),
+    );
+    assert_batch_matches_rows(
+        "batch_nav_sat_fix",
+        (0..10).map(|i| Arc::new(nav_sat_fix(i))).collect(),
+    );
+    assert_batch_matches_rows(
+        "batch_vehicle_state",
+        (0..10).map(|i| Arc::new(vehicle_state(i))).collect(),
+    );
+}
+
+fn header(i: i32, frame_id: &str) -> Header {
+    Header::new(Stamp::new(1634357915 + i, 10_000_000 * i as u32), frame_id.to_string())
+}
+
+fn imu(i: i32) -> Imu {
+    let f = i as f64;
+    Imu::new(
+        header(i, "imu_link"),
+        na::Quaternion::new(0.5, 0.5, -0.5, 0.1 * f),
+        na::Matrix3::from_fn(|r, c| (r * 3 + c) as f64 * 0.01),
+        na::Vector3::new(0.01 * f, -0.02, 0.03),
+        na::Matrix3::from_diagonal_element(0.001),
+        na::Vector3::new(0.1, 0.2, 9.81 + f),
+        na::Matrix3::from_diagonal_element(-1.0),
+    )
+}
+
+fn camera_info(i: i32) -> CameraInfo {
+    CameraInfo::new(
+        header(i, "left_camera"),
+        1536,
+        2048,
+        "plumb_bob".to_string(),
+        vec![-0.1, 0.01, 0.001 * i as f64, 0.0, 0.0],
+        na::Matrix3::new(1000.0, 0.0, 1024.0, 0.0, 1001.0, 768.0, 0.0, 0.0, 1.0),
+        na::Matrix3::identity(),
+        na::Matrix3x4::new(
+            1000.0, 0.0, 1024.0, -120.0, 0.0, 1001.0, 768.0, 0.0, 0.0, 0.0, 1.0, 0.0,
+        ),
+        1,
+        2,
+        RegionOfInterest {
+            x_offset: 8,
+            y_offset: 16,
+            height: 1520,
+            width: 2032,
+            do_rectify: i % 2 == 0,
+        },
+    )
+}
+
+fn twist(i: i32) -> Twist {
+    Twist::new(na::Vector3::new(10.0 + i as f64, 0.1, 0.0), na::Vector3::new(0.0, 0.0, 0.02))
+}
+
+fn twist_stamped(i: i32) -> TwistStamped {
+    TwistStamped::new(header(i, "base_link"), twist(i))
+}
+
+fn odometry(i: i32) -> Odometry {
+    Odometry::new(
+        header(i, "odom"),
+        "base_link".to_string(),
+        PoseWithCovariance {
+            pose: Pose::new(
+                na::Point3::new(i as f64, 2.0 * i as f64, 0.5),
+                na::Quaternion::new(0.9, 0.0, 0.0, 0.1),
+            ),
+            covariance: na::Matrix6::from_fn(|r, c| (r * 6 + c) as f64),
+        },
+        TwistWithCovariance {
+            twist: twist(i),
+            covariance: na::Matrix6::from_diagonal_element(0.5),
+        },
+    )
+}
+
+#[test]
+fn ros_msgs_batch_round_trip_test() {
+    assert_batch_matches_rows("batch_imu", (0..10).map(|i| Arc::new(imu(i))).collect());
+    assert_batch_matches_rows(
+        "batch_camera_info",
+        (0..10).map(|i| Arc::new(camera_info(i))).collect(),
+    );
+    assert_batch_matches_rows(
+        "batch_twist_stamped",
+        (0..10).map(|i| Arc::new(twist_stamped(i))).collect(),
+    );
+    assert_batch_matches_rows(
+        "batch_odometry",
+        (0..10).map(|i| Arc::new(odometry(i))).collect(),
+    );
+}
+
+#[test]
+fn covariance_length_error_test() {
+    let path = TempPath::new("covariance_length_error");
+    let msgs = vec![Arc::new(imu(0))];
+    let batch = Imu::to_record_batch(&msgs).unwrap();
+    let mut columns: Vec<ArrayRef> = batch.columns().to_vec();
+    columns[3] = Arc::new(ListArray::from_iter_primitive::<Float64Type, _, _>(vec![Some(
+        vec![Some(1.0); 4],
+    )]));
+    let batch = RecordBatch::try_new(batch.schema(), columns).unwrap();
+    let mut writer = ArrowWriter::try_new(File::create(&path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+
+    let mut iter = ImuParquetRowIter::new(&path)
+        .with_read_mode(ReadMode::Batches(4))
+        .unwrap();
+    assert_eq!(iter.by_ref().count(), 0);
+    match iter.error() {
+        Some(Error::Decode { field, expected, .. }) => {
+            assert_eq!(field, "orientation_covariance");
+            assert_eq!(*expected, "list<double>");
+        }
+        other => panic!("expected a decode error, got {:?}", other.is_some()),
+    }
+}
+
+// ten images where the seventh has a null image_oid
+fn write_images_with_null(path: &Path) {
+    let images: Vec<Arc<Image>> = (0..10).map(|i| Arc::new(image(i))).collect();
+    let batch = Image::to_record_batch(&images).unwrap();
+    let mut columns: Vec<ArrayRef> = batch.columns().to_vec();
+    columns[7] = Arc::new(
+        (0..10)
+            .map(|i| {
+                if i == 6 {
+                    None
+                } else {
+                    Some(format!("oid{:04}", i))
+                }
+            })
+            .collect::<StringArray>(),
+    );
+    let batch = RecordBatch::try_new(batch.schema(), columns).unwrap();
+    let props = WriterProperties::builder()
+        .set_dictionary_enabled(false)
+        .build();
+    let mut writer =
+        ArrowWriter::try_new(File::create(path).unwrap(), batch.schema(), Some(props)).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
+
+#[test]
+fn batch_decode_error_test() {
+    let path = TempPath::new("batch_decode_error");
+    write_images_with_null(&path);
+
+    let mut iter = ImageParquetRowIter::new(&path)
+        .with_read_mode(ReadMode::Batches(4))
+        .unwrap();
+    assert_eq!(iter.by_ref().count(), 6);
+    match iter.error() {
+        Some(Error::Decode {
+            field,
+            expected,
+            row,
+            ..
+        }) => {
+            assert_eq!(field, "image_oid");
+            assert_eq!(*expected, "string");
+            assert_eq!(*row, Some(6));
+        }
+        other => panic!("expected a decode error, got {:?}", other.is_some()),
+    }
+
+    let mut iter = ImageParquetRowIter::new(&path)
+        .with_read_mode(ReadMode::Batches(4))
+        .unwrap();
+    iter.set_error_policy(RowErrorPolicy::Skip);
+    assert_eq!(iter.by_ref().count(), 9);
+    assert_eq!(iter.skipped_rows(), 1);
+    assert!(iter.error().is_none());
+}
+
+#[test]
+fn batch_range_test() {
+    let path = TempPath::new("batch_range");
+    let images: Vec<Arc<Image>> = (0..100).map(|i| Arc::new(image(i))).collect();
+    let mut writer = ImageParquetWriter::new_with_row_group_size(&path, 10).unwrap();
+    writer.write_all(images.iter().cloned()).unwrap();
+    writer.close().unwrap();
+
+    let start = Utc.timestamp_opt(1634357915 + 25, 0).unwrap();
+    let end = Utc.timestamp_opt(1634357915 + 45, 0).unwrap();
+    let mut iter = ImageParquetRowIter::new(&path);
+    iter.range(start, end).unwrap();
+    iter.set_read_mode(ReadMode::Batches(8)).unwrap();
+    assert_eq!(iter.skipped_row_groups(), 7);
+    let read: Vec<_> = iter.collect();
+    assert_eq!(read.len(), 20);
+    for (a, b) in images[25..45].iter().zip(read.iter()) {
+        assert_eq!(format!("{:?}", a), format!("{:?}", b));
+    }
+
+    let mut batches = ParquetFileBatchIter::new(&path, &Image::arrow_schema(), 8).unwrap();
+    batches
+        .seek(Utc.timestamp_opt(1634357915 + 95, 0).unwrap())
+        .unwrap();
+    assert_eq!(batches.skipped_row_groups(), 9);
+    let mut row_indices = Vec::new();
+    while let Some(batch) = batches.next() {
+        assert_eq!(batch.unwrap().num_rows(), batches.row_indices().len());
+        row_indices.extend_from_slice(batches.row_indices());
+    }
+    assert_eq!(row_indices, vec![95, 96, 97, 98, 99]);
+}
+
+// rows and batches skip the same out of range nanosecs rather than wrapping them
+#[test]
+fn batch_nanosec_out_of_range_test() {
+    let path = TempPath::new("batch_nanosec_out_of_range");
+    write_nav_sat_fixes_with_nanosecs(&path, &[0, 0, 0, 5_000_000_000, 0, -1]);
+
+    for read_mode in [ReadMode::Rows, ReadMode::Batches(4)] {
+        let mut iter = NavSatFixParquetRowIter::new(&path);
+        iter.range(
+            Utc.timestamp_opt(3, 900_000_000).unwrap(),
+            Utc.timestamp_opt(10, 0).unwrap(),
+        )
+        .unwrap();
+        iter.set_read_mode(read_mode).unwrap();
+        iter.set_error_policy(RowErrorPolicy::Skip);
+        assert_eq!(iter.by_ref().count(), 1);
+        assert_eq!(iter.skipped_rows(), 2);
+    }
+}
+
+#[test]
+fn point_cloud2_batch_view_test() {
+    let path = TempPath::new("point_cloud2_batch_view");
+    let clouds: Vec<Arc<PointCloud2>> = (0..6).map(|i| Arc::new(point_cloud2(i))).collect();
+    let mut writer = PointCloud2ParquetWriter::new(&path).unwrap();
+    writer.write_all(clouds.iter().cloned()).unwrap();
+    writer.close().unwrap();
+
+    let batches = ParquetFileBatchIter::new(&path, &PointCloud2::arrow_schema(), 4).unwrap();
+    let mut read = 0;
+    for batch in batches {
+        let view = PointCloud2Batch::try_new(batch.unwrap()).unwrap();
+        for i in 0..view.len() {
+            let cloud = &clouds[read];
+            assert_eq!(view.timestamp(i), Some(cloud.header.timestamp()));
+            assert_eq!(view.frame_id(i), cloud.header.frame_id);
+            assert_eq!(view.width(i), cloud.width);
+            assert_eq!(view.point_step(i), cloud.point_step);
+            assert_eq!(view.data(i), cloud.data.as_slice());
+            assert_eq!(
+                format!("{:?}", view.msg(i).unwrap()),
+                format!("{:?}", cloud)
+            );
+            read += 1;
+        }
+    }
+    assert_eq!(read, clouds.len());
+
+    // a null stamp has no timestamp, rather than the epoch
+    let batch = PointCloud2::to_record_batch(&clouds[..2]).unwrap();
+    let header = batch.column(1).as_any().downcast_ref::<StructArray>().unwrap();
+    let stamp = header.column(0).as_any().downcast_ref::<StructArray>().unwrap();
+    let struct_fields = |array: &StructArray| match array.data_type() {
+        DataType::Struct(fields) => fields.clone(),
+        _ => unreachable!(),
+    };
+    let (header_fields, stamp_fields) = (struct_fields(header), struct_fields(stamp));
+    let secs = Int32Array::from(vec![Some(clouds[0].header.stamp.sec()), None]);
+    let stamp = StructArray::from(vec![
+        (stamp_fields[0].clone(), Arc::new(secs) as ArrayRef),
+        (stamp_fields[1].clone(), stamp.column(1).clone()),
+    ]);
+    let header = StructArray::from(vec![
+        (header_fields[0].clone(), Arc::new(stamp) as ArrayRef),
+        (header_fields[1].clone(), header.column(1).clone()),
+    ]);
+    let mut columns = batch.columns().to_vec();
+    columns[1] = Arc::new(header);
+    let view = PointCloud2Batch::try_new(RecordBatch::try_new(batch.schema(), columns).unwrap()).unwrap();
+    assert_eq!(view.timestamp(0), Some(clouds[0].header.timestamp()));
+    assert_eq!(view.timestamp(1), None);
+}
@@ -0,0 +1,314 @@
+extern crate arrow;
+extern crate chrono;
+extern crate parquet;
+extern crate sdcar_types;
+
+mod common;
+
+use common::*;
+
+use arrow::array::{ArrayRef, StringArray};
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use chrono::{DateTime, TimeZone, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use sdcar_types::msg::image::Image;
+use sdcar_types::msg::nav_sat_fix::NavSatFix;
+use sdcar_types::parquet::ToRecordBatch;
+use sdcar_types::reader::parquet::*;
+use sdcar_types::writer::parquet::*;
+use sdcar_types::{Error, SchemaMismatch};
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+fn images(count: i32) -> Vec<Arc<Image>> {
+    (0..count)
+        .map(|i| {
+            Arc::new(Image::new(
+                1634357915 + i,
+                0,
+                "left_camera".to_string(),
+                1536,
+                2048,
+                "bgr8".to_string(),
+                false,
+                6144,
+                format!("oid{:04}", i),
+            ))
+        })
+        .collect()
+}
+
+fn write_batch(path: &Path, batch: &RecordBatch) {
+    let props = WriterProperties::builder()
+        .set_dictionary_enabled(false)
+        .build();
+    let mut writer =
+        ArrowWriter::try_new(File::create(path).unwrap(), batch.schema(), Some(props)).unwrap();
+    writer.write(batch).unwrap();
+    writer.close().unwrap();
+}
+
+// three images where the second has a null image_oid
+fn write_images_with_null(path: &Path) {
+    let batch = Image::to_record_batch(&images(3)).unwrap();
+    let mut columns: Vec<ArrayRef> = batch.columns().to_vec();
+    columns[7] = Arc::new(StringArray::from(vec![Some("oid0000"), None, Some("oid0002")]));
+    write_batch(path, &RecordBatch::try_new(batch.schema(), columns).unwrap());
+}
+
+#[test]
+fn surface_decode_error_test() {
+    let path = TempPath::new("surface_decode_error");
+    write_images_with_null(&path);
+
+    let mut iter = ImageParquetRowIter::new(&path);
+    assert!(iter.try_next().unwrap().is_ok());
+    match iter.try_next() {
+        Some(Err(Error::Decode {
+            field, expected, row, ..
+        })) => {
+            assert_eq!(field, "image_oid");
+            assert_eq!(expected, "string");
+            assert_eq!(row, Some(1));
+        }
+        other => panic!("expected a decode error, got {:?}", other.map(|r| r.is_ok())),
+    }
+    assert!(iter.next().is_some());
+    assert!(iter.next().is_none());
+    assert!(iter.error().is_none());
+
+    let mut iter = ImageParquetRowIter::new(&path);
+    assert_eq!(iter.by_ref().count(), 1);
+    assert!(iter.error().is_some());
+    assert!(iter.next_msg().is_none());
+}
+
+#[test]
+fn skip_decode_error_test() {
+    let path = TempPath::new("skip_decode_error");
+    write_images_with_null(&path);
+
+    let mut iter = ImageParquetRowIter::new(&path);
+    iter.set_error_policy(RowErrorPolicy::Skip);
+    let oids: Vec<String> = iter.by_ref().map(|image| image.image_oid.clone()).collect();
+    assert_eq!(oids, vec!["oid0000", "oid0002"]);
+    assert!(iter.error().is_none());
+    assert_eq!(iter.skipped_rows(), 1);
+}
+
+#[test]
+fn schema_mismatch_test() {
+    let path = TempPath::new("schema_mismatch");
+    let mut writer = NavSatFixParquetWriter::new(&path).unwrap();
+    writer
+        .write(Arc::new(NavSatFix::new(
+            1595047644,
+            0,
+            "gps".to_string(),
+            0,
+            1,
+            -27.55,
+            153.05,
+            45.5,
+            [0.0; 9],
+            0,
+        )))
+        .unwrap();
+    writer.close().unwrap();
+
+    match ImageParquetRowIter::try_new(&path) {
+        Err(Error::Schema(mismatches)) => {
+            assert!(mismatches.contains(&SchemaMismatch::Missing {
+                column: "height".to_string()
+            }));
+            assert!(mismatches.contains(&SchemaMismatch::Missing {
+                column: "image_oid".to_string()
+            }));
+            assert!(mismatches.iter().any(|mismatch| matches!(
+                mismatch,
+                SchemaMismatch::Mistyped { column, .. } if column == "header.stamp.nanosec"
+            )));
+        }
+        Err(e) => panic!("expected a schema error, got {}", e),
+        Ok(_) => panic!("expected a schema error"),
+    }
+}
+
+#[test]
+fn reordered_columns_test() {
+    let path = TempPath::new("reordered_columns");
+    let images = images(2);
+    let batch = Image::to_record_batch(&images).unwrap();
+    let order: Vec<usize> = (0..batch.num_columns()).rev().collect();
+    let schema = Schema::new(
+        order
+            .iter()
+            .map(|i| batch.schema().field(*i).clone())
+            .collect(),
+    );
+    let columns: Vec<ArrayRef> = order.iter().map(|i| batch.column(*i).clone()).collect();
+    write_batch(
+        &path,
+        &RecordBatch::try_new(Arc::new(schema), columns).unwrap(),
+    );
+
+    let read: Vec<_> = ImageParquetRowIter::new(&path).collect();
+    assert_eq!(read.len(), images.len());
+    for (a, b) in images.iter().zip(read.iter()) {
+        assert_eq!(format!("{:?}", a), format!("{:?}", b));
+    }
+}
+
+// 100 images one second apart in row groups of 10
+fn write_row_groups(path: &Path) -> Vec<Arc<Image>> {
+    let images = images(100);
+    let mut writer = ImageParquetWriter::new_with_row_group_size(path, 10).unwrap();
+    writer.write_all(images.iter().cloned()).unwrap();
+    writer.close().unwrap();
+    images
+}
+
+fn stamp(sec: i64, nanosec: u32) -> DateTime<Utc> {
+    Utc.timestamp_opt(1634357915 + sec, nanosec).unwrap()
+}
+
+fn oids(iter: ImageParquetRowIter) -> Vec<String> {
+    iter.map(|image| image.image_oid.clone()).collect()
+}
+
+#[test]
+fn seek_test() {
+    let path = TempPath::new("seek");
+    write_row_groups(&path);
+
+    let mut iter = ImageParquetRowIter::new(&path);
+    assert_eq!(iter.by_ref().take(3).count(), 3);
+    iter.seek(stamp(55, 0)).unwrap();
+    assert_eq!(iter.skipped_row_groups(), 5);
+    let oids = oids(iter);
+    assert_eq!(oids.len(), 45);
+    assert_eq!(oids[0], "oid0055");
+    assert_eq!(oids[44], "oid0099");
+
+    let mut iter = ImageParquetRowIter::new(&path);
+    iter.seek(stamp(100, 0)).unwrap();
+    assert_eq!(iter.skipped_row_groups(), 10);
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn range_test() {
+    let path = TempPath::new("range");
+    let images = write_row_groups(&path);
+
+    let mut iter = ImageParquetRowIter::new(&path);
+    iter.range(
+        stamp(28, 500_000_000),
+        stamp(48, 0),
+    )
+    .unwrap();
+    assert_eq!(iter.skipped_row_groups(), 7);
+    let read: Vec<_> = iter.collect();
+    assert_eq!(read.len(), 19);
+    for (a, b) in images[29..48].iter().zip(read.iter()) {
+        assert_eq!(format!("{:?}", a), format!("{:?}", b));
+    }
+}
+
+#[test]
+fn range_row_index_test() {
+    let path = TempPath::new("range_row_index");
+    let batch = Image::to_record_batch(&images(20)).unwrap();
+    let mut columns: Vec<ArrayRef> = batch.columns().to_vec();
+    let oids: Vec<Option<String>> = (0..20)
+        .map(|i| if i == 15 { None } else { Some(format!("oid{:04}", i)) })
+        .collect();
+    columns[7] = Arc::new(oids.into_iter().collect::<StringArray>());
+    let props = WriterProperties::builder()
+        .set_dictionary_enabled(false)
+        .set_max_row_group_size(10)
+        .build();
+    let batch = RecordBatch::try_new(batch.schema(), columns).unwrap();
+    let mut writer =
+        ArrowWriter::try_new(File::create(&path).unwrap(), batch.schema(), Some(props)).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+
+    let mut iter = ImageParquetRowIter::new(&path);
+    iter.seek(stamp(12, 0)).unwrap();
+    assert_eq!(iter.skipped_row_groups(), 1);
+    assert_eq!(iter.by_ref().count(), 3);
+    match iter.error() {
+        Some(Error::Decode { row, .. }) => assert_eq!(*row, Some(15)),
+        other => panic!("expected a decode error, got {:?}", other.is_some()),
+    }
+}
+
+// a nanosec too large for uint32 fails the row rather than wrapping into a
+// stamp the range would silently drop
+#[test]
+fn nanosec_out_of_range_test() {
+    let path = TempPath::new("nanosec_out_of_range");
+    write_nav_sat_fixes_with_nanosecs(&path, &[0, 0, 0, 5_000_000_000, 0, 0]);
+
+    let mut iter = NavSatFixParquetRowIter::new(&path);
+    iter.range(
+        Utc.timestamp_opt(3, 900_000_000).unwrap(),
+        Utc.timestamp_opt(10, 0).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(iter.by_ref().count(), 0);
+    match iter.error() {
+        Some(Error::Decode { field, row, .. }) => {
+            assert_eq!(field, "header.stamp.nanosec");
+            assert_eq!(*row, Some(3));
+        }
+        other => panic!("expected a decode error, got {:?}", other.is_some()),
+    }
+}
+
+#[test]
+fn open_file_reader_test() {
+    let path = TempPath::new("open_file_reader");
+    write_row_groups(&path);
+
+    let file = File::open(&path).unwrap();
+    let mut iter = ImageParquetRowIter::try_from_reader(file).unwrap();
+    assert!(iter.path().is_none());
+    assert_eq!(iter.by_ref().take(3).count(), 3);
+    iter.seek(stamp(90, 0)).unwrap();
+    assert_eq!(iter.skipped_row_groups(), 9);
+    assert_eq!(oids(iter)[0], "oid0090");
+
+    let file = File::open(&path).unwrap();
+    let iter = ImageParquetRowIter::try_from_reader(file)
+        .unwrap()
+        .with_read_mode(ReadMode::Batches(16))
+        .unwrap();
+    assert_eq!(iter.count(), 100);
+}
+
+// readers own their path, so they can outlive the strings they were built from
+#[test]
+fn owned_path_reader_test() {
+    let paths: Vec<TempPath> = (0..3)
+        .map(|run| TempPath::new(&format!("owned_path_{}", run)))
+        .collect();
+    for path in &paths {
+        let mut writer = ImageParquetWriter::new(path).unwrap();
+        writer.write_all(images(5)).unwrap();
+        writer.close().unwrap();
+    }
+
+    let iters: Vec<ImageParquetRowIter> = paths
+        .iter()
+        .map(|path| ImageParquetRowIter::new(path.to_string_lossy().to_string()))
+        .collect();
+    for (iter, path) in iters.into_iter().zip(paths.iter()) {
+        assert_eq!(iter.path(), Some(&**path));
+        assert_eq!(iter.count(), 5);
+    }
+}
@@ -0,0 +1,151 @@
+extern crate parquet;
+extern crate sdcar_types;
+
+mod common;
+
+use common::*;
+
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use sdcar_types::msg::image::Image;
+use sdcar_types::msg::nav_sat_fix::NavSatFix;
+use sdcar_types::msg::point_cloud2::{PointCloud2, PointField};
+use sdcar_types::msg::vehicle_state::VehicleState;
+use sdcar_types::msg::Msg;
+use sdcar_types::reader::parquet::*;
+use sdcar_types::writer::parquet::*;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+fn read_rows(path: &Path) -> Vec<parquet::record::Row> {
+    let reader = SerializedFileReader::new(File::open(path).unwrap()).unwrap();
+    reader.get_row_iter(None).unwrap().collect()
+}
+
+fn image(i: i32) -> Image {
+    Image::new(
+        1634357915 + i,
+        123_456_789,
+        "left_camera".to_string(),
+        1536,
+        2048,
+        "bgr8".to_string(),
+        false,
+        6144,
+        format!("oid{:04}", i),
+    )
+}
+
+fn point_cloud2(i: i32) -> PointCloud2 {
+    let fields = vec![
+        PointField::new("x".to_string(), 0, 7, 1),
+        PointField::new("y".to_string(), 4, 7, 1),
+        PointField::new("z".to_string(), 8, 7, 1),
+        PointField::new("intensity".to_string(), 16, 7, 1),
+    ];
+    PointCloud2::new(
+        1634357915 + i,
+        999_999_999,
+        "lidar".to_string(),
+        1,
+        2,
+        fields,
+        false,
+        32,
+        64,
+        (0..64).map(|b| (b + i) as u8).collect(),
+        true,
+    )
+}
+
+#[test]
+fn image_round_trip_test() {
+    let path = TempPath::new("image");
+    let images: Vec<Arc<Image>> = (0..5).map(|i| Arc::new(image(i))).collect();
+
+    let mut writer = ImageParquetWriter::new(&path).unwrap().with_batch_size(2);
+    writer.write_all(images.iter().cloned()).unwrap();
+    assert_eq!(writer.close().unwrap(), 5);
+
+    let read: Vec<_> = ImageParquetRowIter::new(&path).collect();
+    assert_eq!(read.len(), images.len());
+    for (a, b) in images.iter().zip(read.iter()) {
+        assert_eq!(format!("{:?}", a), format!("{:?}", b));
+    }
+}
+
+#[test]
+fn point_cloud2_round_trip_test() {
+    let path = TempPath::new("point_cloud2");
+    let clouds: Vec<Arc<PointCloud2>> = (0..3).map(|i| Arc::new(point_cloud2(i))).collect();
+
+    let mut writer = PointCloud2ParquetWriter::new(&path).unwrap();
+    writer.write_all(clouds.iter().cloned()).unwrap();
+    assert_eq!(writer.close().unwrap(), 3);
+
+    let read: Vec<_> = PointCloud2ParquetRowIter::new(&path).collect();
+    assert_eq!(read.len(), clouds.len());
+    for (a, b) in clouds.iter().zip(read.iter()) {
+        assert_eq!(format!("{:?}", a), format!("{:?}", b));
+    }
+}
+
+#[test]
+fn nav_sat_fix_round_trip_test() {
+    let path = TempPath::new("nav_sat_fix");
+    let covariance = [1.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 3.0];
+    // a covariance type outside the message's constants is kept as well
+    let fixes: Vec<_> = [2, 7]
+        .into_iter()
+        .map(|covariance_type| {
+            Arc::new(NavSatFix::new(
+                1595047644, 500_000_000, "gps".to_string(), 2, 1, -27.55, 153.05, 45.5, covariance,
+                covariance_type,
+            ))
+        })
+        .collect();
+
+    let mut writer = NavSatFixParquetWriter::new(&path).unwrap();
+    writer.write_all(fixes.clone()).unwrap();
+    writer.close().unwrap();
+
+    let rows = read_rows(&path);
+    assert_eq!(rows.len(), 2);
+    for (fix, row) in fixes.iter().zip(rows) {
+        let read = NavSatFix::try_from(row).unwrap();
+        assert_eq!(format!("{:?}", fix), format!("{:?}", read));
+    }
+}
+
+#[test]
+fn vehicle_state_round_trip_test() {
+    let path = TempPath::new("vehicle_state");
+    let state = Arc::new(VehicleState::new(
+        1595047644, 250_000_000, "base_link".to_string(), 1595047643, 750_000_000, 10.0, 2.1,
+        2.2, 2.3, 2.4, 0.1, -0.2, 0.05, 12.5, 0.3, 0.02, 0.218, 36.0, 10.0,
+    ));
+
+    let mut writer = VehicleStateParquetWriter::new(&path).unwrap();
+    writer.write(state.clone()).unwrap();
+    writer.close().unwrap();
+
+    let rows = read_rows(&path);
+    assert_eq!(rows.len(), 1);
+    let read = VehicleState::try_from(rows[0].clone()).unwrap();
+    assert_eq!(format!("{:?}", state), format!("{:?}", read));
+}
+
+#[test]
+fn msg_kind_writer_test() {
+    let path = TempPath::new("msg_kind");
+    let images: Vec<_> = (0..3).map(|i| image(i).msg_kind()).collect();
+
+    let mut writer = MsgKindParquetWriter::new(&path, &images[0]).unwrap();
+    for msg_kind in images {
+        writer.write(msg_kind).unwrap();
+    }
+    assert!(writer.write(point_cloud2(0).msg_kind()).is_err());
+    assert_eq!(writer.close().unwrap(), 3);
+
+    assert_eq!(ImageParquetRowIter::new(&path).count(), 3);
+}
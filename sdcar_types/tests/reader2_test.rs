@@ -0,0 +1,85 @@
+extern crate sdcar_types;
+
+mod common;
+
+use common::*;
+
+use sdcar_types::msg::MsgKind;
+use sdcar_types::reader::combined::*;
+use sdcar_types::reader::parquet::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_FILE: AtomicUsize = AtomicUsize::new(0);
+
+fn merge(a_stamps: &[i64], b_stamps: &[i64], tie_priority: TiePriority) -> Vec<(String, i64)> {
+    let file = NEXT_FILE.fetch_add(1, Ordering::SeqCst);
+    let a_path = TempPath::new(&format!("reader2_a_{}", file));
+    let b_path = TempPath::new(&format!("reader2_b_{}", file));
+    write_images(&a_path, "a", a_stamps);
+    write_images(&b_path, "b", b_stamps);
+
+    let reader = Reader2::<ImageParquetRowIter, ImageParquetRowIter>::new(
+        ImageParquetRowIter::new(&a_path),
+        ImageParquetRowIter::new(&b_path),
+    )
+    .with_tie_priority(tie_priority);
+    let merged = reader
+        .map(|msg| match msg.msg_kind() {
+            MsgKind::Image(image) => (image.image_oid.clone(), msg.timestamp().timestamp_millis()),
+            _ => panic!("expected an image"),
+        })
+        .collect();
+
+    merged
+}
+
+fn pairs(expected: &[(&str, i64)]) -> Vec<(String, i64)> {
+    expected
+        .iter()
+        .map(|(oid, ms)| (oid.to_string(), *ms))
+        .collect()
+}
+
+#[test]
+fn reader2_emits_every_message_once_test() {
+    let merged = merge(&[100, 300, 500], &[200, 400], TiePriority::A);
+    assert_eq!(
+        merged,
+        pairs(&[("a0", 100), ("b0", 200), ("a1", 300), ("b1", 400), ("a2", 500)])
+    );
+}
+
+#[test]
+fn reader2_first_message_from_b_test() {
+    let merged = merge(&[300, 400], &[100, 200], TiePriority::A);
+    assert_eq!(
+        merged,
+        pairs(&[("b0", 100), ("b1", 200), ("a0", 300), ("a1", 400)])
+    );
+}
+
+#[test]
+fn reader2_tie_priority_test() {
+    let merged = merge(&[100, 200], &[100, 200, 200], TiePriority::A);
+    assert_eq!(
+        merged,
+        pairs(&[("a0", 100), ("b0", 100), ("a1", 200), ("b1", 200), ("b2", 200)])
+    );
+
+    let merged = merge(&[100, 200], &[100, 200, 200], TiePriority::B);
+    assert_eq!(
+        merged,
+        pairs(&[("b0", 100), ("a0", 100), ("b1", 200), ("b2", 200), ("a1", 200)])
+    );
+}
+
+#[test]
+fn reader2_empty_stream_test() {
+    let merged = merge(&[], &[100, 200], TiePriority::A);
+    assert_eq!(merged, pairs(&[("b0", 100), ("b1", 200)]));
+
+    let merged = merge(&[100], &[], TiePriority::B);
+    assert_eq!(merged, pairs(&[("a0", 100)]));
+
+    assert!(merge(&[], &[], TiePriority::A).is_empty());
+}
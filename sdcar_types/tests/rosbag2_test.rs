@@ -0,0 +1,267 @@
+extern crate nalgebra as na;
+extern crate rusqlite;
+extern crate sdcar_types;
+
+mod common;
+
+use common::*;
+
+use sdcar_types::cdr::sensor_msgs::{image_oid, ImageData};
+use sdcar_types::cdr::FromCdr;
+use sdcar_types::msg::header::Stamp;
+use sdcar_types::msg::image::Image;
+use sdcar_types::msg::imu::Imu;
+use sdcar_types::msg::nav_sat_fix::NavSatFix;
+use sdcar_types::msg::twist_stamped::TwistStamped;
+use sdcar_types::msg::{Msg, MsgKind};
+use sdcar_types::reader::parquet::{HasRowIter, RowErrorPolicy};
+use sdcar_types::reader::rosbag2::*;
+use sdcar_types::test_support::*;
+use sdcar_types::Error;
+use std::fs::File;
+use std::path::Path;
+
+fn recording(count: i32) -> Recorded {
+    let mut recorded = Vec::new();
+    for i in 0..count {
+        recorded.push(("/gps/fix", NAV_SAT_FIX, log_time(i, 500_000_000), nav_sat_fix_cdr(i, false)));
+        recorded.push(("/imu", IMU, log_time(i, 250_000_000), imu_cdr(i)));
+        let image = image_cdr(i, "left_camera", "mono8", 2, &[i as u8, 1, 2, 3]);
+        recorded.push(("/left/image_raw", IMAGE, log_time(i, 750_000_000), image));
+    }
+    recorded.sort_by_key(|(_, _, log_time, _)| *log_time);
+    recorded
+}
+
+fn write_db3(path: &Path, recorded: &[(&str, &str, i64, Vec<u8>)]) {
+    let connection = rusqlite::Connection::open(path).unwrap();
+    connection
+        .execute_batch(
+            "CREATE TABLE topics(id INTEGER PRIMARY KEY, name TEXT NOT NULL, type TEXT NOT NULL, \
+             serialization_format TEXT NOT NULL, offered_qos_profiles TEXT NOT NULL);
+             CREATE TABLE messages(id INTEGER PRIMARY KEY, topic_id INTEGER NOT NULL, \
+             timestamp INTEGER NOT NULL, data BLOB NOT NULL);",
+        )
+        .unwrap();
+    let mut topics: Vec<&str> = Vec::new();
+    for (topic, msg_type, log_time, data) in recorded {
+        let id = match topics.iter().position(|known| known == topic) {
+            Some(index) => index + 1,
+            None => {
+                topics.push(topic);
+                // ids other than the topic's index, as split files assign them
+                connection
+                    .execute(
+                        "INSERT INTO topics VALUES (?1, ?2, ?3, 'cdr', '')",
+                        rusqlite::params![topics.len() as i64 * 10, topic, msg_type],
+                    )
+                    .unwrap();
+                topics.len()
+            }
+        };
+        connection
+            .execute(
+                "INSERT INTO messages (topic_id, timestamp, data) VALUES (?1, ?2, ?3)",
+                rusqlite::params![id as i64 * 10, log_time, data],
+            )
+            .unwrap();
+    }
+}
+
+/// Writes an MCAP file with one chunk per `chunk_size` messages, each
+/// compressed as `compression`, and a summary section if `summary`.
+fn write_mcap(path: &Path, recorded: &Recorded, compression: &str, chunk_size: usize, summary: bool) {
+    std::fs::write(path, chunked_mcap_bytes(recorded, &[], compression, chunk_size, summary)).unwrap();
+}
+
+#[test]
+fn cdr_decode_test() {
+    let little = NavSatFix::from_cdr_bytes(&nav_sat_fix_cdr(1, false)).unwrap();
+    let big = NavSatFix::from_cdr_bytes(&nav_sat_fix_cdr(1, true)).unwrap();
+    assert_eq!(format!("{:?}", little), format!("{:?}", big));
+    assert_eq!(little.header.stamp, Stamp::new(START_SEC + 1, 500_000_000));
+    assert_eq!(little.header.frame_id, "gps");
+    assert_eq!((little.status, little.service), (2, 1));
+    assert_eq!(little.altitude, 45.5);
+    assert_eq!(little.position_covariance_type, 2);
+
+    let imu = Imu::from_cdr_bytes(&imu_cdr(2)).unwrap();
+    assert_eq!(imu.orientation, na::Quaternion::new(0.99, 0.0, 0.0, 0.1));
+    assert!(!imu.has_orientation());
+    assert_eq!(imu.angular_velocity, na::Vector3::new(0.01, 0.02, 0.06));
+    assert_eq!(imu.linear_acceleration.z, 9.81);
+
+    let image = ImageData::from_cdr_bytes(&image_cdr(3, "left_camera", "mono8", 2, &[3, 1, 2, 3])).unwrap();
+    assert_eq!((image.image.height, image.image.width, image.image.step), (2, 2, 2));
+    assert_eq!(image.image.encoding, "mono8");
+    assert_eq!(image.data, vec![3, 1, 2, 3]);
+    assert_eq!(image.image.image_oid, image_oid(&image.data));
+    assert_eq!(image.image.image_oid.len(), 64);
+
+    let twist = TwistStamped::from_cdr_bytes(&twist_stamped_cdr(1)).unwrap();
+    assert_eq!(twist.twist.linear.x, 11.0);
+    assert_eq!(twist.twist.angular.z, 0.05);
+
+    let mut truncated = nav_sat_fix_cdr(0, false);
+    truncated.truncate(32);
+    match NavSatFix::from_cdr_bytes(&truncated) {
+        Err(Error::Decode { field, expected, .. }) => {
+            assert_eq!(field, "latitude");
+            assert_eq!(expected, "float64");
+        }
+        other => panic!("expected a decode error, got {:?}", other.is_ok()),
+    }
+}
+
+#[test]
+fn sqlite3_bag_test() {
+    let dir = TempDir::new("rosbag2_sqlite3");
+    let recorded = recording(6);
+    // split as the recorder does, the second file numbering topics anew
+    write_db3(&dir.join("run_0.db3"), &recorded[..8]);
+    write_db3(&dir.join("run_1.db3"), &recorded[8..]);
+    std::fs::write(dir.join("metadata.yaml"), "rosbag2_bagfile_information: {}\n").unwrap();
+
+    let bag = Rosbag2::open(&dir).unwrap();
+    assert_eq!(bag.format(), StorageFormat::Sqlite3);
+    let names: Vec<&str> = bag.topics().iter().map(|topic| topic.name.as_str()).collect();
+    assert_eq!(names, vec!["/imu", "/gps/fix", "/left/image_raw"]);
+    assert_eq!(bag.topics()[1].msg_type, NAV_SAT_FIX);
+    assert_eq!(bag.topics()[1].serialization_format, "cdr");
+
+    let log_times: Vec<i64> = bag.messages().map(|message| message.unwrap().log_time).collect();
+    assert_eq!(log_times.len(), recorded.len());
+    assert!(log_times.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    let fixes: Vec<_> = bag.msg_reader::<NavSatFix>("/gps/fix").unwrap().collect();
+    assert_eq!(fixes.len(), 6);
+    for (i, fix) in fixes.iter().enumerate() {
+        assert_eq!(fix.header.stamp.sec(), START_SEC + i as i32);
+    }
+    assert!(bag.msg_reader::<Imu>("/gps/fix").is_err());
+    assert!(bag.msg_reader::<Imu>("/missing").is_err());
+
+    let images = ImageBagRowIter::try_new(&dir).unwrap();
+    assert_eq!(images.count(), 6);
+
+    let mut reader = bag.merged_reader().unwrap();
+    assert_eq!(reader.num_sources(), 3);
+    assert_eq!(reader.source_name(1), "/gps/fix");
+    let msgs: Vec<_> = reader.by_ref().collect();
+    assert_eq!(msgs.len(), recorded.len());
+    assert!(msgs.windows(2).all(|pair| pair[0].timestamp() <= pair[1].timestamp()));
+    assert!(matches!(msgs[0].msg_kind, MsgKind::Imu(_)));
+}
+
+#[test]
+fn mcap_bag_test() {
+    let dir = TempDir::new("rosbag2_mcap");
+    let mut recorded = recording(5);
+    for i in 0..5 {
+        recorded.push(("/twist", TWIST_STAMPED, log_time(i, 900_000_000), twist_stamped_cdr(i)));
+    }
+    recorded.sort_by_key(|(_, _, log_time, _)| *log_time);
+
+    for (name, compression, chunk_size, summary) in [
+        ("zstd.mcap", "zstd", 4, true),
+        ("lz4.mcap", "lz4", 3, false),
+        ("plain.mcap", "", 0, false),
+    ] {
+        let path = dir.join(name);
+        write_mcap(&path, &recorded, compression, chunk_size, summary);
+
+        let bag = Rosbag2::open(&path).unwrap();
+        assert_eq!(bag.format(), StorageFormat::Mcap, "{}", name);
+        assert_eq!(bag.topics().len(), 4, "{}", name);
+        assert_eq!(bag.topic_index("/twist"), Some(3), "{}", name);
+        assert_eq!(bag.messages().count(), recorded.len(), "{}", name);
+
+        let imus: Vec<_> = bag.msg_reader::<Imu>("/imu").unwrap().collect();
+        assert_eq!(imus.len(), 5, "{}", name);
+        assert_eq!(imus[4].angular_velocity.z, 0.12);
+
+        let twists: Vec<_> = TwistStampedBagRowIter::try_new(&path).unwrap().collect();
+        assert_eq!(twists.len(), 5, "{}", name);
+        assert_eq!(twists[2].twist.linear.x, 12.0);
+
+        let from_reader = NavSatFixBagRowIter::try_from_reader(File::open(&path).unwrap()).unwrap();
+        assert_eq!(from_reader.count(), 5, "{}", name);
+
+        let reader = bag.merged_reader().unwrap();
+        assert_eq!(reader.num_sources(), 4);
+        let msgs: Vec<_> = reader.collect();
+        assert_eq!(msgs.len(), recorded.len(), "{}", name);
+        assert!(msgs.windows(2).all(|pair| pair[0].timestamp() <= pair[1].timestamp()));
+    }
+}
+
+#[test]
+fn bag_decode_error_test() {
+    let dir = TempDir::new("rosbag2_decode_error");
+    let path = dir.join("bad.mcap");
+    let mut recorded = recording(4);
+    // truncate the third fix
+    let bad = recorded
+        .iter()
+        .enumerate()
+        .filter(|(_, (topic, _, _, _))| *topic == "/gps/fix")
+        .nth(2)
+        .unwrap()
+        .0;
+    recorded[bad].3.truncate(20);
+    write_mcap(&path, &recorded, "zstd", 5, true);
+
+    let bag = Rosbag2::open(&path).unwrap();
+    let mut fixes = bag.msg_reader::<NavSatFix>("/gps/fix").unwrap();
+    assert_eq!(fixes.by_ref().count(), 2);
+    match fixes.error() {
+        Some(Error::Decode { row, .. }) => assert_eq!(*row, Some(2)),
+        other => panic!("expected a decode error, got {:?}", other.is_some()),
+    }
+
+    let mut fixes = bag.msg_reader::<NavSatFix>("/gps/fix").unwrap();
+    fixes.set_error_policy(RowErrorPolicy::Skip);
+    assert_eq!(fixes.by_ref().count(), 3);
+    assert_eq!(fixes.skipped_rows(), 1);
+    assert!(fixes.error().is_none());
+
+    let images: Vec<_> = bag.msg_reader::<Image>("/left/image_raw").unwrap().collect();
+    assert_eq!(images.len(), 4);
+    assert_eq!(images[0].timestamp(), Stamp::new(START_SEC, 750_000_000).to_datetime());
+
+    std::fs::write(dir.join("empty.db3"), b"not a database").unwrap();
+    assert!(Rosbag2::open(dir.join("empty.db3")).is_err());
+    assert!(Rosbag2::open(dir.join("missing")).is_err());
+}
+
+// the offset of the first chunk's uncompressed size in an MCAP file
+fn uncompressed_size_offset(bytes: &[u8]) -> usize {
+    let mut pos = 8;
+    while bytes[pos] != 0x06 {
+        pos += 9 + u64::from_le_bytes(bytes[pos + 1..pos + 9].try_into().unwrap()) as usize;
+    }
+    pos + 9 + 16
+}
+
+#[test]
+fn mcap_chunk_size_test() {
+    let dir = TempDir::new("rosbag2_chunk_size");
+    let path = dir.join("zstd.mcap");
+    write_mcap(&path, &recording(4), "zstd", 4, false);
+    let bytes = std::fs::read(&path).unwrap();
+    let offset = uncompressed_size_offset(&bytes);
+    let actual = u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+
+    // a size far beyond the limit is rejected before decompressing, and one
+    // below it that does not match the data is not allocated up front
+    for (size, expected) in [(u64::MAX, "limit"), (1 << 40, "limit"), (1 << 29, "malformed"), (actual - 1, "malformed")] {
+        let mut corrupt = bytes.clone();
+        corrupt[offset..offset + 8].copy_from_slice(&size.to_le_bytes());
+        std::fs::write(&path, &corrupt).unwrap();
+        // without a summary, opening the bag reads the chunks for the topics
+        match Rosbag2::open(&path).err() {
+            Some(Error::Bag(message)) => assert!(message.contains(expected), "{}: {}", size, message),
+            other => panic!("expected a bag error for {}, got {:?}", size, other),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+extern crate chrono;
+extern crate sdcar_types;
+
+mod common;
+
+use common::*;
+
+use chrono::NaiveDate;
+use sdcar_types::session::*;
+use sdcar_types::Error;
+
+// one stamp a second, `ms` past the second
+fn stamps_1hz(count: i64, ms: i64) -> Vec<i64> {
+    (0..count).map(|i| (1634357915 + i) * 1000 + ms).collect()
+}
+
+#[test]
+fn session_discovery_test() {
+    let dir = TempDir::new("session_discovery");
+    let parquet = dir.join("parquet");
+    std::fs::create_dir(&parquet).unwrap();
+    write_images(
+        parquet.join("left_image_20211028165901.parquet"),
+        "left_camera",
+        &stamps_1hz(3, 0),
+    );
+    write_images(
+        parquet.join("right_image_20211028165901.parquet"),
+        "right_camera",
+        &stamps_1hz(2, 0),
+    );
+    write_nav_sat_fixes(
+        parquet.join("nav_sat_fix_20211028165901.parquet"),
+        &stamps_1hz(4, 500),
+    );
+    write_images(
+        parquet.join("left_image_20211028105702.parquet"),
+        "left_camera",
+        &stamps_1hz(1, 0),
+    );
+    std::fs::write(parquet.join("notes.txt"), "not a topic").unwrap();
+    std::fs::write(parquet.join("left_image_latest.parquet"), "").unwrap();
+
+    let session = Session::open(&dir).unwrap();
+    assert_eq!(session.image_dir(), dir.join("images"));
+    assert_eq!(session.rectified_image_dir(), dir.join("images/rectified"));
+    let ids: Vec<&str> = session.runs().iter().map(|run| run.id()).collect();
+    assert_eq!(ids, vec!["20211028105702", "20211028165901"]);
+
+    let run = session.run("20211028165901").unwrap();
+    assert_eq!(
+        run.start_time(),
+        NaiveDate::from_ymd_opt(2021, 10, 28).and_then(|d| d.and_hms_opt(16, 59, 1))
+    );
+    assert_eq!(
+        run.topics().collect::<Vec<_>>(),
+        vec![Topic::LeftImage, Topic::RightImage, Topic::NavSatFix]
+    );
+    assert_eq!(
+        run.topic_path(Topic::NavSatFix),
+        Some(parquet.join("nav_sat_fix_20211028165901.parquet").as_path())
+    );
+    assert_eq!(run.rectified_image_dir(), dir.join("images/rectified"));
+    assert_eq!(run.left_image().unwrap().count(), 3);
+    assert_eq!(run.right_image().unwrap().count(), 2);
+    assert_eq!(run.nav_sat_fix().unwrap().count(), 4);
+    match run.point_cloud2() {
+        Err(Error::MissingTopic { run, topic }) => {
+            assert_eq!(run, "20211028165901");
+            assert_eq!(topic, "point_cloud2");
+        }
+        _ => panic!("expected a missing topic error"),
+    }
+
+    let reader = run.merged_reader().unwrap();
+    assert_eq!(reader.num_sources(), 3);
+    assert_eq!(reader.source_name(2), "nav_sat_fix");
+    assert_eq!(reader.count(), 9);
+}
+
+#[test]
+fn topic_name_test() {
+    for topic in Topic::ALL.iter() {
+        assert_eq!(Topic::from_name(topic.name()), Some(*topic));
+    }
+    assert_eq!(Topic::from_name("lidar"), None);
+}
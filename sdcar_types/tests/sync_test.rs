@@ -0,0 +1,103 @@
+extern crate chrono;
+extern crate sdcar_types;
+
+mod common;
+
+use common::*;
+
+use chrono::Duration;
+use sdcar_types::reader::merged::*;
+use sdcar_types::reader::parquet::*;
+use sdcar_types::reader::sync::*;
+
+#[test]
+fn approximate_time_sync_test() {
+    let lidar_path = TempPath::new("sync_lidar");
+    let left_path = TempPath::new("sync_left");
+    let right_path = TempPath::new("sync_right");
+    write_point_clouds(&lidar_path, &[1000, 1100, 1200, 1300, 1400]);
+    write_images(&left_path, "left", &[1005, 1105, 1190, 1340, 1405]);
+    write_images(&right_path, "right", &[1010, 1095, 1210, 1320, 1410]);
+
+    let reader = MergedReader::new()
+        .with_source("point_cloud2", PointCloud2ParquetRowIter::new(&lidar_path))
+        .with_source("left_image", ImageParquetRowIter::new(&left_path))
+        .with_source("right_image", ImageParquetRowIter::new(&right_path));
+    let mut sync = ApproximateTimeSync::new(reader, Duration::milliseconds(30));
+
+    let mut frames = Vec::new();
+    for set in sync.by_ref() {
+        let (point_cloud2, left, right) = (
+            set.point_cloud2(0).unwrap(),
+            set.image(1).unwrap(),
+            set.image(2).unwrap(),
+        );
+        assert!(set.spread() <= Duration::milliseconds(30));
+        frames.push((
+            point_cloud2.header.timestamp().timestamp_millis(),
+            left.image_oid.clone(),
+            right.image_oid.clone(),
+        ));
+    }
+    assert_eq!(
+        frames,
+        vec![
+            (1000, "left0".to_string(), "right0".to_string()),
+            (1100, "left1".to_string(), "right1".to_string()),
+            (1200, "left2".to_string(), "right2".to_string()),
+            (1400, "left4".to_string(), "right4".to_string()),
+        ]
+    );
+    assert_eq!(sync.dropped_pivots(), 1);
+}
+
+#[test]
+fn stale_policy_test() {
+    let lidar_path = TempPath::new("stale_lidar");
+    let gps_path = TempPath::new("stale_gps");
+    write_point_clouds(&lidar_path, &[1000, 1100, 1200]);
+    write_nav_sat_fixes(&gps_path, &[1050, 2000]);
+
+    let sync_stamps = |stale_policy: StalePolicy| -> Vec<(i64, i64)> {
+        let reader = MergedReader::new()
+            .with_source("point_cloud2", PointCloud2ParquetRowIter::new(&lidar_path))
+            .with_source("nav_sat_fix", NavSatFixParquetRowIter::new(&gps_path));
+        ApproximateTimeSync::new(reader, Duration::milliseconds(200))
+            .with_stale_policy(stale_policy)
+            .map(|set| {
+                (
+                    set.timestamp().timestamp_millis(),
+                    set.nav_sat_fix(1).unwrap().header.timestamp().timestamp_millis(),
+                )
+            })
+            .collect()
+    };
+
+    assert_eq!(
+        sync_stamps(StalePolicy::Reuse),
+        vec![(1000, 1050), (1100, 1050), (1200, 1050)]
+    );
+    assert_eq!(sync_stamps(StalePolicy::Drop), vec![(1000, 1050)]);
+}
+
+#[test]
+fn sync_pivot_test() {
+    let lidar_path = TempPath::new("pivot_lidar");
+    let gps_path = TempPath::new("pivot_gps");
+    write_point_clouds(&lidar_path, &[1000, 1100, 1200, 1300]);
+    write_nav_sat_fixes(&gps_path, &[1090, 1310]);
+
+    let reader = MergedReader::new()
+        .with_source("point_cloud2", PointCloud2ParquetRowIter::new(&lidar_path))
+        .with_source("nav_sat_fix", NavSatFixParquetRowIter::new(&gps_path));
+    let stamps: Vec<(i64, i64)> = ApproximateTimeSync::new(reader, Duration::milliseconds(50))
+        .with_pivot(1)
+        .map(|set| {
+            (
+                set.point_cloud2(0).unwrap().header.timestamp().timestamp_millis(),
+                set.timestamp().timestamp_millis(),
+            )
+        })
+        .collect();
+    assert_eq!(stamps, vec![(1100, 1090), (1300, 1310)]);
+}
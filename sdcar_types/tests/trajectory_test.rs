@@ -0,0 +1,151 @@
+extern crate chrono;
+extern crate sdcar_types;
+
+use chrono::{Duration, TimeZone, Utc};
+use sdcar_types::msg::nav_sat_fix::NavSatFix;
+use sdcar_types::trajectory::*;
+use std::f64::consts::PI;
+
+const LATITUDE: f64 = 35.681236;
+const LONGITUDE: f64 = 139.767125;
+// metres per degree of latitude near the origin
+const METRES_PER_DEGREE: f64 = 110_950.0;
+
+// a fix `ms` milliseconds into the run, `north` and `east` metres from the origin
+fn fix(ms: i64, north: f64, east: f64, status: i8, variance: f64) -> NavSatFix {
+    let sec = 1595047644 + (ms / 1000) as i32;
+    let nanosec = (ms % 1000 * 1_000_000) as u32;
+    NavSatFix::new(
+        sec,
+        nanosec,
+        "gnss".to_string(),
+        status,
+        1,
+        LATITUDE + north / METRES_PER_DEGREE,
+        LONGITUDE + east / (METRES_PER_DEGREE * LATITUDE.to_radians().cos()),
+        40.0,
+        [variance, 0.0, 0.0, 0.0, variance, 0.0, 0.0, 0.0, 4.0 * variance],
+        2,
+    )
+}
+
+#[test]
+fn filter_test() {
+    let fixes = vec![
+        fix(0, 0.0, 0.0, 2, 0.01),
+        fix(100, 0.0, 1.0, -1, 0.01),
+        fix(200, 0.0, 2.0, 0, 25.0),
+        fix(300, 0.0, 3.0, 2, 0.01),
+        fix(250, 0.0, 2.5, 2, 0.01),
+    ];
+    let trajectory = Trajectory::new().with_fixes(&fixes);
+    assert_eq!(trajectory.len(), 3);
+    assert_eq!(trajectory.rejected(), 2);
+
+    let trajectory = Trajectory::new()
+        .with_filter(FixFilter::new().with_max_horizontal_std(1.0))
+        .with_fixes(&fixes);
+    assert_eq!(trajectory.len(), 2);
+    let trajectory = Trajectory::new()
+        .with_filter(FixFilter::new().with_min_status(2).with_max_vertical_std(0.1))
+        .with_fixes(&fixes);
+    assert_eq!(trajectory.len(), 0);
+    assert!(trajectory.origin().is_none());
+
+    let mut unknown = fix(0, 0.0, 0.0, 0, 1e6);
+    unknown.position_covariance_type = 0;
+    assert!(FixFilter::new().with_max_horizontal_std(1.0).accepts(&unknown));
+    // as is one of a type the message does not define
+    unknown.position_covariance_type = 4;
+    assert!(FixFilter::new().with_max_horizontal_std(1.0).accepts(&unknown));
+
+    // a negative variance reads as a standard deviation of zero, as it does
+    // for the covariance's own accuracies
+    let mut negative = fix(0, 0.0, 0.0, 0, 0.01);
+    negative.position_covariance[0] = -1.0;
+    negative.position_covariance[8] = -1.0;
+    assert_eq!(negative.covariance().vertical_accuracy(), Some(0.0));
+    assert!(FixFilter::new()
+        .with_max_horizontal_std(1.0)
+        .with_max_vertical_std(1.0)
+        .accepts(&negative));
+}
+
+#[test]
+fn enu_test() {
+    let fixes: Vec<NavSatFix> = (0..=10)
+        .map(|i| fix(i * 100, 10.0, i as f64, 0, 0.01))
+        .collect();
+    let trajectory = Trajectory::new().with_fixes(&fixes);
+    let origin = trajectory.origin().unwrap();
+    assert!((origin.0 - fixes[0].latitude).abs() < 1e-12);
+    let points = trajectory.points();
+    assert!(points[0].enu.norm() < 1e-9);
+    assert!((points[10].enu.x - 10.0).abs() < 0.05, "east {}", points[10].enu.x);
+    assert!(points[10].enu.y.abs() < 0.05);
+    assert!((trajectory.length() - 10.0).abs() < 0.05);
+
+    // a shared origin puts the track 10 m north
+    let trajectory = Trajectory::new()
+        .with_origin(LATITUDE, LONGITUDE, 40.0)
+        .with_fixes(&fixes);
+    assert!((trajectory.points()[0].enu.y - 10.0).abs() < 0.05);
+    assert!(trajectory.points()[0].enu.z.abs() < 0.01);
+}
+
+#[test]
+fn interpolate_test() {
+    let fixes = vec![
+        fix(0, 0.0, 0.0, 0, 0.01),
+        fix(1000, 0.0, 10.0, 0, 0.01),
+        fix(2000, 10.0, 10.0, 0, 0.01),
+        fix(3000, 10.0, 10.0, 0, 0.01),
+    ];
+    let trajectory = Trajectory::new().with_fixes(&fixes);
+    let start = trajectory.start().unwrap();
+    assert_eq!(start, Utc.timestamp_opt(1595047644, 0).unwrap());
+    assert_eq!(trajectory.end(), Some(start + Duration::seconds(3)));
+
+    let position = trajectory.position_at(start + Duration::milliseconds(250)).unwrap();
+    assert!((position.x - 2.5).abs() < 0.05);
+    let pose = trajectory.pose_at(start + Duration::milliseconds(250)).unwrap();
+    assert!(pose.yaw.abs() < 0.01);
+    let pose = trajectory.pose_at(start + Duration::milliseconds(1500)).unwrap();
+    assert!((pose.y - 5.0).abs() < 0.05);
+    assert!((pose.yaw - PI / 2.0).abs() < 0.01);
+    // standing still keeps the last heading
+    let pose = trajectory.pose_at(start + Duration::milliseconds(2500)).unwrap();
+    assert!((pose.yaw - PI / 2.0).abs() < 0.01);
+    let (latitude, _, _) = trajectory.lla_at(start + Duration::seconds(3)).unwrap();
+    assert!((latitude - fixes[3].latitude).abs() < 1e-12);
+
+    assert!(trajectory.pose_at(start - Duration::milliseconds(1)).is_none());
+    assert!(trajectory.position_at(start + Duration::milliseconds(3001)).is_none());
+    assert!(Trajectory::new().pose_at(start).is_none());
+}
+
+#[test]
+fn export_test() {
+    let fixes = vec![fix(0, 0.0, 0.0, 0, 0.01), fix(100, 0.0, 1.0, 0, 0.01)];
+    let trajectory = Trajectory::new().with_fixes(&fixes);
+    let coordinate = format!("{:.9},{:.9},40.000", fixes[1].longitude, fixes[1].latitude);
+
+    let geojson = trajectory.to_geojson("run \"1\"");
+    assert!(geojson.starts_with("{\"type\":\"FeatureCollection\""));
+    assert!(geojson.contains("\"name\":\"run \\\"1\\\"\""));
+    assert!(geojson.contains(&format!("[{}]", coordinate)));
+    assert!(geojson.contains("\"2020-07-18T04:47:24.100Z\""));
+
+    let gpx = trajectory.to_gpx("a & b");
+    assert!(gpx.contains("<name>a &amp; b</name>"));
+    assert_eq!(gpx.matches("<trkpt ").count(), 2);
+    assert!(gpx.contains(&format!(
+        "<trkpt lat=\"{:.9}\" lon=\"{:.9}\"><ele>40.000</ele><time>2020-07-18T04:47:24.000Z</time>",
+        fixes[0].latitude, fixes[0].longitude
+    )));
+
+    let kml = trajectory.to_kml("run");
+    assert!(kml.contains("<coordinates>"));
+    assert!(kml.contains(&coordinate));
+    assert!(kml.ends_with("</kml>\n"));
+}
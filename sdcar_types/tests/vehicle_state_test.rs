@@ -0,0 +1,152 @@
+extern crate chrono;
+extern crate sdcar_types;
+
+use chrono::{Duration, TimeZone, Utc};
+use sdcar_types::kinematics::*;
+use sdcar_types::msg::vehicle_state::VehicleState;
+use sdcar_types::msg::Msg;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+// a state `ms` milliseconds into the run
+fn vehicle_state(ms: i64, v_mps: f32, yaw_rate: f32, sas_angle: f32) -> VehicleState {
+    let sec = 1595047644 + (ms / 1000) as i32;
+    let nanosec = (ms % 1000 * 1_000_000) as u32;
+    let kph = v_mps * 3.6;
+    VehicleState::new(
+        sec,
+        nanosec,
+        "base_link".to_string(),
+        sec,
+        nanosec,
+        kph,
+        kph,
+        kph,
+        kph,
+        kph,
+        0.0,
+        0.0,
+        yaw_rate,
+        sas_angle,
+        0.0,
+        0.01,
+        sas_angle.to_radians(),
+        kph,
+        v_mps,
+    )
+}
+
+#[test]
+fn accessors_test() {
+    let state = VehicleState::new(
+        1595047644, 250_000_000, "base_link".to_string(), 1595047643, 750_000_000, 10.0, 2.1,
+        2.2, 2.3, 2.4, 0.1, -0.2, 0.05, 12.5, 0.3, 0.02, 0.218, 36.0, 10.0,
+    );
+    assert_eq!(state.stamp_sec(), 1595047644);
+    assert_eq!(state.stamp_nanosec(), 250_000_000);
+    assert_eq!(state.frame_id(), "base_link");
+    assert_eq!(
+        state.timeref_stamp(),
+        Utc.timestamp_opt(1595047643, 750_000_000).unwrap()
+    );
+    assert_eq!(state.vs_eng(), 10.0);
+    assert_eq!(state.wheel_fl(), 2.1);
+    assert_eq!(state.wheel_fr(), 2.2);
+    assert_eq!(state.wheel_rl(), 2.3);
+    assert_eq!(state.wheel_rr(), 2.4);
+    assert_eq!(state.long_accel(), 0.1);
+    assert_eq!(state.lat_accel(), -0.2);
+    assert_eq!(state.yaw_rate(), 0.05);
+    assert_eq!(state.sas_angle(), 12.5);
+    assert_eq!(state.sas_speed(), 0.3);
+    assert_eq!(state.dt_sec(), 0.02);
+    assert_eq!(state.sas_radians(), 0.218);
+    assert_eq!(state.vs(), 36.0);
+    assert_eq!(state.v_mps(), 10.0);
+
+    assert!((state.average_wheel_speed() - 2.25).abs() < 1e-6);
+    assert!((state.average_wheel_speed_mps() - 0.625).abs() < 1e-6);
+    assert!((state.yaw_rate_radians() - 0.05f32.to_radians()).abs() < 1e-9);
+}
+
+#[test]
+fn curvature_test() {
+    let state = vehicle_state(0, 10.0, 0.0, 140.0);
+    let geometry = VehicleGeometry::new(2.7, 14.0);
+    assert!((state.road_wheel_angle(&geometry) - 10f32.to_radians()).abs() < 1e-6);
+    let expected = 10f32.to_radians().tan() / 2.7;
+    assert!((state.curvature(&geometry) - expected).abs() < 1e-6);
+    assert_eq!(vehicle_state(0, 10.0, 0.0, 0.0).curvature(&geometry), 0.0);
+    assert!(vehicle_state(0, 10.0, 0.0, -90.0).curvature(&geometry) < 0.0);
+}
+
+#[test]
+fn straight_line_test() {
+    let states: Vec<Arc<VehicleState>> = (0..=10)
+        .map(|i| Arc::new(vehicle_state(i * 100, 10.0, 0.0, 0.0)))
+        .collect();
+    let track = DeadReckoning::new()
+        .with_initial_pose(1.0, 2.0, PI / 2.0)
+        .track(states.iter().cloned());
+    assert_eq!(track.len(), 11);
+    assert_eq!(track[0].timestamp, states[0].timestamp());
+    let last = track[10];
+    assert!((last.x - 1.0).abs() < 1e-9);
+    assert!((last.y - 12.0).abs() < 1e-6);
+    assert!((last.yaw - PI / 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn constant_turn_test() {
+    // a quarter circle to the left in 5 s
+    let yaw_rate = 90.0 / 5.0;
+    let states: Vec<VehicleState> = (0..=500)
+        .map(|i| vehicle_state(i * 10, 10.0, yaw_rate, 0.0))
+        .collect();
+    let track = DeadReckoning::new().track(&states);
+    let radius = 10.0 / (yaw_rate as f64).to_radians();
+    let last = track.last().unwrap();
+    assert!((last.x - radius).abs() < 1e-3, "x {}", last.x);
+    assert!((last.y - radius).abs() < 1e-3, "y {}", last.y);
+    assert!((last.yaw - PI / 2.0).abs() < 1e-6);
+
+    // the same turn from the steering angle, with no yaw rate signal
+    let geometry = VehicleGeometry::default();
+    let sas_angle =
+        (((yaw_rate as f64).to_radians() / 10.0 * 2.7).atan().to_degrees() * 14.0) as f32;
+    let states: Vec<VehicleState> = (0..=500)
+        .map(|i| vehicle_state(i * 10, 10.0, 0.0, sas_angle))
+        .collect();
+    let track = DeadReckoning::new()
+        .with_heading_source(HeadingSource::Steering(geometry))
+        .with_speed_source(SpeedSource::WheelSpeed)
+        .track(&states);
+    let last = track.last().unwrap();
+    assert!((last.yaw - PI / 2.0).abs() < 1e-4, "yaw {}", last.yaw);
+    assert!((last.x - radius).abs() < 1e-2);
+}
+
+#[test]
+fn dropout_test() {
+    let states = [
+        vehicle_state(0, 10.0, 0.0, 0.0),
+        vehicle_state(100, 10.0, 0.0, 0.0),
+        vehicle_state(5100, 10.0, 0.0, 0.0),
+        vehicle_state(5200, 10.0, 0.0, 0.0),
+    ];
+    let mut dead_reckoning = DeadReckoning::new().with_max_step(Duration::milliseconds(500));
+    assert!(dead_reckoning.pose().is_none());
+    let track: Vec<Pose2D> = states.iter().map(|s| dead_reckoning.update(s)).collect();
+    assert!((track[1].x - 1.0).abs() < 1e-6);
+    assert!((track[2].x - 1.0).abs() < 1e-6);
+    assert!((track[3].x - 2.0).abs() < 1e-6);
+    assert_eq!(dead_reckoning.pose(), Some(track[3]));
+    assert_eq!(track[3].isometry().translation.vector.x, track[3].x);
+}
+
+#[test]
+fn normalize_angle_test() {
+    assert!((normalize_angle(3.0 * PI / 2.0) + PI / 2.0).abs() < 1e-12);
+    assert!((normalize_angle(-3.0 * PI / 2.0) - PI / 2.0).abs() < 1e-12);
+    assert!((normalize_angle(PI) - PI).abs() < 1e-12);
+}